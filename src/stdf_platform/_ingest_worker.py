@@ -43,7 +43,7 @@ def main():
     t_parse = time.monotonic() - t0
     print(
         f"[worker] parsed: {len(data.parts)} parts, {len(data.test_results)} results "
-        f"({t_parse:.1f}s)",
+        f"({t_parse:.1f}s, ~{data.estimated_bytes() / (1024 * 1024):.0f} MB in memory)",
         file=sys.stderr,
     )
 
@@ -74,6 +74,7 @@ def main():
         "wafer_count": len(data.wafers),
         "part_count": len(data.parts),
         "test_count": len(data.tests),
+        "is_complete": data.is_complete,
     }, sys.stdout)
 
 
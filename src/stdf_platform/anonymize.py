@@ -0,0 +1,206 @@
+"""Anonymized copies of STDF files, for sharing datalogs outside the company.
+
+anonymize() rewrites a file record by record (records.py): identifying text
+fields (lot, sub-lot, wafer, operator, part type, equipment, ...) are replaced
+by salted hashes, free-text records (DTR, GDR) and fields (STR texts
+included) are dropped or blanked, and
+timestamps are shifted by a fixed offset. Everything else — test numbers,
+results, limits, bins, coordinates — is copied byte for byte, so the copy
+parses and analyses like the original.
+
+The same value always maps to the same hash (within one salt), across fields
+and files: WIR and WRR WAFER_IDs still match, and several files of one lot
+still share a lot id. Keep the salt secret; the returned key maps the hashes
+back to the original values.
+"""
+
+import hashlib
+import struct
+from dataclasses import dataclass, field
+from pathlib import Path
+from typing import BinaryIO
+
+from .parser import REC_NAMES, sniff_byte_order
+from .records import FIELD_LAYOUTS, decode_fields, encode_fields, iter_records
+
+# C*n fields replaced by a hash of their value
+HASH_FIELDS: dict[str, tuple[str, ...]] = {
+    "MIR": (
+        "LOT_ID", "SBLOT_ID", "PART_TYP", "JOB_NAM", "OPER_NAM", "NODE_NAM", "FAMLY_ID",
+        "FACIL_ID", "FLOOR_ID", "SPEC_NAM", "ENG_ID", "SERL_NUM", "SUPR_NAM", "PROC_ID",
+        "DSGN_REV", "SETUP_ID", "PKG_TYP",
+    ),
+    "SDR": (
+        "HAND_ID", "CARD_ID", "LOAD_ID", "DIB_ID", "CABL_ID", "CONT_ID", "LASR_ID", "EXTR_ID",
+    ),
+    "WIR": ("WAFER_ID",),
+    "WRR": ("WAFER_ID", "FABWF_ID", "FRAME_ID", "MASK_ID"),
+    "PRR": ("PART_TXT",),
+}
+# Free-text C*n fields emptied
+CLEAR_FIELDS: dict[str, tuple[str, ...]] = {
+    "ATR": ("CMD_LINE",),
+    "MIR": ("USER_TXT", "AUX_FILE"),
+    "MRR": ("USR_DESC", "EXC_DESC"),
+    "WRR": ("USR_DESC", "EXC_DESC"),
+    # STR has no FIELD_LAYOUTS entry: these are blanked in place (_clear_str)
+    "STR": ("TEST_TXT", "ALARM_ID", "PROG_TXT", "RSLT_TXT", "USER_TXT"),
+}
+# STR C*n fields after the fixed CONT_FLG..TEST_FLG header (10 bytes), in order
+_STR_TEXTS = ("LOG_TYP", "TEST_TXT", "ALARM_ID", "PROG_TXT", "RSLT_TXT")
+# U*4 epoch-second fields shifted by time_offset (0 = not set, left alone)
+TIME_FIELDS: dict[str, tuple[str, ...]] = {
+    "ATR": ("MOD_TIM",),
+    "MIR": ("SETUP_T", "START_T"),
+    "MRR": ("FINISH_T",),
+    "WIR": ("START_T",),
+    "WRR": ("FINISH_T",),
+}
+
+
+@dataclass
+class AnonymizeOptions:
+    """What anonymize() scrubs. The defaults suit sharing with an ATE vendor."""
+
+    # Mixed into every hash; without it, hashes of guessable lot ids could be
+    # reversed by brute force
+    salt: str = ""
+    # Hex digits kept of each SHA-256 (C*n fields hold up to 255 characters)
+    hash_length: int = 12
+    hash_fields: dict[str, tuple[str, ...]] = field(default_factory=lambda: dict(HASH_FIELDS))
+    clear_fields: dict[str, tuple[str, ...]] = field(default_factory=lambda: dict(CLEAR_FIELDS))
+    # Whole record types left out of the copy (DTR / GDR carry free text)
+    drop_records: set[str] = field(default_factory=lambda: {"DTR", "GDR"})
+    # Seconds added to every timestamp (negative moves them back)
+    time_offset: int = 0
+    # Records with no known layout (vendor types) may hold anything: dropped
+    # unless set
+    keep_unknown_records: bool = False
+
+
+@dataclass
+class AnonymizeReport:
+    records: int = 0  # records written
+    dropped: dict[str, int] = field(default_factory=dict)  # rec name -> records left out
+    # replacement hash -> original value, for mapping results back
+    key: dict[str, str] = field(default_factory=dict)
+
+
+def anonymize(
+    path: Path, out: BinaryIO, options: AnonymizeOptions | None = None,
+) -> AnonymizeReport:
+    """Write an anonymized copy of the (uncompressed) STDF file at `path` to `out`.
+
+    Records that must be scrubbed are decoded and re-encoded (undecoded bytes
+    at their end are dropped); one that doesn't decode raises ValueError
+    rather than leak. A record cut off by the end of the file ends the copy.
+    """
+    options = options or AnonymizeOptions()
+    report = AnonymizeReport()
+    hashes: dict[str, str] = {}
+
+    def hashed(value: str) -> str:
+        if not value:
+            return value
+        if value not in hashes:
+            digest = hashlib.sha256((options.salt + value).encode("latin-1", "replace"))
+            hashes[value] = digest.hexdigest()[:options.hash_length].upper()
+            report.key[hashes[value]] = value
+        return hashes[value]
+
+    def refused(e: ValueError) -> ValueError:
+        return ValueError(f"{path}: cannot anonymize record {report.records + 1}: {e}")
+
+    with open(path, "rb") as f:
+        endian = sniff_byte_order(f)[0]
+        header = struct.Struct(endian + "HBB")
+        for rec_typ, rec_sub, rec_len, body in iter_records(f, endian):
+            name = REC_NAMES.get((rec_typ, rec_sub), f"{rec_typ}/{rec_sub}")
+            if rec_typ < 0 or rec_len != len(body):
+                report.dropped[name] = report.dropped.get(name, 0) + 1
+                break
+            unknown = name not in FIELD_LAYOUTS and name != "STR"
+            if name in options.drop_records or (unknown and not options.keep_unknown_records):
+                report.dropped[name] = report.dropped.get(name, 0) + 1
+                continue
+            to_hash = options.hash_fields.get(name, ())
+            to_clear = options.clear_fields.get(name, ())
+            to_shift = TIME_FIELDS.get(name, ()) if options.time_offset else ()
+            if name == "STR":
+                if to_clear:
+                    try:
+                        body = _clear_str(body, endian, to_clear)
+                    except ValueError as e:
+                        raise refused(e) from e
+            elif to_hash or to_clear or to_shift:
+                try:
+                    fields, _used = decode_fields(name, body, endian)
+                except ValueError as e:
+                    raise refused(e) from e
+                for key in to_hash:
+                    if key in fields:
+                        fields[key] = hashed(fields[key])
+                for key in to_clear:
+                    if key in fields:
+                        fields[key] = ""
+                for key in to_shift:
+                    if fields.get(key):
+                        fields[key] = min(max(fields[key] + options.time_offset, 1), 0xFFFFFFFF)
+                body = encode_fields(name, fields, endian)
+            out.write(header.pack(len(body), rec_typ, rec_sub) + body)
+            report.records += 1
+    return report
+
+
+def _clear_str(body: bytes, endian: str, names: tuple[str, ...]) -> bytes:
+    """STR `body` with the C*n fields in `names` emptied and, for USER_TXT,
+    every C*f entry filled with spaces (its width is fixed by UTX_SIZE).
+    Raises ValueError if the body ends inside a field it has to walk."""
+
+    def need(end: int):
+        if end > len(body):
+            raise ValueError("STR body ends inside a field")
+
+    def u2(offset: int) -> int:
+        need(offset + 2)
+        return struct.unpack_from(endian + "H", body, offset)[0]
+
+    out = bytearray(body[:10])
+    pos = 10
+    for name in _STR_TEXTS:
+        need(pos + 1)
+        end = pos + 1 + body[pos]
+        need(end)
+        out += b"\x00" if name in names else body[pos:end]
+        pos = end
+    if "USER_TXT" not in names:
+        return bytes(out + body[pos:])
+
+    out_start = pos  # fields after RSLT_TXT are copied, USER_TXT blanked
+    need(pos + 2)
+    fmu_flg = body[pos + 1]
+    pos += 2  # Z_VAL, FMU_FLG
+    for bit in (0x01, 0x04):  # MASK_MAP, FAL_MAP
+        if fmu_flg & bit:
+            pos += 2 + (u2(pos) + 7) // 8
+    pos += 28  # CYC_CNT, TOTF_CNT, TOTL_CNT, CYC_BASE, BIT_BASE
+    cond_cnt, lim_cnt = u2(pos), u2(pos + 2)
+    pos += 4
+    need(pos + 11)
+    cyc, pmr, chn, pat, bit, usr1, usr2, usr3, utx = body[pos:pos + 9]
+    pos += 11 + 6 * lim_cnt  # sizes, CAP_BGN, LIM_INDX, LIM_SPEC
+    for _ in range(cond_cnt):
+        need(pos + 1)
+        pos += 1 + body[pos]
+    for size in (cyc, pmr, chn, 1, 1, 1, pat, bit, usr1, usr2, usr3):
+        if pos >= len(body):
+            break
+        pos += 2 + u2(pos) * size
+    need(pos)
+    tail = bytearray(body[out_start:])
+    if pos < len(body):
+        count = u2(pos)
+        need(pos + 2 + count * utx)
+        start = pos + 2 - out_start
+        tail[start:start + count * utx] = b" " * (count * utx)
+    return bytes(out + tail)
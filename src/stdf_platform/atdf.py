@@ -0,0 +1,507 @@
+"""ATDF (ASCII Test Data Format, V2) — a parsed STDF file as text records, and back.
+
+One record per line as ``REC:field|field|...`` in the ATDF field order
+(which differs from the binary STDF order), trailing empty fields dropped,
+lines over 80 characters continued on lines starting with a space. Times are
+``HH:MM:SS DD-MON-YYYY`` (UTC), R*4 values in the shortest text that reads
+back as the same single-precision float.
+
+Written from STDFData, so it shows what the parser decoded: lot records (FAR,
+ATR, MIR, RDR, PMR, PGR, WCR), per wafer WIR / per part PIR, PTR / MPR / FTR
+results, DTR, PRR, then WRR, and the TSR / HBR / SBR / PCR / MRR summaries.
+Records the parser keeps no test data for (PLR, SDR, the V4-2007 scan
+records, GDR, vendor records) are not re-emitted.
+
+Input goes the other way round: atdf_to_stdf() encodes each ATDF record as
+the binary STDF record, which the regular parser then decodes — parse_stdf()
+and parse_reader() do this for any input starting with "FAR:", so ATDF files
+give the same STDFData as their binary twins. Records without an encoding
+here are counted in ParseStats.skipped_records; offsets in parse warnings
+are those of the translated binary stream.
+"""
+
+import calendar
+import struct
+import time
+from typing import BinaryIO, Iterable, Iterator, TextIO
+
+from .parser import STDFData, U4_MISSING
+from .records import FIELD_LAYOUTS, REC_KEYS, encode_fields
+
+_LINE_MAX = 80  # ATDF line length, continuation lines included
+
+# ATDF field order per record (ATDF V2 spec; names as in the STDF V4 spec)
+ATDF_FIELDS = {
+    "FAR": ("DATA_TYP", "STDF_VER", "ATDF_VER", "SCAL_FLG"),
+    "ATR": ("MOD_TIM", "CMD_LINE"),
+    "MIR": (
+        "LOT_ID", "PART_TYP", "JOB_NAM", "NODE_NAM", "TSTR_TYP", "SETUP_T",
+        "START_T", "OPER_NAM", "MODE_COD", "STAT_NUM", "SBLOT_ID", "TEST_COD",
+        "RTST_COD", "JOB_REV", "EXEC_TYP", "EXEC_VER", "PROT_COD", "CMOD_COD",
+        "BURN_TIM", "TST_TEMP", "USER_TXT", "AUX_FILE", "PKG_TYP", "FAMLY_ID",
+        "DATE_COD", "FACIL_ID", "FLOOR_ID", "PROC_ID", "OPER_FRQ", "SPEC_NAM",
+        "SPEC_VER", "FLOW_ID", "SETUP_ID", "DSGN_REV", "ENG_ID", "ROM_COD",
+        "SERL_NUM", "SUPR_NAM",
+    ),
+    "MRR": ("FINISH_T", "DISP_COD", "USR_DESC", "EXC_DESC"),
+    "PCR": ("HEAD_NUM", "SITE_NUM", "PART_CNT", "RTST_CNT", "ABRT_CNT", "GOOD_CNT", "FUNC_CNT"),
+    "HBR": ("HEAD_NUM", "SITE_NUM", "HBIN_NUM", "HBIN_CNT", "HBIN_PF", "HBIN_NAM"),
+    "SBR": ("HEAD_NUM", "SITE_NUM", "SBIN_NUM", "SBIN_CNT", "SBIN_PF", "SBIN_NAM"),
+    "PMR": ("PMR_INDX", "CHAN_TYP", "CHAN_NAM", "PHY_NAM", "LOG_NAM", "HEAD_NUM", "SITE_NUM"),
+    "PGR": ("GRP_INDX", "GRP_NAM", "PMR_INDX"),
+    "RDR": ("RTST_BIN",),
+    "WIR": ("HEAD_NUM", "START_T", "SITE_GRP", "WAFER_ID"),
+    "WRR": (
+        "HEAD_NUM", "FINISH_T", "PART_CNT", "WAFER_ID", "SITE_GRP", "RTST_CNT",
+        "ABRT_CNT", "GOOD_CNT", "FUNC_CNT", "FABWF_ID", "FRAME_ID", "MASK_ID",
+        "USR_DESC", "EXC_DESC",
+    ),
+    "WCR": (
+        "WF_FLAT", "POS_X", "POS_Y", "WAFR_SIZ", "DIE_HT", "DIE_WID", "WF_UNITS",
+        "CENTER_X", "CENTER_Y",
+    ),
+    "PIR": ("HEAD_NUM", "SITE_NUM"),
+    "PRR": (
+        "HEAD_NUM", "SITE_NUM", "PART_ID", "NUM_TEST", "PASS_FAIL", "HARD_BIN",
+        "SOFT_BIN", "X_COORD", "Y_COORD", "RETEST", "ABORT", "TEST_T",
+        "PART_TXT", "PART_FIX",
+    ),
+    "TSR": (
+        "HEAD_NUM", "SITE_NUM", "TEST_NUM", "TEST_NAM", "TEST_TYP", "EXEC_CNT",
+        "FAIL_CNT", "ALRM_CNT", "SEQ_NAME", "TEST_LBL", "TEST_TIM", "TEST_MIN",
+        "TEST_MAX", "TST_SUMS", "TST_SQRS",
+    ),
+    "PTR": (
+        "TEST_NUM", "HEAD_NUM", "SITE_NUM", "RESULT", "PASS_FAIL", "ALARM_FLG",
+        "TEST_TXT", "ALARM_ID", "UNITS", "LO_LIMIT", "HI_LIMIT", "RES_SCAL",
+        "LLM_SCAL", "HLM_SCAL", "C_RESFMT", "C_LLMFMT", "C_HLMFMT", "LO_SPEC",
+        "HI_SPEC",
+    ),
+    "MPR": (
+        "TEST_NUM", "HEAD_NUM", "SITE_NUM", "RTN_STAT", "RTN_RSLT", "PASS_FAIL",
+        "ALARM_FLG", "TEST_TXT", "ALARM_ID", "UNITS", "LO_LIMIT", "HI_LIMIT",
+        "START_IN", "INCR_IN", "RTN_INDX",
+    ),
+    "FTR": (
+        "TEST_NUM", "HEAD_NUM", "SITE_NUM", "PASS_FAIL", "ALARM_FLG", "VECT_NAM",
+        "TIME_SET", "CYCL_CNT", "REL_VADR", "REPT_CNT", "NUM_FAIL", "XFAIL_AD",
+        "YFAIL_AD", "VECT_OFF", "RTN_INDX", "RTN_STAT", "PGM_INDX", "PGM_STAT",
+        "FAIL_PIN", "OP_CODE", "TEST_TXT", "ALARM_ID", "PROG_TXT", "RSLT_TXT",
+        "PATG_NUM", "SPIN_MAP",
+    ),
+    "DTR": ("TEXT_DAT",),
+}
+
+
+def format_time(epoch: int | None) -> str:
+    """STDF U*4 time -> ATDF "08:23:02 23-JUL-1992" (UTC); 0 / None -> ""."""
+    if not epoch:
+        return ""
+    return time.strftime("%H:%M:%S %d-%b-%Y", time.gmtime(epoch)).upper()
+
+
+def format_r4(value: float | None) -> str:
+    """Shortest text that reads back as the same R*4 value ("" for None)."""
+    if value is None:
+        return ""
+    packed = struct.pack("<f", value)
+    for digits in range(6, 10):
+        text = f"{value:.{digits}g}"
+        if struct.pack("<f", float(text)) == packed:
+            return text
+    return repr(value)
+
+
+def _text(value) -> str:
+    if value is None:
+        return ""
+    if isinstance(value, float):
+        return format_r4(value)
+    if isinstance(value, (list, tuple)):
+        return ",".join(_text(v) for v in value)
+    return str(value)
+
+
+def format_record(rec: str, values: dict) -> str:
+    """One ATDF record from {FIELD: value} (fields in ATDF_FIELDS[rec]; missing
+    ones are empty), wrapped to _LINE_MAX with space-led continuation lines."""
+    fields = [_text(values.get(name)) for name in ATDF_FIELDS[rec]]
+    while fields and fields[-1] == "":
+        fields.pop()
+    line = f"{rec}:" + "|".join(fields)
+    lines = []
+    while len(line) > _LINE_MAX:
+        # break after the last field separator that fits, else mid-field
+        cut = line.rfind("|", 0, _LINE_MAX) + 1 or _LINE_MAX
+        lines.append(line[:cut])
+        line = " " + line[cut:]
+    lines.append(line)
+    return "\n".join(lines)
+
+
+def _part_records(data: STDFData, part: dict, results: list[dict], dtrs: list[dict],
+                  seen: set) -> Iterator[str]:
+    """PIR, results, DTRs and PRR of one part. Test names, limits and units go
+    on the first result of each test per head/site only (`seen`), as testers
+    write them."""
+    head, site = part["head_num"], part["site_num"]
+    yield format_record("PIR", {"HEAD_NUM": head, "SITE_NUM": site})
+    i = 0
+    while i < len(results):
+        row = results[i]
+        test = data.tests.get(row["test_num"], {})
+        key = (row["test_num"], row["head_num"], row["site_num"])
+        first = key not in seen
+        seen.add(key)
+        values = {
+            "TEST_NUM": row["test_num"], "HEAD_NUM": row["head_num"],
+            "SITE_NUM": row["site_num"], "PASS_FAIL": "" if row["passed"] else "F",
+            "ALARM_ID": row.get("alarm_id") or "",
+        }
+        limits = data.site_limits.get(key, test)
+        if first:
+            values.update({
+                "TEST_TXT": test.get("test_name", ""), "UNITS": test.get("units", ""),
+                "LO_LIMIT": limits.get("lo_limit"), "HI_LIMIT": limits.get("hi_limit"),
+            })
+        rec_type = test.get("rec_type", "PTR")
+        i += 1
+        if rec_type == "FTR":
+            values.update({
+                "TEST_TXT": row.get("test_txt", ""),
+                "FAIL_PIN": row.get("fail_pins"),
+                **{name.upper(): row.get(name) for name in (
+                    "vect_nam", "time_set", "cycl_cnt", "rel_vadr", "rept_cnt",
+                    "num_fail", "xfail_ad", "yfail_ad", "vect_off", "rtn_indx",
+                    "rtn_stat", "pgm_indx", "pgm_stat", "op_code", "prog_txt",
+                    "rslt_txt", "patg_num",
+                )},
+            })
+            yield format_record("FTR", values)
+        elif rec_type == "MPR":
+            # the per-pin rows of one MPR are consecutive
+            pins = [row]
+            while i < len(results) and (
+                (results[i]["test_num"], results[i]["head_num"], results[i]["site_num"]) == key
+            ):
+                pins.append(results[i])
+                i += 1
+            values.update({
+                "RTN_STAT": [r["rtn_stat"] for r in pins if r.get("rtn_stat") is not None],
+                "RTN_RSLT": [r["result"] for r in pins if r["result"] is not None],
+                "RTN_INDX": [r["pin_num"] for r in pins if r.get("pin_num") is not None],
+            })
+            yield format_record("MPR", values)
+        else:
+            values["RESULT"] = row["result"]
+            if first:
+                values.update({
+                    "C_RESFMT": test.get("res_fmt", ""), "C_LLMFMT": test.get("llm_fmt", ""),
+                    "C_HLMFMT": test.get("hlm_fmt", ""),
+                    "LO_SPEC": test.get("lo_spec"), "HI_SPEC": test.get("hi_spec"),
+                })
+            yield format_record("PTR", values)
+    for dtr in dtrs:
+        yield format_record("DTR", {"TEXT_DAT": dtr["text"]})
+    yield format_record("PRR", {
+        "HEAD_NUM": head, "SITE_NUM": site, "PART_ID": part.get("tester_part_id", ""),
+        "NUM_TEST": part.get("test_count"),
+        "PASS_FAIL": ("P" if part["passed"] else "F") if part.get("pass_fail_valid", True) else "",
+        "HARD_BIN": part["hard_bin"], "SOFT_BIN": part["soft_bin"],
+        "X_COORD": part["x_coord"], "Y_COORD": part["y_coord"],
+        "RETEST": "I" if part.get("retested") else "",
+        "ABORT": "Y" if part.get("aborted") else "",
+        "TEST_T": part.get("test_time") or "",
+        "PART_TXT": part.get("part_txt", ""), "PART_FIX": part.get("part_fix", ""),
+    })
+
+
+def iter_atdf(data: STDFData) -> Iterator[str]:
+    """ATDF records (each one string, continuation lines included) for `data`."""
+    yield format_record("FAR", {"DATA_TYP": "A", "STDF_VER": 4, "ATDF_VER": 2, "SCAL_FLG": "U"})
+    for atr in data.audit_trail:
+        yield format_record("ATR", {"MOD_TIM": format_time(atr["mod_time"]),
+                                    "CMD_LINE": atr["cmd_line"]})
+    yield format_record("MIR", {
+        "LOT_ID": data.lot_id, "PART_TYP": data.part_type, "JOB_NAM": data.job_name,
+        "TSTR_TYP": data.tester_type, "START_T": format_time(data.start_time),
+        "OPER_NAM": data.operator, "TEST_COD": data.test_code, "JOB_REV": data.job_rev,
+        "TST_TEMP": data.conditions.temperature_raw, "FLOW_ID": data.conditions.flow_id,
+    })
+    if data.retest_bins is not None:
+        yield format_record("RDR", {"RTST_BIN": data.retest_bins})
+    for index, pin in data.pins.items():
+        yield format_record("PMR", {
+            "PMR_INDX": index, **{k.upper(): v for k, v in pin.items()},
+        })
+    for index, group in data.pin_groups.items():
+        yield format_record("PGR", {
+            "GRP_INDX": index, "GRP_NAM": group["grp_nam"], "PMR_INDX": group["pmr_indexes"],
+        })
+    wcr = data.wafer_config
+    if wcr is not None:
+        yield format_record("WCR", {
+            "WF_FLAT": wcr.flat, "POS_X": wcr.pos_x, "POS_Y": wcr.pos_y,
+            "WAFR_SIZ": wcr.wafer_size, "DIE_HT": wcr.die_height, "DIE_WID": wcr.die_width,
+            "WF_UNITS": wcr.units, "CENTER_X": wcr.center_x, "CENTER_Y": wcr.center_y,
+        })
+
+    results: dict[str, list[dict]] = {}
+    for row in data.test_results:
+        results.setdefault(row["part_id"], []).append(row)
+    dtrs: dict[str, list[dict]] = {}
+    for row in data.datalog_text:
+        dtrs.setdefault(row["part_id"], []).append(row)
+    for dtr in dtrs.pop("", []):
+        yield format_record("DTR", {"TEXT_DAT": dtr["text"]})
+    parts: dict[str, list[dict]] = {}
+    for part in data.parts:
+        parts.setdefault(part["wafer_id"] or "", []).append(part)
+
+    seen: set = set()
+    wafer_ids = {w["wafer_id"] for w in data.wafers}
+    for wafer_id in [w for w in parts if w not in wafer_ids]:  # no WIR (final test)
+        for part in parts.pop(wafer_id):
+            pid = part["part_id"]
+            yield from _part_records(data, part, results.get(pid, []), dtrs.get(pid, []), seen)
+    for wafer in data.wafers:
+        yield format_record("WIR", {
+            "HEAD_NUM": wafer["head_num"], "START_T": format_time(wafer["start_time"]),
+            "WAFER_ID": wafer["wafer_id"],
+        })
+        for part in parts.pop(wafer["wafer_id"], []):
+            pid = part["part_id"]
+            yield from _part_records(data, part, results.get(pid, []), dtrs.get(pid, []), seen)
+        if "finish_time" in wafer:
+            yield format_record("WRR", {
+                "HEAD_NUM": wafer["head_num"], "FINISH_T": format_time(wafer["finish_time"]),
+                "PART_CNT": wafer["part_count"], "WAFER_ID": wafer["wafer_id"],
+                "RTST_CNT": wafer["rtst_count"], "ABRT_CNT": wafer["abrt_count"],
+                "GOOD_CNT": wafer["good_count"], "FABWF_ID": wafer["fabwf_id"],
+                "FRAME_ID": wafer["frame_id"], "MASK_ID": wafer["mask_id"],
+                "USR_DESC": wafer["user_desc"], "EXC_DESC": wafer["exc_desc"],
+            })
+
+    for tsr in data.test_synopses:
+        yield format_record("TSR", {
+            "HEAD_NUM": tsr["head_num"], "SITE_NUM": tsr["site_num"],
+            "TEST_NUM": tsr["test_num"], "TEST_NAM": tsr["test_name"],
+            "TEST_TYP": tsr["test_type"], "EXEC_CNT": tsr["exec_count"],
+            "FAIL_CNT": tsr["fail_count"], "ALRM_CNT": tsr["alarm_count"],
+            "SEQ_NAME": tsr["seq_name"], "TEST_LBL": tsr["test_label"],
+            "TEST_TIM": tsr["test_time"], "TEST_MIN": tsr["test_min"],
+            "TEST_MAX": tsr["test_max"], "TST_SUMS": tsr["test_sum"],
+            "TST_SQRS": tsr["test_sum_sq"],
+        })
+    for rec, sites, totals in (
+        ("HBR", data.site_bins_hard, data.bins_hard), ("SBR", data.site_bins_soft, data.bins_soft),
+    ):
+        p = rec[0]
+        rows = list(sites.values()) + [
+            {**row, "head_num": 255, "site_num": 255} for row in totals.values()
+        ]
+        for row in rows:
+            yield format_record(rec, {
+                "HEAD_NUM": row["head_num"], "SITE_NUM": row["site_num"],
+                f"{p}BIN_NUM": row["bin_num"], f"{p}BIN_CNT": row["bin_count"],
+                f"{p}BIN_PF": row["bin_pf"].strip(), f"{p}BIN_NAM": row["bin_name"],
+            })
+    for (head, site), counts in data.part_counts.items():
+        yield format_record("PCR", {
+            "HEAD_NUM": head, "SITE_NUM": site, "PART_CNT": counts["part_count"],
+            "RTST_CNT": counts["rtst_count"], "ABRT_CNT": counts["abrt_count"],
+            "GOOD_CNT": counts["good_count"], "FUNC_CNT": counts["func_count"],
+        })
+    yield format_record("MRR", {
+        "FINISH_T": format_time(data.finish_time), "DISP_COD": data.disp_code,
+        "USR_DESC": data.user_desc, "EXC_DESC": data.exc_desc,
+    })
+
+
+def write_atdf(data: STDFData, out: TextIO) -> int:
+    """Write `data` as ATDF to a text stream. Returns the record count."""
+    count = 0
+    for record in iter_atdf(data):
+        out.write(record + "\n")
+        count += 1
+    return count
+
+
+# ---------------------------------------------------------------------------
+# ATDF -> binary STDF
+
+# Leading binary fields every record has (written even when the ATDF fields
+# are empty), and STDF missing values for empty optional fields; other empty
+# fields are 0 / "" / []. Field order and types: records.FIELD_LAYOUTS.
+_MIN_FIELDS = {
+    "ATR": 1, "MIR": 3, "MRR": 1, "PCR": 2, "HBR": 4, "SBR": 4, "PMR": 1, "PGR": 3,
+    "RDR": 1, "WIR": 1, "WRR": 1, "WCR": 1, "PIR": 2, "PRR": 5, "TSR": 4, "PTR": 5,
+    "MPR": 5, "FTR": 4, "DTR": 1,
+}
+_MISSING = {
+    "MRR": {"DISP_COD": " "},
+    "PCR": dict.fromkeys(("PART_CNT", "RTST_CNT", "ABRT_CNT", "GOOD_CNT", "FUNC_CNT"), U4_MISSING),
+    "WCR": {"CENTER_X": -32768, "CENTER_Y": -32768},
+    "PRR": {"SOFT_BIN": 65535, "X_COORD": -32768, "Y_COORD": -32768},
+    "TSR": dict.fromkeys(("EXEC_CNT", "FAIL_CNT", "ALRM_CNT"), U4_MISSING),
+    "FTR": {"PATG_NUM": 255},
+}
+_TIME_FIELDS = {"SETUP_T", "START_T", "FINISH_T", "MOD_TIM"}
+# ATDF alarm flag characters -> TEST_FLG bits
+_ALARM_BITS = {"A": 0x01, "U": 0x04, "T": 0x08, "N": 0x10, "X": 0x20}
+
+
+def parse_time(text: str) -> int:
+    """ATDF "08:23:02 23-JUL-1992" (UTC) -> STDF U*4 epoch seconds."""
+    return calendar.timegm(time.strptime(text.strip().title(), "%H:%M:%S %d-%b-%Y"))
+
+
+def _zero(kind: str):
+    if kind.startswith("k"):
+        return []
+    return {"C1": " ", "Cn": "", "Sn": "", "Bn": "", "Dn": {"bits": 0, "data": ""},
+            "R4": 0.0, "R8": 0.0}.get(kind, 0)
+
+
+def _value(field: str, kind: str, text: str):
+    """ATDF field text -> the records.encode_fields value of type `kind`."""
+    if field in _TIME_FIELDS:
+        return parse_time(text)
+    if kind.startswith("k") or kind == "Dn":
+        items = [t.strip() for t in text.split(",") if t.strip()]
+        if kind == "kR4":
+            return [float(t) for t in items]
+        if kind != "Dn":
+            return [int(t) for t in items]
+        bits = [int(t) for t in items]  # D*n: the numbers of the bits set
+        data = bytearray((max(bits) + 8) // 8 if bits else 0)
+        for i in bits:
+            data[i >> 3] |= 1 << (i & 7)
+        return {"bits": max(bits) + 1 if bits else 0, "data": data.hex()}
+    if kind == "R4":
+        return float(text)
+    if kind == "C1":
+        return text[:1]
+    if kind == "Cn":
+        return text[:255]
+    if kind == "Bn":
+        return bytes.fromhex(text).hex()
+    return int(text)
+
+
+def _derive(rec: str, atdf: dict[str, str], values: dict):
+    """Binary-only fields (flags, array counts) from the ATDF fields of `rec`."""
+    if rec in ("PTR", "MPR", "FTR"):
+        flg = 0x80 if atdf.get("PASS_FAIL", "").strip() == "F" else 0
+        for ch in atdf.get("ALARM_FLG", ""):
+            flg |= _ALARM_BITS.get(ch, 0)
+        if rec == "PTR" and "RESULT" not in values:
+            flg |= 0x02  # RESULT invalid
+        values["TEST_FLG"] = flg
+    if rec in ("PTR", "MPR"):
+        # empty limit = invalid here (the first record's applies); spec limits absent
+        opt = 0
+        for bit, name in ((2, "LO_SPEC"), (3, "HI_SPEC"), (4, "LO_LIMIT"), (5, "HI_LIMIT")):
+            if name not in values:
+                opt |= 1 << bit
+        if rec == "MPR" and not ("START_IN" in values and "INCR_IN" in values):
+            opt |= 0x02
+        values["OPT_FLAG"] = opt
+    if rec == "MPR":
+        n = max(len(values.get("RTN_STAT", [])), len(values.get("RTN_INDX", [])))
+        values["RTN_ICNT"], values["RSLT_CNT"] = n, len(values.get("RTN_RSLT", []))
+        for name in ("RTN_STAT", "RTN_INDX"):
+            if name in values:
+                values[name] = values[name] + [0] * (n - len(values[name]))
+    elif rec == "FTR":
+        opt = 0
+        for bit, names in ((0, ("CYCL_CNT",)), (1, ("REL_VADR",)), (2, ("REPT_CNT",)),
+                           (3, ("NUM_FAIL",)), (4, ("XFAIL_AD", "YFAIL_AD")), (5, ("VECT_OFF",))):
+            if any(name not in values for name in names):
+                opt |= 1 << bit
+        values["OPT_FLAG"] = opt
+        for count, indx, stat in (("RTN_ICNT", "RTN_INDX", "RTN_STAT"),
+                                  ("PGM_ICNT", "PGM_INDX", "PGM_STAT")):
+            n = len(values.get(indx, []))
+            values[count] = n
+            values[stat] = (values.get(stat, []) + [0] * n)[:n]
+    elif rec == "PRR":
+        pf = atdf.get("PASS_FAIL", "").strip()
+        flg = {"P": 0, "F": 0x08}.get(pf, 0x10)  # anything else: no pass/fail indication
+        flg |= {"I": 0x01, "C": 0x02}.get(atdf.get("RETEST", "").strip(), 0)
+        if atdf.get("ABORT", "").strip() == "Y":
+            flg |= 0x04
+        values["PART_FLG"] = flg
+    elif rec == "TSR":
+        opt = 0
+        for bit, name in ((0, "TEST_MIN"), (1, "TEST_MAX"), (2, "TEST_TIM"),
+                          (4, "TST_SUMS"), (5, "TST_SQRS")):
+            if name not in values:
+                opt |= 1 << bit
+        values["OPT_FLAG"] = opt
+    elif rec == "PGR":
+        values["INDX_CNT"] = len(values.get("PMR_INDX", []))
+    elif rec == "RDR":
+        values["NUM_BINS"] = len(values.get("RTST_BIN", []))
+
+
+def encode_record(rec: str, fields: list[str]) -> bytes:
+    """Binary STDF record (little endian, header included) for one ATDF record.
+
+    Trailing optional fields the ATDF record leaves empty are dropped, as
+    binary writers do; empty fields in between take the STDF missing value.
+    """
+    layout = FIELD_LAYOUTS[rec]
+    atdf = dict(zip(ATDF_FIELDS[rec], fields))
+    kinds = {name: kind for name, kind, *_count in layout}
+    values = {
+        name: _value(name, kinds[name], text)
+        for name, text in atdf.items() if name in kinds and text.strip() != ""
+    }
+    last = max(
+        [i for i, (name, *_) in enumerate(layout) if name in values],
+        default=-1,
+    )
+    last = max(last, _MIN_FIELDS[rec] - 1)
+    _derive(rec, atdf, values)
+    missing = _MISSING.get(rec, {})
+    body = encode_fields(rec, {
+        name: values[name] if name in values else missing.get(name, _zero(kind))
+        for name, kind, *_count in layout[:last + 1]
+    })
+    return struct.pack("<HBB", len(body), *REC_KEYS[rec]) + body
+
+
+def iter_atdf_records(lines: Iterable[str]) -> Iterator[tuple[str, list[str]]]:
+    """(record name, field texts) per ATDF record, continuation lines joined."""
+    rec = None
+    for line in lines:
+        line = line.rstrip("\r\n")
+        if line.startswith(" ") and rec is not None:
+            rec += line[1:]
+            continue
+        if rec is not None:
+            yield rec[:3], rec[4:].split("|")
+        rec = line if line[3:4] == ":" else None
+    if rec is not None:
+        yield rec[:3], rec[4:].split("|")
+
+
+def atdf_to_stdf(lines: Iterable[str], out: BinaryIO) -> dict[str, int]:
+    """Write the ATDF records in `lines` to `out` as binary STDF (little endian).
+
+    Returns the records that were skipped (no binary encoding here), by name.
+    A field that doesn't read as its type raises ValueError naming the record.
+    """
+    skipped: dict[str, int] = {}
+    for n, (rec, fields) in enumerate(iter_atdf_records(lines), 1):
+        if rec == "FAR":
+            out.write(struct.pack("<HBBBB", 2, 0, 10, 2, 4))  # CPU_TYPE 2 = little endian
+        elif rec in _MIN_FIELDS:
+            try:
+                out.write(encode_record(rec, fields))
+            except (ValueError, struct.error) as e:
+                raise ValueError(f"ATDF {rec} record {n}: {e}") from e
+        else:
+            skipped[rec] = skipped.get(rec, 0) + 1
+    return skipped
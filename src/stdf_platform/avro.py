@@ -0,0 +1,165 @@
+"""Minimal Avro object container files (no avro/fastavro dependency).
+
+Enough of the Avro 1.x spec for flat tables: one record schema whose fields
+are all nullable primitives (``["null", T]`` with T one of boolean / long /
+double / string / bytes), written as a container file with the schema
+embedded, ``null`` or ``deflate`` codec. Any Avro reader (fastavro, Spark,
+Hive, Kafka Connect) reads the output; read_avro() reads back what this
+module writes (tests, quick checks) — not arbitrary Avro.
+"""
+
+import io
+import json
+import os
+import struct
+import zlib
+from pathlib import Path
+from typing import BinaryIO, Iterator
+
+MAGIC = b"Obj\x01"
+_BLOCK_RECORDS = 4000  # records per container block
+
+# Python value type -> Avro primitive (bool before int: bool is an int)
+_TYPES = ((bool, "boolean"), (int, "long"), (float, "double"), (str, "string"), (bytes, "bytes"))
+
+
+def _long(n: int) -> bytes:
+    """Avro long: zig-zag, then base-128 varint."""
+    n = (n << 1) ^ (n >> 63)
+    out = bytearray()
+    while n > 0x7F:
+        out.append((n & 0x7F) | 0x80)
+        n >>= 7
+    out.append(n)
+    return bytes(out)
+
+
+def _blob(b: bytes) -> bytes:
+    return _long(len(b)) + b
+
+
+_ENCODERS = {
+    "boolean": lambda v: b"\x01" if v else b"\x00",
+    "long": lambda v: _long(int(v)),
+    "double": lambda v: struct.pack("<d", float(v)),
+    "string": lambda v: _blob(str(v).encode("utf-8")),
+    "bytes": lambda v: _blob(bytes(v)),
+}
+
+
+def infer_schema(rows: list[dict], name: str) -> dict:
+    """Record schema for `rows`: every key (union over rows, first-seen order)
+    as a nullable field typed from its first non-None value; ints mixed with
+    floats become double, anything else unknown becomes string."""
+    types: dict[str, str | None] = {}
+    for row in rows:
+        for key, value in row.items():
+            seen = types.get(key)
+            if value is None:
+                types.setdefault(key, None)
+                continue
+            kind = next((t for py, t in _TYPES if isinstance(value, py)), "string")
+            if seen is None:
+                types[key] = kind
+            elif {seen, kind} == {"long", "double"}:
+                types[key] = "double"
+    return {
+        "type": "record",
+        "name": name,
+        "fields": [
+            {"name": key, "type": ["null", kind or "string"], "default": None}
+            for key, kind in types.items()
+        ],
+    }
+
+
+def write_avro(
+    path: Path, rows: list[dict], name: str, codec: str = "deflate",
+    schema: dict | None = None,
+) -> int:
+    """Write `rows` to an Avro container file (schema from infer_schema unless
+    given). codec is "null" or "deflate". Returns the record count."""
+    if codec not in ("null", "deflate"):
+        raise ValueError(f"unsupported Avro codec {codec!r}")
+    schema = schema or infer_schema(rows, name)
+    fields = [(f["name"], _ENCODERS[f["type"][1]]) for f in schema["fields"]]
+    sync = os.urandom(16)
+    with open(path, "wb") as out:
+        meta = {"avro.schema": json.dumps(schema).encode("utf-8"), "avro.codec": codec.encode()}
+        out.write(MAGIC + _long(len(meta)))
+        for key, value in meta.items():
+            out.write(_blob(key.encode()) + _blob(value))
+        out.write(_long(0) + sync)
+        for start in range(0, len(rows), _BLOCK_RECORDS):
+            block = rows[start:start + _BLOCK_RECORDS]
+            data = bytearray()
+            for row in block:
+                for key, encode in fields:
+                    value = row.get(key)
+                    # union branch: 0 = null, 1 = the value
+                    data += b"\x00" if value is None else b"\x02" + encode(value)
+            payload = bytes(data)
+            if codec == "deflate":
+                packer = zlib.compressobj(wbits=-15)  # raw deflate, per the spec
+                payload = packer.compress(payload) + packer.flush()
+            out.write(_long(len(block)) + _long(len(payload)) + payload + sync)
+    return len(rows)
+
+
+def _read_long(f: BinaryIO) -> int:
+    shift = n = 0
+    while True:
+        byte = f.read(1)[0]
+        n |= (byte & 0x7F) << shift
+        shift += 7
+        if byte < 0x80:
+            return (n >> 1) ^ -(n & 1)
+
+
+def _read_blob(f: BinaryIO) -> bytes:
+    return f.read(_read_long(f))
+
+
+_DECODERS = {
+    "boolean": lambda f: f.read(1) == b"\x01",
+    "long": _read_long,
+    "double": lambda f: struct.unpack("<d", f.read(8))[0],
+    "string": lambda f: _read_blob(f).decode("utf-8"),
+    "bytes": _read_blob,
+}
+
+
+def read_avro(path: Path) -> tuple[dict, Iterator[dict]]:
+    """(schema, rows) of a container file written by write_avro."""
+    raw = Path(path).read_bytes()
+    f = io.BytesIO(raw)
+    if f.read(4) != MAGIC:
+        raise ValueError(f"{path}: not an Avro container file")
+    meta = {}
+    while count := _read_long(f):
+        if count < 0:
+            _read_long(f)  # block byte size
+        for _ in range(abs(count)):
+            key = _read_blob(f).decode()
+            meta[key] = _read_blob(f)
+    sync = f.read(16)
+    schema = json.loads(meta["avro.schema"])
+    codec = meta.get("avro.codec", b"null").decode()
+    fields = [(fd["name"], _DECODERS[fd["type"][1]]) for fd in schema["fields"]]
+
+    def rows() -> Iterator[dict]:
+        while f.tell() < len(raw):
+            count = _read_long(f)
+            payload = _read_blob(f)
+            if codec == "deflate":
+                payload = zlib.decompress(payload, wbits=-15)
+            block = io.BytesIO(payload)
+            for _ in range(count):
+                yield {
+                    key: decode(block) if _read_long(block) else None
+                    for key, decode in fields
+                }
+            if f.read(16) != sync:
+                raise ValueError(f"{path}: bad sync marker")
+
+    return schema, rows()
@@ -207,6 +207,203 @@ def serve(ctx, host: str | None, port: int | None):
     uvicorn.run(create_app(config), host=host, port=port)
 
 
+@main.command()
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+def census(stdf_file: Path):
+    """Count records per type (headers only — no decoding).
+
+    STDF_FILE: Path to an uncompressed STDF file
+
+    Example: stdf census lot001.stdf
+    """
+    from .parser import census as run_census
+
+    stats = run_census(stdf_file)
+    if not stats:
+        console.print("[yellow]No records found[/yellow]")
+        return
+
+    table = Table(title=f"Record Census — {stdf_file.name}")
+    table.add_column("Record", style="cyan")
+    table.add_column("Typ/Sub")
+    table.add_column("Count", justify="right")
+    table.add_column("Bytes", justify="right")
+    table.add_column("Min", justify="right")
+    table.add_column("Avg", justify="right")
+    table.add_column("Max", justify="right")
+
+    for name, entry in stats.items():
+        table.add_row(
+            name,
+            f"{entry['rec_typ']}/{entry['rec_sub']}",
+            f"{entry['count']:,}",
+            f"{entry['total_bytes']:,}",
+            str(entry["min_len"]),
+            f"{entry['avg_len']:.1f}",
+            str(entry["max_len"]),
+        )
+
+    console.print(table)
+
+
+@main.command()
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.option("--repeat", "-n", default=3, show_default=True, help="Runs per mode (best is reported)")
+def bench(stdf_file: Path, repeat: int):
+    """Measure parse throughput (MB/s, records/s) on your own data.
+
+    Parses the file with and without test result collection so the cost of
+    the datalog itself is visible.
+
+    STDF_FILE: Path to an uncompressed STDF file
+
+    Example: stdf bench lot001.stdf -n 5
+    """
+    from .parser import bench_parse
+
+    try:
+        rows = bench_parse(stdf_file, repeat=repeat)
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+    table = Table(title=f"Parse Throughput — {stdf_file.name} (best of {repeat})")
+    table.add_column("Mode", style="cyan")
+    table.add_column("Seconds", justify="right")
+    table.add_column("MB/s", justify="right")
+    table.add_column("Records/s", justify="right")
+    table.add_column("Records", justify="right")
+    table.add_column("Results", justify="right")
+
+    for row in rows:
+        table.add_row(
+            row["mode"],
+            f"{row['seconds']:.3f}",
+            f"{row['mb_per_s']:.1f}",
+            f"{row['records_per_s']:,.0f}",
+            f"{row['records']:,}",
+            f"{row['results']:,}",
+        )
+
+    console.print(table)
+
+
+@main.command()
+@click.argument("output", type=click.Path(dir_okay=False, path_type=Path))
+@click.option("--lot", "lot_id", default="SYNTH01", show_default=True, help="MIR LOT_ID")
+@click.option("--wafers", default=1, show_default=True, help="Wafer count (0 = final test, no WIR/WRR)")
+@click.option("--parts", "parts_per_wafer", default=100, show_default=True, help="Parts per wafer")
+@click.option("--sites", default=1, show_default=True, help="Sites tested in parallel")
+@click.option("--tests", "num_tests", default=10, show_default=True, help="Parametric tests per part")
+@click.option("--fail-rate", default=0.01, show_default=True, help="Fraction of results outside the limits, per test")
+@click.option("--big-endian", is_flag=True, help="Write a big-endian (CPU_TYPE 1) file")
+@click.option("--seed", default=0, show_default=True, help="Random seed (same seed, same bytes)")
+def testgen(output: Path, lot_id: str, wafers: int, parts_per_wafer: int, sites: int,
+            num_tests: int, fail_rate: float, big_endian: bool, seed: int):
+    """Write a synthetic STDF file (deterministic fixtures for benchmarks and tests).
+
+    OUTPUT: STDF file to write
+
+    Example: stdf testgen bench.stdf --wafers 25 --parts 2000 --sites 8 --tests 200
+    """
+    from .testgen import GenOptions, default_tests, generate_stdf
+
+    try:
+        report = generate_stdf(output, GenOptions(
+            lot_id=lot_id, wafers=wafers, parts_per_wafer=parts_per_wafer, sites=sites,
+            tests=default_tests(num_tests), fail_rate=fail_rate,
+            endian=">" if big_endian else "<", seed=seed,
+        ))
+        console.print(f"[green]✓[/green] Wrote {output}: {report.parts:,} parts "
+                      f"({report.good:,} good), {report.results:,} results, "
+                      f"{output.stat().st_size / 1e6:.1f} MB")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@main.command(name="diff")
+@click.argument("file_a", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("file_b", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.option("--tolerance", default=0.0, show_default=True,
+              help="Results closer than this count as equal")
+def diff_cmd(file_a: Path, file_b: Path, tolerance: float):
+    """Compare two STDF files: header fields, part counts, bins, test results.
+
+    Dies are matched by wafer and X/Y (tester part id in final test).
+    Exits 1 when the files differ.
+
+    Example: stdf diff lot001.stdf lot001_rerun.stdf --tolerance 1e-6
+    """
+    from .diff import compare
+
+    try:
+        diff = compare(file_a, file_b, tolerance=tolerance)
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+    if diff.identical:
+        console.print("[green]✓[/green] No differences")
+        return
+    for name, (a, b) in diff.header.items():
+        console.print(f"  {name}: {a!r} → {b!r}")
+    console.print(f"  parts: {diff.part_count[0]:,} → {diff.part_count[1]:,}, "
+                  f"good: {diff.good_count[0]:,} → {diff.good_count[1]:,}")
+    for kind, bins in (("hard", diff.bins_hard), ("soft", diff.bins_soft)):
+        for num, (a, b) in bins.items():
+            console.print(f"  {kind} bin {num}: {a:,} → {b:,}")
+    if diff.bin_changes:
+        console.print(f"  {len(diff.bin_changes):,} dies changed bin")
+    if diff.dies_only_a or diff.dies_only_b:
+        console.print(f"  dies only in A: {len(diff.dies_only_a):,}, only in B: {len(diff.dies_only_b):,}")
+    for label, nums in (("A", diff.tests_only_a), ("B", diff.tests_only_b)):
+        if nums:
+            console.print(f"  tests only in {label}: {', '.join(map(str, nums))}")
+
+    if diff.tests:
+        table = Table(title="Test Differences")
+        table.add_column("Test", style="cyan")
+        table.add_column("Name")
+        table.add_column("Count A → B", justify="right")
+        table.add_column("Fails A → B", justify="right")
+        table.add_column("Mean A", justify="right")
+        table.add_column("Mean B", justify="right")
+        table.add_column("Changed", justify="right")
+        table.add_column("Max |Δ|", justify="right")
+        for t in diff.tests:
+            table.add_row(
+                str(t.test_num), t.test_name,
+                f"{t.count_a:,} → {t.count_b:,}", f"{t.fails_a:,} → {t.fails_b:,}",
+                "" if t.mean_a is None else f"{t.mean_a:.6g}",
+                "" if t.mean_b is None else f"{t.mean_b:.6g}",
+                f"{t.changed:,} / {t.paired:,}", f"{t.max_abs_delta:.6g}",
+            )
+        console.print(table)
+    sys.exit(1)
+
+
+@main.command()
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+def validate(stdf_file: Path):
+    """Check an STDF file with the strict parser (QA gate).
+
+    Exits 1 at the first malformed record, or when FAR / MIR / MRR is
+    missing or a part or wafer is left open.
+
+    Example: stdf validate lot001.stdf || mv lot001.stdf quarantine/
+    """
+    from .parser import ParseOptions, parse_stdf
+
+    try:
+        data = parse_stdf(stdf_file, ParseOptions(strict=True, collect_results=False))
+    except Exception as e:
+        console.print(f"[red]Invalid:[/red] {e}")
+        sys.exit(1)
+    console.print(f"[green]✓[/green] {stdf_file.name}: {data.stats.record_count:,} records, "
+                  f"{len(data.parts):,} parts")
+
+
 # ── db group ──────────────────────────────────────────────────────
 
 @main.group()
@@ -792,7 +989,7 @@ def fetch(ctx, product: tuple, test_type: tuple, limit: int | None, ingest: bool
 
 @main.group(name="export")
 def export_grp():
-    """Export data to CSV or Parquet."""
+    """Export data to CSV or Parquet, or convert an STDF file directly."""
     pass
 
 
@@ -940,6 +1137,674 @@ def export_lot(ctx, lot_ids: tuple, output: Path, pivot: bool):
         sys.exit(1)
 
 
+@export_grp.command(name="convert")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, allow_dash=True,
+                                             path_type=Path))
+@click.argument("output", type=click.Path(path_type=Path))
+@click.option("--format", "-f", "fmt",
+              type=click.Choice(["jsonl", "ndjson", "csv", "parquet", "avro", "duckdb", "delta", "postgres"]),
+              default="jsonl", show_default=True,
+              help="jsonl = one part + its results per line (OUTPUT is a file); "
+                   "ndjson = one line per part / result, streamed while parsing "
+                   "(OUTPUT is a file or - for stdout); "
+                   "csv/parquet/avro = parts + results tables (OUTPUT is a directory); "
+                   "postgres = parts/results COPY files + load.sql for psql (OUTPUT is a directory); "
+                   "duckdb = parts + results tables appended to OUTPUT, a database file; "
+                   "delta = parts + results Delta Lake tables under OUTPUT, appended, "
+                   "partitioned by lot_id (needs stdf2pq[delta])")
+@click.option("--stream", is_flag=True,
+              help="Write parts as they are parsed instead of parsing the whole file "
+                   "first (memory bounded by a Parquet row group; test names that only "
+                   "appear in TSRs stay empty). Always on for ndjson, duckdb and delta.")
+def export_convert(stdf_file: Path, output: Path, fmt: str, stream: bool):
+    """
+    Convert an STDF file directly (no ingest) to JSON Lines, NDJSON, CSV, Parquet,
+    Avro, DuckDB, Delta Lake or PostgreSQL COPY files.
+
+    STDF_FILE: Path to the STDF file, or - to read stdin. gzip/bz2/xz/zstd
+    compression is detected from the data, not the name.
+    OUTPUT: Output file (jsonl / ndjson / duckdb) or directory (csv / parquet / avro / delta /
+    postgres)
+
+    Example:
+        stdf export convert lot001.stdf lot001.jsonl
+        stdf export convert lot001.stdf out/ -f parquet
+        stdf export convert big_ft.stdf out/ -f parquet --stream
+        stdf export convert lot001.stdf - -f ndjson | vector --config ship.toml
+        stdf export convert lot002.stdf yield.duckdb -f duckdb   # appends
+        stdf export convert lot002.stdf lake/stdf -f delta       # appends
+        stdf export convert lot001.stdf pg/ -f postgres && psql -f pg/load.sql
+        aws s3 cp s3://bucket/lot001.stdf.gz - | stdf export convert - lot001.jsonl
+    """
+    from .parser import parse_reader, parse_stdf
+    from .export import (
+        AvroSink, CsvSink, DeltaSink, DuckDBSink, JsonLinesSink, NdjsonSink,
+        ParquetSink, PostgresSink, export_to_sink, stream_to_sink,
+    )
+
+    try:
+        source = sys.stdin.buffer if str(stdf_file) == "-" else stdf_file
+        if fmt == "ndjson":
+            # Streamed: parts are written as they are parsed, never all held
+            if str(output) == "-":
+                stream_to_sink(source, NdjsonSink(sys.stdout))
+                return  # stdout carries the data: no summary line
+            with open(output, "w", encoding="utf-8") as out:
+                n = stream_to_sink(source, NdjsonSink(out))
+            console.print(f"[green]✓[/green] Exported {n:,} parts to {output}")
+            return
+        if fmt == "duckdb":
+            n = stream_to_sink(source, DuckDBSink(output))
+            console.print(f"[green]✓[/green] Appended {n:,} parts to {output}")
+            return
+        if fmt == "delta":
+            n = stream_to_sink(source, DeltaSink(output))
+            console.print(f"[green]✓[/green] Appended {n:,} parts to {output}/parts, {output}/results")
+            return
+        if not stream:
+            source = parse_reader(source) if str(stdf_file) == "-" else parse_stdf(source)
+        write = stream_to_sink if stream else export_to_sink
+        if fmt == "jsonl":
+            with open(output, "w", encoding="utf-8") as out:
+                n = write(source, JsonLinesSink(out))
+        elif fmt == "csv":
+            n = write(source, CsvSink(output))
+        elif fmt == "avro":
+            n = write(source, AvroSink(output))
+        elif fmt == "postgres":
+            n = write(source, PostgresSink(output))
+        else:
+            source_file = None if str(stdf_file) == "-" else stdf_file
+            n = write(source, ParquetSink(output, source_file=source_file))
+        console.print(f"[green]✓[/green] Exported {n:,} parts to {output}")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@export_grp.command(name="atdf")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("output", type=click.Path(dir_okay=False, allow_dash=True, path_type=Path))
+def export_atdf(stdf_file: Path, output: Path):
+    """
+    Re-emit a parsed STDF file as ATDF (ASCII Test Data Format) text.
+
+    STDF_FILE: Path to the STDF file
+    OUTPUT: Output .atd file, or - for stdout
+
+    Example:
+        stdf export atdf lot001.stdf lot001.atd
+        stdf export atdf lot001.stdf - | grep ^PRR
+    """
+    from .atdf import write_atdf
+    from .parser import parse_stdf
+
+    try:
+        data = parse_stdf(stdf_file)
+        if str(output) == "-":
+            write_atdf(data, sys.stdout)
+            return
+        with open(output, "w", encoding="utf-8") as out:
+            n = write_atdf(data, out)
+        console.print(f"[green]✓[/green] Wrote {n:,} ATDF records to {output}")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@export_grp.command(name="kafka")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.option("--bootstrap", required=True, help="Kafka bootstrap servers (host:port[,...])")
+@click.option("--topic", required=True, help="Topic to publish part payloads to")
+def export_kafka(stdf_file: Path, bootstrap: str, topic: str):
+    """
+    Publish one JSON message per part (results nested) to a Kafka topic.
+
+    Requires the kafka extra: pip install 'stdf2pq[kafka]'
+
+    Example:
+        stdf export kafka lot001.stdf --bootstrap broker:9092 --topic stdf.parts
+    """
+    from .export import KafkaSink, export_to_sink
+    from .parser import parse_stdf
+
+    try:
+        sink = KafkaSink(topic, bootstrap_servers=bootstrap)
+        n = export_to_sink(parse_stdf(stdf_file), sink)
+        console.print(f"[green]✓[/green] Published {n} parts to {topic}")
+    except ImportError:
+        console.print("[red]Error:[/red] confluent-kafka not installed (pip install 'stdf2pq[kafka]')")
+        sys.exit(1)
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@export_grp.command(name="e142")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("out_dir", type=click.Path(file_okay=False, path_type=Path))
+@click.option("--bin", "bin_kind", type=click.Choice(["soft", "hard"]), default="soft",
+              show_default=True, help="Bin number mapped per die")
+@click.option("--retest", "retest_files", multiple=True,
+              type=click.Path(exists=True, dir_okay=False, path_type=Path),
+              help="Retest STDF file(s) of the same wafers, in test order (repeatable); "
+                   "the latest bin per die wins")
+@click.option("--pos-x", type=click.Choice(["L", "R"], case_sensitive=False),
+              help="Direction of increasing X (overrides the WCR; default R)")
+@click.option("--pos-y", type=click.Choice(["U", "D"], case_sensitive=False),
+              help="Direction of increasing Y (overrides the WCR; default D)")
+@click.option("--flat", type=click.Choice(["U", "D", "L", "R"], case_sensitive=False),
+              help="Flat / notch position (overrides the WCR)")
+def export_e142(stdf_file: Path, out_dir: Path, bin_kind: str, retest_files: tuple[Path, ...],
+                 pos_x: str | None, pos_y: str | None, flat: str | None):
+    """
+    Write SEMI E142 wafer map XML (one file per wafer) from an STDF file.
+
+    STDF_FILE: Path to the (CP) STDF file
+    OUT_DIR: Output directory ({lot}_{wafer}.xml)
+
+    Example:
+        stdf export e142 lot001.stdf maps/
+        stdf export e142 lot001.stdf maps/ --retest lot001_rt1.stdf
+    """
+    from .parser import parse_stdf
+    from .wafermap import final_dies, set_orientation, write_e142
+
+    try:
+        data = parse_stdf(stdf_file)
+        if pos_x or pos_y or flat:
+            set_orientation(data, pos_x, pos_y, flat)
+        parts = None
+        if retest_files:
+            parts = final_dies(data, [parse_stdf(p) for p in retest_files])
+        paths = write_e142(data, out_dir, bin_field=f"{bin_kind}_bin", parts=parts)
+        if not paths:
+            console.print("[yellow]No wafer dies (x/y) found — nothing to map[/yellow]")
+            return
+        console.print(f"[green]✓[/green] Wrote {len(paths)} wafer map(s) to {out_dir}")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@export_grp.command(name="sinf")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("out_dir", type=click.Path(file_okay=False, path_type=Path))
+@click.option("--bin", "bin_kind", type=click.Choice(["soft", "hard"]), default="soft",
+              show_default=True, help="Bin number mapped per die")
+@click.option("--retest", "retest_files", multiple=True,
+              type=click.Path(exists=True, dir_okay=False, path_type=Path),
+              help="Retest STDF file(s) of the same wafers, in test order (repeatable); "
+                   "the latest bin per die wins")
+@click.option("--pos-x", type=click.Choice(["L", "R"], case_sensitive=False),
+              help="Direction of increasing X (overrides the WCR; default R)")
+@click.option("--pos-y", type=click.Choice(["U", "D"], case_sensitive=False),
+              help="Direction of increasing Y (overrides the WCR; default D)")
+@click.option("--flat", type=click.Choice(["U", "D", "L", "R"], case_sensitive=False),
+              help="Flat / notch position (overrides the WCR)")
+def export_sinf(stdf_file: Path, out_dir: Path, bin_kind: str, retest_files: tuple[Path, ...],
+                 pos_x: str | None, pos_y: str | None, flat: str | None):
+    """
+    Write SINF wafer maps (one file per wafer) from an STDF file.
+
+    STDF_FILE: Path to the (CP) STDF file
+    OUT_DIR: Output directory ({lot}_{wafer}.sinf)
+
+    Example:
+        stdf export sinf lot001.stdf maps/
+        stdf export sinf lot001.stdf maps/ --retest lot001_rt1.stdf
+        stdf export sinf lot001.stdf maps/ --pos-y U --flat D   # no WCR in file
+    """
+    from .parser import parse_stdf
+    from .wafermap import final_dies, set_orientation, write_sinf
+
+    try:
+        data = parse_stdf(stdf_file)
+        if pos_x or pos_y or flat:
+            set_orientation(data, pos_x, pos_y, flat)
+        parts = None
+        if retest_files:
+            parts = final_dies(data, [parse_stdf(p) for p in retest_files])
+        paths = write_sinf(data, out_dir, bin_field=f"{bin_kind}_bin", parts=parts)
+        if not paths:
+            console.print("[yellow]No wafer dies (x/y) found — nothing to map[/yellow]")
+            return
+        console.print(f"[green]✓[/green] Wrote {len(paths)} wafer map(s) to {out_dir}")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@export_grp.command(name="pins")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("output", type=click.Path(dir_okay=False, path_type=Path))
+def export_pins(stdf_file: Path, output: Path):
+    """
+    Write the pin map (PMR) and pin groups (PGR) of an STDF file as JSON.
+
+    STDF_FILE: Path to the STDF file
+    OUTPUT: Output JSON file
+
+    Example:
+        stdf export pins lot001.stdf pins.json
+    """
+    from .export import export_pin_configuration
+    from .parser import parse_stdf
+
+    try:
+        data = parse_stdf(stdf_file)
+        export_pin_configuration(data, output)
+        console.print(
+            f"[green]✓[/green] Wrote {len(data.pins)} pins, "
+            f"{len(data.pin_groups)} groups to {output}"
+        )
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@export_grp.command(name="tables")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("out_dir", type=click.Path(file_okay=False, path_type=Path))
+@click.option("--partition-by", "-p", default="",
+              help="Comma-separated columns for a Hive layout, e.g. lot_id,wafer_id")
+def export_tables(stdf_file: Path, out_dir: Path, partition_by: str):
+    """
+    Write the parsed parts / test_results / wafers / bins tables as Parquet.
+
+    One file per table, or with --partition-by one Hive-partitioned directory
+    per table (parts/lot_id=X/wafer_id=Y/<file stem>-0.parquet) for Athena/Trino.
+
+    STDF_FILE: Path to the STDF file
+    OUT_DIR: Output directory
+
+    Example:
+        stdf export tables lot001.stdf tables/
+        stdf export tables lot001.stdf s3-staging/ -p lot_id,wafer_id
+    """
+    from .export import parse_to_parquet
+
+    columns = [c.strip() for c in partition_by.split(",") if c.strip()] or None
+    try:
+        counts = parse_to_parquet(stdf_file, out_dir, partition_by=columns)
+        summary = ", ".join(f"{n:,} {name}" for name, n in counts.items())
+        console.print(f"[green]✓[/green] Wrote {summary} to {out_dir}")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@export_grp.command(name="limits")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("output", type=click.Path(dir_okay=False, path_type=Path))
+def export_limits(stdf_file: Path, output: Path):
+    """
+    Write the test limit table (number, name, limits, units, per-site variants).
+
+    JSON if OUTPUT ends in .json, CSV otherwise. Keep one per program
+    revision and diff them to see limit changes.
+
+    STDF_FILE: Path to the STDF file
+    OUTPUT: Output .csv or .json file
+
+    Example:
+        stdf export limits lot001.stdf limits_rev3.csv
+    """
+    from .export import export_limit_table
+    from .parser import ParseOptions, parse_stdf
+
+    try:
+        data = parse_stdf(stdf_file, ParseOptions(collect_results=False))
+        count = export_limit_table(data, output)
+        console.print(f"[green]✓[/green] Wrote {count:,} test limits to {output}")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@export_grp.command(name="scan-fails")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("output", type=click.Path(dir_okay=False, path_type=Path))
+def export_scan_fails(stdf_file: Path, output: Path):
+    """
+    Write STR (V4-2007 scan test) fail cycles as CSV for scan diagnosis.
+
+    One row per failing cycle: part, test, pattern, cycle, pin, chain, bit.
+
+    STDF_FILE: Path to the STDF file
+    OUTPUT: Output CSV file
+
+    Example:
+        stdf export scan-fails lot001.stdf scan_fails.csv
+    """
+    from .export import export_scan_failures
+    from .parser import parse_stdf
+
+    try:
+        data = parse_stdf(stdf_file)
+        count = export_scan_failures(data, output)
+        console.print(f"[green]✓[/green] Wrote {count:,} scan failures to {output}")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+# ── records group ─────────────────────────────────────────────────
+
+@main.group(name="records")
+def records_grp():
+    """Record-level access to STDF files (lossless dump / rebuild)."""
+    pass
+
+
+@records_grp.command(name="dump")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("output", type=click.Path(dir_okay=False, allow_dash=True, path_type=Path))
+def records_dump(stdf_file: Path, output: Path):
+    """
+    Dump every record with all its fields as JSON Lines.
+
+    Lossless: `stdf records build` turns the output back into a
+    byte-identical STDF file. Records or bytes that do not decode are kept
+    as hex.
+
+    STDF_FILE: Path to the (uncompressed) STDF file
+    OUTPUT: Output .jsonl file, or - for stdout
+
+    Example:
+        stdf records dump lot001.stdf lot001.jsonl
+        stdf records dump lot001.stdf - | grep '"MIR"'
+    """
+    from .records import dump_json
+
+    try:
+        if str(output) == "-":
+            dump_json(stdf_file, sys.stdout)
+            return
+        with open(output, "w", encoding="utf-8") as out:
+            n = dump_json(stdf_file, out)
+        console.print(f"[green]✓[/green] Dumped {n:,} records to {output}")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@records_grp.command(name="build")
+@click.argument("json_file", type=click.Path(exists=True, dir_okay=False, allow_dash=True, path_type=Path))
+@click.argument("output", type=click.Path(dir_okay=False, path_type=Path))
+def records_build(json_file: Path, output: Path):
+    """
+    Rebuild an STDF file from `stdf records dump` output.
+
+    JSON_FILE: Record dump (.jsonl), or - for stdin
+    OUTPUT: Output STDF file
+
+    Example:
+        stdf records build lot001.jsonl lot001.stdf
+    """
+    from .records import build_stdf
+
+    try:
+        with open(output, "wb") as out:
+            if str(json_file) == "-":
+                n = build_stdf(sys.stdin, out)
+            else:
+                with open(json_file, encoding="utf-8") as lines:
+                    n = build_stdf(lines, out)
+        console.print(f"[green]✓[/green] Wrote {n:,} records to {output}")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@records_grp.command(name="anonymize")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("output", type=click.Path(dir_okay=False, path_type=Path))
+@click.option("--salt", envvar="STDF_ANON_SALT", default="",
+              help="Secret mixed into the hashes (or $STDF_ANON_SALT)")
+@click.option("--time-offset", type=int, default=0, show_default=True,
+              help="Seconds added to every timestamp (negative moves them back)")
+@click.option("--keep-text", is_flag=True, help="Keep DTR / GDR records")
+@click.option("--key-file", type=click.Path(dir_okay=False, path_type=Path),
+              help="Write the hash -> original value key as JSON (keep it private)")
+def records_anonymize(stdf_file: Path, output: Path, salt: str, time_offset: int,
+                      keep_text: bool, key_file: Path | None):
+    """
+    Write an anonymized copy of an STDF file for sharing outside the company.
+
+    Lot, sub-lot, wafer, operator, part type and similar fields become salted
+    hashes (the same value always gets the same hash), DTR / GDR records and
+    free-text fields are removed, vendor records are dropped. Test data is
+    copied unchanged.
+
+    STDF_FILE: Path to the (uncompressed) STDF file
+    OUTPUT: Anonymized STDF file
+
+    Example:
+        stdf records anonymize lot001.stdf share/lot001.stdf --salt "$SECRET" --time-offset -86400
+    """
+    import json
+
+    from .anonymize import AnonymizeOptions, anonymize
+
+    options = AnonymizeOptions(salt=salt, time_offset=time_offset)
+    if keep_text:
+        options.drop_records = set()
+    try:
+        with open(output, "wb") as out:
+            report = anonymize(stdf_file, out, options)
+        if key_file is not None:
+            key_file.write_text(json.dumps(report.key, indent=2), encoding="utf-8")
+        dropped = ", ".join(f"{n} {rec}" for rec, n in sorted(report.dropped.items()))
+        console.print(f"[green]✓[/green] Wrote {report.records:,} records to {output}"
+                      + (f" (dropped {dropped})" if dropped else ""))
+        if not salt:
+            console.print("[yellow]No --salt: hashes of guessable values can be reversed[/yellow]")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@records_grp.command(name="merge")
+@click.argument("output", type=click.Path(dir_okay=False, path_type=Path))
+@click.argument("stdf_files", nargs=-1, required=True,
+                type=click.Path(exists=True, dir_okay=False, path_type=Path))
+def records_merge(output: Path, stdf_files: tuple[Path, ...]):
+    """
+    Merge STDF files of one lot (per-site-controller or retest files) into one.
+
+    Files are taken in test order (MIR start time). Part and wafer records are
+    copied; PRR PART_IDs that clash with an earlier file are renumbered; bin,
+    part count and test synopsis records are rebuilt from the summed counts.
+
+    OUTPUT: Merged STDF file
+    STDF_FILES: The (uncompressed) files of the lot
+
+    Example:
+        stdf records merge LOT1.stdf LOT1_site*.stdf
+    """
+    from .merge import merge_stdf
+
+    try:
+        with open(output, "wb") as out:
+            n = merge_stdf(list(stdf_files), out)
+        console.print(f"[green]✓[/green] Merged {len(stdf_files)} files ({n:,} records) into {output}")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@records_grp.command(name="split")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("out_dir", type=click.Path(file_okay=False, path_type=Path))
+@click.option("--by", type=click.Choice(["wafer", "site"]), default="wafer", show_default=True,
+              help="One output file per wafer (WIR..WRR) or per test site")
+def records_split(stdf_file: Path, out_dir: Path, by: str):
+    """
+    Split an STDF file into one file per wafer or per site.
+
+    Each output gets the lot header records (MIR, SDR, PMR, ...) and the MRR;
+    its HBR/SBR summary is recounted from its own parts.
+
+    STDF_FILE: Path to the (uncompressed) STDF file
+    OUT_DIR: Directory for <name>_<wafer>.stdf / <name>_site<n>.stdf
+
+    Example:
+        stdf records split lot001.stdf wafers/
+        stdf records split ft_lot.stdf sites/ --by site
+    """
+    from .split import split_stdf
+
+    try:
+        paths = split_stdf(stdf_file, out_dir, by)
+        console.print(f"[green]✓[/green] Wrote {len(paths)} files to {out_dir}")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@records_grp.command(name="filter")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("output", type=click.Path(dir_okay=False, path_type=Path))
+@click.option("--drop", "drop", default="",
+              help="Comma-separated record types to leave out (e.g. PTR,MPR,FTR)")
+@click.option("--keep", "keep", default=None,
+              help="Comma-separated record types to keep; everything else is left out")
+@click.option("--summary", is_flag=True,
+              help="Leave out per-test detail (PTR, MPR, FTR, STR): parts, bins and TSRs remain")
+def records_filter(stdf_file: Path, output: Path, drop: str, keep: str | None, summary: bool):
+    """
+    Copy an STDF file without selected record types (compact archives).
+
+    Records kept are copied byte for byte. An OUTPUT ending in .gz is
+    gzip-compressed (the parser reads it directly).
+
+    STDF_FILE: Path to the (uncompressed) STDF file
+    OUTPUT: Filtered STDF file
+
+    Example:
+        stdf records filter lot001.stdf archive/lot001.stdf.gz --summary
+        stdf records filter lot001.stdf lot001_nodtr.stdf --drop DTR,GDR
+    """
+    import gzip
+
+    from .records import DETAIL_RECORDS, filter_records
+
+    names = {n.strip().upper() for n in drop.split(",") if n.strip()}
+    if summary:
+        names |= DETAIL_RECORDS
+    kept = None if keep is None else {n.strip().upper() for n in keep.split(",") if n.strip()}
+    try:
+        opener = gzip.open if output.suffix == ".gz" else open
+        with opener(output, "wb") as out:
+            n, dropped = filter_records(stdf_file, out, names, kept)
+        detail = ", ".join(f"{count:,} {rec}" for rec, count in sorted(dropped.items()))
+        console.print(f"[green]✓[/green] Wrote {n:,} records to {output}"
+                      + (f" (dropped {detail})" if detail else ""))
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@records_grp.command(name="repair")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("output", type=click.Path(dir_okay=False, path_type=Path))
+def records_repair(stdf_file: Path, output: Path):
+    """
+    Copy a corrupt STDF file, skipping the bytes that broke record framing.
+
+    At an implausible record header (REC_LEN past the end of the file,
+    unknown record type) the copy resumes at the next plausible header;
+    each skipped stretch is listed.
+
+    STDF_FILE: Path to the (uncompressed) STDF file
+    OUTPUT: Repaired STDF file
+
+    Example:
+        stdf records repair damaged.stdf repaired.stdf
+    """
+    from .records import repair_stdf
+
+    try:
+        with open(output, "wb") as out:
+            n, skipped = repair_stdf(stdf_file, out)
+        for offset, nbytes in skipped:
+            console.print(f"  skipped {nbytes:,} bytes at offset 0x{offset:X}")
+        total = sum(nbytes for _, nbytes in skipped)
+        console.print(f"[green]✓[/green] Wrote {n:,} records to {output}"
+                      + (f" ({total:,} bytes skipped)" if total else ""))
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@records_grp.command(name="normalize")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.argument("output", type=click.Path(dir_okay=False, path_type=Path))
+def records_normalize(stdf_file: Path, output: Path):
+    """
+    Rewrite an STDF file in canonical record order.
+
+    Header records first (FAR, ATR, MIR, SDR, PMR, ...), then each wafer
+    (WIR, its parts with their test records, WRR), then the TSR/HBR/SBR/PCR
+    summaries and the MRR. Fixes files from merge tools that interleave
+    records; records themselves are copied byte for byte.
+
+    STDF_FILE: Path to the (uncompressed) STDF file
+    OUTPUT: Normalized STDF file
+
+    Example:
+        stdf records normalize merged.stdf merged_sorted.stdf
+    """
+    from .normalize import normalize_stdf
+
+    try:
+        with open(output, "wb") as out:
+            report = normalize_stdf(stdf_file, out)
+        detail = ", ".join(f"{count:,} {rec}" for rec, count in sorted(report.dropped.items()))
+        console.print(f"[green]✓[/green] Wrote {report.records:,} records to {output}, "
+                      f"{report.moved:,} moved" + (f" (dropped {detail})" if detail else ""))
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
+
+@records_grp.command(name="edit-mir")
+@click.argument("stdf_file", type=click.Path(exists=True, dir_okay=False, path_type=Path))
+@click.option("--set", "assignments", multiple=True, required=True, metavar="FIELD=VALUE",
+              help="MIR field to change, e.g. LOT_ID=AB1234 (repeatable)")
+def records_edit_mir(stdf_file: Path, assignments: tuple[str, ...]):
+    """
+    Correct MIR fields (lot id, sub-lot, timestamps, operator, ...) in place.
+
+    Only the MIR is re-encoded; every other record stays as it is. Field
+    names as in the STDF spec (LOT_ID, SBLOT_ID, SETUP_T, START_T,
+    OPER_NAM, ...); timestamps in epoch seconds.
+
+    STDF_FILE: Path to the (uncompressed) STDF file — modified
+
+    Example:
+        stdf records edit-mir lot001.stdf --set LOT_ID=AB1234 --set SBLOT_ID=AB1234.1
+    """
+    from .records import FIELD_LAYOUTS, edit_mir
+
+    kinds = {name: kind for name, kind, *_ in FIELD_LAYOUTS["MIR"]}
+    try:
+        changes = {}
+        for item in assignments:
+            name, sep, value = item.partition("=")
+            name = name.strip().upper()
+            if not sep:
+                raise ValueError(f"expected FIELD=VALUE, got {item!r}")
+            kind = kinds.get(name, "Cn")
+            changes[name] = int(value) if kind[0] in "UI" else value
+        in_place = edit_mir(stdf_file, changes)
+        how = "patched in place" if in_place else "MIR resized, file rewritten"
+        console.print(f"[green]✓[/green] Updated {', '.join(changes)} in {stdf_file} ({how})")
+    except Exception as e:
+        console.print(f"[red]Error:[/red] {e}")
+        sys.exit(1)
+
 
 if __name__ == "__main__":
     main()
@@ -70,6 +70,9 @@ class ServerConfig:
     host: str = "0.0.0.0"
     port: int = 8555
     max_rows: int = 10000
+    # POST /api/parse (upload an STDF file, get summary/JSONL/Parquet back)
+    enable_parse: bool = False
+    max_upload_mb: int = 512
 
 
 @dataclass
@@ -193,7 +196,7 @@ class Config:
             ) if processing_data else ProcessingConfig(),
             server=ServerConfig(
                 **{k: v for k, v in server_data.items()
-                   if k in ("host", "port", "max_rows")}
+                   if k in ("host", "port", "max_rows", "enable_parse", "max_upload_mb")}
             ),
             filters=filters,
             exclude=exclude,
@@ -0,0 +1,189 @@
+"""Differences between two parsed STDF files.
+
+compare(a, b) lines up two files of the same material — before / after a
+retest, or one datalog run through two converter versions — and reports:
+
+- header: MIR / MRR fields that differ (lot id, job, times, ...)
+- parts: part and good counts; dies whose bins changed
+- bins: hard / soft bin counts that differ
+- tests: per test number, result count / fail count / mean in each file and
+  the per-die result deltas (results paired by die and test, in test order)
+
+Dies are matched by (wafer_id, x, y) when the part has coordinates, else by
+tester part id (or PART_TXT), else by file order; a die tested more than once
+counts its last test.
+"""
+
+import math
+from dataclasses import dataclass, field
+from pathlib import Path
+
+from .parser import STDFData, parse_stdf
+
+HEADER_FIELDS = (
+    "lot_id", "part_type", "job_name", "job_rev", "start_time", "finish_time",
+    "tester_type", "operator", "test_code", "disp_code", "spec_version",
+)
+
+
+@dataclass
+class ResultDelta:
+    """One test number in both files. Means over finite results; the paired
+    figures cover dies with the test in both files."""
+
+    test_num: int
+    test_name: str = ""
+    count_a: int = 0
+    count_b: int = 0
+    fails_a: int = 0
+    fails_b: int = 0
+    mean_a: float | None = None
+    mean_b: float | None = None
+    paired: int = 0  # results matched die for die
+    changed: int = 0  # paired results differing by more than the tolerance
+    max_abs_delta: float = 0.0
+
+
+@dataclass
+class StdfDiff:
+    """What compare() found; empty dicts / lists mean no difference."""
+
+    header: dict[str, tuple] = field(default_factory=dict)  # field -> (a, b)
+    part_count: tuple[int, int] = (0, 0)
+    good_count: tuple[int, int] = (0, 0)
+    bins_hard: dict[int, tuple[int, int]] = field(default_factory=dict)  # bin -> counts
+    bins_soft: dict[int, tuple[int, int]] = field(default_factory=dict)
+    # Dies in both files whose hard or soft bin changed: {die, hard_bin: (a, b),
+    # soft_bin: (a, b), passed: (a, b)}
+    bin_changes: list[dict] = field(default_factory=list)
+    dies_only_a: list[tuple] = field(default_factory=list)
+    dies_only_b: list[tuple] = field(default_factory=list)
+    tests: list[ResultDelta] = field(default_factory=list)  # tests that differ
+    tests_only_a: list[int] = field(default_factory=list)
+    tests_only_b: list[int] = field(default_factory=list)
+
+    @property
+    def identical(self) -> bool:
+        return not (
+            self.header or self.part_count[0] != self.part_count[1]
+            or self.good_count[0] != self.good_count[1] or self.bins_hard or self.bins_soft
+            or self.bin_changes or self.dies_only_a or self.dies_only_b or self.tests
+            or self.tests_only_a or self.tests_only_b
+        )
+
+
+def _die_key(part: dict, unlabeled: int) -> tuple:
+    """(wafer_id, x, y); else ("", tester part id or PART_TXT); else ("#", n)
+    for the n-th part in the file with neither (`unlabeled` so far)."""
+    if part["x_coord"] != -32768 and part["y_coord"] != -32768:
+        return part["wafer_id"], part["x_coord"], part["y_coord"]
+    label = part["tester_part_id"] or part["part_txt"]
+    return ("", label) if label else ("#", unlabeled)
+
+
+def _dies(data: STDFData) -> tuple[dict[tuple, dict], dict[str, tuple]]:
+    """die key -> last part tested there, and part_id -> die key."""
+    dies: dict[tuple, dict] = {}
+    keys: dict[str, tuple] = {}
+    unlabeled = 0
+    for part in data.parts:
+        key = _die_key(part, unlabeled)
+        unlabeled += key[0] == "#"
+        dies[key] = part
+        keys[part["part_id"]] = key
+    return dies, keys
+
+
+def _results(data: STDFData, dies: dict, keys: dict) -> dict[int, dict[tuple, list]]:
+    """test_num -> {die key: results of the die's last part, in test order}."""
+    last = {part["part_id"] for part in dies.values()}
+    by_test: dict[int, dict[tuple, list]] = {}
+    for row in data.test_results:
+        if row["part_id"] in last:
+            die_results = by_test.setdefault(row["test_num"], {})
+            die_results.setdefault(keys[row["part_id"]], []).append(row)
+    return by_test
+
+
+def _bin_counts(bins: dict[int, dict]) -> dict[int, int]:
+    return {num: row["bin_count"] for num, row in bins.items()}
+
+
+def _count_diff(a: dict[int, int], b: dict[int, int]) -> dict[int, tuple[int, int]]:
+    return {
+        num: (a.get(num, 0), b.get(num, 0))
+        for num in sorted(a.keys() | b.keys()) if a.get(num, 0) != b.get(num, 0)
+    }
+
+
+def _mean(rows: list[dict]) -> float | None:
+    values = [r["result"] for r in rows if r["result"] is not None and math.isfinite(r["result"])]
+    return sum(values) / len(values) if values else None
+
+
+def _same(x: float | None, y: float | None, tolerance: float) -> bool:
+    """Results equal within tolerance (None / NaN only equal themselves)."""
+    if x is None or y is None:
+        return x is y
+    if math.isnan(x) or math.isnan(y):
+        return math.isnan(x) and math.isnan(y)
+    return x == y or abs(y - x) <= tolerance
+
+
+def compare(a: Path | STDFData, b: Path | STDFData, tolerance: float = 0.0) -> StdfDiff:
+    """Differences between two STDF files (paths are parsed with parse_stdf).
+    Results closer than `tolerance` count as equal."""
+    a = a if isinstance(a, STDFData) else parse_stdf(Path(a))
+    b = b if isinstance(b, STDFData) else parse_stdf(Path(b))
+    diff = StdfDiff()
+
+    for name in HEADER_FIELDS:
+        if getattr(a, name) != getattr(b, name):
+            diff.header[name] = (getattr(a, name), getattr(b, name))
+
+    dies_a, keys_a = _dies(a)
+    dies_b, keys_b = _dies(b)
+    diff.part_count = (len(a.parts), len(b.parts))
+    diff.good_count = (sum(p["passed"] for p in a.parts), sum(p["passed"] for p in b.parts))
+    diff.bins_hard = _count_diff(_bin_counts(a.bins_hard), _bin_counts(b.bins_hard))
+    diff.bins_soft = _count_diff(_bin_counts(a.bins_soft), _bin_counts(b.bins_soft))
+    diff.dies_only_a = [key for key in dies_a if key not in dies_b]
+    diff.dies_only_b = [key for key in dies_b if key not in dies_a]
+    for key, part_a in dies_a.items():
+        part_b = dies_b.get(key)
+        if part_b is None:
+            continue
+        bins = {
+            name: (part_a[name], part_b[name]) for name in ("hard_bin", "soft_bin", "passed")
+        }
+        if bins["hard_bin"][0] != bins["hard_bin"][1] or bins["soft_bin"][0] != bins["soft_bin"][1]:
+            diff.bin_changes.append({"die": key, **bins})
+
+    results_a = _results(a, dies_a, keys_a)
+    results_b = _results(b, dies_b, keys_b)
+    diff.tests_only_a = sorted(results_a.keys() - results_b.keys())
+    diff.tests_only_b = sorted(results_b.keys() - results_a.keys())
+    for test_num in sorted(results_a.keys() & results_b.keys()):
+        per_die_a, per_die_b = results_a[test_num], results_b[test_num]
+        rows_a = [r for rows in per_die_a.values() for r in rows]
+        rows_b = [r for rows in per_die_b.values() for r in rows]
+        delta = ResultDelta(
+            test_num=test_num,
+            test_name=(a.tests.get(test_num) or b.tests.get(test_num) or {}).get("test_name", ""),
+            count_a=len(rows_a), count_b=len(rows_b),
+            fails_a=sum(not r["passed"] for r in rows_a),
+            fails_b=sum(not r["passed"] for r in rows_b),
+            mean_a=_mean(rows_a), mean_b=_mean(rows_b),
+        )
+        for key, die_a in per_die_a.items():
+            for row_a, row_b in zip(die_a, per_die_b.get(key, ())):
+                delta.paired += 1
+                x, y = row_a["result"], row_b["result"]
+                if x is not None and y is not None and math.isfinite(x) and math.isfinite(y):
+                    delta.max_abs_delta = max(delta.max_abs_delta, abs(y - x))
+                delta.changed += not _same(x, y, tolerance) or row_a["passed"] != row_b["passed"]
+        if (
+            delta.changed or delta.count_a != delta.count_b or delta.fails_a != delta.fails_b
+        ):
+            diff.tests.append(delta)
+    return diff
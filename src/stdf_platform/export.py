@@ -0,0 +1,1000 @@
+"""Exporters that work directly on parsed STDF (STDFData) — no Parquet store.
+
+The Parquet/DuckDB path (storage.py, views.py) is the analysis store; these are
+the hand-off formats for other systems (MES, assembly houses, log pipelines).
+
+Every output format is an ExportSink; export_to_sink() drives any sink with the
+same call sequence, so adding a format means writing one small class.
+stream_to_sink() drives a sink while parsing, for files too big to hold.
+"""
+
+import csv
+import hashlib
+import json
+import math
+from abc import ABC, abstractmethod
+from contextlib import ExitStack
+from pathlib import Path
+from typing import BinaryIO, Callable, Iterator, TextIO
+
+from .parser import ParseOptions, STDFData, STDFParser, parse_stdf
+
+
+def _clean(value):
+    """NaN/inf are not valid JSON — emit null instead."""
+    if isinstance(value, float) and not math.isfinite(value):
+        return None
+    return value
+
+
+def lot_metadata(data: STDFData) -> dict:
+    """Lot-level (MIR/MRR) fields handed to ExportSink.begin_lot()."""
+    return {
+        "lot_id": data.lot_id,
+        "part_type": data.part_type,
+        "job_name": data.job_name,
+        "job_rev": data.job_rev,
+        "start_time": data.start_time,
+        "finish_time": data.finish_time,
+        "tester_type": data.tester_type,
+        "operator": data.operator,
+        "test_code": data.test_code,
+        "disp_code": data.disp_code,
+        "user_desc": data.user_desc,
+        "exc_desc": data.exc_desc,
+        "temperature": data.conditions.temperature_raw,
+        "temperature_c": data.conditions.temperature_c,
+        "flow_id": data.conditions.flow_id,
+        "voltage_corner": data.conditions.voltage_corner,
+    }
+
+
+# lot_metadata() fields embedded in Parquet key-value metadata (as "stdf.<field>")
+_PARQUET_LOT_KEYS = (
+    "lot_id", "part_type", "job_name", "job_rev", "start_time", "finish_time", "tester_type",
+)
+
+
+def parquet_metadata(lot: dict, source_file: Path | None = None) -> dict[str, str]:
+    """File-level Parquet key-value metadata describing the lot, so a file is
+    self-describing without a manifest: "stdf.lot_id", ..., plus the source
+    file's name and SHA-256 when `source_file` is given."""
+    meta = {f"stdf.{key}": str(lot.get(key, "")) for key in _PARQUET_LOT_KEYS}
+    if source_file is not None:
+        digest = hashlib.sha256()
+        with open(source_file, "rb") as f:
+            for chunk in iter(lambda: f.read(1024 * 1024), b""):
+                digest.update(chunk)
+        meta["stdf.source_file"] = Path(source_file).name
+        meta["stdf.source_sha256"] = digest.hexdigest()
+    return meta
+
+
+def _result_row(data: STDFData, r: dict) -> dict:
+    """A test_results row with its test definition inline."""
+    test = data.tests.get(r["test_num"], {})
+    return {
+        "part_id": r["part_id"],
+        "test_num": r["test_num"],
+        "test_name": test.get("test_name", ""),
+        "rec_type": test.get("rec_type", ""),
+        "section": r.get("section", ""),
+        "result": _clean(r.get("result")),
+        "passed": r["passed"],
+        "lo_limit": _clean(test.get("lo_limit")),
+        "hi_limit": _clean(test.get("hi_limit")),
+        "units": test.get("units", ""),
+        "pin_num": r.get("pin_num"),
+        "pin_name": r.get("pin_name"),
+    }
+
+
+def _results_by_part(data: STDFData) -> dict[str, list[dict]]:
+    """Group test results per part_id, each row carrying its test definition inline."""
+    by_part: dict[str, list[dict]] = {}
+    for r in data.test_results:
+        by_part.setdefault(r["part_id"], []).append(_result_row(data, r))
+    return by_part
+
+
+def iter_part_payloads(data: STDFData) -> Iterator[dict]:
+    """Yield one nested payload per part: part fields + its own results list.
+
+    This is the per-unit shape MES systems ingest::
+
+        {"part_id": ..., "lot_id": ..., "hard_bin": ..., ...,
+         "results": [{"test_num": ..., "test_name": ..., "result": ...}, ...]}
+
+    Parts come out in file order; each result carries its test definition
+    (name, limits, units) inline so a payload is self-contained.
+    """
+    by_part = _results_by_part(data)
+    for part in data.parts:
+        payload = {k: _clean(v) for k, v in part.items()}
+        payload["results"] = by_part.get(part["part_id"], [])
+        yield payload
+
+
+def pin_configuration(data: STDFData) -> dict:
+    """Tester pin setup from PMR/PGR: channel → names, and pin groups.
+
+    ::
+
+        {"pins": {pmr_index: {"chan_typ", "chan_nam", "phy_nam", "log_nam",
+                              "head_num", "site_num", "name"}},
+         "groups": {grp_index: {"name": ..., "pins": [pmr_index, ...],
+                                "pin_names": [name, ...]}}}
+
+    ``name`` is the resolved display name (NMR ATPG name > LOG_NAM > PHY_NAM >
+    CHAN_NAM), the same one MPR per-pin results carry as pin_name.
+    """
+    pins = {
+        index: {**pin, "name": data.pin_name(index) or ""}
+        for index, pin in sorted(data.pins.items())
+    }
+    groups = {
+        index: {
+            "name": group["grp_nam"],
+            "pins": group["pmr_indexes"],
+            "pin_names": [data.pin_name(i) or "" for i in group["pmr_indexes"]],
+        }
+        for index, group in sorted(data.pin_groups.items())
+    }
+    return {"pins": pins, "groups": groups}
+
+
+def export_pin_configuration(data: STDFData, path: Path) -> None:
+    """Write pin_configuration() as JSON (indices become string keys)."""
+    with open(path, "w", encoding="utf-8") as out:
+        json.dump(pin_configuration(data), out, ensure_ascii=False, indent=2)
+
+
+LIMIT_TABLE_COLUMNS = [
+    "test_num", "test_name", "rec_type", "test_type", "head_num", "site_num",
+    "lo_limit", "hi_limit", "units", "lo_spec", "hi_spec",
+]
+
+
+def _same_limit(a: float | None, b: float | None) -> bool:
+    if a is None or b is None:
+        return a is b
+    return a == b or (math.isnan(a) and math.isnan(b))
+
+
+def limit_table(data: STDFData) -> list[dict]:
+    """Test definitions as a limit table, one dict per test (test_num order).
+
+    ::
+
+        {"test_num", "test_name", "rec_type", "test_type",
+         "lo_limit", "hi_limit", "units", "lo_spec", "hi_spec",
+         "site_limits": [{"head_num", "site_num", "lo_limit", "hi_limit"}, ...]}
+
+    ``site_limits`` lists only the (head, site) pairs whose limits differ
+    from the test-level ones, so it is empty for programs without per-site
+    limits. Diffing two of these tables shows what a program revision changed.
+    """
+    variants: dict[int, list[dict]] = {}
+    for (test_num, head_num, site_num), lim in sorted(data.site_limits.items()):
+        test = data.tests.get(test_num, {})
+        if _same_limit(lim["lo_limit"], test.get("lo_limit")) and _same_limit(
+            lim["hi_limit"], test.get("hi_limit")
+        ):
+            continue
+        variants.setdefault(test_num, []).append({
+            "head_num": head_num,
+            "site_num": site_num,
+            "lo_limit": _clean(lim["lo_limit"]),
+            "hi_limit": _clean(lim["hi_limit"]),
+        })
+    return [
+        {
+            "test_num": test_num,
+            "test_name": test["test_name"],
+            "rec_type": test["rec_type"],
+            "test_type": test["test_type"],
+            "lo_limit": _clean(test["lo_limit"]),
+            "hi_limit": _clean(test["hi_limit"]),
+            "units": test["units"],
+            "lo_spec": _clean(test["lo_spec"]),
+            "hi_spec": _clean(test["hi_spec"]),
+            "site_limits": variants.get(test_num, []),
+        }
+        for test_num, test in sorted(data.tests.items())
+    ]
+
+
+def export_limit_table(data: STDFData, path: Path) -> int:
+    """Write limit_table() to `path`: JSON for a .json suffix, CSV otherwise.
+
+    The CSV is flat: one row per test with empty head_num/site_num, followed
+    by one row per per-site variant carrying its head/site. Returns test count.
+    """
+    table = limit_table(data)
+    if path.suffix.lower() == ".json":
+        with open(path, "w", encoding="utf-8") as out:
+            json.dump(table, out, ensure_ascii=False, indent=2)
+        return len(table)
+    with open(path, "w", newline="", encoding="utf-8") as out:
+        writer = csv.DictWriter(out, fieldnames=LIMIT_TABLE_COLUMNS, extrasaction="ignore")
+        writer.writeheader()
+        for test in table:
+            writer.writerow(test)
+            for site in test["site_limits"]:
+                writer.writerow({**test, **site})
+    return len(table)
+
+
+SCAN_FAILURE_COLUMNS = [
+    "lot_id", "wafer_id", "part_id", "head_num", "site_num",
+    "test_num", "pattern", "cycle", "pin_num", "pin_name", "chain", "bit_pos",
+    "expected", "captured", "new_data",
+]
+
+
+def export_scan_failures(data: STDFData, path: Path) -> int:
+    """Write data.scan_failures (STR fail cycles) as CSV — one row per fail,
+    in file order, empty cells for arrays the tester didn't record. This is the
+    flat (part, test, pattern, cycle, pin) list scan-diagnosis tools load.
+    Returns row count.
+    """
+    with open(path, "w", newline="", encoding="utf-8") as out:
+        writer = csv.DictWriter(out, fieldnames=SCAN_FAILURE_COLUMNS)
+        writer.writeheader()
+        writer.writerows(data.scan_failures)
+    return len(data.scan_failures)
+
+
+class ExportSink(ABC):
+    """An output format for parsed STDF data.
+
+    Call sequence (see export_to_sink): begin_lot() once, then for each part
+    write_part() immediately followed by write_results_batch() with that
+    part's results (possibly empty), write_wafer() for each wafer after its
+    last part, then finish() once. Only write_part() and
+    write_results_batch() must be implemented.
+    """
+
+    def begin_lot(self, lot: dict) -> None:
+        """Receive lot-level metadata (lot_metadata()) before any part."""
+        self.lot = lot
+
+    @abstractmethod
+    def write_part(self, part: dict) -> None:
+        """Receive one part (PRR) row."""
+
+    @abstractmethod
+    def write_results_batch(self, results: list[dict]) -> None:
+        """Receive the results of the part just written."""
+
+    def write_wafer(self, wafer: dict) -> None:
+        """Receive one wafer (WIR/WRR) row, after the wafer's parts."""
+
+    def finish(self) -> None:
+        """Flush and close outputs."""
+
+
+def export_to_sink(data: STDFData, sink: ExportSink) -> int:
+    """Drive `sink` with every part of `data` (and its results). Returns part count."""
+    sink.begin_lot(lot_metadata(data))
+    by_part = _results_by_part(data)
+    # wafers go out after their last part; wafers without parts at the end
+    last_part = {part["wafer_id"]: i for i, part in enumerate(data.parts)}
+    wafers_after: dict[int, list[dict]] = {}
+    for wafer in data.wafers:
+        i = last_part.get(wafer["wafer_id"], len(data.parts))
+        wafers_after.setdefault(i, []).append(wafer)
+    for i, part in enumerate(data.parts):
+        sink.write_part(part)
+        sink.write_results_batch(by_part.get(part["part_id"], []))
+        for wafer in wafers_after.get(i, ()):
+            sink.write_wafer(wafer)
+    for wafer in wafers_after.get(len(data.parts), ()):
+        sink.write_wafer(wafer)
+    sink.finish()
+    return len(data.parts)
+
+
+def stream_to_sink(
+    source: Path | BinaryIO, sink: ExportSink, options: ParseOptions | None = None,
+) -> int:
+    """Parse `source` (a path, or a binary stream as for parse_reader) straight
+    into `sink`, without keeping the parsed parts and results: each part goes
+    to the sink as its touchdown completes (STDFParser.stream_parts).
+
+    Same call sequence as export_to_sink, but begin_lot() gets the metadata
+    known at the first part (MRR fields are still empty) and test names that
+    only a TSR carries (end of file) come out empty. Returns part count.
+    """
+    parser = STDFParser(options)
+    count = 0
+    begun = False
+
+    def begin() -> None:
+        nonlocal begun
+        if not begun:
+            sink.begin_lot(lot_metadata(parser.data))
+            begun = True
+
+    def emit(part: dict, results: list[dict]) -> None:
+        nonlocal count
+        begin()
+        sink.write_part(part)
+        sink.write_results_batch([_result_row(parser.data, r) for r in results])
+        count += 1
+
+    def emit_wafer(wafer: dict) -> None:
+        begin()
+        sink.write_wafer(wafer)
+
+    parser.stream_parts(emit).stream_wafers(emit_wafer)
+    if hasattr(source, "read"):
+        parser.parse_reader(source)
+    else:
+        parser.parse(Path(source))
+    begin()
+    sink.finish()
+    return count
+
+
+class _PayloadSink(ExportSink):
+    """Base for sinks emitting one nested iter_part_payloads()-shaped dict per part."""
+
+    _pending: dict | None = None
+
+    def write_part(self, part: dict) -> None:
+        self._pending = {k: _clean(v) for k, v in part.items()}
+
+    def write_results_batch(self, results: list[dict]) -> None:
+        payload = self._pending
+        payload["results"] = results
+        self._pending = None
+        self.emit(payload)
+
+    @abstractmethod
+    def emit(self, payload: dict) -> None:
+        """Deliver one complete part payload."""
+
+
+class JsonLinesSink(_PayloadSink):
+    """Part-centric JSON Lines: one iter_part_payloads()-shaped object per line."""
+
+    def __init__(self, out: TextIO):
+        self.out = out
+
+    def emit(self, payload: dict) -> None:
+        self.out.write(json.dumps(payload, ensure_ascii=False))
+        self.out.write("\n")
+
+
+class NdjsonSink(ExportSink):
+    """Flat NDJSON: one line per part, then one per result of that part, each
+    tagged "type": "part" / "result" (results join to parts by part_id).
+    Flushed per part, so a log shipper tailing the output sees parts as
+    they complete under stream_to_sink."""
+
+    def __init__(self, out: TextIO):
+        self.out = out
+
+    def write_part(self, part: dict) -> None:
+        self._write_line({"type": "part", **{k: _clean(v) for k, v in part.items()}})
+
+    def write_results_batch(self, results: list[dict]) -> None:
+        for result in results:
+            self._write_line({"type": "result", **result})
+        self.out.flush()
+
+    def _write_line(self, row: dict) -> None:
+        self.out.write(json.dumps(row, ensure_ascii=False))
+        self.out.write("\n")
+
+
+def _json_bytes(payload: dict) -> bytes:
+    return json.dumps(payload, ensure_ascii=False).encode("utf-8")
+
+
+class KafkaSink(_PayloadSink):
+    """Publish one part payload per message to a Kafka topic (key = part_id).
+
+    Values are JSON by default; pass `serializer` (dict -> bytes) for Avro or
+    another schema'd encoding. Needs confluent-kafka (``stdf2pq[kafka]``)
+    unless a ready `producer` (anything with produce/poll/flush) is given.
+    """
+
+    def __init__(
+        self,
+        topic: str,
+        bootstrap_servers: str | None = None,
+        producer=None,
+        serializer: Callable[[dict], bytes] = _json_bytes,
+    ):
+        if producer is None:
+            from confluent_kafka import Producer
+
+            producer = Producer({"bootstrap.servers": bootstrap_servers})
+        self.topic = topic
+        self.producer = producer
+        self.serializer = serializer
+
+    def emit(self, payload: dict) -> None:
+        self.producer.produce(
+            self.topic,
+            key=str(payload["part_id"]).encode("utf-8"),
+            value=self.serializer(payload),
+        )
+        self.producer.poll(0)  # serve delivery callbacks, keep the queue drained
+
+    def finish(self) -> None:
+        self.producer.flush()
+
+
+class MemorySink(ExportSink):
+    """Keeps what it is given in lists: lot, parts, results (rows as every
+    sink gets them, test names and units joined in) and wafers. For tests
+    and for custom processing without writing a sink class."""
+
+    def __init__(self):
+        self.lot: dict = {}
+        self.parts: list[dict] = []
+        self.results: list[dict] = []
+        self.wafers: list[dict] = []
+
+    def write_part(self, part: dict) -> None:
+        self.parts.append(part)
+
+    def write_results_batch(self, results: list[dict]) -> None:
+        self.results.extend(results)
+
+    def write_wafer(self, wafer: dict) -> None:
+        self.wafers.append(wafer)
+
+
+class CsvSink(ExportSink):
+    """Flat parts.csv + results.csv in `out_dir` (results joined to parts by
+    part_id), and wafers.csv when the lot has wafers.
+
+    Columns are the declared ones (_PART_ARROW_TYPES / _RESULT_ARROW_TYPES),
+    parts.csv also gets any other columns of the first part (merge
+    provenance, ...); keys beyond those are left out. Both files get their
+    header even when the lot has no parts.
+    """
+
+    def __init__(self, out_dir: Path):
+        out_dir.mkdir(parents=True, exist_ok=True)
+        self.out_dir = out_dir
+        self._wafers: list[dict] = []
+        with ExitStack() as stack:
+            self._part_file, self._result_file = (
+                stack.enter_context(open(out_dir / name, "w", newline="", encoding="utf-8"))
+                for name in ("parts.csv", "results.csv")
+            )
+            self._files = stack.pop_all()
+        self._parts: csv.DictWriter | None = None
+        self._results = csv.DictWriter(
+            self._result_file, fieldnames=[col for col, _ in _RESULT_ARROW_TYPES],
+            extrasaction="ignore",
+        )
+        self._results.writeheader()
+
+    def _part_writer(self, part: dict) -> csv.DictWriter:
+        if self._parts is None:
+            columns = dict.fromkeys(col for col, _ in _PART_ARROW_TYPES)
+            columns.update(dict.fromkeys(part))
+            self._parts = csv.DictWriter(
+                self._part_file, fieldnames=list(columns), extrasaction="ignore",
+            )
+            self._parts.writeheader()
+        return self._parts
+
+    def write_part(self, part: dict) -> None:
+        self._part_writer(part).writerow(part)
+
+    def write_results_batch(self, results: list[dict]) -> None:
+        self._results.writerows(results)
+
+    def write_wafer(self, wafer: dict) -> None:
+        self._wafers.append(wafer)
+
+    def finish(self) -> None:
+        with self._files:
+            self._part_writer({})
+        if self._wafers:
+            columns = _columns(self._wafers)
+            with open(self.out_dir / "wafers.csv", "w", newline="", encoding="utf-8") as f:
+                writer = csv.writer(f)
+                writer.writerow(columns)
+                writer.writerows(zip(*columns.values()))
+
+
+# _result_row() columns as Arrow types (a result batch may be all-null in a
+# column, e.g. pin_num before the first MPR, so its type can't be inferred)
+_RESULT_ARROW_TYPES = (
+    ("part_id", "string"), ("test_num", "int64"), ("test_name", "string"),
+    ("rec_type", "string"), ("section", "string"), ("result", "float64"),
+    ("passed", "bool"), ("lo_limit", "float64"), ("hi_limit", "float64"),
+    ("units", "string"), ("pin_num", "int64"), ("pin_name", "string"),
+)
+# data.parts columns as Arrow types, for sinks that declare them up front (a
+# type taken from the first value breaks on a later int / float mix)
+_PART_ARROW_TYPES = (
+    ("part_id", "string"), ("seq_part_id", "string"), ("tester_part_id", "string"),
+    ("part_txt", "string"), ("part_fix", "string"), ("lot_id", "string"),
+    ("wafer_id", "string"), ("head_num", "int64"), ("site_num", "int64"),
+    ("x_coord", "int64"), ("y_coord", "int64"), ("hard_bin", "int64"),
+    ("soft_bin", "int64"), ("passed", "bool"), ("retested", "bool"), ("aborted", "bool"),
+    ("pass_fail_valid", "bool"), ("in_retest_bin", "bool"), ("test_count", "int64"),
+    ("test_time", "int64"), ("result_count", "int64"), ("fail_count", "int64"),
+    ("num_test_delta", "int64"),
+)
+
+
+class ParquetSink(ExportSink):
+    """Flat parts.parquet + results.parquet in `out_dir` (pyarrow).
+
+    Rows are written as a row group whenever `row_group_rows` results are
+    buffered (and at finish), so under stream_to_sink memory stays bounded
+    by one row group whatever the file size. Wafers go to wafers.parquet at
+    finish (when the lot has any). The files carry parquet_metadata() of the
+    lot (with the hash of `source_file` when given).
+    """
+
+    def __init__(
+        self, out_dir: Path, compression: str = "zstd", source_file: Path | None = None,
+        row_group_rows: int = 1_000_000,
+    ):
+        self.out_dir = out_dir
+        self.compression = compression
+        self.source_file = source_file
+        self.row_group_rows = row_group_rows
+        self.lot: dict = {}
+        self._parts: list[dict] = []
+        self._results: list[dict] = []
+        self._wafers: list[dict] = []
+        self._writers: dict = {}  # table name -> open pq.ParquetWriter
+        self._metadata: dict[str, str] | None = None  # parquet_metadata(), hashed once
+
+    def write_part(self, part: dict) -> None:
+        self._parts.append(part)
+
+    def write_results_batch(self, results: list[dict]) -> None:
+        self._results.extend(results)
+        if len(self._results) >= self.row_group_rows:
+            self._flush()
+
+    def write_wafer(self, wafer: dict) -> None:
+        self._wafers.append(wafer)
+
+    def _flush(self) -> None:
+        for name, rows in (("parts", self._parts), ("results", self._results)):
+            if rows:
+                self._write(name, rows)
+                rows.clear()
+
+    def _write(self, name: str, rows: list[dict]) -> None:
+        import pyarrow as pa
+        import pyarrow.parquet as pq
+
+        writer = self._writers.get(name)
+        if writer is not None:
+            writer.write_table(pa.Table.from_pylist(rows, schema=writer.schema_arrow))
+            return
+        if name == "results":
+            schema = pa.schema([(col, pa.type_for_alias(t)) for col, t in _RESULT_ARROW_TYPES])
+        else:
+            # columns all-None so far (wafer_id of a final-test file) are strings
+            schema = pa.schema([
+                (f.name, pa.string() if pa.types.is_null(f.type) else f.type)
+                for f in pa.Table.from_pylist(rows).schema
+            ])
+        if self._metadata is None:
+            self._metadata = parquet_metadata(self.lot, self.source_file)
+        schema = schema.with_metadata(self._metadata)
+        self.out_dir.mkdir(parents=True, exist_ok=True)
+        writer = self._writers[name] = pq.ParquetWriter(
+            self.out_dir / f"{name}.parquet", schema, compression=self.compression,
+        )
+        writer.write_table(pa.Table.from_pylist(rows, schema=schema))
+
+    def finish(self) -> None:
+        self._flush()
+        for name in ("parts", "results"):
+            if name not in self._writers:  # no rows at all: still write the file
+                self._write(name, [])
+        for writer in self._writers.values():
+            writer.close()
+        if self._wafers:
+            import pyarrow as pa
+            import pyarrow.parquet as pq
+
+            if self._metadata is None:
+                self._metadata = parquet_metadata(self.lot, self.source_file)
+            pq.write_table(
+                pa.table(_columns(self._wafers), metadata=self._metadata),
+                self.out_dir / "wafers.parquet", compression=self.compression,
+            )
+
+
+class AvroSink(ExportSink):
+    """Flat parts.avro + results.avro container files in `out_dir`, schema
+    embedded (inferred from the rows, every field nullable; see avro.py),
+    written at finish. codec: "deflate" or "null"."""
+
+    def __init__(self, out_dir: Path, codec: str = "deflate"):
+        self.out_dir = out_dir
+        self.codec = codec
+        self._parts: list[dict] = []
+        self._results: list[dict] = []
+
+    def write_part(self, part: dict) -> None:
+        self._parts.append(part)
+
+    def write_results_batch(self, results: list[dict]) -> None:
+        self._results.extend(results)
+
+    def finish(self) -> None:
+        from .avro import write_avro
+
+        self.out_dir.mkdir(parents=True, exist_ok=True)
+        for name, record in (("parts", "Part"), ("results", "TestResult")):
+            write_avro(
+                self.out_dir / f"{name}.avro", getattr(self, f"_{name}"), record, self.codec,
+            )
+
+
+# Declared Arrow type -> DuckDB column type
+_DUCKDB_ARROW_TYPES = {
+    "string": "VARCHAR", "int64": "BIGINT", "float64": "DOUBLE", "bool": "BOOLEAN",
+}
+
+
+class DuckDBSink(ExportSink):
+    """parts + results tables in a DuckDB database file, created on first use
+    and appended to after that: run it over several STDF files (results join
+    to parts by part_id, which carries the lot). Inserted through pyarrow in
+    batches of `batch_rows` results, so memory stays bounded under
+    stream_to_sink. A ready `connection` can be passed instead of a path.
+    Tables get the declared column types (_PART_ARROW_TYPES /
+    _RESULT_ARROW_TYPES); other columns are added as VARCHAR when they first
+    appear.
+    """
+
+    def __init__(self, db_path: Path | None = None, batch_rows: int = 100_000, connection=None):
+        if connection is None:
+            import duckdb
+
+            Path(db_path).parent.mkdir(parents=True, exist_ok=True)
+            connection = duckdb.connect(str(db_path))
+            self._owns_connection = True
+        else:
+            self._owns_connection = False
+        self.conn = connection
+        self.batch_rows = batch_rows
+        self._parts: list[dict] = []
+        self._results: list[dict] = []
+        self._columns: dict[str, set[str]] = {}  # table -> its columns, once created
+
+    def write_part(self, part: dict) -> None:
+        self._parts.append(part)
+
+    def write_results_batch(self, results: list[dict]) -> None:
+        self._results.extend(results)
+        if len(self._results) >= self.batch_rows:
+            self._flush()
+
+    def _flush(self) -> None:
+        for table, rows in (("parts", self._parts), ("results", self._results)):
+            if rows:
+                self._insert(table, rows)
+                rows.clear()
+
+    def _insert(self, table: str, rows: list[dict]) -> None:
+        import pyarrow as pa
+
+        batch = pa.table(_columns(rows))
+        columns = self._columns.get(table)
+        if columns is None:
+            columns = self._columns[table] = self._table_columns(table)
+        for name in batch.column_names:
+            if name not in columns:  # not declared (merge provenance, ...): text
+                self.conn.execute(f"ALTER TABLE {table} ADD COLUMN {name} VARCHAR")
+                columns.add(name)
+        self.conn.register("_stdf_batch", batch)
+        try:
+            self.conn.execute(f"INSERT INTO {table} BY NAME SELECT * FROM _stdf_batch")
+        finally:
+            self.conn.unregister("_stdf_batch")
+
+    def _table_columns(self, table: str) -> set[str]:
+        """Columns of `table`, created with the declared column types if it
+        doesn't exist yet (a first batch can be all-None in a column)."""
+        declared = _PART_ARROW_TYPES if table == "parts" else _RESULT_ARROW_TYPES
+        columns = ", ".join(f"{col} {_DUCKDB_ARROW_TYPES[t]}" for col, t in declared)
+        self.conn.execute(f"CREATE TABLE IF NOT EXISTS {table} ({columns})")
+        return {d[0] for d in self.conn.execute(f"SELECT * FROM {table} LIMIT 0").description}
+
+    def finish(self) -> None:
+        self._flush()
+        if self._owns_connection:
+            self.conn.close()
+
+
+class DeltaSink(ExportSink):
+    """parts + results Delta Lake tables (``<table_uri>/parts``,
+    ``<table_uri>/results``), appended to and partitioned by lot_id — results
+    get the lot_id column for that. Columns new to a table (a newer parser,
+    another tester's fields) are merged into its schema instead of failing
+    the append. Batches of `batch_rows` results, one Delta commit each.
+
+    Needs deltalake (``stdf2pq[delta]``) and pyarrow. `storage_options` go to
+    delta-rs as is (S3 / ADLS / GCS credentials and endpoints).
+    """
+
+    def __init__(
+        self, table_uri: str | Path, batch_rows: int = 1_000_000,
+        storage_options: dict[str, str] | None = None,
+    ):
+        from deltalake import write_deltalake
+
+        self._write_deltalake = write_deltalake
+        self.table_uri = str(table_uri).rstrip("/")
+        self.batch_rows = batch_rows
+        self.storage_options = storage_options
+        self.lot: dict = {}
+        self._parts: list[dict] = []
+        self._results: list[dict] = []
+
+    def write_part(self, part: dict) -> None:
+        self._parts.append(part)
+
+    def write_results_batch(self, results: list[dict]) -> None:
+        lot_id = self.lot.get("lot_id", "")
+        self._results.extend({"lot_id": lot_id, **r} for r in results)
+        if len(self._results) >= self.batch_rows:
+            self._flush()
+
+    def _flush(self) -> None:
+        import pyarrow as pa
+
+        for name, rows in (("parts", self._parts), ("results", self._results)):
+            if not rows:
+                continue
+            if name == "results":
+                schema = pa.schema(
+                    [("lot_id", pa.string())]
+                    + [(col, pa.type_for_alias(t)) for col, t in _RESULT_ARROW_TYPES]
+                )
+                table = pa.Table.from_pylist(rows, schema=schema)
+            else:
+                table = pa.table(_columns(rows))
+                # all-None columns (wafer_id of a final-test file) as strings
+                table = table.cast(pa.schema([
+                    (f.name, pa.string() if pa.types.is_null(f.type) else f.type)
+                    for f in table.schema
+                ]))
+            self._write_deltalake(
+                f"{self.table_uri}/{name}", table, mode="append", partition_by=["lot_id"],
+                schema_mode="merge", storage_options=self.storage_options,
+            )
+            rows.clear()
+
+    def finish(self) -> None:
+        self._flush()
+
+
+# Python value type -> PostgreSQL column type (bool before int: bool is an int)
+_PG_ARROW_TYPES = {"string": "text", "int64": "bigint", "float64": "double precision", "bool": "boolean"}
+# COPY text format: backslash, tab, newline and CR are escaped
+_PG_ESCAPES = str.maketrans({"\\": "\\\\", "\t": "\\t", "\n": "\\n", "\r": "\\r"})
+
+
+def _pg_text(value) -> str:
+    """One COPY text-format field: \\N for None and NaN/inf (no result, no
+    limit), t/f for booleans, strings escaped."""
+    value = _clean(value)
+    if value is None:
+        return "\\N"
+    if isinstance(value, bool):
+        return "t" if value else "f"
+    if isinstance(value, float):
+        return repr(value)
+    return str(value).translate(_PG_ESCAPES)
+
+
+class PostgresSink(ExportSink):
+    """parts + results for PostgreSQL bulk load, in COPY text format.
+
+    To files (default): ``parts.copy`` and ``results.copy`` in `out_dir`,
+    streamed as parts arrive, plus ``load.sql`` (CREATE TABLE IF NOT EXISTS
+    and psql ``\\copy``) written at finish — ``psql -f out/load.sql``. Or,
+    with a psycopg 3 `connection` (``stdf2pq[postgres]``), straight into the
+    database with COPY FROM STDIN, creating the tables on first use, in
+    batches of `batch_rows` results; committing is left to the caller.
+
+    NULL (\\N) stands for missing values, NaN/inf results and absent limits.
+    Column types are declared (_PART_ARROW_TYPES / _RESULT_ARROW_TYPES); other
+    columns of the first part (merge provenance, ...) are text.
+    """
+
+    def __init__(
+        self, out_dir: Path | None = None, connection=None, batch_rows: int = 100_000,
+    ):
+        if (out_dir is None) == (connection is None):
+            raise ValueError("PostgresSink needs exactly one of out_dir or connection")
+        self.out_dir = out_dir
+        self.conn = connection
+        self.batch_rows = batch_rows
+        self._types: dict[str, dict[str, str]] = {
+            "parts": {c: _PG_ARROW_TYPES[t] for c, t in _PART_ARROW_TYPES},
+            "results": {c: _PG_ARROW_TYPES[t] for c, t in _RESULT_ARROW_TYPES},
+        }
+        self._started: set[str] = set()  # tables with rows written (columns fixed)
+        self._created: set[str] = set()
+        self._rows: dict[str, list[dict]] = {"parts": [], "results": []}
+        self._files = {}
+        if out_dir is not None:
+            out_dir.mkdir(parents=True, exist_ok=True)
+            self._files = {
+                name: open(out_dir / f"{name}.copy", "w", encoding="utf-8", newline="")
+                for name in ("parts", "results")
+            }
+
+    def write_part(self, part: dict) -> None:
+        self._add("parts", [part])
+
+    def write_results_batch(self, results: list[dict]) -> None:
+        self._add("results", results)
+        if self.conn is not None and len(self._rows["results"]) >= self.batch_rows:
+            self._flush()
+
+    def _add(self, name: str, rows: list[dict]) -> None:
+        if not rows:
+            return
+        if name not in self._started:
+            self._started.add(name)
+            for key in rows[0]:
+                self._types[name].setdefault(key, "text")
+        if name in self._files:
+            self._files[name].writelines(self._lines(name, rows))
+        else:
+            self._rows[name].extend(rows)
+
+    def _lines(self, name: str, rows: list[dict]) -> Iterator[str]:
+        columns = list(self._types[name])
+        for row in rows:
+            yield "\t".join(_pg_text(row.get(c)) for c in columns) + "\n"
+
+    def _create_sql(self, name: str) -> str:
+        columns = ",\n".join(
+            f"    {col} {kind}" for col, kind in self._types[name].items()
+        )
+        return f"CREATE TABLE IF NOT EXISTS {name} (\n{columns}\n);\n"
+
+    def _flush(self) -> None:
+        with self.conn.cursor() as cur:
+            for name, rows in self._rows.items():
+                if not rows:
+                    continue
+                if name not in self._created:
+                    cur.execute(self._create_sql(name))
+                    self._created.add(name)
+                columns = list(self._types[name])
+                with cur.copy(f"COPY {name} ({', '.join(columns)}) FROM STDIN") as copy:
+                    for line in self._lines(name, rows):
+                        copy.write(line)
+                rows.clear()
+
+    def finish(self) -> None:
+        if self.conn is not None:
+            self._flush()
+            return
+        for f in self._files.values():
+            f.close()
+        with open(self.out_dir / "load.sql", "w", encoding="utf-8") as out:
+            for name in ("parts", "results"):
+                columns = ", ".join(self._types[name])
+                # psql resolves \copy paths against its own working directory
+                source = str((self.out_dir / f"{name}.copy").resolve()).replace("'", "''")
+                out.write(self._create_sql(name))
+                out.write(f"\\copy {name} ({columns}) FROM '{source}'\n\n")
+
+
+def _columns(rows: list[dict]) -> dict[str, list]:
+    """Rows -> {column: values}; columns are the union of the rows' keys (WIR-only
+    wafers lack the WRR fields, ...), missing cells None."""
+    names = list(dict.fromkeys(key for row in rows for key in row))
+    return {name: [row.get(name) for row in rows] for name in names}
+
+
+def bin_rows(data: STDFData) -> list[dict]:
+    """HBR/SBR counts as flat rows: per-site counts, then the totals as head 255
+    / site 255 (the STDF summary convention). bin_type is "H" or "S"."""
+    rows = []
+    lot_id = data.lot_id
+    for bin_type, sites, totals in (
+        ("H", data.site_bins_hard, data.bins_hard),
+        ("S", data.site_bins_soft, data.bins_soft),
+    ):
+        rows.extend({"lot_id": lot_id, "bin_type": bin_type, **row} for row in sites.values())
+        rows.extend(
+            {"lot_id": lot_id, "bin_type": bin_type, "head_num": 255, "site_num": 255, **row}
+            for row in totals.values()
+        )
+    return rows
+
+
+def parse_to_parquet(
+    stdf_file: Path,
+    out_dir: Path,
+    options: ParseOptions | None = None,
+    compression: str = "zstd",
+    partition_by: list[str] | None = None,
+) -> dict[str, int]:
+    """Parse `stdf_file` into parts / test_results / wafers / bins .parquet in
+    `out_dir`, one file per table as parsed (no nesting, no test definitions
+    joined in — unlike ParquetSink). Returns rows written per table.
+
+    partition_by (e.g. ["lot_id", "wafer_id"]) writes each table as a
+    Hive-partitioned directory instead — ``parts/lot_id=X/wafer_id=Y/<stem>-0.parquet``
+    — the layout Athena/Trino/Spark catalogs read. A table is partitioned by
+    those of the columns it has (bins carry no wafer_id); the partition
+    columns live in the paths, not in the files. Files are named after
+    `stdf_file`, so other files of the same lot / wafer (retests) land beside
+    its files instead of replacing them. Every file carries
+    parquet_metadata() for the lot and `stdf_file`.
+    """
+    import pyarrow as pa
+    import pyarrow.parquet as pq
+
+    data = parse_stdf(Path(stdf_file), options)
+    tables = {
+        "parts": data.parts,
+        "test_results": data.test_results,
+        "wafers": data.wafers,
+        "bins": bin_rows(data),
+    }
+    out_dir = Path(out_dir)
+    out_dir.mkdir(parents=True, exist_ok=True)
+    meta = parquet_metadata(lot_metadata(data), Path(stdf_file))
+    for name, rows in tables.items():
+        table = pa.table(_columns(rows), metadata=meta)
+        if partition_by is None:
+            pq.write_table(table, out_dir / f"{name}.parquet", compression=compression)
+        elif rows:
+            _write_partitioned(
+                table, out_dir / name, partition_by, compression, Path(stdf_file).stem,
+            )
+    return {name: len(rows) for name, rows in tables.items()}
+
+
+def _write_partitioned(
+    table, base_dir: Path, partition_by: list[str], compression: str, stem: str,
+) -> None:
+    """Write `table` under base_dir as a Hive-partitioned dataset (col=value
+    dirs), as files "<stem>-<n>.parquet": rewriting the same source replaces
+    its files, another source's stay."""
+    import pyarrow.dataset as ds
+
+    stem = stem.replace("{", "_").replace("}", "_")  # braces are template syntax
+
+    columns = [c for c in partition_by if c in table.column_names]
+    ds.write_dataset(
+        table,
+        base_dir,
+        format="parquet",
+        partitioning=columns or None,
+        partitioning_flavor="hive" if columns else None,
+        basename_template=stem + "-{i}.parquet",
+        existing_data_behavior="overwrite_or_ignore",
+        file_options=ds.ParquetFileFormat().make_write_options(compression=compression),
+    )
+
+
+def write_part_payloads(data: STDFData, out: TextIO) -> int:
+    """Write iter_part_payloads() as JSON Lines (one part per line). Returns part count."""
+    return export_to_sink(data, JsonLinesSink(out))
+
+
+def export_part_payloads(data: STDFData, path: Path) -> int:
+    """write_part_payloads() to a file path. Returns part count."""
+    with open(path, "w", encoding="utf-8") as out:
+        return write_part_payloads(data, out)
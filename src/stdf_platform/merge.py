@@ -0,0 +1,339 @@
+"""Merge STDF files split from one lot (one file per site controller, or the
+original run plus its retests) into one logical lot.
+
+merge() parses the files and stitches the results into a single STDFData:
+part ids renumbered so they stay unique, bin / part counts and test
+synopses summed across files. merge_stdf() writes the same merge as one
+binary STDF file: the first file's header, every file's part and wafer
+records in turn, then summary records (PCR / HBR / SBR / TSR / MRR)
+rebuilt from the merged counts.
+"""
+
+import struct
+from dataclasses import fields as dataclass_fields, replace
+from pathlib import Path
+from typing import BinaryIO
+
+from .parser import (
+    PART_ROW_TABLES, REC_NAMES, U4_MISSING, ParseOptions, ParseStats, STDFData, parse_stdf,
+    sniff_byte_order, unique_part_id,
+)
+from .records import REC_KEYS, decode_fields, encode_fields, iter_records
+
+
+def _by_start_time(paths: list[Path], datas: list[STDFData]) -> list[tuple[Path, STDFData]]:
+    """Files in test order (MIR START_T; given order on ties), so retests
+    come after the run they retest."""
+    return sorted(zip(paths, datas), key=lambda pd: pd[1].start_time)
+
+
+def merge(paths: list[Path], options: ParseOptions | None = None) -> STDFData:
+    """Parse `paths` (files of one lot) and merge them, in test order."""
+    paths = [Path(p) for p in paths]
+    pairs = _by_start_time(paths, [parse_stdf(p, options) for p in paths])
+    return merge_data([d for _, d in pairs], [p.name for p, _ in pairs])
+
+
+def _add(a: int | None, b: int | None) -> int | None:
+    """Sum of two counts where None is "not reported" (and stays so)."""
+    return None if a is None or b is None else a + b
+
+
+def _merge_synopsis(row: dict, other: dict) -> None:
+    """Fold TSR row `other` (same head / site / test) into `row`."""
+    execs = (row["exec_count"], other["exec_count"])
+    if row["test_time"] is not None and other["test_time"] is not None and None not in execs \
+            and sum(execs):
+        row["test_time"] = (row["test_time"] * execs[0] + other["test_time"] * execs[1]) / sum(execs)
+    else:
+        row["test_time"] = None
+    for key in ("exec_count", "fail_count", "alarm_count", "test_sum", "test_sum_sq"):
+        row[key] = _add(row[key], other[key])
+    for key, pick in (("test_min", min), ("test_max", max)):
+        values = [v for v in (row[key], other[key]) if v is not None]
+        row[key] = pick(values) if values else None
+    for key in ("test_type", "test_name", "seq_name", "test_label"):
+        row[key] = row[key] or other[key]
+
+
+def _merged_part_ids(lot_id: str, datas: list[STDFData]) -> list[list[tuple[str, str]]]:
+    """(part_id, seq_part_id) per part of each file, as parsing the files
+    as one would give them: "<lot>_<wafer>_<n>" with n counting on across
+    files ("-2", ... if taken), unless the tester PART_ID is free."""
+    used: set[str] = set()
+    counter = 0
+    out = []
+    for data in datas:
+        ids = []
+        for part in data.parts:
+            counter += 1
+            seq = unique_part_id(f"{lot_id}_{part['wafer_id']}_{counter}", used)
+            new = seq
+            if part["tester_part_id"]:
+                candidate = f"{lot_id}_{part['wafer_id']}_{part['tester_part_id']}"
+                if candidate not in used:
+                    new = candidate
+            used.add(new)
+            ids.append((new, seq))
+        out.append(ids)
+    return out
+
+
+def merge_data(datas: list[STDFData], names: list[str] | None = None) -> STDFData:
+    """One STDFData for a lot parsed as several files (in test order).
+
+    Lot metadata is the first file's, with the earliest start and latest
+    finish time. Part ids are given as the parser gives them within one file
+    (see _merged_part_ids), numbered on across files: a tester PART_ID
+    already taken by an earlier file falls back to the sequential id. Rows
+    referring to a part follow it.
+    Bin, part (PCR) and wafer bin counts and test synopses are summed per
+    head / site / bin or test. Test definitions, limits and pins are the
+    first file's, extended by later ones. Wafer rows are kept per file.
+    Raises ValueError when the files are from different lots.
+    """
+    if not datas:
+        raise ValueError("nothing to merge")
+    names = names or [f"file {i + 1}" for i in range(len(datas))]
+    lots = {d.lot_id for d in datas}
+    if len(lots) > 1:
+        raise ValueError(f"files are from different lots: {', '.join(sorted(lots))}")
+
+    first = datas[0]
+    merged = STDFData(
+        lot_id=first.lot_id, part_type=first.part_type, job_name=first.job_name,
+        job_rev=first.job_rev, tester_type=first.tester_type, operator=first.operator,
+        test_code=first.test_code, spec_version=first.spec_version,
+        conditions=first.conditions, wafer_config=first.wafer_config,
+    )
+    starts = [d.start_time for d in datas if d.start_time]
+    merged.start_time = min(starts, default=0)
+    merged.finish_time = max(d.finish_time for d in datas)
+    last = datas[-1]
+    merged.disp_code, merged.user_desc, merged.exc_desc = last.disp_code, last.user_desc, last.exc_desc
+    merged.is_complete = all(d.is_complete for d in datas)
+    if any(d.retest_bins is not None for d in datas):
+        listed = [d.retest_bins for d in datas if d.retest_bins is not None]
+        merged.retest_bins = [] if [] in listed else sorted({b for bins in listed for b in bins})
+
+    part_ids = _merged_part_ids(merged.lot_id, datas)
+    synopses: dict[tuple[int, int, int], dict] = {}
+    wafer_bins: dict[tuple[str, str, int], dict] = {}
+    for data, name, ids in zip(datas, names, part_ids):
+        renamed: dict[str, str] = {}
+        for part, (new, seq) in zip(data.parts, ids):
+            renamed.setdefault(part["part_id"], new)
+            merged.parts.append({**part, "part_id": new, "seq_part_id": seq})
+        for table in PART_ROW_TABLES + ("chip_ids",):
+            getattr(merged, table).extend(
+                {**row, "part_id": renamed.get(row["part_id"], row["part_id"])}
+                for row in getattr(data, table)
+            )
+
+        merged.wafers.extend(dict(w) for w in data.wafers)
+        merged.audit_trail.extend(data.audit_trail)
+        merged.version_updates.extend(
+            u for u in data.version_updates if u not in merged.version_updates
+        )
+        for table in ("tests", "site_limits", "pin_map", "atpg_names", "pins", "pin_groups",
+                      "pin_lists", "pattern_sequences", "cell_names", "scan_chains"):
+            target = getattr(merged, table)
+            for key, value in getattr(data, table).items():
+                target.setdefault(key, value)
+        merged.site_descriptions.extend(
+            s for s in data.site_descriptions if s not in merged.site_descriptions
+        )
+        merged.scan_structures.extend(
+            s for s in data.scan_structures if s not in merged.scan_structures
+        )
+        for key, rows in data.user_records.items():
+            merged.user_records.setdefault(key, []).extend(rows)
+        merged.unknown_records.extend(data.unknown_records)
+
+        for totals, src in ((merged.bins_hard, data.bins_hard), (merged.bins_soft, data.bins_soft),
+                            (merged.site_bins_hard, data.site_bins_hard),
+                            (merged.site_bins_soft, data.site_bins_soft)):
+            for key, row in src.items():
+                if key in totals:
+                    totals[key]["bin_count"] += row["bin_count"]
+                    totals[key]["bin_name"] = totals[key]["bin_name"] or row["bin_name"]
+                else:
+                    totals[key] = dict(row)
+        for key, row in data.part_counts.items():
+            if key in merged.part_counts:
+                mine = merged.part_counts[key]
+                for field in row:
+                    if field.endswith("_count"):
+                        mine[field] = _add(mine[field], row[field])
+            else:
+                merged.part_counts[key] = dict(row)
+        for row in data.wafer_bins:
+            key = (row["wafer_id"], row["bin_type"], row["bin_num"])
+            if key in wafer_bins:
+                wafer_bins[key]["part_count"] += row["part_count"]
+            else:
+                wafer_bins[key] = dict(row)
+        for row in data.test_synopses:
+            key = (row["head_num"], row["site_num"], row["test_num"])
+            if key in synopses:
+                _merge_synopsis(synopses[key], row)
+            else:
+                synopses[key] = dict(row)
+
+        merged.warnings.extend(f"{name}: {w}" for w in data.warnings)
+        # offsets stay those of the file named in the message
+        merged.diagnostics.extend(
+            replace(d, message=f"{name}: {d.message}") for d in data.diagnostics
+        )
+        for f in dataclass_fields(ParseStats):
+            mine, theirs = getattr(merged.stats, f.name), getattr(data.stats, f.name)
+            if isinstance(mine, dict):
+                for key, n in theirs.items():
+                    mine[key] = mine.get(key, 0) + n
+            else:
+                setattr(merged.stats, f.name, mine + theirs)
+
+    merged.test_synopses = list(synopses.values())
+    merged.wafer_bins = list(wafer_bins.values())
+    merged.stats.table_bytes = merged.table_sizes()
+    return merged
+
+
+# Records taken from the first file only (lot-level header)
+_FIRST_ONLY = {"FAR", "MIR", "VUR", "RDR", "WCR"}
+# Other header records: each distinct one kept, ahead of the first part
+_HEADER = {"ATR", "SDR", "PMR", "PGR", "PLR", "NMR", "PSR", "CNR", "SSR", "CDR"}
+# Summary records rebuilt from the merged counts
+_SUMMARY = {"PCR", "HBR", "SBR", "TSR", "MRR"}
+
+
+def _summary_records(merged: STDFData, mrr: dict | None) -> list[tuple[str, dict]]:
+    """(record name, fields) of the merged PCR / HBR / SBR / TSR / MRR."""
+    out: list[tuple[str, dict]] = []
+    for (head, site), row in merged.part_counts.items():
+        out.append(("PCR", {"HEAD_NUM": head, "SITE_NUM": site, **{
+            f"{name.upper()}_CNT": U4_MISSING if row[f"{name}_count"] is None
+            else row[f"{name}_count"]
+            for name in ("part", "rtst", "abrt", "good", "func")
+        }}))
+    for rec, sites, totals in (("HBR", merged.site_bins_hard, merged.bins_hard),
+                               ("SBR", merged.site_bins_soft, merged.bins_soft)):
+        p = rec[0]
+        rows = [(r["head_num"], r["site_num"], r) for r in sites.values()]
+        rows += [(255, 255, r) for r in totals.values()]
+        for head, site, r in rows:
+            out.append((rec, {
+                "HEAD_NUM": head, "SITE_NUM": site, f"{p}BIN_NUM": r["bin_num"],
+                f"{p}BIN_CNT": r["bin_count"], f"{p}BIN_PF": r["bin_pf"] or " ",
+                f"{p}BIN_NAM": r["bin_name"],
+            }))
+    for s in merged.test_synopses:
+        opt_flag = 0xC8  # bits 3, 6, 7 reserved (1)
+        stats = {}
+        for bit, key, field in ((2, "test_time", "TEST_TIM"), (0, "test_min", "TEST_MIN"),
+                                (1, "test_max", "TEST_MAX"), (4, "test_sum", "TST_SUMS"),
+                                (5, "test_sum_sq", "TST_SQRS")):
+            if s[key] is None:
+                opt_flag |= 1 << bit
+            stats[field] = s[key] or 0.0
+        out.append(("TSR", {
+            "HEAD_NUM": s["head_num"], "SITE_NUM": s["site_num"],
+            "TEST_TYP": s["test_type"] or " ", "TEST_NUM": s["test_num"],
+            **{f: U4_MISSING if s[k] is None else s[k] for f, k in (
+                ("EXEC_CNT", "exec_count"), ("FAIL_CNT", "fail_count"),
+                ("ALRM_CNT", "alarm_count"))},
+            "TEST_NAM": s["test_name"], "SEQ_NAME": s["seq_name"], "TEST_LBL": s["test_label"],
+            "OPT_FLAG": opt_flag,
+            "TEST_TIM": stats["TEST_TIM"], "TEST_MIN": stats["TEST_MIN"],
+            "TEST_MAX": stats["TEST_MAX"], "TST_SUMS": stats["TST_SUMS"],
+            "TST_SQRS": stats["TST_SQRS"],
+        }))
+    if mrr is not None:
+        out.append(("MRR", {**mrr, "FINISH_T": merged.finish_time}))
+    return out
+
+
+def merge_stdf(paths: list[Path], out: BinaryIO, options: ParseOptions | None = None) -> int:
+    """Write the files of one lot (see merge()) to `out` as one STDF file.
+
+    Part and wafer records are copied in test order; a PRR PART_ID already
+    used by an earlier file is replaced by the part's sequential number, so
+    the written file parses to merge()'s part ids. Header records are each
+    kept once (PMRs once per PMR_INDX, the first file's winning). Files must
+    share a byte order. Returns the record count.
+    """
+    paths = [Path(p) for p in paths]
+    pairs = _by_start_time(paths, [parse_stdf(p, options) for p in paths])
+    merged = merge_data([d for _, d in pairs], [p.name for p, _ in pairs])
+
+    files = []
+    endian = None
+    for path, _data in pairs:
+        with open(path, "rb") as f:
+            file_endian = sniff_byte_order(f)[0]
+            records = [
+                (REC_NAMES.get((typ, sub), ""), typ, sub, body)
+                for typ, sub, rec_len, body in iter_records(f, file_endian)
+                if typ >= 0 and rec_len == len(body)
+            ]
+        if endian is None:
+            endian = file_endian
+        elif file_endian != endian:
+            raise ValueError(f"{path}: byte order differs from {pairs[0][0].name}")
+        files.append(records)
+
+    header = struct.Struct(endian + "HBB")
+    count = 0
+
+    def write(typ: int, sub: int, body: bytes) -> None:
+        nonlocal count
+        out.write(header.pack(len(body), typ, sub) + body)
+        count += 1
+
+    written: set[tuple[int, int, bytes]] = set()
+    pmr_indexes: set[int] = set()
+    mrr = None
+    for i, records in enumerate(files):
+        for name, typ, sub, body in records:
+            if name == "MRR":
+                mrr = decode_fields("MRR", body, endian)[0]
+            if name == "RDR" or (typ, sub, body) in written:
+                continue  # RDR: rebuilt from the merged retest bins
+            if name == "PMR":
+                index = decode_fields("PMR", body, endian)[0].get("PMR_INDX")
+                if index in pmr_indexes:
+                    continue
+                pmr_indexes.add(index)
+            if name in _FIRST_ONLY and i == 0 or name in _HEADER:
+                write(typ, sub, body)
+                written.add((typ, sub, body))
+            if name == "MIR" and i == 0 and merged.retest_bins is not None:
+                write(*REC_KEYS["RDR"], encode_fields("RDR", {
+                    "NUM_BINS": len(merged.retest_bins), "RTST_BIN": merged.retest_bins,
+                }, endian))
+
+    offset = 0
+    for records, (_path, data) in zip(files, pairs):
+        # merged parts of this file with a tester PART_ID, in PRR order
+        parts = iter([
+            p for p in merged.parts[offset:offset + len(data.parts)] if p["tester_part_id"]
+        ])
+        offset += len(data.parts)
+        for name, typ, sub, body in records:
+            if name in _FIRST_ONLY or name in _HEADER or name in _SUMMARY:
+                continue
+            if name == "PRR":
+                try:
+                    fields, used_bytes = decode_fields("PRR", body, endian)
+                except ValueError:
+                    fields, used_bytes = {}, 0  # copied as is
+                part = next(parts, None) if fields.get("PART_ID") else None
+                prefix = part and f"{merged.lot_id}_{part['wafer_id']}_"
+                if part and part["part_id"] != prefix + fields["PART_ID"]:
+                    fields["PART_ID"] = part["part_id"].removeprefix(prefix)
+                    body = encode_fields("PRR", fields, endian) + body[used_bytes:]
+            write(typ, sub, body)
+
+    for name, fields in _summary_records(merged, mrr):
+        write(*REC_KEYS[name], encode_fields(name, fields, endian))
+    return count
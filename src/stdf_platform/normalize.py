@@ -0,0 +1,170 @@
+"""Rewrite an STDF file into canonical record order.
+
+Some merge tools write records out of STDF order: SDRs after the first WIR,
+site summaries in the middle of a wafer, interleaved multi-site parts, a PCR
+after the MRR. Analysis tools that insist on the spec's order refuse such
+files; normalize_stdf() rewrites them as
+
+    FAR, ATR, VUR, MIR, RDR, SDR, PSR, NMR, CNR, SSR, CDR, PMR, PGR, PLR, WCR,
+    per wafer: WIR, per part (PIR, its test records, PRR), WRR,
+    TSR, HBR, SBR, PCR, MRR
+
+Records keep their relative order within each slot, and are copied byte for
+byte. A part's records are found by head / site between its PIR and PRR, a
+wafer's by head between its WIR and WRR (a wafer on a head with none open
+goes to the only open wafer, if there is one). Records tied to neither —
+DTR, GDR, BPS / EPS, vendor records — stay with the part or wafer of the
+record before them. Parts without a WIR (final test) stay in file order
+between the header and the summary.
+"""
+
+from bisect import bisect_left
+from dataclasses import dataclass, field
+from pathlib import Path
+from typing import BinaryIO
+
+from .parser import REC_NAMES, sniff_byte_order
+from .records import HEAD_OFFSET, iter_records
+
+HEADER_ORDER = (
+    "FAR", "ATR", "VUR", "MIR", "RDR", "SDR", "PSR", "NMR", "CNR", "SSR", "CDR",
+    "PMR", "PGR", "PLR", "WCR",
+)
+SUMMARY_ORDER = ("TSR", "HBR", "SBR", "PCR")
+_PART_RECORDS = {"PIR", "PRR", "PTR", "MPR", "FTR", "STR"}
+
+# (file index, offset, size) of one record
+_Ref = tuple[int, int, int]
+
+
+@dataclass
+class NormalizeReport:
+    """What normalize_stdf() did."""
+
+    records: int = 0  # written
+    # Fewest records that changed place: the others kept their relative order
+    moved: int = 0
+    # Records left out: FAR / MIR after the first, MRR before the last, and a
+    # record cut off by the end of the file ("cut off")
+    dropped: dict[str, int] = field(default_factory=dict)
+
+
+class _Wafer:
+    def __init__(self, wir: _Ref):
+        self.wir = wir
+        self.items: list = []  # refs and parts (lists of refs)
+        self.wrr: _Ref | None = None
+
+
+def normalize_stdf(path: Path, out: BinaryIO) -> NormalizeReport:
+    """Copy the (uncompressed) STDF file at `path` to `out` in canonical
+    record order (see the module doc)."""
+    report = NormalizeReport()
+    header: dict[str, list[_Ref]] = {name: [] for name in HEADER_ORDER}
+    summary: dict[str, list[_Ref]] = {name: [] for name in SUMMARY_ORDER}
+    mrr: _Ref | None = None
+    body: list = []  # wafers, parts and loose records between header and summary
+    open_wafers: dict[int, _Wafer] = {}  # head -> wafer with WIR but no WRR yet
+    last_wafer: _Wafer | None = None
+    open_parts: dict[tuple[int, int], list[_Ref]] = {}  # (head, site) -> part
+    last_part: list[_Ref] | None = None  # open part of the previous part record
+
+    def drop(name: str):
+        report.dropped[name] = report.dropped.get(name, 0) + 1
+
+    def wafer_for(head: int) -> _Wafer | None:
+        wafer = open_wafers.get(head)
+        if wafer is None and len(open_wafers) == 1:
+            wafer = next(iter(open_wafers.values()))
+        return wafer
+
+    with open(path, "rb") as f:
+        endian = sniff_byte_order(f)[0]
+        offset = 0
+        for index, (typ, sub, rec_len, rec_body) in enumerate(iter_records(f, endian)):
+            ref = (index, offset, 4 + len(rec_body) if typ >= 0 else len(rec_body))
+            offset += ref[2]
+            if typ < 0 or rec_len != len(rec_body):
+                drop("cut off")
+                continue
+            name = REC_NAMES.get((typ, sub))
+            head_at = HEAD_OFFSET.get(name)
+            head_site = (
+                tuple(rec_body[head_at:head_at + 2])
+                if head_at is not None and len(rec_body) >= head_at + 2 else None
+            )
+
+            if name in header:
+                if name in ("FAR", "MIR") and header[name]:
+                    drop(name)
+                else:
+                    header[name].append(ref)
+            elif name == "MRR":
+                if mrr is not None:
+                    drop(name)
+                mrr = ref
+            elif name in summary:
+                summary[name].append(ref)
+            elif name == "WIR" and rec_body:
+                last_wafer = open_wafers[rec_body[0]] = _Wafer(ref)
+                body.append(last_wafer)
+            elif name == "WRR" and rec_body and wafer_for(rec_body[0]) is not None:
+                wafer = wafer_for(rec_body[0])
+                wafer.wrr = ref
+                open_wafers = {h: w for h, w in open_wafers.items() if w is not wafer}
+            elif name == "PIR" and head_site is not None:
+                last_part = open_parts[head_site] = [ref]
+                wafer = wafer_for(head_site[0])
+                (wafer.items if wafer is not None else body).append(last_part)
+            elif name in _PART_RECORDS and head_site in open_parts:
+                part = open_parts[head_site]
+                part.append(ref)
+                last_part = part
+                if name == "PRR":
+                    del open_parts[head_site]
+                    last_part = None
+            elif last_part is not None:
+                last_part.append(ref)  # DTR / GDR / ... inside a part
+            elif last_wafer is not None and last_wafer.wrr is None:
+                last_wafer.items.append(ref)
+            else:
+                body.append(ref)
+
+        order: list[_Ref] = [ref for name in HEADER_ORDER for ref in header[name]]
+
+        def add(items: list):
+            for item in items:
+                if isinstance(item, _Wafer):
+                    order.append(item.wir)
+                    add(item.items)
+                    if item.wrr is not None:
+                        order.append(item.wrr)
+                elif isinstance(item, list):
+                    order.extend(item)
+                else:
+                    order.append(item)
+
+        add(body)
+        order += [ref for name in SUMMARY_ORDER for ref in summary[name]]
+        if mrr is not None:
+            order.append(mrr)
+
+        for _, rec_offset, size in order:
+            f.seek(rec_offset)
+            out.write(f.read(size))
+
+    report.records = len(order)
+    report.moved = len(order) - _longest_increasing([ref[0] for ref in order])
+    return report
+
+
+def _longest_increasing(values: list[int]) -> int:
+    """Length of the longest strictly increasing subsequence of `values`."""
+    tails: list[int] = []
+    for v in values:
+        i = bisect_left(tails, v)
+        if i == len(tails):
+            tails.append(v)
+        else:
+            tails[i] = v
+    return len(tails)
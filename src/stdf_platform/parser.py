@@ -1,10 +1,19 @@
 """STDF binary parser - pure Python, no external dependencies."""
 
+import hashlib
+import io
+import json
+import os
+import re
+import shutil
 import struct
 import logging
+import sys
+import tempfile
+import time
 from pathlib import Path
-from dataclasses import dataclass, field
-from typing import BinaryIO
+from dataclasses import dataclass, field, replace
+from typing import Any, BinaryIO, Callable
 
 logger = logging.getLogger(__name__)
 
@@ -12,6 +21,300 @@ logger = logging.getLogger(__name__)
 _STRUCT_HEADER_LE = struct.Struct("<H")
 _STRUCT_HEADER_BE = struct.Struct(">H")
 
+# Cap on STDFData.warnings / diagnostics — a corrupt multi-GB file could
+# otherwise produce one per record. ParseStats counters keep counting past it.
+_MAX_WARNINGS = 1000
+
+# Rows sampled per table by STDFData.table_sizes() (deep-sizing every row of a
+# multi-million-row test_results list would cost more than the parse).
+_SIZE_SAMPLE = 1000
+
+# Decompressed streams (stdin, compressed files) are spooled here before
+# parsing: in RAM up to this size, then to a temp file. The decoder seeks.
+_SPOOL_MAX = 64 * 1024 * 1024
+# Bytes of the file start searched for the FAR / walked to infer byte order
+_SNIFF_BYTES = 64 * 1024
+# Bytes read / decompressed / decoded by STDFParser.parse_async between
+# yields to the event loop
+_ASYNC_SLICE = 1024 * 1024
+# First bytes of an ATDF (text) file: its FAR record
+_ATDF_MAGIC = b"FAR:"
+# STDFData.save_cache() file: magic, a JSON header line (version, source
+# size / mtime_ns, parse options), then the STDFData as JSON (see
+# _cache_encode). Bump the version whenever STDFData's fields change meaning.
+_CACHE_MAGIC = b"STDFDATA-CACHE\n"
+CACHE_VERSION = 4
+# Distinct C*n strings kept decoded (STDFParser._decode_cn); cleared when full
+_CN_CACHE_MAX = 4096
+
+# Leading magic bytes -> compression, for auto-detection
+_COMPRESSION_MAGIC = [
+    (b"\x1f\x8b", "gzip"),
+    (b"BZh", "bz2"),
+    (b"\xfd7zXZ\x00", "xz"),
+    (b"\x28\xb5\x2f\xfd", "zstd"),
+]
+
+
+def detect_compression(head: bytes) -> str | None:
+    """"gzip", "bz2", "xz" or "zstd" from a stream's first bytes; None for raw STDF."""
+    for magic, name in _COMPRESSION_MAGIC:
+        if head.startswith(magic):
+            return name
+    return None
+
+
+def open_decompressed(stream: BinaryIO) -> BinaryIO:
+    """`stream` with gzip/bz2/xz/zstd compression undone (detected, not by name).
+
+    Works on non-seekable streams such as stdin; uncompressed input is returned
+    as is (wrapped for peeking if needed). zstd needs the zstandard package.
+    """
+    if not hasattr(stream, "peek"):
+        stream = io.BufferedReader(stream)
+    compression = detect_compression(stream.peek(6)[:6])
+    if compression == "gzip":
+        import gzip
+        return gzip.GzipFile(fileobj=stream, mode="rb")
+    if compression == "bz2":
+        import bz2
+        return bz2.BZ2File(stream, mode="rb")
+    if compression == "xz":
+        import lzma
+        return lzma.LZMAFile(stream, mode="rb")
+    if compression == "zstd":
+        try:
+            import zstandard
+        except ImportError as e:
+            raise ImportError(
+                "zstd-compressed input needs zstandard (pip install 'stdf2pq[zstd]')"
+            ) from e
+        return zstandard.ZstdDecompressor().stream_reader(stream)
+    return stream
+
+
+class STDFError(Exception):
+    """Something wrong with the STDF content, as opposed to OSError (the input
+    can't be read): catch it to tell a bad file from a broken disk. `offset`
+    is the file offset of the record at fault, when there is one."""
+
+    def __init__(self, message: str, offset: int | None = None):
+        super().__init__(message)
+        self.offset = offset
+
+
+class STDFFormatError(STDFError, ValueError):
+    """Input that is not a usable STDF file (no MIR where one is needed, a
+    malformed FAR, not STDF at all)."""
+
+
+class STDFVersionError(STDFFormatError):
+    """A FAR STDF_VER other than 4: earlier versions lay records out differently."""
+
+    def __init__(self, version: int, offset: int | None = None):
+        super().__init__(f"STDF_VER {version} is not supported (V4 only)", offset)
+        self.version = version
+
+
+class STDFRecordError(STDFError, ValueError):
+    """A malformed record (`rec`, e.g. "PTR"): cut off, fields past REC_LEN, ..."""
+
+    def __init__(self, message: str, rec: str = "", offset: int | None = None):
+        super().__init__(message, offset)
+        self.rec = rec
+
+
+class STDFDecodeError(STDFError, ValueError):
+    """Raised in strict string mode (ParseOptions.string_errors="strict")."""
+
+
+class STDFTruncatedError(EOFError):
+    """A record field that runs past its record (or the file): `got` of its
+    `size` bytes are there. Caught per record by the parser and reported as
+    "PTR at offset 0x1234: RESULT field truncated (2 of 4 bytes)"."""
+
+    def __init__(self, field: str, got: int, size: int, eof: bool = False):
+        super().__init__(field, got, size)
+        self.field = field
+        self.got = got
+        self.size = size
+        self.eof = eof  # the file ended inside the field
+
+
+@dataclass
+class ParseOptions:
+    """Knobs controlling what the parser collects."""
+
+    # Datalog reduction: keep only the first N failing test records per part
+    # (passing results are dropped). part["fail_count"] still counts every
+    # failure, so the summary survives the reduction. None = keep everything.
+    max_fails_per_part: int | None = None
+    # False = skip test_results rows entirely (test definitions, parts and the
+    # per-part result/fail counts are still collected) — summary-only parsing.
+    collect_results: bool = True
+    # Files without a MIR have no lot_id (part_ids come out as "__1"). The
+    # lot_id is then taken from fallback_lot_id, else the file name stem when
+    # lot_id_from_filename is set; either way a warning is recorded.
+    fallback_lot_id: str | None = None
+    lot_id_from_filename: bool = False
+    # C*n fields that are not clean ASCII are always decoded with replacement
+    # characters and counted in ParseStats.lossy_strings. "report" also adds a
+    # warning naming the record and offset; "strict" raises STDFDecodeError.
+    string_errors: str = "replace"  # "replace" | "report" | "strict"
+    # Collect test_results rows only for these test numbers (definitions and
+    # per-part counts still cover every test). None = all tests.
+    tests: set[int] | None = None
+    # Keep records with no decoder (vendor types, anything unrecognized) as
+    # raw bytes in STDFData.unknown_records, for re-emission and debugging.
+    keep_unknown_records: bool = False
+    # C*f (fixed-length) strings are space-padded to their width; False keeps
+    # the padding (NULs are always dropped).
+    trim_cf: bool = True
+    # False = drop data.parts rows once each touchdown completes (bins, wafers,
+    # tests and results are still collected) — for header / bin / yield only
+    # callers; part-derived tables (wafer_bins) come out empty.
+    collect_parts: bool = True
+    # Stop collecting test_results rows once this many are held (an MPR's
+    # pins may go a few over); part result/fail counts still cover every
+    # record. None = no cap.
+    max_results: int | None = None
+    # Decode only these record types (names: {"MIR", "HBR", "SBR", "MRR"});
+    # the others are skipped unread. The FAR is always decoded. Parts need
+    # PIR and PRR, their results PTR / MPR / FTR. None = every record.
+    records: set[str] | None = None
+    # Strict (QA gate): the first malformed record — fields past REC_LEN or
+    # bytes left after them, a cut-off or implausible record, a decoder
+    # failure — or missing FAR / MIR / MRR, or a part or wafer left open,
+    # raises STDFRecordError / STDFFormatError instead of being reported in
+    # data.warnings and skipped. Sequencing breaks (order_violations) and
+    # records without a decoder are still only reported.
+    strict: bool = False
+
+
+@dataclass
+class ParseStats:
+    """Record-level bookkeeping from one parse — diagnostics, not test data."""
+
+    record_count: int = 0
+    # rec_name -> records whose required fields needed more bytes than REC_LEN
+    # (truncated/corrupt record, or a decoder reading past its record)
+    overrun_records: dict[str, int] = field(default_factory=dict)
+    # rec_name -> bytes left un-decoded at the end of records / records with any.
+    # Large counts point at vendor extensions or optional fields we don't read.
+    leftover_bytes: dict[str, int] = field(default_factory=dict)
+    leftover_records: dict[str, int] = field(default_factory=dict)
+    # rec_name -> records with no decoder (skipped whole; e.g. DTR, vendor types)
+    skipped_records: dict[str, int] = field(default_factory=dict)
+    # rec_name -> records whose decoder raised (record skipped, parse continued)
+    error_records: dict[str, int] = field(default_factory=dict)
+    # rec_name -> records breaking STDF sequencing rules (test record outside
+    # PIR/PRR, PRR without PIR, WRR without WIR, anything after MRR)
+    order_violations: dict[str, int] = field(default_factory=dict)
+    # Implausible record headers (REC_LEN past the end of the file, unknown
+    # record type) after which the parser scanned forward to the next
+    # plausible header, and the bytes it skipped doing so
+    resync_count: int = 0
+    resync_bytes: int = 0
+    # rec_name -> C*n fields that needed lossy decoding (non-ASCII bytes)
+    lossy_strings: dict[str, int] = field(default_factory=dict)
+    # table -> estimated in-memory bytes at the end of the parse (STDFData.table_sizes)
+    table_bytes: dict[str, int] = field(default_factory=dict)
+
+
+@dataclass
+class Diagnostic:
+    """One non-fatal finding of a parse, as data (STDFData.diagnostics).
+
+    kind is one of: "truncated" (a field cut off by REC_LEN or the end of the
+    file), "overrun" (fields need more than REC_LEN), "leftover" (bytes after
+    a record's last field), "resync" (implausible header skipped), "decoder"
+    (record could not be decoded), "order" (STDF sequencing broken),
+    "missing" (no FAR / MIR), "version" (FAR STDF_VER not 4), "incomplete"
+    (no MRR, part or wafer left open), "string" (non-ASCII C*n,
+    string_errors="report"), "skipped" (records with no decoder), "limit"
+    (ParseOptions.max_results reached).
+    """
+
+    kind: str
+    message: str
+    rec: str = ""  # record name ("PTR", "180/7"); "" when not about one record
+    offset: int | None = None  # file offset of that record's header
+
+
+@dataclass
+class ConditionsInfo:
+    """Test temperature / flow / voltage corner, typed for corner-split analysis.
+
+    MIR TST_TEMP / FLOW_ID / TEST_COD win; DTR text and GDR strings of the form
+    ``KEY=VALUE`` / ``KEY: VALUE`` (or a GDR key string followed by its value)
+    fill whatever the MIR left empty. Recognized keys are in _CONDITION_KEYS.
+    """
+
+    temperature_raw: str = ""  # as written (e.g. "25", "-40C", "HOT")
+    temperature_c: float | None = None  # numeric °C when temperature_raw is one
+    flow_id: str = ""
+    test_code: str = ""
+    voltage_corner: str = ""  # e.g. "LV", "NV", "HV", "VMIN"
+
+
+@dataclass
+class WaferConfig:
+    """WCR wafer geometry — what wafer maps need to be scaled and oriented.
+
+    Sizes are in `units`; values the tester left unknown keep the spec's
+    missing markers (0, -32768) or "" for the direction/flat characters.
+    """
+
+    wafer_size: float = 0.0  # diameter
+    die_height: float = 0.0
+    die_width: float = 0.0
+    units: int = 0  # 0=unknown 1=inch 2=cm 3=mm 4=mil
+    flat: str = ""  # U/D/L/R: flat or notch position
+    center_x: int = -32768  # X of the center die
+    center_y: int = -32768
+    pos_x: str = ""  # L/R: direction of increasing X
+    pos_y: str = ""  # U/D: direction of increasing Y
+
+    @property
+    def mm_per_unit(self) -> float | None:
+        """Millimetres per `units` (None when the unit is unknown)."""
+        return {1: 25.4, 2: 10.0, 3: 1.0, 4: 0.0254}.get(self.units)
+
+
+# GDR V*n type codes -> (type name, STDFParser reader). 0 is the B*0 pad
+# byte; 13 (N*1) is a nibble stored in a whole byte.
+_VN_TYPES = {
+    1: ("U1", "_read_u1"), 2: ("U2", "_read_u2"), 3: ("U4", "_read_u4"),
+    4: ("I1", "_read_i1"), 5: ("I2", "_read_i2"), 6: ("I4", "_read_i4"),
+    7: ("R4", "_read_r4"), 8: ("R8", "_read_r8"),
+    10: ("Cn", "_read_cn"), 11: ("Bn", "_read_bn"), 12: ("Dn", "_read_dn"),
+}
+
+
+# DTR/GDR key (case-insensitive) -> ConditionsInfo field
+_CONDITION_KEYS = {
+    "temp": "temperature_raw",
+    "temperature": "temperature_raw",
+    "tst_temp": "temperature_raw",
+    "test_temp": "temperature_raw",
+    "flow": "flow_id",
+    "flow_id": "flow_id",
+    "corner": "voltage_corner",
+    "vcorner": "voltage_corner",
+    "v_corner": "voltage_corner",
+    "voltage_corner": "voltage_corner",
+    "vdd_corner": "voltage_corner",
+}
+# KEY=VALUE / KEY: VALUE; a "value" followed by its own = or : is the next key
+_RE_CONDITION = re.compile(r"\b([A-Za-z_]+)\s*[:=]\s*([^\s,;:=]+)(?![^\s,;:=]|\s*[:=])")
+_RE_TEMPERATURE = re.compile(r"^\s*([+-]?\d+(?:\.\d+)?)\s*(?:°?\s*C)?\s*$", re.IGNORECASE)
+
+
+def parse_temperature(raw: str) -> float | None:
+    """'25', '-40C', '125 °C' -> float °C; anything else (e.g. 'HOT') -> None."""
+    m = _RE_TEMPERATURE.match(raw)
+    return float(m.group(1)) if m else None
+
 
 @dataclass
 class STDFData:
@@ -27,6 +330,22 @@ class STDFData:
     tester_type: str = ""
     operator: str = ""
     test_code: str = ""  # CP1, FT2 等（MIR.TEST_CODから取得）
+    # MRR lot disposition: DISP_COD (" " / "" = normal, else tester-defined,
+    # e.g. hold) and the operator's USR_DESC / EXC_DESC texts
+    disp_code: str = ""
+    user_desc: str = ""
+    exc_desc: str = ""
+    # File dialect: "V4" from FAR STDF_VER, "V4-2007" when a VUR names that
+    # update ("" without FAR). version_updates = VUR UPD_NAMs in file order.
+    spec_version: str = ""
+    version_updates: list[str] = field(default_factory=list)
+
+    # ATR entries in file order: {mod_time (epoch s), cmd_line}
+    audit_trail: list[dict] = field(default_factory=list)
+
+    # RDR: hardware bins being retested in this file. None = no RDR (not a
+    # retest file); [] = RDR with NUM_BINS 0, i.e. all bins retested.
+    retest_bins: list[int] | None = None
 
     # Records by type
     wafers: list[dict] = field(default_factory=list)
@@ -35,23 +354,302 @@ class STDFData:
     test_results: list[dict] = field(default_factory=list)
     # EN-SO-CHIPID_R occurrences decoded from GDR (one row per die occurrence)
     chip_ids: list[dict] = field(default_factory=list)
+    # GDRs in file order: {wafer_id, part_id ("" outside PIR/PRR), fields:
+    # [(type, value), ...]} with type "U1".."R8", "Cn", "Bn", "Dn" or "N1"
+    generic_data: list[dict] = field(default_factory=list)
+    # DTR lines in file order: {wafer_id, part_id ("" outside PIR/PRR), text}
+    datalog_text: list[dict] = field(default_factory=list)
+    # STR (V4-2007 scan) fails, one row per failing cycle: {part_id, test_num,
+    # pattern, cycle, pin_num, pin_name, chain, bit_pos, expected, captured, ...}
+    scan_failures: list[dict] = field(default_factory=list)
+    # One row per scan test execution (continuation STRs merged): {part_id,
+    # test_num, head_num, site_num, psr_ref, passed, cycle_count, total_fails,
+    # logged_fails, fail_rows, records, ...}
+    scan_tests: list[dict] = field(default_factory=list)
+    # Lot bin totals by bin number: {bin_num, bin_name, bin_pf, bin_count}.
+    # bin_count is the HBR/SBR summary (head 255) count, or the sum of the
+    # per-site counts when the file has no summary for that bin.
     bins_hard: dict[int, dict] = field(default_factory=dict)
     bins_soft: dict[int, dict] = field(default_factory=dict)
+    # Per-site HBR/SBR rows by (head_num, site_num, bin_num): {head_num,
+    # site_num, bin_num, bin_name, bin_pf, bin_count}
+    site_bins_hard: dict[tuple[int, int, int], dict] = field(default_factory=dict)
+    site_bins_soft: dict[tuple[int, int, int], dict] = field(default_factory=dict)
+    # Limits per (test_num, head, site) as first seen there: {lo_limit, hi_limit}.
+    # Test programs can set site-specific limits; tests[] keeps the first seen.
+    site_limits: dict[tuple[int, int, int], dict] = field(default_factory=dict)
+    # TSR rows as recorded (per head/site, head 255 = all sites): exec/fail/
+    # alarm counts, time, min/max, sums; None where the tester left it invalid
+    test_synopses: list[dict] = field(default_factory=list)
+    # Per-wafer bin table derived from the PRRs in each WIR/WRR window (HBR/SBR
+    # only carry lot totals): {wafer_id, bin_type H|S, bin_num, bin_name,
+    # bin_pf, part_count}. Every PRR counts, retests included, as in WRR.
+    wafer_bins: list[dict] = field(default_factory=list)
     # PMR pin map: pmr_index -> pin_name (LOG_NAM > PHY_NAM > CHAN_NAM)
     pin_map: dict[int, str] = field(default_factory=dict)
+    # NMR (V4-2007) ATPG pin names by PMR index; override pin_map in pin_name()
+    atpg_names: dict[int, str] = field(default_factory=dict)
+    # PMR detail: pmr_index -> {chan_typ, chan_nam, phy_nam, log_nam, head_num, site_num}
+    pins: dict[int, dict] = field(default_factory=dict)
+    # PGR pin groups: grp_index -> {grp_nam, pmr_indexes}
+    pin_groups: dict[int, dict] = field(default_factory=dict)
+    # PCR tester part counts: (head_num, site_num) -> {part_count, rtst_count,
+    # abrt_count, good_count, func_count}; None = not reported (4294967295).
+    # Head 255 is the all-sites summary.
+    part_counts: dict[tuple[int, int], dict] = field(default_factory=dict)
+    # PLR display format per pin/group index: {mode, radix, pgm_char, rtn_char,
+    # pgm_chal, rtn_chal} (char lists per PGR member; see _parse_plr)
+    pin_lists: dict[int, dict] = field(default_factory=dict)
+    # SDR equipment per site group: {head_num, site_grp, site_nums, hand_typ,
+    # hand_id, card_typ, card_id, load_typ, load_id, dib_typ, dib_id, ...}
+    site_descriptions: list[dict] = field(default_factory=list)
+    # PSR pattern sequences by PSR_INDX (STR PSR_REF): {name, total_patterns,
+    # patterns: [{begin, end, file, label, file_uid, atpg_desc, src_id}]}
+    pattern_sequences: dict[int, dict] = field(default_factory=dict)
+    # CNR scan cell names: (chain, bit_pos) -> CELL_NAM (scan_failures chain/bit_pos)
+    cell_names: dict[tuple[int, int], str] = field(default_factory=dict)
+    # SSR scan structures in file order: {name, chains: [CDR index, ...]}
+    scan_structures: list[dict] = field(default_factory=list)
+    # CDR scan chains by CDR_INDX: {name, length, sin_pin, sout_pin,
+    # master_clocks, slave_clocks, inv_val, cells: [cell name, ...]}
+    scan_chains: dict[int, dict] = field(default_factory=dict)
+    # Values returned by STDFParser.register_record handlers, per (rec_typ,
+    # rec_sub), in file order (None returns are not kept)
+    user_records: dict[tuple[int, int], list] = field(default_factory=dict)
+    # Records with no decoder as (rec_typ, rec_sub, header offset, body bytes),
+    # in file order; only with ParseOptions.keep_unknown_records
+    unknown_records: list[tuple[int, int, int, bytes]] = field(default_factory=list)
+    # WCR wafer geometry (None if the file has no WCR)
+    wafer_config: WaferConfig | None = None
+    # MRR present and every PIR/WIR closed by its PRR/WRR. False for truncated
+    # or still-being-written files (reasons are in warnings) — quarantine those.
+    is_complete: bool = False
+
+    # Temperature / flow / voltage corner (MIR, recognized DTR/GDR patterns)
+    conditions: ConditionsInfo = field(default_factory=ConditionsInfo)
+
+    # Parse diagnostics (non-fatal; capped at _MAX_WARNINGS each). Every
+    # warning is also a Diagnostic, which adds the kind, record and offset;
+    # leftover bytes after a record's fields are diagnostics only (vendor
+    # tails are common enough not to warn about).
+    stats: ParseStats = field(default_factory=ParseStats)
+    warnings: list[str] = field(default_factory=list)
+    diagnostics: list[Diagnostic] = field(default_factory=list)
 
     # Internal state
     _current_wafer: str = ""
 
+    def table_sizes(self) -> dict[str, int]:
+        """Estimated in-memory bytes per table (wafers, parts, test_results, ...).
+
+        Sampled: the first _SIZE_SAMPLE rows are sized (row container + values,
+        shared objects counted once) and scaled to the table length. Good
+        enough to decide whether a parsed lot is worth caching in RAM.
+        """
+        return {
+            "wafers": _rows_bytes(self.wafers),
+            "parts": _rows_bytes(self.parts),
+            "tests": _rows_bytes(self.tests),
+            "site_limits": _rows_bytes(self.site_limits),
+            "test_synopses": _rows_bytes(self.test_synopses),
+            "test_results": _rows_bytes(self.test_results),
+            "chip_ids": _rows_bytes(self.chip_ids),
+            "generic_data": _rows_bytes(self.generic_data),
+            "datalog_text": _rows_bytes(self.datalog_text),
+            "scan_failures": _rows_bytes(self.scan_failures),
+            "scan_tests": _rows_bytes(self.scan_tests),
+            "bins_hard": _rows_bytes(self.bins_hard),
+            "bins_soft": _rows_bytes(self.bins_soft),
+            "site_bins_hard": _rows_bytes(self.site_bins_hard),
+            "site_bins_soft": _rows_bytes(self.site_bins_soft),
+            "wafer_bins": _rows_bytes(self.wafer_bins),
+            "part_counts": _rows_bytes(self.part_counts),
+            "pin_map": _rows_bytes(self.pin_map),
+            "atpg_names": _rows_bytes(self.atpg_names),
+            "pins": _rows_bytes(self.pins),
+            "pin_groups": _rows_bytes(self.pin_groups),
+            "pin_lists": _rows_bytes(self.pin_lists),
+            "site_descriptions": _rows_bytes(self.site_descriptions),
+            "user_records": _rows_bytes(self.user_records),
+            "unknown_records": _rows_bytes(self.unknown_records),
+            "pattern_sequences": _rows_bytes(self.pattern_sequences),
+            "cell_names": _rows_bytes(self.cell_names),
+            "scan_structures": _rows_bytes(self.scan_structures),
+            "scan_chains": _rows_bytes(self.scan_chains),
+        }
+
+    def estimated_bytes(self) -> int:
+        """Total of table_sizes() — approximate RAM held by this parsed file."""
+        return sum(self.table_sizes().values())
+
+    def pin_name(self, index: int) -> str | None:
+        """Name for a pin index as test records use them: the NMR ATPG name
+        (V4-2007; ATE names are often truncated), else the PMR pin name, else
+        the PGR group name when the index is a group (None if none apply)."""
+        name = self.atpg_names.get(index) or self.pin_map.get(index)
+        if name is None and index in self.pin_groups:
+            name = self.pin_groups[index]["grp_nam"]
+        return name
+
+    def resolve_pins(self, indexes: list[int]) -> list[int]:
+        """PMR indexes behind a list of pin/group indexes (e.g. FTR RTN_INDX).
+
+        PGR groups expand to their member pins; order is kept, duplicates dropped.
+        """
+        out: dict[int, None] = {}
+        for index in indexes:
+            group = self.pin_groups.get(index)
+            for pin in group["pmr_indexes"] if group is not None else (index,):
+                out[pin] = None
+        return list(out)
+
+    def pattern(self, psr_ref: int, pat_num: int) -> dict | None:
+        """PSR pattern entry an STR fail points at (scan_tests psr_ref,
+        scan_failures pattern), or None if the file doesn't describe it."""
+        sequence = self.pattern_sequences.get(psr_ref)
+        if sequence is None or not 0 <= pat_num < len(sequence["patterns"]):
+            return None
+        return sequence["patterns"][pat_num]
+
+    def save_cache(
+        self, path: Path, source: Path | None = None, options: ParseOptions | None = None,
+    ) -> None:
+        """Write this result to a cache file that load_cache() reads back
+        faster than re-parsing. With `source`, the cache remembers that file's
+        size and mtime (and the `options` it was parsed with) so load_cache()
+        can tell when it is stale. Written atomically. TypeError if a
+        register_record handler returned something other than plain data."""
+        header = {"version": CACHE_VERSION, "options": _options_key(options)}
+        if source is not None:
+            st = Path(source).stat()
+            header.update(size=st.st_size, mtime_ns=st.st_mtime_ns)
+        body = json.dumps(_cache_encode(self), separators=(",", ":"))
+        path = Path(path)
+        path.parent.mkdir(parents=True, exist_ok=True)
+        tmp = path.with_name(path.name + ".tmp")
+        with open(tmp, "wb") as f:
+            f.write(_CACHE_MAGIC)
+            f.write(json.dumps(header).encode() + b"\n")
+            f.write(body.encode())
+        os.replace(tmp, path)
+
+    @classmethod
+    def load_cache(
+        cls, path: Path, source: Path | None = None, options: ParseOptions | None = None,
+    ) -> "STDFData | None":
+        """The STDFData saved by save_cache(), or None when the cache is
+        missing, unreadable, from another cache version, or (given `source`)
+        stale: the source file changed or was parsed with other options.
+
+        The cache is plain data (JSON): a tampered file can't run code, it
+        only fails to load.
+        """
+        try:
+            with open(path, "rb") as f:
+                if f.read(len(_CACHE_MAGIC)) != _CACHE_MAGIC:
+                    return None
+                header = json.loads(f.readline())
+                if not isinstance(header, dict) or header.get("version") != CACHE_VERSION:
+                    return None
+                if source is not None:
+                    st = Path(source).stat()
+                    if (header.get("size"), header.get("mtime_ns"), header.get("options")) != (
+                        st.st_size, st.st_mtime_ns, _options_key(options),
+                    ):
+                        return None
+                data = json.loads(f.read(), object_hook=_cache_decode)
+        except (OSError, ValueError, TypeError, KeyError):
+            return None
+        return data if isinstance(data, cls) else None
+
+
+def _options_key(options: ParseOptions | None) -> str:
+    """ParseOptions as a stable string for cache headers (sets sorted)."""
+    options = options or ParseOptions()
+    if options.tests is not None:
+        options = replace(options, tests=sorted(options.tests))
+    if options.records is not None:
+        options = replace(options, records=sorted(options.records))
+    return repr(options)
+
+
+# Types written to the cache as {"!class": name, "fields": {...}}
+_CACHE_CLASSES = {
+    cls.__name__: cls for cls in (STDFData, ParseStats, Diagnostic, ConditionsInfo, WaferConfig)
+}
+_JSON_SCALARS = (str, int, float, bool, type(None))
+
+
+def _cache_encode(value: Any) -> Any:
+    """`value` as JSON-ready data: tuples, bytes, dicts with non-str keys and
+    the _CACHE_CLASSES dataclasses become one-key tagged objects ("!tuple",
+    "!bytes", "!dict", "!class"); anything else is a TypeError."""
+    t = type(value)
+    if t in _JSON_SCALARS:
+        return value
+    if t is dict:
+        if all(type(k) is str for k in value):
+            if all(type(v) in _JSON_SCALARS for v in value.values()):
+                return value  # a flat row: the common case, as is
+            return {k: _cache_encode(v) for k, v in value.items()}
+        return {"!dict": [[_cache_encode(k), _cache_encode(v)] for k, v in value.items()]}
+    if t is list:
+        return [_cache_encode(v) for v in value]
+    if t is tuple:
+        return {"!tuple": [_cache_encode(v) for v in value]}
+    if t is bytes:
+        return {"!bytes": value.hex()}
+    if _CACHE_CLASSES.get(t.__name__) is t:
+        return {"!class": t.__name__, "fields": _cache_encode(vars(value))}
+    raise TypeError(f"{t.__name__} values can't be cached")
+
+
+def _cache_decode(obj: dict) -> Any:
+    """json object_hook undoing _cache_encode's tags (innermost first)."""
+    if len(obj) == 1:
+        if "!tuple" in obj:
+            return tuple(obj["!tuple"])
+        if "!bytes" in obj:
+            return bytes.fromhex(obj["!bytes"])
+        if "!dict" in obj:
+            return {k: v for k, v in obj["!dict"]}
+    elif len(obj) == 2 and "!class" in obj:
+        return _CACHE_CLASSES[obj["!class"]](**obj["fields"])
+    return obj
+
+
+def _rows_bytes(rows: list | dict) -> int:
+    """Sampled deep size of a list of rows or a keyed dict of rows (see table_sizes)."""
+    total = sys.getsizeof(rows)
+    if not rows:
+        return total
+    values = rows.values() if isinstance(rows, dict) else rows
+    sample = [row for _, row in zip(range(_SIZE_SAMPLE), values)]
+    seen: set[int] = set()
+    sampled = 0
+    for row in sample:
+        sampled += sys.getsizeof(row)
+        for v in row.values() if isinstance(row, dict) else (row,):
+            if id(v) not in seen:
+                seen.add(id(v))
+                sampled += sys.getsizeof(v)
+    if isinstance(rows, dict):
+        sampled += sum(sys.getsizeof(k) for _, k in zip(range(_SIZE_SAMPLE), rows))
+    return total + sampled * len(rows) // len(sample)
+
 
 # STDF Record types (typ, sub)
 REC_FAR = (0, 10)
+REC_ATR = (0, 20)
+REC_VUR = (0, 30)
 REC_MIR = (1, 10)
 REC_MRR = (1, 20)
 REC_PCR = (1, 30)
 REC_HBR = (1, 40)
 REC_SBR = (1, 50)
 REC_PMR = (1, 60)
+REC_PGR = (1, 62)
+REC_PLR = (1, 63)
 REC_WIR = (2, 10)
 REC_WRR = (2, 20)
 REC_WCR = (2, 30)
@@ -61,213 +659,1179 @@ REC_TSR = (10, 30)
 REC_PTR = (15, 10)
 REC_MPR = (15, 15)
 REC_FTR = (15, 20)
+REC_STR = (15, 30)
 REC_SDR = (1, 80)
+REC_PSR = (1, 90)
+REC_NMR = (1, 91)
+REC_CNR = (1, 92)
+REC_SSR = (1, 93)
+REC_CDR = (1, 94)
+REC_BPS = (20, 10)
+REC_EPS = (20, 20)
+REC_RDR = (1, 70)
 REC_GDR = (50, 10)
+REC_DTR = (50, 30)
+
+# Display names for every STDF V4 / V4-2007 record type (census, diagnostics)
+REC_NAMES = {
+    (0, 10): "FAR", (0, 20): "ATR", (0, 30): "VUR",
+    (1, 10): "MIR", (1, 20): "MRR", (1, 30): "PCR", (1, 40): "HBR",
+    (1, 50): "SBR", (1, 60): "PMR", (1, 62): "PGR", (1, 63): "PLR",
+    (1, 70): "RDR", (1, 80): "SDR", (1, 90): "PSR", (1, 91): "NMR",
+    (1, 92): "CNR", (1, 93): "SSR", (1, 94): "CDR",
+    (2, 10): "WIR", (2, 20): "WRR", (2, 30): "WCR",
+    (5, 10): "PIR", (5, 20): "PRR",
+    (10, 30): "TSR",
+    (15, 10): "PTR", (15, 15): "MPR", (15, 20): "FTR", (15, 30): "STR",
+    (20, 10): "BPS", (20, 20): "EPS",
+    (50, 10): "GDR", (50, 30): "DTR",
+}
+
+
+def _cn_fields(*names: str) -> tuple[tuple, ...]:
+    return tuple((name, "Cn") for name in names)
+
+
+# (FIELD, type) in file order; arrays are (FIELD, "k<type>", count FIELD)
+FIELD_LAYOUTS: dict[str, tuple[tuple, ...]] = {
+    "FAR": (("CPU_TYPE", "U1"), ("STDF_VER", "U1")),
+    "ATR": (("MOD_TIM", "U4"), ("CMD_LINE", "Cn")),
+    "VUR": (("UPD_CNT", "U1"), ("UPD_NAM", "kCn", "UPD_CNT")),
+    "MIR": (
+        ("SETUP_T", "U4"), ("START_T", "U4"), ("STAT_NUM", "U1"), ("MODE_COD", "C1"),
+        ("RTST_COD", "C1"), ("PROT_COD", "C1"), ("BURN_TIM", "U2"), ("CMOD_COD", "C1"),
+    ) + _cn_fields(
+        "LOT_ID", "PART_TYP", "NODE_NAM", "TSTR_TYP", "JOB_NAM", "JOB_REV", "SBLOT_ID",
+        "OPER_NAM", "EXEC_TYP", "EXEC_VER", "TEST_COD", "TST_TEMP", "USER_TXT",
+        "AUX_FILE", "PKG_TYP", "FAMLY_ID", "DATE_COD", "FACIL_ID", "FLOOR_ID", "PROC_ID",
+        "OPER_FRQ", "SPEC_NAM", "SPEC_VER", "FLOW_ID", "SETUP_ID", "DSGN_REV", "ENG_ID",
+        "ROM_COD", "SERL_NUM", "SUPR_NAM",
+    ),
+    "MRR": (("FINISH_T", "U4"), ("DISP_COD", "C1"), ("USR_DESC", "Cn"), ("EXC_DESC", "Cn")),
+    "PCR": (("HEAD_NUM", "U1"), ("SITE_NUM", "U1")) + tuple(
+        (name, "U4") for name in ("PART_CNT", "RTST_CNT", "ABRT_CNT", "GOOD_CNT", "FUNC_CNT")
+    ),
+    "HBR": (
+        ("HEAD_NUM", "U1"), ("SITE_NUM", "U1"), ("HBIN_NUM", "U2"), ("HBIN_CNT", "U4"),
+        ("HBIN_PF", "C1"), ("HBIN_NAM", "Cn"),
+    ),
+    "SBR": (
+        ("HEAD_NUM", "U1"), ("SITE_NUM", "U1"), ("SBIN_NUM", "U2"), ("SBIN_CNT", "U4"),
+        ("SBIN_PF", "C1"), ("SBIN_NAM", "Cn"),
+    ),
+    "PMR": (
+        ("PMR_INDX", "U2"), ("CHAN_TYP", "U2"), ("CHAN_NAM", "Cn"), ("PHY_NAM", "Cn"),
+        ("LOG_NAM", "Cn"), ("HEAD_NUM", "U1"), ("SITE_NUM", "U1"),
+    ),
+    "PGR": (
+        ("GRP_INDX", "U2"), ("GRP_NAM", "Cn"), ("INDX_CNT", "U2"),
+        ("PMR_INDX", "kU2", "INDX_CNT"),
+    ),
+    "PLR": (
+        ("GRP_CNT", "U2"), ("GRP_INDX", "kU2", "GRP_CNT"), ("GRP_MODE", "kU2", "GRP_CNT"),
+        ("GRP_RADX", "kU1", "GRP_CNT"), ("PGM_CHAR", "kCn", "GRP_CNT"),
+        ("RTN_CHAR", "kCn", "GRP_CNT"), ("PGM_CHAL", "kCn", "GRP_CNT"),
+        ("RTN_CHAL", "kCn", "GRP_CNT"),
+    ),
+    "RDR": (("NUM_BINS", "U2"), ("RTST_BIN", "kU2", "NUM_BINS")),
+    "SDR": (
+        ("HEAD_NUM", "U1"), ("SITE_GRP", "U1"), ("SITE_CNT", "U1"),
+        ("SITE_NUM", "kU1", "SITE_CNT"),
+    ) + _cn_fields(
+        "HAND_TYP", "HAND_ID", "CARD_TYP", "CARD_ID", "LOAD_TYP", "LOAD_ID", "DIB_TYP",
+        "DIB_ID", "CABL_TYP", "CABL_ID", "CONT_TYP", "CONT_ID", "LASR_TYP", "LASR_ID",
+        "EXTR_TYP", "EXTR_ID",
+    ),
+    "PSR": (
+        ("CONT_FLG", "B1"), ("PSR_INDX", "U2"), ("PSR_NAM", "Cn"), ("OPT_FLG", "B1"),
+        ("TOTP_CNT", "U2"), ("LOCP_CNT", "U2"), ("PAT_BGN", "kU8", "LOCP_CNT"),
+        ("PAT_END", "kU8", "LOCP_CNT"), ("PAT_FILE", "kCn", "LOCP_CNT"),
+        ("PAT_LBL", "kCn", "LOCP_CNT"), ("FILE_UID", "kCn", "LOCP_CNT"),
+        ("ATPG_DSC", "kCn", "LOCP_CNT"), ("SRC_ID", "kCn", "LOCP_CNT"),
+    ),
+    "NMR": (
+        ("CONT_FLG", "B1"), ("TOTM_CNT", "U2"), ("LOCM_CNT", "U2"),
+        ("PMR_INDX", "kU2", "LOCM_CNT"), ("ATPG_NAM", "kCn", "LOCM_CNT"),
+    ),
+    "CNR": (("CHN_NUM", "U2"), ("BIT_POS", "U4"), ("CELL_NAM", "Sn")),
+    "SSR": (("SSR_NAM", "Cn"), ("CHN_CNT", "U2"), ("CHN_LIST", "kU2", "CHN_CNT")),
+    "CDR": (
+        ("CONT_FLG", "B1"), ("CDR_INDX", "U2"), ("CHN_NAM", "Cn"), ("CHN_LEN", "U4"),
+        ("SIN_PIN", "U2"), ("SOUT_PIN", "U2"), ("MSTR_CNT", "U1"),
+        ("M_CLKS", "kU2", "MSTR_CNT"), ("SLAV_CNT", "U1"), ("S_CLKS", "kU2", "SLAV_CNT"),
+        ("INV_VAL", "U1"), ("LST_CNT", "U2"), ("CELL_LST", "kSn", "LST_CNT"),
+    ),
+    "WIR": (("HEAD_NUM", "U1"), ("SITE_GRP", "U1"), ("START_T", "U4"), ("WAFER_ID", "Cn")),
+    "WRR": (("HEAD_NUM", "U1"), ("SITE_GRP", "U1")) + tuple(
+        (name, "U4") for name in (
+            "FINISH_T", "PART_CNT", "RTST_CNT", "ABRT_CNT", "GOOD_CNT", "FUNC_CNT",
+        )
+    ) + _cn_fields("WAFER_ID", "FABWF_ID", "FRAME_ID", "MASK_ID", "USR_DESC", "EXC_DESC"),
+    "WCR": (
+        ("WAFR_SIZ", "R4"), ("DIE_HT", "R4"), ("DIE_WID", "R4"), ("WF_UNITS", "U1"),
+        ("WF_FLAT", "C1"), ("CENTER_X", "I2"), ("CENTER_Y", "I2"), ("POS_X", "C1"),
+        ("POS_Y", "C1"),
+    ),
+    "PIR": (("HEAD_NUM", "U1"), ("SITE_NUM", "U1")),
+    "PRR": (
+        ("HEAD_NUM", "U1"), ("SITE_NUM", "U1"), ("PART_FLG", "B1"), ("NUM_TEST", "U2"),
+        ("HARD_BIN", "U2"), ("SOFT_BIN", "U2"), ("X_COORD", "I2"), ("Y_COORD", "I2"),
+        ("TEST_T", "U4"), ("PART_ID", "Cn"), ("PART_TXT", "Cn"), ("PART_FIX", "Bn"),
+    ),
+    "TSR": (
+        ("HEAD_NUM", "U1"), ("SITE_NUM", "U1"), ("TEST_TYP", "C1"), ("TEST_NUM", "U4"),
+        ("EXEC_CNT", "U4"), ("FAIL_CNT", "U4"), ("ALRM_CNT", "U4"), ("TEST_NAM", "Cn"),
+        ("SEQ_NAME", "Cn"), ("TEST_LBL", "Cn"), ("OPT_FLAG", "B1"), ("TEST_TIM", "R4"),
+        ("TEST_MIN", "R4"), ("TEST_MAX", "R4"), ("TST_SUMS", "R4"), ("TST_SQRS", "R4"),
+    ),
+    "PTR": (
+        ("TEST_NUM", "U4"), ("HEAD_NUM", "U1"), ("SITE_NUM", "U1"), ("TEST_FLG", "B1"),
+        ("PARM_FLG", "B1"), ("RESULT", "R4"), ("TEST_TXT", "Cn"), ("ALARM_ID", "Cn"),
+        ("OPT_FLAG", "B1"), ("RES_SCAL", "I1"), ("LLM_SCAL", "I1"), ("HLM_SCAL", "I1"),
+        ("LO_LIMIT", "R4"), ("HI_LIMIT", "R4"), ("UNITS", "Cn"), ("C_RESFMT", "Cn"),
+        ("C_LLMFMT", "Cn"), ("C_HLMFMT", "Cn"), ("LO_SPEC", "R4"), ("HI_SPEC", "R4"),
+    ),
+    "MPR": (
+        ("TEST_NUM", "U4"), ("HEAD_NUM", "U1"), ("SITE_NUM", "U1"), ("TEST_FLG", "B1"),
+        ("PARM_FLG", "B1"), ("RTN_ICNT", "U2"), ("RSLT_CNT", "U2"),
+        ("RTN_STAT", "kN1", "RTN_ICNT"), ("RTN_RSLT", "kR4", "RSLT_CNT"),
+        ("TEST_TXT", "Cn"), ("ALARM_ID", "Cn"), ("OPT_FLAG", "B1"), ("RES_SCAL", "I1"),
+        ("LLM_SCAL", "I1"), ("HLM_SCAL", "I1"), ("LO_LIMIT", "R4"), ("HI_LIMIT", "R4"),
+        ("START_IN", "R4"), ("INCR_IN", "R4"), ("RTN_INDX", "kU2", "RTN_ICNT"),
+        ("UNITS", "Cn"), ("UNITS_IN", "Cn"), ("C_RESFMT", "Cn"), ("C_LLMFMT", "Cn"),
+        ("C_HLMFMT", "Cn"), ("LO_SPEC", "R4"), ("HI_SPEC", "R4"),
+    ),
+    "FTR": (
+        ("TEST_NUM", "U4"), ("HEAD_NUM", "U1"), ("SITE_NUM", "U1"), ("TEST_FLG", "B1"),
+        ("OPT_FLAG", "B1"), ("CYCL_CNT", "U4"), ("REL_VADR", "U4"), ("REPT_CNT", "U4"),
+        ("NUM_FAIL", "U4"), ("XFAIL_AD", "I4"), ("YFAIL_AD", "I4"), ("VECT_OFF", "I2"),
+        ("RTN_ICNT", "U2"), ("PGM_ICNT", "U2"), ("RTN_INDX", "kU2", "RTN_ICNT"),
+        ("RTN_STAT", "kN1", "RTN_ICNT"), ("PGM_INDX", "kU2", "PGM_ICNT"),
+        ("PGM_STAT", "kN1", "PGM_ICNT"), ("FAIL_PIN", "Dn"), ("VECT_NAM", "Cn"),
+        ("TIME_SET", "Cn"), ("OP_CODE", "Cn"), ("TEST_TXT", "Cn"), ("ALARM_ID", "Cn"),
+        ("PROG_TXT", "Cn"), ("RSLT_TXT", "Cn"), ("PATG_NUM", "U1"), ("SPIN_MAP", "Dn"),
+    ),
+    "BPS": (("SEQ_NAME", "Cn"),),
+    "EPS": (),
+    "GDR": (("FLD_CNT", "U2"), ("GEN_DATA", "kVn", "FLD_CNT")),
+    "DTR": (("TEXT_DAT", "Cn"),),
+}
+
+
+# Records STDFParser._decode_fields decodes, laid out by FIELD_LAYOUTS: how
+# many leading fields must be present, and the defaults differing from
+# _TYPE_DEFAULTS that the optional rest take once the record ends (writers
+# may drop trailing optional fields).
+_REQUIRED = object()
+# U*4 counts (PCR, WRR, TSR) set to this are "not reported"
+U4_MISSING = 4294967295
+_TYPE_DEFAULTS = {"U1": 0, "U2": 0, "U4": 0, "I2": -32768, "R4": 0.0, "C1": "", "Cn": ""}
+_DECODED_RECORDS = {
+    REC_ATR: (1, {}),
+    REC_PMR: (1, {"HEAD_NUM": 1, "SITE_NUM": 1}),
+    REC_MIR: (3, {}),
+    REC_MRR: (1, {"DISP_COD": " "}),
+    REC_PCR: (2, dict.fromkeys(
+        ("PART_CNT", "RTST_CNT", "ABRT_CNT", "GOOD_CNT", "FUNC_CNT"), U4_MISSING,
+    )),
+    REC_HBR: (4, {}),
+    REC_SBR: (4, {}),
+    REC_WIR: (1, {}),
+    REC_WRR: (1, {}),
+    REC_WCR: (1, {"WF_FLAT": " ", "POS_X": " ", "POS_Y": " "}),
+}
+# rec_key -> (field, reader method, default) in file order
+_RECORD_LAYOUTS = {
+    key: tuple(
+        (name.lower(), f"_read_{kind.lower()}",
+         _REQUIRED if i < required else defaults.get(name, _TYPE_DEFAULTS[kind]))
+        for i, (name, kind, *_) in enumerate(FIELD_LAYOUTS[REC_NAMES[key]])
+    )
+    for key, (required, defaults) in _DECODED_RECORDS.items()
+}
+
+
+# Fixed PTR header (FTR's is the same without PARM_FLG): (field, size)
+_PTR_HEADER = (("TEST_NUM", 4), ("HEAD_NUM", 1), ("SITE_NUM", 1), ("TEST_FLG", 1), ("PARM_FLG", 1))
+
+
+def _truncated_field(
+    fields: tuple[tuple[str, int], ...], available: int, eof: bool
+) -> STDFTruncatedError:
+    """The error for a fixed header of `fields` cut short after `available` bytes."""
+    pos = 0
+    for name, size in fields:
+        if pos + size > available:
+            return STDFTruncatedError(name, available - pos, size, eof)
+        pos += size
+    raise ValueError("header is complete")
+
+
+# PTR/MPR optional field explicitly without a value (OPT_FLAG "no limit" /
+# "no spec" bits), as opposed to None = take the default (see _with_defaults)
+_NO_VALUE = object()
+
+
+def _flagged(value, opt_flag: int | None, invalid_bit: int | None, none_bit: int | None):
+    """A PTR/MPR optional field as read -> value, None (use the default) or
+    _NO_VALUE. A missing OPT_FLAG means every field after it defaults."""
+    if opt_flag is None:
+        return None
+    if none_bit is not None and opt_flag & (1 << none_bit):
+        return _NO_VALUE
+    if invalid_bit is not None and opt_flag & (1 << invalid_bit):
+        return None
+    return value
+
+
+# Row tables whose rows carry the part_id of the part open when they were read
+PART_ROW_TABLES = ("test_results", "generic_data", "datalog_text", "scan_failures", "scan_tests")
+# ... and those that also carry wafer_id (backfilled from WRR, see _backfill_wafer_id)
+_WAFER_ROW_TABLES = PART_ROW_TABLES + ("parts", "chip_ids")
+
+
+def unique_part_id(part_id: str, taken: set[str]) -> str:
+    """part_id, or part_id-2, -3, ... if it is in `taken` (part ids in use)."""
+    n, unique = 1, part_id
+    while unique in taken:
+        n += 1
+        unique = f"{part_id}-{n}"
+    return unique
+
+
+def _unpack_nibbles(data: bytes, count: int) -> list[int]:
+    """Unpack `count` N*1 values (two per byte, low nibble first) from `data`."""
+    out = []
+    for byte in data:
+        out.append(byte & 0x0F)
+        out.append(byte >> 4)
+    del out[count:]
+    return out
+
+
+def rec_name(rec_typ: int, rec_sub: int) -> str:
+    """Record display name, e.g. "PTR"; unknown types render as "typ/sub"."""
+    return REC_NAMES.get((rec_typ, rec_sub), f"{rec_typ}/{rec_sub}")
+
+
+def sniff_byte_order(f: BinaryIO) -> tuple[str, bool]:
+    """Byte order ("<" / ">") of the STDF data at f's position, and whether a
+    FAR was found in its first _SNIFF_BYTES. The position is restored.
+
+    The FAR is usually the first record, but merge tools put ATR/GDR records
+    ahead of it and some files have none. The headers are walked in both byte
+    orders: a FAR (REC_LEN 2) met on the way decides by its CPU_TYPE; without
+    one, the order under which more consecutive headers are known record types
+    wins (little endian on a tie).
+    """
+    start = f.tell()
+    head = f.read(_SNIFF_BYTES)
+    f.seek(start)
+    best, best_known = "<", -1
+    for endian, s_u2 in (("<", _STRUCT_HEADER_LE), (">", _STRUCT_HEADER_BE)):
+        pos = known = 0
+        while pos + 4 <= len(head):
+            rec_len = s_u2.unpack_from(head, pos)[0]
+            rec_key = (head[pos + 2], head[pos + 3])
+            if rec_key == REC_FAR and rec_len == 2 and pos + 5 <= len(head):
+                return (">" if head[pos + 4] == 1 else "<"), True
+            if rec_key not in REC_NAMES:
+                break
+            known += 1
+            pos += 4 + rec_len
+        if known > best_known:
+            best, best_known = endian, known
+    return best, False
+
+
+# Any byte offset whose REC_TYP/REC_SUB bytes name a known record (lookahead,
+# so overlapping candidates are all found)
+_KNOWN_KEY_AT = re.compile(
+    b"(?=..(?:" + b"|".join(re.escape(bytes(key)) for key in REC_NAMES) + b"))", re.DOTALL
+)
+_RESYNC_CHUNK = 1024 * 1024
+
+
+def plausible_header(f: BinaryIO, pos: int, end: int, endian: str) -> bool:
+    """Whether a record header at `pos` looks real: a known record type (or a
+    vendor type, 180+) whose body ends at `end` or at another such header
+    that fits before `end`. f's position is left undefined."""
+    s_header = _STRUCT_HEADER_LE if endian == "<" else _STRUCT_HEADER_BE
+    for _ in range(2):  # this header, then the next one
+        f.seek(pos)
+        head = f.read(4)
+        if len(head) < 4:
+            return False
+        rec_len = s_header.unpack_from(head)[0]
+        rec_key = (head[2], head[3])
+        if rec_key not in REC_NAMES and rec_key[0] < 180:
+            return False
+        if rec_len == 0 and rec_key not in (REC_EPS, REC_BPS) and rec_key[0] < 180:
+            return False
+        pos += 4 + rec_len
+        if pos >= end:
+            return pos == end
+    return True
+
+
+def find_record_header(f: BinaryIO, start: int, end: int, endian: str) -> int | None:
+    """Offset of the first plausible record header of a known type in
+    [start, end) (see plausible_header), or None — how a desynchronized
+    stream finds its way back onto record boundaries. f's position is left
+    undefined."""
+    pos = start
+    while pos + 4 <= end:
+        f.seek(pos)
+        chunk = f.read(min(_RESYNC_CHUNK, end - pos))
+        for m in _KNOWN_KEY_AT.finditer(chunk):
+            cand = pos + m.start()
+            if cand + 4 <= end and plausible_header(f, cand, end, endian):
+                return cand
+        if len(chunk) < 4:
+            break
+        pos += len(chunk) - 3  # a header may straddle the chunk boundary
+    return None
 
 
 class STDFParser:
     """Binary STDF V4 parser with pre-compiled struct objects for performance."""
 
-    def __init__(self):
+    def __init__(self, options: ParseOptions | None = None):
+        self.options = options or ParseOptions()
+        # (rec_typ, rec_sub) decoded under ParseOptions.records (None = all)
+        self._decode_keys: set[tuple[int, int]] | None = None
+        if self.options.records is not None:
+            unknown = set(self.options.records) - set(REC_NAMES.values())
+            if unknown:
+                raise ValueError(f"unknown record types: {', '.join(sorted(unknown))}")
+            self._decode_keys = {
+                key for key, name in REC_NAMES.items() if name in self.options.records
+            } | {REC_FAR}
         self.data = STDFData()
         self._part_counter = 0
-        self._cached_part_id = ""  # reused across all test results for current part
+        self._cached_part_id = ""  # part_id of the latest PIR (records without head/site)
         self._current_chip_efuses = []  # EN-SO-CHIPID_R values for the current DUT
+        self._tsr_names: dict[int, str] = {}  # test_num -> TSR TEST_NAM (name fallback)
+        # (head, site) -> [test records, failing test records] since that site's PIR
+        self._site_counts: dict[tuple[int, int], list[int]] = {}
+        # (head, site) with PIR but no PRR yet -> the part_id its rows carry so far
+        self._open_parts: dict[tuple[int, int], str] = {}
+        self._open_wafer = False  # WIR seen without its WRR
+        self._mrr_seen = False
+        self._file_path: Path | None = None
+        self._lot_resolved = False  # MIR seen, or the missing-MIR fallback applied
+        self._rec_key: tuple[int, int] | None = None  # record being decoded (diagnostics)
+        self._rec_start = 0  # file offset of that record's header
+        self._sections: list[str] = []  # BPS SEQ_NAMEs of the open program sections
+        self._section = ""  # their "/"-joined path, stamped on test results
+        # (test, head, site) -> scan_tests row of an STR awaiting its continuation
+        self._open_scans: dict[tuple[int, int, int], dict] = {}
+        # (head, site) -> {table: row count at its PIR}, to relabel the part's
+        # rows when its PRR carries a tester PART_ID (see _close_part)
+        self._part_rows: dict[tuple[int, int], dict[str, int]] = {}
+        self._part_ids: set[str] = set()  # part_ids handed out so far
+        self._wafer_rows: dict[str, int] = {}  # {table: row count} at the open wafer's WIR
+        # part consumer set by stream_parts (None = keep parts in data.parts)
+        self._part_handler: Callable[[dict, list[dict]], Any] | None = None
+        self._parts_ready = False  # closed parts waiting for _flush_parts
+        # Drop closed parts at each flush (ParseOptions.collect_parts False)
+        self._drop_parts = not self.options.collect_parts
+        self._results_flushed = 0  # test_results rows handed to stream_parts
+        self._results_capped = False  # ParseOptions.max_results reached
+        # wafer consumer set by stream_wafers, and closed wafers waiting for it
+        self._wafer_handler: Callable[[dict], Any] | None = None
+        self._wafers_ready: list[dict] = []
+        # (rec_typ, rec_sub) -> user decoder (see register_record); kept across parses
+        self._record_handlers: dict[tuple[int, int], Callable[[bytes, str], Any]] = {}
+        self._far_seen = False
+        self._bin_summaries: set[tuple[str, int]] = set()  # (H|S, bin) with a head-255 HBR/SBR
+        # (test_num, head, site) -> PTR/MPR optional field values to default to
+        self._value_defaults: dict[tuple[int, int, int], list] = {}
+        # raw C*n bytes -> decoded str (ASCII only), see _decode_cn
+        self._cn_cache: dict[bytes, str] = {}
         self._set_endian("<")  # Little endian by default
 
+    def register_record(
+        self, rec_typ: int, rec_sub: int, handler: Callable[[bytes, str], Any],
+    ) -> "STDFParser":
+        """Decode a vendor record type (REC_TYP 180-255) with `handler`.
+
+        handler(body, endian) gets the record body and "<"/">" (the FAR CPU
+        type) and returns whatever it decodes; non-None values are collected
+        in data.user_records[(rec_typ, rec_sub)]. An exception is handled like
+        any decoder failure (counted in stats.error_records, parse continues).
+        Returns the parser, so registrations chain.
+        """
+        if not 180 <= rec_typ <= 255:
+            raise ValueError(f"REC_TYP {rec_typ} is not a vendor record type (180-255)")
+        self._record_handlers[(rec_typ, rec_sub)] = handler
+        return self
+
+    def stream_parts(self, handler: Callable[[dict, list[dict]], Any]) -> "STDFParser":
+        """Hand each part to handler(part, results) instead of keeping it.
+
+        Parts go out in file order once no part is open (after the last PRR of
+        a touchdown), with their data.test_results rows, and are then dropped:
+        data.parts / data.test_results stay about one touchdown long however
+        big the file. Results outside any part are dropped too, and tables
+        derived from data.parts at the end (wafer_bins, counts of wafers
+        without a WRR) don't cover streamed parts. Exceptions from the handler
+        end the parse. Returns the parser, like register_record.
+        """
+        self._part_handler = handler
+        return self
+
+    def stream_wafers(self, handler: Callable[[dict], Any]) -> "STDFParser":
+        """Also hand each wafer row to handler(wafer) once it is closed (its WRR,
+        or where it is closed without one), after the stream_parts handler got
+        its parts. Wafers stay in data.wafers. Returns the parser.
+        """
+        self._wafer_handler = handler
+        return self
+
+    def _flush_wafers(self):
+        """Pass the closed wafers to the stream_wafers handler."""
+        wafers, self._wafers_ready = self._wafers_ready, []
+        for wafer in wafers:
+            self._wafer_handler(wafer)
+
+    def _flush_parts(self):
+        """Pass the closed parts and their results to the stream_parts handler
+        (without one — ParseOptions.collect_parts False — drop the parts)."""
+        self._parts_ready = False
+        parts, results = self.data.parts, self.data.test_results
+        if self._part_handler is None:
+            parts.clear()
+            if "parts" in self._wafer_rows:
+                self._wafer_rows["parts"] = 0
+            return
+        self._results_flushed += len(results)
+        # Keyed by the closing PRR's head / site too: a part's rows can't go
+        # to another site's part whatever their part_ids
+        by_part: dict[tuple[str, int, int], list[dict]] = {}
+        for row in results:
+            by_part.setdefault(
+                (row["part_id"], row["head_num"], row["site_num"]), []
+            ).append(row)
+        for part in parts:
+            key = (part["part_id"], part["head_num"], part["site_num"])
+            self._part_handler(part, by_part.get(key, []))
+        parts.clear()
+        results.clear()
+        for name in ("parts", "test_results"):
+            if name in self._wafer_rows:
+                self._wafer_rows[name] = 0
+
     def _set_endian(self, endian: str):
         """Set endianness and rebuild all pre-compiled struct objects."""
+        self._endian = endian
         self._s_u1 = struct.Struct(endian + "B")
         self._s_u2 = struct.Struct(endian + "H")
         self._s_u4 = struct.Struct(endian + "I")
         self._s_i1 = struct.Struct(endian + "b")
         self._s_i2 = struct.Struct(endian + "h")
+        self._s_i4 = struct.Struct(endian + "i")
         self._s_r4 = struct.Struct(endian + "f")
+        self._s_r8 = struct.Struct(endian + "d")
+        self._s_u8 = struct.Struct(endian + "Q")
         # Pre-compiled headers for hot-path record types
         self._s_ftr_hdr = struct.Struct(endian + "IBBB")   # test_num, head, site, test_flg
+        # CYCL_CNT, REL_VADR, REPT_CNT, NUM_FAIL, XFAIL_AD, YFAIL_AD, VECT_OFF
+        self._s_ftr_fixed = struct.Struct(endian + "IIIIiih")
         self._s_ptr_hdr = struct.Struct(endian + "IBBBB")  # test_num, head, site, test_flg, parm_flg
 
     def _read_u1(self, f: BinaryIO) -> int:
         data = f.read(1)
         if len(data) < 1:
-            raise EOFError()
+            raise EOFError(len(data), 1)
         return self._s_u1.unpack(data)[0]
 
     def _read_u2(self, f: BinaryIO) -> int:
         data = f.read(2)
         if len(data) < 2:
-            raise EOFError()
+            raise EOFError(len(data), 2)
         return self._s_u2.unpack(data)[0]
 
     def _read_u4(self, f: BinaryIO) -> int:
         data = f.read(4)
         if len(data) < 4:
-            raise EOFError()
+            raise EOFError(len(data), 4)
         return self._s_u4.unpack(data)[0]
 
     def _read_i1(self, f: BinaryIO) -> int:
         data = f.read(1)
         if len(data) < 1:
-            raise EOFError()
+            raise EOFError(len(data), 1)
         return self._s_i1.unpack(data)[0]
 
     def _read_i2(self, f: BinaryIO) -> int:
         data = f.read(2)
         if len(data) < 2:
-            raise EOFError()
+            raise EOFError(len(data), 2)
         return self._s_i2.unpack(data)[0]
 
+    def _read_i4(self, f: BinaryIO) -> int:
+        data = f.read(4)
+        if len(data) < 4:
+            raise EOFError(len(data), 4)
+        return self._s_i4.unpack(data)[0]
+
     def _read_r4(self, f: BinaryIO) -> float:
         data = f.read(4)
         if len(data) < 4:
-            raise EOFError()
+            raise EOFError(len(data), 4)
         return self._s_r4.unpack(data)[0]
 
+    def _read_r8(self, f: BinaryIO) -> float:
+        data = f.read(8)
+        if len(data) < 8:
+            raise EOFError(len(data), 8)
+        return self._s_r8.unpack(data)[0]
+
+    def _read_u8(self, f: BinaryIO) -> int:
+        data = f.read(8)
+        if len(data) < 8:
+            raise EOFError(len(data), 8)
+        return self._s_u8.unpack(data)[0]
+
+    def _read_uf_array(self, f: BinaryIO, count: int, size: int) -> list[int]:
+        """Read `count` unsigned ints of `size` bytes each (kxU1/kxU2 arrays, STR's xU*f)."""
+        if count == 0:
+            return []  # empty arrays may declare size 0
+        code = {1: "B", 2: "H", 4: "I", 8: "Q"}.get(size)
+        if code is None:
+            raise ValueError(f"unsupported U*f size {size}")
+        nbytes = count * size
+        data = f.read(nbytes)
+        if len(data) < nbytes:
+            raise EOFError(len(data), nbytes)
+        return list(struct.unpack(f"{self._endian}{count}{code}", data))
+
+    def _read_cn_array(self, f: BinaryIO, count: int) -> list[str]:
+        """Read a kxC*n array (`count` length-prefixed strings)."""
+        return [self._read_cn(f) for _ in range(count)]
+
+    def _read_sn(self, f: BinaryIO) -> str:
+        """Read an S*n string (U*2 length; V4-2007 CNR). UTF-8, invalid bytes replaced."""
+        length = self._read_u2(f)
+        raw = f.read(length)
+        if len(raw) < length:
+            raise EOFError(len(raw), length)
+        return raw.decode("utf-8", errors="replace").replace("\x00", "").strip()
+
+    def _read_bn(self, f: BinaryIO) -> bytes:
+        """Read a B*n field (U*1 byte count, then the bytes)."""
+        length = self._read_u1(f)
+        data = f.read(length)
+        if len(data) < length:
+            raise EOFError(len(data), length)
+        return data
+
+    def _read_dn(self, f: BinaryIO) -> bytes:
+        """Read a D*n bit field (U*2 bit count, then ceil(count/8) bytes)."""
+        nbytes = (self._read_u2(f) + 7) // 8
+        data = f.read(nbytes)
+        if len(data) < nbytes:
+            raise EOFError(len(data), nbytes)
+        return data
+
+    def _read_nibbles(self, f: BinaryIO, count: int) -> list[int]:
+        """Read an array of `count` N*1 values (ceil(count/2) bytes)."""
+        nbytes = (count + 1) // 2
+        data = f.read(nbytes)
+        if len(data) < nbytes:
+            raise EOFError(len(data), nbytes)
+        return _unpack_nibbles(data, count)
+
+    def _read_c1(self, f: BinaryIO) -> str:
+        """Read a C*1 character."""
+        return chr(self._read_u1(f))
+
+    def _decode_fields(self, f: BinaryIO, rec_len: int, rec_key: tuple[int, int]) -> dict:
+        """Decode a record laid out in _RECORD_LAYOUTS into {field: value}."""
+        start_pos = f.tell()
+        end = start_pos + rec_len
+        fields = {}
+        for name, reader, default in _RECORD_LAYOUTS[rec_key]:
+            pos = f.tell()
+            if default is not _REQUIRED and pos >= end:
+                fields[name] = default
+                continue
+            try:
+                fields[name] = getattr(self, reader)(f)
+            except EOFError as e:
+                # readers raise EOFError(got, size) for their last short read
+                got = f.tell() - pos
+                size = got + e.args[1] - e.args[0] if len(e.args) == 2 else got + 1
+                raise STDFTruncatedError(name.upper(), got, size, eof=True) from None
+            if f.tell() > end:
+                raise STDFTruncatedError(name.upper(), max(end - pos, 0), f.tell() - pos)
+        return fields
+
+    def _read_cf(self, f: BinaryIO, length: int) -> str:
+        """Read a C*f string of `length` bytes (ASCII, as _decode_cn; trailing
+        spaces trimmed unless ParseOptions.trim_cf is off)."""
+        offset = f.tell()
+        raw = f.read(length)
+        if len(raw) < length:
+            raise EOFError(len(raw), length)
+        if not raw.isascii():
+            self._note_lossy(raw, offset)
+        text = raw.decode("ascii", errors="replace").replace("\x00", "")
+        return text.rstrip(" ") if self.options.trim_cf else text
+
     def _read_cn(self, f: BinaryIO) -> str:
         """Read character string (length-prefixed)."""
+        offset = f.tell()
         length = self._read_u1(f)
         if length == 0:
             return ""
-        data = f.read(length)
-        try:
-            return data.decode("ascii", errors="replace").replace("\x00", "").strip()
-        except Exception:
-            return ""
+        raw = f.read(length)
+        if len(raw) < length:
+            raise EOFError(len(raw), length)
+        return self._decode_cn(raw, offset)
+
+    def _decode_cn(self, raw: bytes | memoryview, offset: int) -> str:
+        """Decode the C*n field at file `offset` (its length byte) from its data bytes.
+
+        ASCII; see ParseOptions.string_errors for non-ASCII data. Test names,
+        units and the like repeat on every part, so decoded strings are cached
+        by their bytes: a repeat costs one lookup and shares one str object. A
+        read-only memoryview (a slice of a record body) looks up without
+        copying.
+        """
+        text = self._cn_cache.get(raw)
+        if text is not None:
+            return text
+        raw = bytes(raw)
+        if not raw.isascii():
+            self._note_lossy(raw, offset)  # not cached: counted per occurrence
+            return raw.decode("ascii", errors="replace").replace("\x00", "").strip()
+        text = raw.decode("ascii").replace("\x00", "").strip()
+        if len(self._cn_cache) >= _CN_CACHE_MAX:
+            self._cn_cache.clear()
+        self._cn_cache[raw] = text
+        return text
+
+    def _note_lossy(self, raw: bytes, offset: int):
+        """Count (and per string_errors, report or reject) a non-ASCII C*n field."""
+        name = rec_name(*self._rec_key) if self._rec_key else "?"
+        stats = self.data.stats
+        stats.lossy_strings[name] = stats.lossy_strings.get(name, 0) + 1
+        mode = self.options.string_errors
+        if mode == "replace":
+            return
+        msg = (
+            f"{name} at offset 0x{self._rec_start:X}: C*n field at +{offset - self._rec_start - 4} "
+            f"is not ASCII ({raw[:16]!r})"
+        )
+        if mode == "strict":
+            raise STDFDecodeError(msg, self._rec_start)
+        self._warn(msg + ", decoded with replacement characters", "string", name, self._rec_start)
 
     def _read_header(self, f: BinaryIO) -> tuple[int, int, int]:
         """Read 4-byte record header. Returns (rec_len, rec_typ, rec_sub)."""
         data = f.read(4)
         if len(data) < 4:
-            raise EOFError()
+            raise EOFError(len(data), 4)
         rec_len = self._s_u2.unpack(data[0:2])[0]
         rec_typ = data[2]
         rec_sub = data[3]
         return rec_len, rec_typ, rec_sub
 
+    def _warn(self, msg: str, kind: str, rec: str = "", offset: int | None = None):
+        """Record a non-fatal parse finding (also logged at debug level)."""
+        logger.debug(msg)
+        warnings = self.data.warnings
+        if len(warnings) < _MAX_WARNINGS:
+            warnings.append(msg)
+        elif len(warnings) == _MAX_WARNINGS:
+            warnings.append("further warnings suppressed")
+        self._diagnose(kind, msg, rec, offset)
+
+    def _diagnose(self, kind: str, msg: str, rec: str = "", offset: int | None = None):
+        """Add a Diagnostic (see _warn; some findings are diagnostics only)."""
+        if len(self.data.diagnostics) < _MAX_WARNINGS:
+            self.data.diagnostics.append(Diagnostic(kind, msg, rec, offset))
+
+    def _fault(self, error: STDFError, kind: str, rec: str = ""):
+        """A malformed-file finding: raised under ParseOptions.strict, else a warning."""
+        if self.options.strict:
+            raise error
+        self._warn(str(error), kind, rec or getattr(error, "rec", ""), error.offset)
+
+    def _note_overrun(
+        self, rec_key: tuple[int, int], offset: int, needed: int | None, rec_len: int
+    ):
+        """Flag a record whose fields needed more than REC_LEN bytes.
+
+        needed=None means the file ended inside the record (truncated file).
+        """
+        name = rec_name(*rec_key)
+        overruns = self.data.stats.overrun_records
+        overruns[name] = overruns.get(name, 0) + 1
+        if needed is None:
+            msg = f"{name} at offset 0x{offset:X}: truncated by end of file"
+        else:
+            msg = (
+                f"{name} at offset 0x{offset:X}: fields need {needed} bytes "
+                f"but REC_LEN is {rec_len}"
+            )
+        kind = "truncated" if needed is None else "overrun"
+        self._fault(STDFRecordError(msg, name, offset), kind)
+
+    def _note_truncated(self, rec_key: tuple[int, int], offset: int, err: STDFTruncatedError):
+        """Flag a record one of whose fields runs past REC_LEN or the file."""
+        name = rec_name(*rec_key)
+        overruns = self.data.stats.overrun_records
+        overruns[name] = overruns.get(name, 0) + 1
+        where = " by end of file" if err.eof else ""
+        self._fault(STDFRecordError(
+            f"{name} at offset 0x{offset:X}: {err.field} field truncated{where} "
+            f"({err.got} of {err.size} bytes)", name, offset,
+        ), "truncated")
+
+    def _note_order(self, msg: str):
+        """Flag the current record for breaking an STDF sequencing rule."""
+        name = rec_name(*self._rec_key) if self._rec_key else "?"
+        stats = self.data.stats
+        stats.order_violations[name] = stats.order_violations.get(name, 0) + 1
+        self._warn(f"{self._here()}: {msg}", "order", name, self._rec_start)
+
+    def _desynced(
+        self, f: BinaryIO, pos: int, rec_len: int, rec_key: tuple[int, int], end: int
+    ) -> bool:
+        """Whether the record header at `pos` is implausible: its body runs
+        past the end of the stream, or it has an unknown type below the vendor
+        range and no plausible header follows it. f's position is kept."""
+        body_end = pos + 4 + rec_len
+        if body_end > end:
+            return True
+        if rec_key in REC_NAMES or rec_key[0] >= 180 or body_end == end:
+            return False
+        ok = plausible_header(f, body_end, end, self._endian)
+        f.seek(pos + 4)
+        return not ok
+
+    def _resync(
+        self, f: BinaryIO, pos: int, rec_len: int, rec_key: tuple[int, int], end: int
+    ) -> bool:
+        """Scan forward from the implausible header at `pos` to the next
+        plausible one and continue there. False (f's position kept) when
+        there is none: the header is then taken at face value, as before."""
+        if self.options.strict:
+            raise STDFRecordError(
+                f"offset 0x{pos:X}: implausible record header ({rec_name(*rec_key)}, "
+                f"REC_LEN {rec_len})", rec_name(*rec_key), pos,
+            )
+        found = find_record_header(f, pos + 1, end, self._endian)
+        if found is None:
+            f.seek(pos + 4)
+            return False
+        stats = self.data.stats
+        stats.resync_count += 1
+        stats.resync_bytes += found - pos
+        f.seek(found)
+        head = f.read(4)
+        f.seek(found)
+        self._warn(
+            f"offset 0x{pos:X}: implausible record header ({rec_name(*rec_key)}, "
+            f"REC_LEN {rec_len}) — skipped {found - pos:,} bytes to the "
+            f"{rec_name(head[2], head[3])} at offset 0x{found:X}",
+            "resync", rec_name(*rec_key), pos,
+        )
+        return True
+
+    def _note_leftover(self, rec_key: tuple[int, int], nbytes: int):
+        """Count bytes a decoder left un-consumed at the end of its record."""
+        name = rec_name(*rec_key)
+        stats = self.data.stats
+        stats.leftover_bytes[name] = stats.leftover_bytes.get(name, 0) + nbytes
+        stats.leftover_records[name] = stats.leftover_records.get(name, 0) + 1
+        msg = f"{self._here()}: {nbytes} byte(s) after its last field"
+        if self.options.strict:
+            raise STDFRecordError(msg, name, self._rec_start)
+        self._diagnose("leftover", msg, name, self._rec_start)
+
     def _parse_far(self, f: BinaryIO, rec_len: int):
         """Parse File Attributes Record — sets endianness for all subsequent reads."""
         cpu_type = self._read_u1(f)
-        _stdf_ver = self._read_u1(f)
+        stdf_ver = self._read_u1(f)
+        if stdf_ver != 4:
+            self._fault(STDFVersionError(stdf_ver, self._rec_start), "version")
         self._set_endian(">" if cpu_type == 1 else "<")
+        if self.data.stats.record_count > 1 and not self._far_seen:
+            self._note_order("FAR is not the first record")
+        self._far_seen = True
+        if not self.data.version_updates:
+            self.data.spec_version = f"V{stdf_ver}"
+
+    def _parse_vur(self, f: BinaryIO, rec_len: int):
+        """Parse Version Update Record — which STDF extensions the file uses.
+
+        The V4-2007 spec has a single UPD_NAM C*n; its later errata (and most
+        writers since) use UPD_CNT plus a kxC*n list. The list form is tried
+        first and kept if it consumes the record exactly.
+        """
+        start_pos = f.tell()
+        body = f.read(rec_len)
+        spans, pos = [], 1  # (start, end) of each UPD_NAM in the list form
+        for _ in range(body[0] if body else 0):
+            end = pos + 1 + body[pos] if pos < len(body) else len(body) + 1
+            spans.append((pos + 1, end))
+            pos = end
+        if pos != len(body):  # not the list form: a single C*n
+            spans = [(1, 1 + body[0])] if body else []
+        names = [self._decode_cn(body[i:j], start_pos + i - 1) for i, j in spans]
+        names = [name for name in names if name]
+        self.data.version_updates.extend(names)
+        if any(name.upper().startswith("V4-2007") for name in names):
+            self.data.spec_version = "V4-2007"
+
+    def _parse_atr(self, f: BinaryIO, rec_len: int):
+        """Parse Audit Trail Record — one entry per tool that modified the file."""
+        fields = self._decode_fields(f, rec_len, REC_ATR)
+        self.data.audit_trail.append({"mod_time": fields["mod_tim"], "cmd_line": fields["cmd_line"]})
 
     def _parse_pmr(self, f: BinaryIO, rec_len: int):
         """Parse Pin Map Record — builds pin_map index → name for MPR resolution."""
-        start_pos = f.tell()
-        pmr_indx = self._read_u2(f)
-        _chan_typ = self._read_u2(f) if f.tell() - start_pos < rec_len else 0
-        chan_nam = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        phy_nam = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        log_nam = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
+        pin = self._decode_fields(f, rec_len, REC_PMR)
+        pmr_indx = pin.pop("pmr_indx")
         # Prefer LOG_NAM → PHY_NAM → CHAN_NAM as the human-readable pin name
-        pin_name = log_nam or phy_nam or chan_nam
-        self.data.pin_map[pmr_indx] = pin_name
-        remaining = rec_len - (f.tell() - start_pos)
-        if remaining > 0:
-            f.read(remaining)
+        self.data.pin_map[pmr_indx] = pin["log_nam"] or pin["phy_nam"] or pin["chan_nam"]
+        self.data.pins[pmr_indx] = pin
+
+    def _parse_pgr(self, f: BinaryIO, rec_len: int):
+        """Parse Pin Group Record — group index → name and member PMR indexes."""
+        start_pos = f.tell()
+        grp_indx = self._read_u2(f)
+        grp_nam = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
+        indx_cnt = self._read_u2(f) if f.tell() - start_pos < rec_len else 0
+        pmr_indexes = [self._read_u2(f) for _ in range(indx_cnt)]
+        self.data.pin_groups[grp_indx] = {"grp_nam": grp_nam, "pmr_indexes": pmr_indexes}
+
+    def _parse_plr(self, f: BinaryIO, rec_len: int):
+        """Parse Pin List Record — how datalogs display each pin/group's states.
+
+        GRP_MODE (0x10 = normal, 0x20 = SCIO, ...) and GRP_RADX (0 = default,
+        2/8/10/16 = bin/oct/dec/hex, 20 = symbolic) per index; PGM/RTN_CHAR and
+        _CHAL are the state characters (first/second char when two per state).
+        """
+        start_pos = f.tell()
+        grp_cnt = self._read_u2(f)
+        grp_indx = self._read_uf_array(f, grp_cnt, 2)
+
+        def optional(read):
+            return read() if f.tell() - start_pos < rec_len else []
+
+        grp_mode = optional(lambda: self._read_uf_array(f, grp_cnt, 2))
+        grp_radx = optional(lambda: self._read_uf_array(f, grp_cnt, 1))
+        pgm_char, rtn_char, pgm_chal, rtn_chal = (
+            optional(lambda: self._read_cn_array(f, grp_cnt)) for _ in range(4)
+        )
+
+        def at(values: list, i: int, default):
+            return values[i] if i < len(values) else default
+
+        for i, index in enumerate(grp_indx):
+            self.data.pin_lists[index] = {
+                "mode": at(grp_mode, i, 0),
+                "radix": at(grp_radx, i, 0),
+                "pgm_char": at(pgm_char, i, ""),
+                "rtn_char": at(rtn_char, i, ""),
+                "pgm_chal": at(pgm_chal, i, ""),
+                "rtn_chal": at(rtn_chal, i, ""),
+            }
+
+    def _parse_sdr(self, f: BinaryIO, rec_len: int):
+        """Parse Site Description Record — handler/probe card/load board/... per site group."""
+        start_pos = f.tell()
+        head_num = self._read_u1(f)
+        site_grp = self._read_u1(f)
+        site_cnt = self._read_u1(f)
+        site_nums = self._read_uf_array(f, site_cnt, 1)
+        row = {"head_num": head_num, "site_grp": site_grp, "site_nums": site_nums}
+        # HAND, CARD (probe card), LOAD (load board), DIB, CABL, CONT (contactor),
+        # LASR, EXTR (extra equipment): a type then an ID string each
+        for kind in ("hand", "card", "load", "dib", "cabl", "cont", "lasr", "extr"):
+            for suffix in ("typ", "id"):
+                row[f"{kind}_{suffix}"] = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
+        self.data.site_descriptions.append(row)
+
+    def _parse_psr(self, f: BinaryIO, rec_len: int):
+        """Parse Pattern Sequence Record (V4-2007) into data.pattern_sequences.
+
+        A sequence longer than one record continues in further PSRs with the
+        same PSR_INDX (CONT_FLG bit 0); their LOCP_CNT patterns are appended.
+        OPT_FLG bits 0-3 mark PAT_LBL/FILE_UID/ATPG_DSC/SRC_ID as not present.
+        """
+        start_pos = f.tell()
+        _cont_flg = self._read_u1(f)
+        psr_indx = self._read_u2(f)
+        psr_nam = self._read_cn(f)
+        opt_flg = self._read_u1(f)
+        totp_cnt = self._read_u2(f)
+        locp_cnt = self._read_u2(f)
+        pat_bgn = self._read_uf_array(f, locp_cnt, 8)
+        pat_end = self._read_uf_array(f, locp_cnt, 8)
+        pat_file = self._read_cn_array(f, locp_cnt)
+        texts = {}
+        for bit, name in enumerate(("label", "file_uid", "atpg_desc", "src_id")):
+            present = not opt_flg & (1 << bit) and f.tell() - start_pos < rec_len
+            texts[name] = self._read_cn_array(f, locp_cnt) if present else []
+
+        sequence = self.data.pattern_sequences.setdefault(psr_indx, {
+            "name": psr_nam, "total_patterns": totp_cnt, "patterns": [],
+        })
+        for i in range(locp_cnt):
+            pattern = {"begin": pat_bgn[i], "end": pat_end[i], "file": pat_file[i]}
+            for name, values in texts.items():
+                pattern[name] = values[i] if i < len(values) else None
+            sequence["patterns"].append(pattern)
+
+    def _parse_nmr(self, f: BinaryIO, rec_len: int):
+        """Parse Name Map Record (V4-2007) — full ATPG names for PMR indexes.
+
+        Long maps continue in further NMRs (CONT_FLG bit 0); each carries its
+        own LOCM_CNT entries, so records are simply merged.
+        """
+        _cont_flg = self._read_u1(f)
+        _totm_cnt = self._read_u2(f)
+        locm_cnt = self._read_u2(f)
+        pmr_indx = self._read_uf_array(f, locm_cnt, 2)
+        for index, name in zip(pmr_indx, self._read_cn_array(f, locm_cnt)):
+            if name:
+                self.data.atpg_names[index] = name
+
+    def _parse_cnr(self, f: BinaryIO, rec_len: int):
+        """Parse Cell Name Record (V4-2007) — the scan cell at a chain bit position."""
+        chn_num = self._read_u2(f)
+        bit_pos = self._read_u4(f)
+        self.data.cell_names[(chn_num, bit_pos)] = self._read_sn(f)
+
+    def _parse_ssr(self, f: BinaryIO, rec_len: int):
+        """Parse Scan Structure Record (V4-2007) — a named set of CDR chains."""
+        ssr_nam = self._read_cn(f)
+        chn_cnt = self._read_u2(f)
+        self.data.scan_structures.append({
+            "name": ssr_nam,
+            "chains": self._read_uf_array(f, chn_cnt, 2),
+        })
+
+    def _parse_cdr(self, f: BinaryIO, rec_len: int):
+        """Parse Chain Description Record (V4-2007) into data.scan_chains.
+
+        SIN_PIN/SOUT_PIN and the clock lists are PMR indexes. A cell list too
+        long for one record continues in further CDRs with the same CDR_INDX
+        (CONT_FLG bit 0); their cells are appended.
+        """
+        _cont_flg = self._read_u1(f)
+        cdr_indx = self._read_u2(f)
+        chn_nam = self._read_cn(f)
+        chn_len = self._read_u4(f)
+        sin_pin = self._read_u2(f)
+        sout_pin = self._read_u2(f)
+        m_clks = self._read_uf_array(f, self._read_u1(f), 2)
+        s_clks = self._read_uf_array(f, self._read_u1(f), 2)
+        inv_val = self._read_u1(f)
+        cells = [self._read_sn(f) for _ in range(self._read_u2(f))]
+
+        chain = self.data.scan_chains.setdefault(cdr_indx, {
+            "name": chn_nam,
+            "length": chn_len,
+            "sin_pin": sin_pin,
+            "sout_pin": sout_pin,
+            "master_clocks": m_clks,
+            "slave_clocks": s_clks,
+            "inv_val": inv_val,
+            "cells": [],
+        })
+        chain["cells"].extend(cells)
+
+    def _parse_rdr(self, f: BinaryIO, rec_len: int):
+        """Parse Retest Data Record — the hardware bins this (retest) file re-tests."""
+        num_bins = self._read_u2(f)
+        self.data.retest_bins = self._read_uf_array(f, num_bins, 2)
 
     def _parse_mir(self, f: BinaryIO, rec_len: int):
         """Parse Master Information Record."""
-        start_pos = f.tell()
-        
-        setup_t = self._read_u4(f)
-        start_t = self._read_u4(f)
-        stat_num = self._read_u1(f)
-        mode_cod = chr(self._read_u1(f)) if f.tell() - start_pos < rec_len else ""
-        rtst_cod = chr(self._read_u1(f)) if f.tell() - start_pos < rec_len else ""
-        prot_cod = chr(self._read_u1(f)) if f.tell() - start_pos < rec_len else ""
-        burn_tim = self._read_u2(f) if f.tell() - start_pos < rec_len else 0
-        cmod_cod = chr(self._read_u1(f)) if f.tell() - start_pos < rec_len else ""
-        lot_id = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        part_typ = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        node_nam = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        tstr_typ = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        job_nam = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        job_rev = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        
-        # Additional optional fields
-        sblot_id = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        oper_nam = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        exec_typ = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        exec_ver = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        test_cod = self._read_cn(f) if f.tell() - start_pos < rec_len else ""  # CP1, FT2等
-
-        self.data.lot_id = lot_id
-        self.data.part_type = part_typ
-        self.data.job_name = job_nam
-        self.data.job_rev = job_rev
-        self.data.start_time = start_t
-        self.data.tester_type = tstr_typ
-        self.data.operator = oper_nam
-        self.data.test_code = test_cod
-
-        # Skip remaining optional fields
-        remaining = rec_len - (f.tell() - start_pos)
-        if remaining > 0:
-            f.read(remaining)
+        mir = self._decode_fields(f, rec_len, REC_MIR)
+        self.data.lot_id = mir["lot_id"]
+        self.data.part_type = mir["part_typ"]
+        self.data.job_name = mir["job_nam"]
+        self.data.job_rev = mir["job_rev"]
+        self.data.start_time = mir["start_t"]
+        self.data.tester_type = mir["tstr_typ"]
+        self.data.operator = mir["oper_nam"]
+        self.data.test_code = mir["test_cod"]  # CP1, FT2等
+        self._lot_resolved = True
+
+        conditions = self.data.conditions
+        conditions.test_code = mir["test_cod"]
+        for key, value in (("temperature_raw", mir["tst_temp"]), ("flow_id", mir["flow_id"])):
+            if value:
+                setattr(conditions, key, value)
+        conditions.temperature_c = parse_temperature(conditions.temperature_raw)
+
+    def _note_condition(self, key: str, value: str):
+        """Fill a ConditionsInfo field from a DTR/GDR key/value, unless already set."""
+        name = _CONDITION_KEYS.get(key.lower())
+        conditions = self.data.conditions
+        if name is None or not value or getattr(conditions, name):
+            return
+        setattr(conditions, name, value)
+        if name == "temperature_raw":
+            conditions.temperature_c = parse_temperature(value)
+
+    def _scan_conditions(self, text: str):
+        for key, value in _RE_CONDITION.findall(text):
+            self._note_condition(key, value)
+
+    def _resolve_missing_mir(self, where: str):
+        """Apply the missing-MIR lot_id fallback (see ParseOptions) once."""
+        if self._lot_resolved:
+            return
+        self._lot_resolved = True
+        lot_id = self.options.fallback_lot_id
+        if lot_id is None and self.options.lot_id_from_filename and self._file_path:
+            lot_id = self._file_path.stem
+        self.data.lot_id = lot_id or ""
+        self._fault(STDFFormatError(
+            f"no MIR {where}: "
+            + (f"lot_id set to {lot_id!r}" if lot_id else "lot metadata left empty"),
+        ), "missing", "MIR")
 
     def _parse_mrr(self, f: BinaryIO, rec_len: int):
         """Parse Master Results Record."""
-        start_pos = f.tell()
-        finish_t = self._read_u4(f)
-        self.data.finish_time = finish_t
-        remaining = rec_len - (f.tell() - start_pos)
-        if remaining > 0:
-            f.read(remaining)
+        mrr = self._decode_fields(f, rec_len, REC_MRR)
+        self.data.finish_time = mrr["finish_t"]
+        self.data.disp_code = mrr["disp_cod"].strip()
+        self.data.user_desc = mrr["usr_desc"]
+        self.data.exc_desc = mrr["exc_desc"]
+        self._mrr_seen = True
 
     def _parse_wir(self, f: BinaryIO, rec_len: int):
         """Parse Wafer Information Record."""
-        start_pos = f.tell()
-        head_num = self._read_u1(f)
-        site_grp = self._read_u1(f) if f.tell() - start_pos < rec_len else 0
-        start_t = self._read_u4(f) if f.tell() - start_pos < rec_len else 0
-        wafer_id = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-
+        self._resolve_missing_mir("before first WIR")
+        wir = self._decode_fields(f, rec_len, REC_WIR)
+        wafer_id = wir["wafer_id"]
+
+        # A WIR while the previous wafer is still open: close it (and its open
+        # parts) here, or its dies would be attributed to the new wafer
+        if self._open_wafer:
+            self._close_open_parts(self._here())
+            self._close_open_wafer(self._here())
         self.data._current_wafer = wafer_id
+        self._open_wafer = True
+        self._wafer_rows = {name: len(getattr(self.data, name)) for name in _WAFER_ROW_TABLES}
         self.data.wafers.append({
             "wafer_id": wafer_id,
             "lot_id": self.data.lot_id,
-            "head_num": head_num,
-            "start_time": start_t,
+            "head_num": wir["head_num"],
+            "start_time": wir["start_t"],
         })
 
-        remaining = rec_len - (f.tell() - start_pos)
-        if remaining > 0:
-            f.read(remaining)
-
     def _parse_wrr(self, f: BinaryIO, rec_len: int):
         """Parse Wafer Results Record."""
-        start_pos = f.tell()
-        head_num = self._read_u1(f)
-        site_grp = self._read_u1(f) if f.tell() - start_pos < rec_len else 0
-        finish_t = self._read_u4(f) if f.tell() - start_pos < rec_len else 0
-        part_cnt = self._read_u4(f) if f.tell() - start_pos < rec_len else 0
-        rtst_cnt = self._read_u4(f) if f.tell() - start_pos < rec_len else 0
-        abrt_cnt = self._read_u4(f) if f.tell() - start_pos < rec_len else 0
-        good_cnt = self._read_u4(f) if f.tell() - start_pos < rec_len else 0
-        func_cnt = self._read_u4(f) if f.tell() - start_pos < rec_len else 0
+        wrr = self._decode_fields(f, rec_len, REC_WRR)
+        wafer_id = wrr["wafer_id"]
+        was_open = self._open_wafer
+        if not was_open:
+            self._note_order("WRR without WIR")
+        self._open_wafer = False
 
         if self.data.wafers:
+            if was_open and wafer_id and not self.data.wafers[-1]["wafer_id"]:
+                self._backfill_wafer_id(wafer_id)
             self.data.wafers[-1].update({
-                "finish_time": finish_t,
-                "part_count": part_cnt,
-                "good_count": good_cnt,
-                "rtst_count": rtst_cnt,
-                "abrt_count": abrt_cnt,
+                "finish_time": wrr["finish_t"],
+                "part_count": wrr["part_cnt"],
+                "good_count": wrr["good_cnt"],
+                "rtst_count": wrr["rtst_cnt"],
+                "abrt_count": wrr["abrt_cnt"],
+                "fabwf_id": wrr["fabwf_id"],
+                "frame_id": wrr["frame_id"],
+                "mask_id": wrr["mask_id"],
+                "user_desc": wrr["usr_desc"],
+                "exc_desc": wrr["exc_desc"],
             })
+            self._wafer_closed()
+
+    def _backfill_wafer_id(self, wafer_id: str):
+        """Give the open wafer (WIR without WAFER_ID) the id its WRR reports.
 
-        remaining = rec_len - (f.tell() - start_pos)
-        if remaining > 0:
-            f.read(remaining)
+        Its rows so far were read under wafer "": they take the id, and part
+        ids built as "<lot>__<n>" become "<lot>_<wafer_id>_<n>".
+        """
+        self.data.wafers[-1]["wafer_id"] = wafer_id
+        self.data._current_wafer = wafer_id
+        old_prefix = f"{self.data.lot_id}__"
+        new_prefix = f"{self.data.lot_id}_{wafer_id}_"
+        renamed: dict[str, str] = {}
+        for name, start in self._wafer_rows.items():
+            rows = getattr(self.data, name)
+            for i in range(start, len(rows)):
+                row = rows[i]
+                if row["wafer_id"]:
+                    continue
+                row["wafer_id"] = wafer_id
+                old = row["part_id"]
+                if old.startswith(old_prefix):
+                    new = renamed.get(old)
+                    if new is None:
+                        new = renamed[old] = new_prefix + old[len(old_prefix):]
+                        self._part_ids.discard(old)
+                        self._part_ids.add(new)
+                    row["part_id"] = new
+                if name == "parts":
+                    row["seq_part_id"] = new_prefix + row["seq_part_id"][len(old_prefix):]
+
+    def _parse_pcr(self, f: BinaryIO, rec_len: int):
+        """Parse Part Count Record — the tester's official counts per head/site."""
+        pcr = self._decode_fields(f, rec_len, REC_PCR)
+        head_num, site_num = pcr["head_num"], pcr["site_num"]
+        row = {"head_num": head_num, "site_num": site_num}
+        for name in ("part", "rtst", "abrt", "good", "func"):
+            n = pcr[f"{name}_cnt"]
+            row[f"{name}_count"] = None if n == U4_MISSING else n
+        self.data.part_counts[(head_num, site_num)] = row
+
+    def _parse_wcr(self, f: BinaryIO, rec_len: int):
+        """Parse Wafer Configuration Record (die size, units, flat, axis directions)."""
+        wcr = self._decode_fields(f, rec_len, REC_WCR)
+        self.data.wafer_config = WaferConfig(
+            wafer_size=wcr["wafr_siz"],
+            die_height=wcr["die_ht"],
+            die_width=wcr["die_wid"],
+            units=wcr["wf_units"],
+            flat=wcr["wf_flat"].strip(),
+            center_x=wcr["center_x"],
+            center_y=wcr["center_y"],
+            pos_x=wcr["pos_x"].strip(),
+            pos_y=wcr["pos_y"].strip(),
+        )
 
     def _parse_pir(self, f: BinaryIO, rec_len: int):
         """Parse Part Information Record."""
+        self._resolve_missing_mir("before first PIR")
         head_num = self._read_u1(f)
         site_num = self._read_u1(f)
+        if (head_num, site_num) in self._open_parts:
+            self._abort_part(head_num, site_num, self._here())
         self._part_counter += 1
-        # Cache part_id once per part so test records don't re-allocate the string 59k times
-        self._cached_part_id = f"{self.data.lot_id}_{self.data._current_wafer}_{self._part_counter}"
+        # Built once per part so test records don't re-allocate the string 59k
+        # times; reserved at once so a tester PART_ID can't take it meanwhile
+        self._cached_part_id = self._unique_part_id(
+            f"{self.data.lot_id}_{self.data._current_wafer}_{self._part_counter}"
+        )
+        self._part_ids.add(self._cached_part_id)
         # Reset ChipID accumulator for this DUT (GDRs arrive between PIR and PRR)
         self._current_chip_efuses = []
+        self._site_counts[(head_num, site_num)] = [0, 0]
+        self._part_rows[(head_num, site_num)] = {
+            name: len(getattr(self.data, name)) for name in PART_ROW_TABLES
+        }
+        self._open_parts[(head_num, site_num)] = self._cached_part_id
 
     def _parse_prr(self, f: BinaryIO, rec_len: int):
         """Parse Part Results Record."""
@@ -281,16 +1845,60 @@ class STDFParser:
         x_coord = self._read_i2(f) if f.tell() - start_pos < rec_len else -32768
         y_coord = self._read_i2(f) if f.tell() - start_pos < rec_len else -32768
         test_t = self._read_u4(f) if f.tell() - start_pos < rec_len else 0
-        # PART_ID then PART_TXT (2D barcode) — the barcode is the unique FT key
-        part_id_serial = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
+        # PART_ID (tester serial), PART_TXT (2D barcode — the unique FT key),
+        # PART_FIX (repair bitfield, B*n)
+        tester_part_id = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
         part_txt = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-
+        part_fix = self._read_bn(f) if f.tell() - start_pos < rec_len else b""
+
+        if (head_num, site_num) not in self._open_parts:
+            self._note_order(f"PRR without PIR (head {head_num}, site {site_num})")
+        self._close_part(
+            head_num, site_num, part_flg, num_test, hard_bin, soft_bin,
+            x_coord, y_coord, test_t, part_txt, tester_part_id, part_fix,
+        )
+
+    def _close_part(
+        self, head_num: int, site_num: int, part_flg: int, num_test: int,
+        hard_bin: int, soft_bin: int, x_coord: int, y_coord: int, test_t: int,
+        part_txt: str, tester_part_id: str = "", part_fix: bytes = b"",
+    ):
+        """Append the part open on (head, site) to data.parts (from its PRR, or synthesized).
+
+        part_id is "<lot>_<wafer>_<PART_ID>" when the PRR has a PART_ID not
+        already used in this file (a retest superseding an earlier part by ID
+        reuses it), else the sequential "<lot>_<wafer>_<n>" given at its PIR
+        (seq_part_id; "-2", "-3", ... appended if a PART_ID already took it).
+        The part's rows since the PIR are relabeled to the tester id.
+        """
         passed = (part_flg & 0x08) == 0
-
-        synth_part_id = f"{self.data.lot_id}_{self.data._current_wafer}_{self._part_counter}"
+        result_count, fail_count = self._site_counts.pop((head_num, site_num), (0, 0))
+
+        seq_part_id = self._open_parts.pop((head_num, site_num), None)
+        if seq_part_id is None:  # PRR without PIR
+            seq_part_id = self._unique_part_id(
+                f"{self.data.lot_id}_{self.data._current_wafer}_{self._part_counter}"
+            )
+        synth_part_id = seq_part_id
+        if tester_part_id:
+            candidate = f"{self.data.lot_id}_{self.data._current_wafer}_{tester_part_id}"
+            if candidate not in self._part_ids:
+                synth_part_id = candidate
+                self._part_ids.discard(seq_part_id)
+        self._part_ids.add(synth_part_id)
+        marks = self._part_rows.pop((head_num, site_num), {})
+        if synth_part_id != seq_part_id:
+            self._relabel_part_rows(marks, seq_part_id, synth_part_id)
+        # Hard bin is one the RDR lists as retested (every bin if it lists
+        # none); False when the file has no RDR
+        retest_bins = self.data.retest_bins
+        in_retest_bin = retest_bins is not None and (not retest_bins or hard_bin in retest_bins)
         part = {
             "part_id": synth_part_id,
+            "seq_part_id": seq_part_id,
+            "tester_part_id": tester_part_id,
             "part_txt": part_txt,
+            "part_fix": part_fix.hex(),
             "lot_id": self.data.lot_id,
             "wafer_id": self.data._current_wafer,
             "head_num": head_num,
@@ -300,10 +1908,23 @@ class STDFParser:
             "hard_bin": hard_bin,
             "soft_bin": soft_bin,
             "passed": passed,
+            # PART_FLG bits 0/1 (supersedes an earlier part by ID / by XY),
+            # bit 2 (abnormal end of test), bit 4 (no pass/fail indication —
+            # `passed` is then meaningless, bit 3 is just 0)
+            "retested": (part_flg & 0x03) != 0,
+            "aborted": (part_flg & 0x04) != 0,
+            "pass_fail_valid": (part_flg & 0x10) == 0,
+            "in_retest_bin": in_retest_bin,
             "test_count": num_test,
             "test_time": test_t,
+            "result_count": result_count,  # test records seen for this part
+            "fail_count": fail_count,  # failing test records seen for this part
+            # NUM_TEST minus records seen: non-zero = dropped/undecoded results
+            "num_test_delta": num_test - result_count,
         }
         self.data.parts.append(part)
+        if (self._part_handler is not None or self._drop_parts) and not self._open_parts:
+            self._parts_ready = True  # flushed between records (see _parse_records)
 
         # Finalize ChipID occurrences accumulated since PIR, binding them to this DUT.
         for occ_idx, efuse in enumerate(self._current_chip_efuses):
@@ -317,29 +1938,203 @@ class STDFParser:
             })
         self._current_chip_efuses = []
 
-        remaining = rec_len - (f.tell() - start_pos)
-        if remaining > 0:
-            f.read(remaining)
+    def _unique_part_id(self, part_id: str) -> str:
+        return unique_part_id(part_id, self._part_ids)
+
+    def _part_id_on(self, head_num: int, site_num: int) -> str:
+        """part_id of the part open on (head, site) (latest part if none is)."""
+        return self._open_parts.get((head_num, site_num), self._cached_part_id)
+
+    def _relabel_part_rows(self, marks: dict[str, int], old: str, new: str):
+        """Rewrite part_id old -> new in the rows added since the part's PIR."""
+        for name, start in marks.items():
+            rows = getattr(self.data, name)
+            for i in range(start, len(rows)):
+                if rows[i]["part_id"] == old:
+                    rows[i]["part_id"] = new
+
+    def _abort_part(self, head_num: int, site_num: int, where: str):
+        """Close the part open on (head, site) with a synthesized PRR."""
+        self._warn(
+            f"{where}: part on head {head_num} site {site_num} has no PRR "
+            "— closed as aborted", "incomplete", "PIR",
+        )
+        seen = self._site_counts.get((head_num, site_num), (0, 0))[0]
+        # PART_FLG: abnormal end (0x04) + failed (0x08) + no pass/fail (0x10),
+        # so a synthesized part never counts toward yield
+        self._close_part(head_num, site_num, 0x1C, seen, 65535, 65535,
+                         -32768, -32768, 0, "")
+
+    def _close_open_parts(self, where: str):
+        """Close every part still open (see _abort_part)."""
+        for head_num, site_num in sorted(self._open_parts):
+            self._abort_part(head_num, site_num, where)
+
+    def _close_open_wafer(self, where: str):
+        """Close the open wafer with WRR counts derived from its parts."""
+        wafer_id = self.data._current_wafer
+        self._warn(
+            f"{where}: wafer {wafer_id!r} has no WRR — closed with counts from its parts",
+            "incomplete", "WIR",
+        )
+        self._open_wafer = False
+        if not self.data.wafers:
+            return
+        parts = [p for p in self.data.parts if p["wafer_id"] == wafer_id]
+        self.data.wafers[-1].update({
+            "finish_time": 0,
+            "part_count": len(parts),
+            "good_count": sum(1 for p in parts if p["passed"]),
+            "rtst_count": sum(1 for p in parts if p["retested"]),
+            "abrt_count": sum(1 for p in parts if p["aborted"]),
+        })
+        self._wafer_closed()
+
+    def _wafer_closed(self):
+        """Queue the last wafer for the stream_wafers handler (see _parse_records)."""
+        if self._wafer_handler is not None:
+            self._wafers_ready.append(self.data.wafers[-1])
+
+    def _here(self) -> str:
+        """'<REC> at offset 0x..' for the record being decoded (warning prefix)."""
+        name = rec_name(*self._rec_key) if self._rec_key else "?"
+        return f"{name} at offset 0x{self._rec_start:X}"
+
+    def _keep_result(self, test_num: int, passed: bool, head_num: int, site_num: int) -> bool:
+        """Whether a test record's rows are collected (see ParseOptions)."""
+        if not self.options.collect_results:
+            return False
+        if self.options.tests is not None and test_num not in self.options.tests:
+            return False
+        cap = self.options.max_results
+        if cap is not None and self._results_flushed + len(self.data.test_results) >= cap:
+            if not self._results_capped:
+                self._results_capped = True
+                self._warn(
+                    f"{self._here()}: max_results ({cap:,}) reached — later results not collected",
+                    "limit",
+                )
+            return False
+        max_fails = self.options.max_fails_per_part
+        if max_fails is None:
+            return True
+        if passed:
+            return False
+        counts = self._site_counts.get((head_num, site_num))
+        return counts is None or counts[1] <= max_fails
+
+    def _count_result(self, head_num: int, site_num: int, passed: bool):
+        """Tally a test record against the part open on (head, site)."""
+        if (head_num, site_num) not in self._open_parts:
+            self._note_order(f"outside PIR/PRR (head {head_num}, site {site_num})")
+        counts = self._site_counts.get((head_num, site_num))
+        if counts is None:
+            counts = self._site_counts[(head_num, site_num)] = [0, 0]
+        counts[0] += 1
+        if not passed:
+            counts[1] += 1
+
+    def _register_test(
+        self,
+        test_num: int,
+        test_type: str,
+        rec_type: str,
+        test_name: str = "",
+        lo_limit: float | None = None,
+        hi_limit: float | None = None,
+        units: str = "",
+        lo_spec: float | None = None,
+        hi_spec: float | None = None,
+        formats: tuple[str, str, str] = ("", "", ""),
+    ):
+        """Add or enrich the test definition for test_num.
+
+        The first record for a test creates the definition; later records only
+        fill fields it left empty (name, limits, units, ...). Testers commonly
+        send the full definition once and bare results afterwards, but the
+        "full" one is not always the first one seen — so first-seen must not
+        win forever. lo_spec/hi_spec and the C_RESFMT/C_LLMFMT/C_HLMFMT
+        `formats` (res_fmt, llm_fmt, hlm_fmt) come from PTRs only.
+        """
+        res_fmt, llm_fmt, hlm_fmt = formats
+        fields = {
+            "test_name": test_name,
+            "lo_limit": lo_limit,
+            "hi_limit": hi_limit,
+            "units": units,
+            "lo_spec": lo_spec,
+            "hi_spec": hi_spec,
+            "res_fmt": res_fmt,
+            "llm_fmt": llm_fmt,
+            "hlm_fmt": hlm_fmt,
+        }
+        test = self.data.tests.get(test_num)
+        if test is None:
+            self.data.tests[test_num] = {
+                "test_num": test_num,
+                **fields,
+                "test_type": test_type,
+                "rec_type": rec_type,
+            }
+            return
+        for key, value in fields.items():
+            if value is not None and value != "" and test[key] in (None, ""):
+                test[key] = value
+
+    def _with_defaults(self, key: tuple[int, int, int], values: list) -> list:
+        """Apply the STDF default-value rule to a PTR/MPR's optional fields.
+
+        Limits, units, formats (and the MPR pin list) are usually sent only on
+        the first record of a test per head/site; later ones omit them or flag
+        them invalid in OPT_FLAG. `values` are as from _flagged: None fields
+        take the value cached for `key`, which the first record (and any later
+        one filling a field it lacked) provides. _NO_VALUE comes out as None.
+        """
+        defaults = self._value_defaults.get(key)
+        if defaults is None:
+            self._value_defaults[key] = list(values)
+        else:
+            for i, value in enumerate(values):
+                if value is None:
+                    values[i] = defaults[i]
+                elif defaults[i] is None:
+                    defaults[i] = value
+        return [None if value is _NO_VALUE else value for value in values]
+
+    def _note_site_limits(
+        self, test_num: int, head_num: int, site_num: int,
+        lo_limit: float | None, hi_limit: float | None,
+    ):
+        """Remember the first limits seen for test_num on (head, site)."""
+        if lo_limit is None and hi_limit is None:
+            return
+        key = (test_num, head_num, site_num)
+        if key not in self.data.site_limits:
+            self.data.site_limits[key] = {"lo_limit": lo_limit, "hi_limit": hi_limit}
 
     def _parse_ptr(self, f: BinaryIO, rec_len: int):
         """Parse Parametric Test Record."""
         body = f.read(rec_len)
+        eof = len(body) < rec_len
         if len(body) < 8:
-            return
+            raise _truncated_field(_PTR_HEADER, len(body), eof)
         test_num, head_num, site_num, test_flg, parm_flg = self._s_ptr_hdr.unpack_from(body, 0)
+        view = memoryview(body)  # C*n fields decode from slices of it, uncopied
         offset = 8
 
         result = None
-        if offset + 4 <= rec_len:
+        if offset + 4 <= len(body):
             result = self._s_r4.unpack_from(body, offset)[0]
             offset += 4
+        elif offset < len(body):
+            raise STDFTruncatedError("RESULT", len(body) - offset, 4, eof)
 
         # test_txt (Cn: 1 byte length prefix)
         test_txt = ""
         if offset < rec_len:
             n = body[offset]; offset += 1
             if n > 0 and offset + n <= rec_len:
-                test_txt = body[offset:offset + n].decode("ascii", errors="replace").replace("\x00", "").strip()
+                test_txt = self._decode_cn(view[offset:offset + n], f.tell() - len(body) + offset - 1)
                 offset += n
 
         # alarm_id — skip bytes but don't store (almost always empty, not queried)
@@ -347,8 +2142,11 @@ class STDFParser:
             n = body[offset]; offset += 1
             offset += n
 
-        # opt_flag(1) + res_scal(1) + llm_scal(1) + hlm_scal(1) = 4 bytes
-        offset += 4
+        # OPT_FLAG, then RES_SCAL, LLM_SCAL, HLM_SCAL (not applied)
+        opt_flag = None
+        if offset < rec_len:
+            opt_flag = body[offset]
+            offset += 4
 
         lo_limit = None
         if offset + 4 <= rec_len:
@@ -364,59 +2162,184 @@ class STDFParser:
         if offset < rec_len:
             n = body[offset]; offset += 1
             if n > 0 and offset + n <= rec_len:
-                units = body[offset:offset + n].decode("ascii", errors="replace").replace("\x00", "").strip()
+                units = self._decode_cn(view[offset:offset + n], f.tell() - len(body) + offset - 1)
+                offset += n
 
-        passed = (test_flg & 0x80) == 0
+        # C_RESFMT, C_LLMFMT, C_HLMFMT (printf-style), then LO_SPEC / HI_SPEC —
+        # the spec limits, absent when OPT_FLAG bit 2 / 3 is set
+        base = f.tell() - len(body)
+        formats = []
+        for _ in range(3):
+            fmt, offset = self._body_cn(view, offset, base)
+            formats.append(fmt or None)
+        specs = []
+        for bit in (2, 3):
+            spec = None
+            if offset + 4 <= rec_len:
+                spec = self._s_r4.unpack_from(body, offset)[0]
+                offset += 4
+            specs.append(_flagged(spec, opt_flag, None, bit))
+
+        # OPT_FLAG bits 4/5: LO/HI_LIMIT invalid (use the default), 6/7: no limit
+        lo_limit, hi_limit, units, lo_spec, hi_spec, *formats = self._with_defaults(
+            (test_num, head_num, site_num),
+            [
+                _flagged(lo_limit, opt_flag, 4, 6),
+                _flagged(hi_limit, opt_flag, 5, 7),
+                units or None,
+                *specs,
+                *formats,
+            ],
+        )
+        units = units or ""
+        formats = [fmt or "" for fmt in formats]
+
+        if offset > rec_len:
+            self._note_overrun(REC_PTR, base - 4, offset, rec_len)
+        elif offset < rec_len:
+            self._note_leftover(REC_PTR, rec_len - offset)
 
-        if test_num not in self.data.tests:
-            self.data.tests[test_num] = {
-                "test_num": test_num,
-                "test_name": test_txt,
-                "lo_limit": lo_limit,
-                "hi_limit": hi_limit,
-                "units": units,
-                "test_type": "P",
-                "rec_type": "PTR",
-            }
+        passed = (test_flg & 0x80) == 0
+        self._count_result(head_num, site_num, passed)
+
+        self._register_test(
+            test_num, "P", "PTR", test_txt, lo_limit, hi_limit, units,
+            lo_spec, hi_spec, tuple(formats),
+        )
+        self._note_site_limits(test_num, head_num, site_num, lo_limit, hi_limit)
+        if not self._keep_result(test_num, passed, head_num, site_num):
+            return
 
         self.data.test_results.append({
             "lot_id": self.data.lot_id,
             "wafer_id": self.data._current_wafer,
-            "part_id": self._cached_part_id,
+            "part_id": self._part_id_on(head_num, site_num),
             "test_num": test_num,
             "head_num": head_num,
             "site_num": site_num,
             "result": result,
             "passed": passed,
             "alarm_id": "",
+            "section": self._section,
         })
 
+    def _body_cn(self, body: bytes | memoryview, offset: int, base: int) -> tuple[str, int]:
+        """C*n at body[offset] -> (string, offset past it).
+
+        An absent field (offset at/after the body end) gives "" and leaves the
+        offset as is. `base` is the body's file offset (for diagnostics).
+        """
+        if offset >= len(body):
+            return "", offset
+        n = body[offset]
+        if n == 0:
+            return "", offset + 1
+        text = self._decode_cn(memoryview(body)[offset + 1:offset + 1 + n], base + offset)
+        return text, offset + 1 + n
+
+    def _body_dn(self, body: bytes, offset: int) -> tuple[bytes, int]:
+        """D*n at body[offset] -> (bit bytes, offset past it); absent as in _body_cn."""
+        if offset >= len(body):
+            return b"", offset
+        if offset + 2 > len(body):
+            return b"", offset + 2
+        nbytes = (self._s_u2.unpack_from(body, offset)[0] + 7) // 8
+        return body[offset + 2:offset + 2 + nbytes], offset + 2 + nbytes
+
     def _parse_ftr(self, f: BinaryIO, rec_len: int):
-        """Parse Functional Test Record."""
+        """Parse Functional Test Record.
+
+        Trailing fields a short record omits keep their defaults. OPT_FLAG bits
+        mark CYCL_CNT (0), REL_VADR (1), REPT_CNT (2), NUM_FAIL (3), X/YFAIL_AD
+        (4) and VECT_OFF (5) invalid; those come out as None. RTN_STAT/PGM_STAT
+        are 0-F per the FTR state table; FAIL_PIN is expanded to PMR indexes.
+        """
         body = f.read(rec_len)
         if len(body) < 7:
-            return
+            raise _truncated_field(_PTR_HEADER[:4], len(body), len(body) < rec_len)
         test_num, head_num, site_num, test_flg = self._s_ftr_hdr.unpack_from(body, 0)
+        base = f.tell() - len(body)
+        size = len(body)
+
+        # OPT_FLAG, CYCL_CNT, REL_VADR, REPT_CNT, NUM_FAIL, XFAIL_AD, YFAIL_AD, VECT_OFF
+        fixed: list[int | None] = [None] * 7
+        offset = 7
+        if size >= 34:
+            offset = 34
+            opt_flag = body[7]
+            fixed = list(self._s_ftr_fixed.unpack_from(body, 8))
+            for bit, fields in ((0, (0,)), (1, (1,)), (2, (2,)), (3, (3,)), (4, (4, 5)), (5, (6,))):
+                if opt_flag & (1 << bit):
+                    for i in fields:
+                        fixed[i] = None
+        cycl_cnt, rel_vadr, rept_cnt, num_fail, xfail_ad, yfail_ad, vect_off = fixed
+
+        arrays: list[list[int]] = [[], [], [], []]  # RTN_INDX, RTN_STAT, PGM_INDX, PGM_STAT
+        fail_pin = b""
+        texts = [""] * 7
+        patg_num = 255
+        if size >= 38:
+            rtn_icnt, pgm_icnt = struct.unpack_from(self._endian + "HH", body, 34)
+            offset = 38
+            for n, slot in ((rtn_icnt, 0), (pgm_icnt, 2)):
+                end = offset + 2 * n + (n + 1) // 2
+                if n and end <= size:
+                    arrays[slot] = list(struct.unpack_from(f"{self._endian}{n}H", body, offset))
+                    arrays[slot + 1] = _unpack_nibbles(body[offset + 2 * n:end], n)
+                offset = end
+            fail_pin, offset = self._body_dn(body, offset)
+            # VECT_NAM, TIME_SET, OP_CODE, TEST_TXT, ALARM_ID, PROG_TXT, RSLT_TXT
+            for i in range(7):
+                texts[i], offset = self._body_cn(body, offset, base)
+            if offset < size:
+                patg_num = body[offset]
+                offset += 1
+            _spin_map, offset = self._body_dn(body, offset)
+        rtn_indx, rtn_stat, pgm_indx, pgm_stat = arrays
+        vect_nam, time_set, op_code, test_txt, alarm_id, prog_txt, rslt_txt = texts
+        fail_pins = [i for i in range(len(fail_pin) * 8) if fail_pin[i >> 3] >> (i & 7) & 1]
+
+        if offset > size:
+            self._note_overrun(REC_FTR, base - 4, offset, rec_len)
+        elif offset < size:
+            self._note_leftover(REC_FTR, size - offset)
         passed = (test_flg & 0x80) == 0
+        self._count_result(head_num, site_num, passed)
 
-        if test_num not in self.data.tests:
-            self.data.tests[test_num] = {
-                "test_num": test_num,
-                "test_name": "",
-                "test_type": "F",
-                "rec_type": "FTR",
-            }
+        self._register_test(test_num, "F", "FTR", test_txt)
+        if not self._keep_result(test_num, passed, head_num, site_num):
+            return
 
         self.data.test_results.append({
             "lot_id": self.data.lot_id,
             "wafer_id": self.data._current_wafer,
-            "part_id": self._cached_part_id,
+            "part_id": self._part_id_on(head_num, site_num),
             "test_num": test_num,
             "head_num": head_num,
             "site_num": site_num,
             "result": None,
             "passed": passed,
-            "alarm_id": "",
+            "alarm_id": alarm_id,
+            "section": self._section,
+            "test_txt": test_txt,
+            "vect_nam": vect_nam,
+            "time_set": time_set,
+            "op_code": op_code,
+            "prog_txt": prog_txt,
+            "rslt_txt": rslt_txt,
+            "num_fail": num_fail,
+            "cycl_cnt": cycl_cnt,
+            "rel_vadr": rel_vadr,
+            "rept_cnt": rept_cnt,
+            "xfail_ad": xfail_ad,
+            "yfail_ad": yfail_ad,
+            "vect_off": vect_off,
+            "patg_num": None if patg_num == 255 else patg_num,
+            "rtn_indx": rtn_indx,
+            "rtn_stat": rtn_stat,
+            "pgm_indx": pgm_indx,
+            "pgm_stat": pgm_stat,
+            "fail_pins": fail_pins,
         })
 
     def _parse_mpr(self, f: BinaryIO, rec_len: int):
@@ -435,14 +2358,7 @@ class STDFParser:
         # RTN_STAT: Array of return states (nibbles)
         rtn_stat = []
         if rtn_icnt > 0 and f.tell() - start_pos < rec_len:
-            num_bytes = (rtn_icnt + 1) // 2
-            for _ in range(num_bytes):
-                if f.tell() - start_pos >= rec_len:
-                    break
-                byte = self._read_u1(f)
-                rtn_stat.append(byte & 0x0F)
-                if len(rtn_stat) < rtn_icnt:
-                    rtn_stat.append((byte >> 4) & 0x0F)
+            rtn_stat = self._read_nibbles(f, rtn_icnt)
         
         # RTN_RSLT: Array of results (R*4)
         results = []
@@ -454,7 +2370,7 @@ class STDFParser:
         # Optional fields (order per STDF V4 spec)
         test_txt = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
         alarm_id = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-        opt_flag = self._read_u1(f) if f.tell() - start_pos < rec_len else 0xFF
+        opt_flag = self._read_u1(f) if f.tell() - start_pos < rec_len else None
         res_scal = self._read_i1(f) if f.tell() - start_pos < rec_len else 0
         llm_scal = self._read_i1(f) if f.tell() - start_pos < rec_len else 0
         hlm_scal = self._read_i1(f) if f.tell() - start_pos < rec_len else 0
@@ -472,37 +2388,49 @@ class STDFParser:
         
         # UNITS, C_RESFMT, C_LLMFMT, C_HLMFMT, LO_SPEC, HI_SPEC are last
         units = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
+
+        # Default-value rule as for PTR; a later MPR may also leave RTN_ICNT 0
+        # and rely on the first one's RTN_INDX
+        lo_limit, hi_limit, units, default_indx = self._with_defaults(
+            (test_num, head_num, site_num),
+            [
+                _flagged(lo_limit, opt_flag, 4, 6),
+                _flagged(hi_limit, opt_flag, 5, 7),
+                units or None,
+                rtn_indx or None,
+            ],
+        )
+        units = units or ""
+        if not rtn_indx and results and default_indx:
+            rtn_indx = default_indx[:len(results)]
         
         passed = (test_flg & 0x80) == 0
+        self._count_result(head_num, site_num, passed)
 
-        # Register test definition
-        if test_num not in self.data.tests:
-            self.data.tests[test_num] = {
-                "test_num": test_num,
-                "test_name": test_txt,
-                "lo_limit": lo_limit,
-                "hi_limit": hi_limit,
-                "units": units,
-                "test_type": "M",
-                "rec_type": "MPR",
-            }
+        self._register_test(test_num, "M", "MPR", test_txt, lo_limit, hi_limit, units)
+        self._note_site_limits(test_num, head_num, site_num, lo_limit, hi_limit)
+        if not self._keep_result(test_num, passed, head_num, site_num):
+            return
 
         # Expand per-pin results: each pin becomes a separate row.
         # When both arrays are empty (pass/fail-only MPR with no measurements), emit
         # one summary row rather than a phantom row from max(..., 1).
+        part_id = self._part_id_on(head_num, site_num)
         if not results and not rtn_indx:
             self.data.test_results.append({
                 "lot_id": self.data.lot_id,
                 "wafer_id": self.data._current_wafer,
-                "part_id": self._cached_part_id,
+                "part_id": part_id,
                 "test_num": test_num,
                 "head_num": head_num,
                 "site_num": site_num,
                 "result": None,
                 "passed": passed,
                 "alarm_id": alarm_id,
+                "section": self._section,
                 "pin_num": None,
                 "pin_name": None,
+                "rtn_stat": None,
             })
         else:
             if rtn_icnt != rslt_cnt:
@@ -516,108 +2444,280 @@ class STDFParser:
                 self.data.test_results.append({
                     "lot_id": self.data.lot_id,
                     "wafer_id": self.data._current_wafer,
-                    "part_id": self._cached_part_id,
+                    "part_id": part_id,
                     "test_num": test_num,
                     "head_num": head_num,
                     "site_num": site_num,
                     "result": results[i] if i < len(results) else None,
                     "passed": passed,
                     "alarm_id": alarm_id,
+                    "section": self._section,
                     "pin_num": pin_idx,
-                    "pin_name": self.data.pin_map.get(pin_idx) if pin_idx is not None else None,
+                    "pin_name": self.data.pin_name(pin_idx) if pin_idx is not None else None,
+                    # RTN_STAT nibble for this pin (0-9 per the MPR state table)
+                    "rtn_stat": rtn_stat[i] if i < len(rtn_stat) else None,
                 })
 
-        remaining = rec_len - (f.tell() - start_pos)
-        if remaining > 0:
-            f.read(remaining)
+    def _parse_str(self, f: BinaryIO, rec_len: int):
+        """Parse Scan Test Record (V4-2007) into data.scan_tests and data.scan_failures.
 
-    def _parse_hbr(self, f: BinaryIO, rec_len: int):
-        """Parse Hardware Bin Record."""
+        One scan_failures row per captured fail: CYC_OFST/PMR_INDX/CHN_NUM/
+        EXP_DATA/CAP_DATA/NEW_DATA/PAT_NUM/BIT_POS are parallel arrays (absent
+        ones have a zero count). Cycles and bit positions are rebased by
+        CYC_BASE/BIT_BASE. A test whose fails don't fit one record continues
+        in further STRs (CONT_FLG bit 0); it is one scan_tests row, with the
+        header of its first STR, and counts as one test record, on its last STR.
+        """
         start_pos = f.tell()
+        cont_flg = self._read_u1(f)
+        test_num = self._read_u4(f)
         head_num = self._read_u1(f)
         site_num = self._read_u1(f)
-        hbin_num = self._read_u2(f)
-        hbin_cnt = self._read_u4(f)
-        hbin_pf = chr(self._read_u1(f)) if f.tell() - start_pos < rec_len else ""
-        hbin_nam = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-
-        self.data.bins_hard[hbin_num] = {
-            "bin_num": hbin_num,
-            "bin_name": hbin_nam,
-            "bin_pf": hbin_pf,
-            "bin_count": hbin_cnt,
-        }
+        psr_ref = self._read_u2(f)
+        test_flg = self._read_u1(f)
+        log_typ = self._read_cn(f)
+        test_txt = self._read_cn(f)
+        alarm_id = self._read_cn(f)
+        prog_txt = self._read_cn(f)
+        rslt_txt = self._read_cn(f)
+        _z_val = self._read_u1(f)
+        fmu_flg = self._read_u1(f)
+        if fmu_flg & 0x01:
+            self._read_dn(f)  # MASK_MAP
+        if fmu_flg & 0x04:
+            self._read_dn(f)  # FAL_MAP
+        cyc_cnt = self._read_u8(f)
+        totf_cnt = self._read_u4(f)
+        totl_cnt = self._read_u4(f)
+        cyc_base = self._read_u8(f)
+        bit_base = self._read_u4(f)
+        cond_cnt = self._read_u2(f)
+        lim_cnt = self._read_u2(f)
+        cyc_size, pmr_size, chn_size, pat_size, bit_size, *usr_sizes, utx_size = (
+            self._read_u1(f) for _ in range(9)
+        )
+        _cap_bgn = self._read_u2(f)
+        f.read(6 * lim_cnt)  # LIM_INDX, LIM_SPEC
+        conditions = [self._read_cn(f) for _ in range(cond_cnt)]
+
+        def array(size: int) -> list[int]:
+            if f.tell() - start_pos >= rec_len:
+                return []
+            return self._read_uf_array(f, self._read_u2(f), size)
+
+        cycles = array(cyc_size)
+        pins = array(pmr_size)
+        chains = array(chn_size)
+        expected, captured, new = (array(1) for _ in range(3))
+        patterns = array(pat_size)
+        bits = array(bit_size)
+        for size in usr_sizes:  # USR1..USR3
+            array(size)
+        user_txt = []
+        if f.tell() - start_pos < rec_len:
+            user_txt = [self._read_cf(f, utx_size) for _ in range(self._read_u2(f))]
 
-        remaining = rec_len - (f.tell() - start_pos)
-        if remaining > 0:
-            f.read(remaining)
+        passed = (test_flg & 0x80) == 0
+        last = not cont_flg & 0x01
+        if last:
+            self._count_result(head_num, site_num, passed)
+        self._register_test(test_num, "S", "STR", test_txt)
+        key = (test_num, head_num, site_num)
+        scan = self._open_scans.pop(key, None)
+        if not self._keep_result(test_num, passed, head_num, site_num):
+            return
+
+        if scan is None:
+            scan = {
+                "lot_id": self.data.lot_id,
+                "wafer_id": self.data._current_wafer,
+                "part_id": self._part_id_on(head_num, site_num),
+                "test_num": test_num,
+                "head_num": head_num,
+                "site_num": site_num,
+                "psr_ref": psr_ref,
+                "passed": passed,
+                "log_type": log_typ,
+                "alarm_id": alarm_id,
+                "prog_txt": prog_txt,
+                "rslt_txt": rslt_txt,
+                "conditions": conditions,
+                "cycle_count": cyc_cnt,
+                "total_fails": totf_cnt,
+                "logged_fails": totl_cnt,
+                "fail_rows": 0,
+                "records": 0,
+                "user_text": [],
+            }
+            self.data.scan_tests.append(scan)
+        scan["records"] += 1
+        scan["user_text"].extend(user_txt)
+        if not last:
+            self._open_scans[key] = scan
+
+        def at(values: list[int], i: int) -> int | None:
+            return values[i] if i < len(values) else None
+
+        count = max(len(cycles), len(pins), len(chains), len(patterns), len(bits))
+        scan["fail_rows"] += count
+        part_id = self._part_id_on(head_num, site_num)
+        for i in range(count):
+            cycle, pin, bit = at(cycles, i), at(pins, i), at(bits, i)
+            self.data.scan_failures.append({
+                "lot_id": self.data.lot_id,
+                "wafer_id": self.data._current_wafer,
+                "part_id": part_id,
+                "test_num": test_num,
+                "head_num": head_num,
+                "site_num": site_num,
+                "pattern": at(patterns, i),
+                "cycle": None if cycle is None else cyc_base + cycle,
+                "pin_num": pin,
+                "pin_name": None if pin is None else self.data.pin_name(pin),
+                "chain": at(chains, i),
+                "bit_pos": None if bit is None else bit_base + bit,
+                "expected": at(expected, i),
+                "captured": at(captured, i),
+                "new_data": at(new, i),
+            })
+
+    def _parse_hbr(self, f: BinaryIO, rec_len: int):
+        """Parse Hardware Bin Record."""
+        self._record_bin("H", *self._decode_fields(f, rec_len, REC_HBR).values())
 
     def _parse_sbr(self, f: BinaryIO, rec_len: int):
         """Parse Software Bin Record."""
+        self._record_bin("S", *self._decode_fields(f, rec_len, REC_SBR).values())
+
+    def _record_bin(
+        self, bin_type: str, head_num: int, site_num: int, bin_num: int,
+        count: int, bin_pf: str, bin_name: str,
+    ):
+        """File an HBR/SBR: per-site rows into site_bins_*, the head-255 summary
+        (or, until one arrives, the per-site sum) into the bins_* total."""
+        if bin_type == "H":
+            totals, sites = self.data.bins_hard, self.data.site_bins_hard
+        else:
+            totals, sites = self.data.bins_soft, self.data.site_bins_soft
+        total = totals.setdefault(bin_num, {
+            "bin_num": bin_num, "bin_name": bin_name, "bin_pf": bin_pf, "bin_count": 0,
+        })
+        if bin_name:
+            total["bin_name"] = bin_name
+        if bin_pf.strip():
+            total["bin_pf"] = bin_pf
+        if head_num == 255:
+            total["bin_count"] = count
+            self._bin_summaries.add((bin_type, bin_num))
+            return
+        sites[(head_num, site_num, bin_num)] = {
+            "head_num": head_num,
+            "site_num": site_num,
+            "bin_num": bin_num,
+            "bin_name": bin_name,
+            "bin_pf": bin_pf,
+            "bin_count": count,
+        }
+        if (bin_type, bin_num) not in self._bin_summaries:
+            total["bin_count"] = sum(
+                row["bin_count"] for key, row in sites.items() if key[2] == bin_num
+            )
+
+    def _parse_tsr(self, f: BinaryIO, rec_len: int):
+        """Parse Test Synopsis Record into data.test_synopses.
+
+        TEST_NAM is also kept as a test-name fallback: testers often leave
+        PTR/FTR TEST_TXT empty to save space; the TSRs at the end of the file
+        still carry the name (see _finalize).
+        """
         start_pos = f.tell()
         head_num = self._read_u1(f)
         site_num = self._read_u1(f)
-        sbin_num = self._read_u2(f)
-        sbin_cnt = self._read_u4(f)
-        sbin_pf = chr(self._read_u1(f)) if f.tell() - start_pos < rec_len else ""
-        sbin_nam = self._read_cn(f) if f.tell() - start_pos < rec_len else ""
-
-        self.data.bins_soft[sbin_num] = {
-            "bin_num": sbin_num,
-            "bin_name": sbin_nam,
-            "bin_pf": sbin_pf,
-            "bin_count": sbin_cnt,
-        }
+        test_typ = chr(self._read_u1(f))
+        test_num = self._read_u4(f)
 
-        remaining = rec_len - (f.tell() - start_pos)
-        if remaining > 0:
-            f.read(remaining)
+        def opt(read, default):
+            return read(f) if f.tell() - start_pos < rec_len else default
+
+        counts = [opt(self._read_u4, 4294967295) for _ in range(3)]
+        exec_cnt, fail_cnt, alrm_cnt = (None if n == 4294967295 else n for n in counts)
+        test_nam = opt(self._read_cn, "")
+        seq_name = opt(self._read_cn, "")
+        test_lbl = opt(self._read_cn, "")
+        # OPT_FLAG bit set = field invalid: 0 TEST_MIN, 1 TEST_MAX, 2 TEST_TIM,
+        # 4 TST_SUMS, 5 TST_SQRS. A missing OPT_FLAG means none are present.
+        opt_flag = opt(self._read_u1, 0xFF)
+        stats = [opt(self._read_r4, None) for _ in range(5)]
+        for bit, i in ((2, 0), (0, 1), (1, 2), (4, 3), (5, 4)):
+            if opt_flag & (1 << bit):
+                stats[i] = None
+        test_tim, test_min, test_max, tst_sums, tst_sqrs = stats
+
+        if test_nam and test_num not in self._tsr_names:
+            self._tsr_names[test_num] = test_nam
+        self.data.test_synopses.append({
+            "head_num": head_num,
+            "site_num": site_num,  # head 255 = summary over all sites
+            "test_num": test_num,
+            "test_type": test_typ.strip(),  # P / F / M / S / space = unknown
+            "test_name": test_nam,
+            "seq_name": seq_name,
+            "test_label": test_lbl,
+            "exec_count": exec_cnt,
+            "fail_count": fail_cnt,
+            "alarm_count": alrm_cnt,
+            "test_time": test_tim,  # average seconds per execution
+            "test_min": test_min,
+            "test_max": test_max,
+            "test_sum": tst_sums,
+            "test_sum_sq": tst_sqrs,
+        })
 
-    def _parse_gdr(self, f: BinaryIO, rec_len: int):
-        """Parse Generic Data Record, extracting EN-SO-CHIPID_R value strings.
+    def _read_vn(self, f: BinaryIO, end: int) -> list[tuple[str, object]]:
+        """Read V*n generic data fields up to file offset `end`.
 
-        GDR holds FLD_CNT variable-type generic data fields. We walk the fields
-        (bounded by rec_len for robustness against pad-byte counting) collecting
-        Cn strings in order, then pair each ``EN-SO-CHIPID_R`` key with the next
-        string as its eFuse value. Multiple occurrences per DUT are preserved.
+        Returns (type, value) pairs: "U1".."U4", "I1".."I4" -> int, "R4"/"R8"
+        -> float, "Cn" -> str, "Bn"/"Dn" -> bytes, "N1" -> int. B*0 pad bytes
+        are skipped. An unknown type code stops the walk (its length is unknown)
+        with a warning; the rest of the record is left as leftover bytes.
         """
-        start_pos = f.tell()
-        end = start_pos + rec_len
-        self._read_u2(f)  # FLD_CNT (advisory; we bound by rec_len instead)
-
-        strings: list[str] = []
+        fields: list[tuple[str, object]] = []
         while f.tell() < end:
             type_code = self._read_u1(f)
             if type_code == 0:          # B*0 pad byte
                 continue
-            elif type_code == 1:        # U*1
-                f.read(1)
-            elif type_code == 2:        # U*2
-                f.read(2)
-            elif type_code == 3:        # U*4
-                f.read(4)
-            elif type_code == 4:        # I*1
-                f.read(1)
-            elif type_code == 5:        # I*2
-                f.read(2)
-            elif type_code == 6:        # I*4
-                f.read(4)
-            elif type_code == 7:        # R*4
-                f.read(4)
-            elif type_code == 8:        # R*8
-                f.read(8)
-            elif type_code == 10:       # C*n
-                strings.append(self._read_cn(f))
-            elif type_code == 11:       # B*n
-                blen = self._read_u1(f)
-                f.read(blen)
-            elif type_code == 12:       # D*n (bit field)
-                nbits = self._read_u2(f)
-                f.read((nbits + 7) // 8)
-            elif type_code == 13:       # N*1 (nibble)
-                f.read(1)
-            else:
-                break  # unknown type — cannot determine length, stop safely
+            if type_code == 13:         # N*1 (nibble, one byte)
+                fields.append(("N1", self._read_u1(f) & 0x0F))
+                continue
+            vn_type = _VN_TYPES.get(type_code)
+            if vn_type is None:
+                self._warn(
+                    f"{self._here()}: unknown V*n type code {type_code}, rest of record skipped",
+                    "decoder", "GDR", self._rec_start,
+                )
+                break
+            name, reader = vn_type
+            fields.append((name, getattr(self, reader)(f)))
+        return fields
+
+    def _parse_gdr(self, f: BinaryIO, rec_len: int):
+        """Parse Generic Data Record into data.generic_data (typed V*n fields).
+
+        The fields are walked bounded by rec_len (FLD_CNT is advisory: some
+        testers count pad bytes, some don't). The C*n strings are then scanned
+        in order: each ``EN-SO-CHIPID_R`` key is paired with the next string as
+        its eFuse value (multiple occurrences per DUT preserved), and condition
+        keys (see ConditionsInfo) likewise.
+        """
+        start_pos = f.tell()
+        self._read_u2(f)  # FLD_CNT
+        fields = self._read_vn(f, start_pos + rec_len)
+        self.data.generic_data.append({
+            "wafer_id": self.data._current_wafer,
+            "part_id": self._cached_part_id if self._open_parts else "",
+            "fields": fields,
+        })
+        strings = [value for kind, value in fields if kind == "Cn"]
 
         # Import here to keep the parser import-light. Accept both the
         # digit-zero ("EN-S0-") and letter-O ("EN-SO-") spellings of the key.
@@ -627,74 +2727,610 @@ class STDFParser:
             if strings[i] in CHIPID_KEYS:
                 self._current_chip_efuses.append(strings[i + 1])
                 i += 2
+            elif strings[i].lower() in _CONDITION_KEYS:
+                self._note_condition(strings[i], strings[i + 1])
+                i += 2
             else:
                 i += 1
+        for text in strings:
+            self._scan_conditions(text)
+
+    def _parse_bps(self, f: BinaryIO, rec_len: int):
+        """Parse Begin Program Section — push SEQ_NAME onto the section path."""
+        seq_name = self._read_cn(f) if rec_len > 0 else ""
+        self._sections.append(seq_name)
+        self._section = "/".join(self._sections)
+
+    def _parse_eps(self, f: BinaryIO, rec_len: int):
+        """Parse End Program Section — pop the innermost section."""
+        if not self._sections:
+            self._note_order("EPS without BPS")
+            return
+        self._sections.pop()
+        self._section = "/".join(self._sections)
+
+    def _parse_dtr(self, f: BinaryIO, rec_len: int):
+        """Parse Datalog Text Record into data.datalog_text (also scanned for
+        test-condition patterns)."""
+        text = self._read_cn(f)
+        self.data.datalog_text.append({
+            "wafer_id": self.data._current_wafer,
+            "part_id": self._cached_part_id if self._open_parts else "",
+            "text": text,
+        })
+        self._scan_conditions(text)
 
-    def parse(self, file_path: Path) -> STDFData:
-        """Parse an STDF file."""
-        self.data = STDFData()
-        self._part_counter = 0
-        self._current_chip_efuses = []
+    def _parse_user_record(self, f: BinaryIO, rec_len: int):
+        """Hand a vendor record to its registered handler (see register_record)."""
+        body = f.read(rec_len)
+        if len(body) < rec_len:
+            raise EOFError(len(body), rec_len)
+        value = self._record_handlers[self._rec_key](body, self._endian)
+        if value is not None:
+            self.data.user_records.setdefault(self._rec_key, []).append(value)
 
+    def parse(self, file_path: Path) -> STDFData:
+        """Parse an STDF file (gzip/bz2/xz/zstd-compressed files and ATDF text
+        are detected)."""
         with open(file_path, "rb") as f:
+            head = f.peek(6)[:6]
+            if detect_compression(head) is not None or head.startswith(_ATDF_MAGIC):
+                name = Path(file_path)
+                if name.suffix.lower() in (".gz", ".bz2", ".xz", ".zst"):
+                    name = name.with_suffix("")  # LOT.stdf.gz -> LOT.stdf
+                return self.parse_reader(f, name=str(name))
+            self._reset(file_path)
+            self._detect_byte_order(f)
+            self._parse_records(f)
+        self._finalize()
+        return self.data
+
+    def parse_reader(self, stream: BinaryIO, name: str | None = None) -> STDFData:
+        """Parse STDF from a binary stream, e.g. sys.stdin.buffer.
+
+        Compression is auto-detected (see open_decompressed), and so is ATDF
+        text (see atdf.atdf_to_stdf). The stream need not be seekable: it is
+        spooled (RAM, then a temp file) first. `name` stands in for the file
+        name (lot_id_from_filename); None for stdin.
+        """
+        with tempfile.SpooledTemporaryFile(max_size=_SPOOL_MAX) as spool:
+            shutil.copyfileobj(open_decompressed(stream), spool, 1024 * 1024)
+            spool.seek(0)
+            if spool.read(len(_ATDF_MAGIC)) == _ATDF_MAGIC:
+                spool.seek(0)
+                return self._parse_atdf(spool, name)
+            spool.seek(0)
+            self._reset(name)
+            self._detect_byte_order(spool)
+            self._parse_records(spool)
+        self._finalize()
+        return self.data
+
+    def parse_bytes(self, data: bytes, name: str | None = None) -> STDFData:
+        """Parse STDF held in memory (a network payload, an archive member).
+
+        Uncompressed binary STDF is decoded straight from `data`; compressed
+        input and ATDF text go through parse_reader. `name` as for parse_reader.
+        """
+        head = bytes(data[:max(6, len(_ATDF_MAGIC))])
+        if detect_compression(head) or head.startswith(_ATDF_MAGIC):
+            return self.parse_reader(io.BytesIO(data), name)
+        f = io.BytesIO(data)
+        self._reset(name)
+        self._detect_byte_order(f)
+        self._parse_records(f)
+        self._finalize()
+        return self.data
+
+    async def parse_async(self, reader: Any, name: str | None = None) -> STDFData:
+        """Parse STDF read from an asyncio stream, for async ingestion services.
+
+        `reader` is anything with a coroutine read(n) (asyncio.StreamReader,
+        an object-storage response body) or an async iterable of byte chunks.
+        It is spooled as it arrives (RAM, then a temp file), then decompressed
+        and decoded on the loop's thread — no worker thread per file —
+        yielding to the loop every _ASYNC_SLICE bytes. Each slice still
+        blocks the loop while it runs, and so do temp file writes past
+        _SPOOL_MAX and ATDF translation (done in one go). Compression / ATDF
+        and `name` as for parse_reader.
+        """
+        import asyncio
+
+        with tempfile.SpooledTemporaryFile(max_size=_SPOOL_MAX) as raw, \
+                tempfile.SpooledTemporaryFile(max_size=_SPOOL_MAX) as plain:
+            if hasattr(reader, "read"):
+                while chunk := await reader.read(_ASYNC_SLICE):
+                    raw.write(chunk)
+            else:
+                async for chunk in reader:
+                    raw.write(chunk)
+            raw.seek(0)
+            spool = raw
+            if detect_compression(raw.read(6)):
+                raw.seek(0)
+                source = open_decompressed(raw)
+                while chunk := source.read(_ASYNC_SLICE):
+                    plain.write(chunk)
+                    await asyncio.sleep(0)
+                spool = plain
+            spool.seek(0)
+            if spool.read(len(_ATDF_MAGIC)) == _ATDF_MAGIC:
+                spool.seek(0)
+                return self._parse_atdf(spool, name)
+            spool.seek(0)
+            self._reset(name)
+            self._detect_byte_order(spool)
+            start = spool.tell()
+            size = spool.seek(0, os.SEEK_END)
+            spool.seek(start)
             while True:
-                try:
-                    rec_len, rec_typ, rec_sub = self._read_header(f)
-
-                    # rec_len=0 means zero-padded or corrupt data — skip
-                    if rec_len == 0:
-                        continue
-
-                    rec_key = (rec_typ, rec_sub)
-                    start_pos = f.tell()
-
-                    if rec_key == REC_FAR:
-                        self._parse_far(f, rec_len)
-                    elif rec_key == REC_PMR:
-                        self._parse_pmr(f, rec_len)
-                    elif rec_key == REC_MIR:
-                        self._parse_mir(f, rec_len)
-                    elif rec_key == REC_MRR:
-                        self._parse_mrr(f, rec_len)
-                    elif rec_key == REC_WIR:
-                        self._parse_wir(f, rec_len)
-                    elif rec_key == REC_WRR:
-                        self._parse_wrr(f, rec_len)
-                    elif rec_key == REC_PIR:
-                        self._parse_pir(f, rec_len)
-                    elif rec_key == REC_PRR:
-                        self._parse_prr(f, rec_len)
-                    elif rec_key == REC_PTR:
-                        self._parse_ptr(f, rec_len)
-                    elif rec_key == REC_MPR:
-                        self._parse_mpr(f, rec_len)
-                    elif rec_key == REC_FTR:
-                        self._parse_ftr(f, rec_len)
-                    elif rec_key == REC_HBR:
-                        self._parse_hbr(f, rec_len)
-                    elif rec_key == REC_SBR:
-                        self._parse_sbr(f, rec_len)
-                    elif rec_key == REC_GDR:
-                        self._parse_gdr(f, rec_len)
-                    else:
-                        # Skip unknown record
-                        f.read(rec_len)
-
-                    # Ensure we consumed exactly rec_len bytes
-                    consumed = f.tell() - start_pos
-                    if consumed < rec_len:
-                        f.read(rec_len - consumed)
-
-                except EOFError:
+                start = spool.tell()
+                self._parse_records(spool, start + _ASYNC_SLICE)
+                if spool.tell() >= size or spool.tell() <= start:
                     break
-                except Exception as e:
-                    logger.debug("Skipping record (typ=%s, sub=%s): %s", rec_typ, rec_sub, e)
-                    continue
+                await asyncio.sleep(0)  # let other coroutines run between slices
+        self._finalize()
+        return self.data
 
+    def _parse_atdf(self, text: BinaryIO, name: str | None) -> STDFData:
+        """Parse ATDF text: translated to binary STDF records, then decoded."""
+        from .atdf import atdf_to_stdf
+
+        with tempfile.SpooledTemporaryFile(max_size=_SPOOL_MAX) as spool:
+            skipped = atdf_to_stdf((line.decode("latin-1") for line in text), spool)
+            spool.seek(0)
+            self._reset(name)
+            self._parse_records(spool)
+        counts = self.data.stats.skipped_records
+        for rec, n in skipped.items():
+            counts[rec] = counts.get(rec, 0) + n
+        self._finalize()
         return self.data
 
+    def parse_ranges(self, file_path: Path, ranges: list[tuple[int, int, int]]) -> STDFData:
+        """Parse only the byte ranges of `file_path` given as (start, end, parts_before).
 
-def parse_stdf(file_path: Path) -> STDFData:
+        Ranges must start on record boundaries, the first one containing the
+        FAR. parts_before (PIRs preceding the range) keeps the sequential ids
+        (seq_part_id) identical to a full parse. part_id can still differ: a
+        PRR PART_ID, or a sequential id, already taken by a part outside the
+        ranges is not seen as a duplicate here. Used by record_index.parse_indexed.
+        """
+        self._reset(file_path)
+        with open(file_path, "rb") as f:
+            self._detect_byte_order(f)
+            for start, end, parts_before in ranges:
+                f.seek(start)
+                self._part_counter = parts_before
+                self._parse_records(f, end)
+        self._finalize()
+        return self.data
+
+    def _reset(self, file_path: Path | str | None):
+        """Fresh STDFData and per-parse state."""
+        self.data = STDFData()
+        self._part_counter = 0
+        self._current_chip_efuses = []
+        self._tsr_names = {}
+        self._site_counts = {}
+        self._open_parts = {}
+        self._open_wafer = False
+        self._mrr_seen = False
+        self._file_path = Path(file_path) if file_path is not None else None
+        self._lot_resolved = False
+        self._sections = []
+        self._section = ""
+        self._open_scans = {}
+        self._part_rows = {}
+        self._part_ids = set()
+        self._wafer_rows = {}
+        self._far_seen = False
+        self._bin_summaries = set()
+        self._value_defaults = {}
+        self._cn_cache = {}
+        self._parts_ready = False
+        self._results_flushed = 0
+        self._results_capped = False
+        self._wafers_ready = []
+        self._set_endian("<")
+
+    def _detect_byte_order(self, f: BinaryIO):
+        """Set the byte order before the first record (see sniff_byte_order),
+        so records ahead of the FAR — or a file without one — decode right."""
+        self._set_endian(sniff_byte_order(f)[0])
+
+    def _parse_records(self, f: BinaryIO, end: int | None = None):
+        """Decode records from the current position up to `end` (default: EOF)."""
+        pos = f.tell()
+        stream_end = f.seek(0, os.SEEK_END)
+        f.seek(pos)
+        while end is None or f.tell() < end:
+            if self._parts_ready:
+                self._flush_parts()  # outside the per-record error handling
+            if self._wafers_ready:
+                self._flush_wafers()
+            rec_key = None
+            try:
+                rec_len, rec_typ, rec_sub = self._read_header(f)
+
+                # rec_len=0 means zero-padded or corrupt data — skip, except
+                # for the records whose fields are all optional (EPS has none)
+                if rec_len == 0 and (rec_typ, rec_sub) not in (REC_EPS, REC_BPS):
+                    if self.options.strict and rec_typ < 180:
+                        name = rec_name(rec_typ, rec_sub)
+                        raise STDFRecordError(
+                            f"{name} at offset 0x{f.tell() - 4:X}: REC_LEN 0", name, f.tell() - 4,
+                        )
+                    continue
+
+                rec_key = (rec_typ, rec_sub)
+                start_pos = f.tell()
+                if self._desynced(f, start_pos - 4, rec_len, rec_key, stream_end) and self._resync(
+                    f, start_pos - 4, rec_len, rec_key, stream_end
+                ):
+                    continue
+                self._rec_key, self._rec_start = rec_key, start_pos - 4
+                if self._mrr_seen:
+                    self._note_order("record after MRR")
+                self.data.stats.record_count += 1
+
+                if self._decode_keys is not None and rec_key not in self._decode_keys:
+                    f.seek(rec_len, os.SEEK_CUR)  # not in ParseOptions.records
+                elif rec_key == REC_FAR:
+                    self._parse_far(f, rec_len)
+                elif rec_key == REC_VUR:
+                    self._parse_vur(f, rec_len)
+                elif rec_key == REC_ATR:
+                    self._parse_atr(f, rec_len)
+                elif rec_key == REC_PMR:
+                    self._parse_pmr(f, rec_len)
+                elif rec_key == REC_PGR:
+                    self._parse_pgr(f, rec_len)
+                elif rec_key == REC_PLR:
+                    self._parse_plr(f, rec_len)
+                elif rec_key == REC_PSR:
+                    self._parse_psr(f, rec_len)
+                elif rec_key == REC_NMR:
+                    self._parse_nmr(f, rec_len)
+                elif rec_key == REC_CNR:
+                    self._parse_cnr(f, rec_len)
+                elif rec_key == REC_SSR:
+                    self._parse_ssr(f, rec_len)
+                elif rec_key == REC_CDR:
+                    self._parse_cdr(f, rec_len)
+                elif rec_key == REC_MIR:
+                    self._parse_mir(f, rec_len)
+                elif rec_key == REC_MRR:
+                    self._parse_mrr(f, rec_len)
+                elif rec_key == REC_WIR:
+                    self._parse_wir(f, rec_len)
+                elif rec_key == REC_WRR:
+                    self._parse_wrr(f, rec_len)
+                elif rec_key == REC_WCR:
+                    self._parse_wcr(f, rec_len)
+                elif rec_key == REC_PCR:
+                    self._parse_pcr(f, rec_len)
+                elif rec_key == REC_SDR:
+                    self._parse_sdr(f, rec_len)
+                elif rec_key == REC_RDR:
+                    self._parse_rdr(f, rec_len)
+                elif rec_key == REC_PIR:
+                    self._parse_pir(f, rec_len)
+                elif rec_key == REC_PRR:
+                    self._parse_prr(f, rec_len)
+                elif rec_key == REC_PTR:
+                    self._parse_ptr(f, rec_len)
+                elif rec_key == REC_MPR:
+                    self._parse_mpr(f, rec_len)
+                elif rec_key == REC_FTR:
+                    self._parse_ftr(f, rec_len)
+                elif rec_key == REC_STR:
+                    self._parse_str(f, rec_len)
+                elif rec_key == REC_HBR:
+                    self._parse_hbr(f, rec_len)
+                elif rec_key == REC_SBR:
+                    self._parse_sbr(f, rec_len)
+                elif rec_key == REC_GDR:
+                    self._parse_gdr(f, rec_len)
+                elif rec_key == REC_TSR:
+                    self._parse_tsr(f, rec_len)
+                elif rec_key == REC_DTR:
+                    self._parse_dtr(f, rec_len)
+                elif rec_key == REC_BPS:
+                    self._parse_bps(f, rec_len)
+                elif rec_key == REC_EPS:
+                    self._parse_eps(f, rec_len)
+                elif rec_key in self._record_handlers:
+                    self._parse_user_record(f, rec_len)
+                else:
+                    # No decoder for this type: skip it, but count it
+                    skipped = self.data.stats.skipped_records
+                    name = rec_name(*rec_key)
+                    skipped[name] = skipped.get(name, 0) + 1
+                    body = f.read(rec_len)
+                    if self.options.keep_unknown_records:
+                        self.data.unknown_records.append(
+                            (rec_typ, rec_sub, self._rec_start, body)
+                        )
+
+                # Ensure we consumed exactly rec_len bytes. A decoder that
+                # read past REC_LEN has eaten into the next record's header:
+                # flag it and re-sync to the declared record boundary.
+                consumed = f.tell() - start_pos
+                if consumed > rec_len:
+                    self._note_overrun(rec_key, start_pos - 4, consumed, rec_len)
+                    f.seek(start_pos + rec_len)
+                elif consumed < rec_len:
+                    self._note_leftover(rec_key, rec_len - consumed)
+                    f.read(rec_len - consumed)
+
+            except STDFTruncatedError as e:
+                self._note_truncated(rec_key, start_pos - 4, e)
+                if e.eof:
+                    break
+                f.seek(start_pos + rec_len)  # re-sync to the declared record boundary
+                continue
+            except EOFError:
+                if rec_key is not None:
+                    self._note_overrun(rec_key, start_pos - 4, None, rec_len)
+                break
+            except STDFError:
+                raise
+            except Exception as e:
+                errors = self.data.stats.error_records
+                name = rec_name(rec_typ, rec_sub)
+                errors[name] = errors.get(name, 0) + 1
+                if self.options.strict:
+                    raise STDFRecordError(
+                        f"{self._here()}: decoder failed ({e!r})", name, self._rec_start,
+                    ) from e
+                self._warn(
+                    f"{self._here()}: skipped, decoder failed ({e!r})", "decoder", name,
+                    self._rec_start,
+                )
+                # Re-sync to the declared record boundary
+                f.seek(start_pos + rec_len)
+                continue
+
+    def _finalize(self):
+        """Post-parse fix-ups that need records from later in the file."""
+        self._resolve_missing_mir("in file")  # no-op unless there was no MIR/WIR/PIR at all
+        if not self._far_seen and self.data.stats.record_count:
+            order = "big" if self._endian == ">" else "little"
+            self._fault(
+                STDFFormatError(f"no FAR in file — byte order inferred as {order} endian"),
+                "missing", "FAR",
+            )
+        # TSRs come after every PTR/FTR, so name backfill can only happen here
+        for test_num, test in self.data.tests.items():
+            if not test.get("test_name") and test_num in self._tsr_names:
+                test["test_name"] = self._tsr_names[test_num]
+        skipped = self.data.stats.skipped_records
+        if skipped:
+            self._warn(
+                f"skipped {sum(skipped.values()):,} record(s) with no decoder: "
+                + ", ".join(f"{name} x{n:,}" for name, n in skipped.items()),
+                "skipped",
+            )
+        self._check_complete()
+        self._close_open_parts("end of file")
+        if self._part_handler is not None or self._drop_parts:
+            self._flush_parts()
+        if self._open_wafer:
+            self._close_open_wafer("end of file")
+        if self._wafers_ready:
+            self._flush_wafers()
+        self._derive_wafer_bins()
+        self.data.stats.table_bytes = self.data.table_sizes()
+
+    def _derive_wafer_bins(self):
+        """Fill data.wafer_bins from part bins, grouped by wafer (file order)."""
+        counts: dict[str, dict[tuple[str, int], int]] = {}
+        for part in self.data.parts:
+            if not part["wafer_id"]:
+                continue  # FT / parts outside any WIR
+            wafer = counts.setdefault(part["wafer_id"], {})
+            for key in (("H", part["hard_bin"]), ("S", part["soft_bin"])):
+                wafer[key] = wafer.get(key, 0) + 1
+        defs = {"H": self.data.bins_hard, "S": self.data.bins_soft}
+        for wafer_id, wafer in counts.items():
+            for (bin_type, bin_num), n in sorted(wafer.items()):
+                d = defs[bin_type].get(bin_num, {})
+                self.data.wafer_bins.append({
+                    "wafer_id": wafer_id,
+                    "bin_type": bin_type,
+                    "bin_num": bin_num,
+                    "bin_name": d.get("bin_name", ""),
+                    "bin_pf": d.get("bin_pf", ""),
+                    "part_count": n,
+                })
+
+    def _check_complete(self):
+        """Set data.is_complete, warning once per reason the file looks unfinished."""
+        reasons = []
+        if not self._mrr_seen:
+            reasons.append("no MRR")
+        if self._open_parts:
+            reasons.append(f"{len(self._open_parts)} part(s) with PIR but no PRR")
+        if self._open_wafer:
+            reasons.append(f"wafer {self.data._current_wafer!r} with WIR but no WRR")
+        for reason in reasons:
+            self._fault(STDFFormatError(f"incomplete file: {reason}"), "incomplete")
+        self.data.is_complete = not reasons
+
+
+def parse_stdf(file_path: Path, options: ParseOptions | None = None) -> STDFData:
     """Parse an STDF file using the optimized Python parser."""
-    parser = STDFParser()
+    parser = STDFParser(options)
     return parser.parse(file_path)
+
+
+def cache_path_for(file_path: Path, cache_dir: Path) -> Path:
+    """Cache file for `file_path` in `cache_dir`: "<file name>.<path hash>.json",
+    so same-named files from different directories don't share one."""
+    key = hashlib.sha1(str(Path(file_path).resolve()).encode()).hexdigest()[:16]
+    return Path(cache_dir) / f"{Path(file_path).name}.{key}.json"
+
+
+def parse_cached(
+    file_path: Path, cache_dir: Path, options: ParseOptions | None = None,
+) -> STDFData:
+    """parse_stdf() through a cache in `cache_dir` (see cache_path_for): the
+    first call parses and saves, later calls load the cache until the file
+    or the options change. A cache that can't be written (read-only
+    location) is skipped.
+
+    `cache_dir` is required and should be a directory only this service
+    writes to, not one beside the data (e.g. an FTP drop)."""
+    file_path = Path(file_path)
+    cache_path = cache_path_for(file_path, cache_dir)
+    data = STDFData.load_cache(cache_path, file_path, options)
+    if data is None:
+        data = parse_stdf(file_path, options)
+        try:
+            data.save_cache(cache_path, file_path, options)
+        except OSError:
+            pass
+    return data
+
+
+def parse_reader(
+    stream: BinaryIO, options: ParseOptions | None = None, name: str | None = None,
+) -> STDFData:
+    """Parse STDF from a binary stream (stdin, a pipe, an open archive member)."""
+    return STDFParser(options).parse_reader(stream, name)
+
+
+def parse_bytes(
+    data: bytes, options: ParseOptions | None = None, name: str | None = None,
+) -> STDFData:
+    """Parse STDF from memory (bytes, bytearray or memoryview), no temp file."""
+    return STDFParser(options).parse_bytes(data, name)
+
+
+async def parse_async(
+    reader: Any, options: ParseOptions | None = None, name: str | None = None,
+) -> STDFData:
+    """Parse STDF from an asyncio stream or async iterable of byte chunks."""
+    return await STDFParser(options).parse_async(reader, name)
+
+
+def consistency_report(data: STDFData) -> list[dict]:
+    """List parts whose PRR disagrees with the test records actually collected.
+
+    Two checks per part:
+
+    - ``pass_fail_mismatch``: the part is recomputed as failing if any of its
+      test records failed. Mismatches usually mean a bin override on the tester
+      (PRR fail, every test passed) or an incomplete datalog (PRR pass, yet a
+      failing result was logged). Parts whose PRR carries no pass/fail
+      indication (PART_FLG bit 4) have nothing to compare and are skipped.
+    - ``num_test_mismatch``: PRR NUM_TEST differs from the number of test
+      records seen (``num_test_delta`` != 0) — results the tester claims to
+      have run but that never reached us, or records we failed to decode.
+
+    Returns one row per (part, issue).
+    """
+    rows = []
+    for part in data.parts:
+        issues = []
+        fail_count = part.get("fail_count", 0)
+        if part.get("pass_fail_valid", True) and part["passed"] != (fail_count == 0):
+            if part["passed"]:
+                detail = f"PRR pass but {fail_count} failing test record(s)"
+            else:
+                detail = "PRR fail but no failing test records"
+            issues.append(("pass_fail_mismatch", detail))
+        delta = part.get("num_test_delta", 0)
+        if delta:
+            issues.append((
+                "num_test_mismatch",
+                f"NUM_TEST {part['test_count']} but {part['result_count']} test record(s) seen",
+            ))
+        for issue, detail in issues:
+            rows.append({
+                "part_id": part["part_id"],
+                "wafer_id": part["wafer_id"],
+                "x_coord": part["x_coord"],
+                "y_coord": part["y_coord"],
+                "hard_bin": part["hard_bin"],
+                "soft_bin": part["soft_bin"],
+                "issue": issue,
+                "detail": detail,
+            })
+    return rows
+
+
+def census(file_path: Path) -> dict[str, dict]:
+    """Count records per type without decoding any record body.
+
+    Walks the 4-byte headers only (seeking over each body), so it is cheap even
+    on multi-GB files — the first thing to run on an unfamiliar tester's output.
+    Endianness is taken from the FAR (see sniff_byte_order).
+
+    Returns {rec_name: {rec_typ, rec_sub, count, total_bytes, min_len,
+    avg_len, max_len}} in first-seen order. Lengths are REC_LEN (body only);
+    total_bytes includes the 4-byte headers, so the totals sum to the file size
+    (barring a truncated final record).
+    """
+    stats: dict[str, dict] = {}
+    with open(file_path, "rb") as f:
+        endian = sniff_byte_order(f)[0]
+        s_u2 = _STRUCT_HEADER_BE if endian == ">" else _STRUCT_HEADER_LE
+        header = f.read(4)
+        while len(header) == 4:
+            rec_len = s_u2.unpack_from(header, 0)[0]
+            rec_typ, rec_sub = header[2], header[3]
+            name = rec_name(rec_typ, rec_sub)
+            entry = stats.get(name)
+            if entry is None:
+                entry = stats[name] = {
+                    "rec_typ": rec_typ, "rec_sub": rec_sub, "count": 0,
+                    "total_bytes": 0, "min_len": rec_len, "max_len": rec_len,
+                }
+            entry["count"] += 1
+            entry["total_bytes"] += rec_len + 4
+            entry["min_len"] = min(entry["min_len"], rec_len)
+            entry["max_len"] = max(entry["max_len"], rec_len)
+            f.seek(rec_len, 1)
+            header = f.read(4)
+
+    for entry in stats.values():
+        entry["avg_len"] = (entry["total_bytes"] - 4 * entry["count"]) / entry["count"]
+    return stats
+
+
+def bench_parse(file_path: Path, repeat: int = 3) -> list[dict]:
+    """Measure parse throughput on `file_path`, with and without result collection.
+
+    Each mode is run `repeat` times and the fastest run is reported (the page
+    cache is warm after the first pass, so best-of-N is the stable number).
+
+    Returns one row per mode: {mode, seconds, mb_per_s, records_per_s,
+    records, results}.
+    """
+    size_mb = Path(file_path).stat().st_size / (1024 * 1024)
+    rows = []
+    for mode, options in (
+        ("full", ParseOptions()),
+        ("no results", ParseOptions(collect_results=False)),
+    ):
+        best = None
+        for _ in range(max(repeat, 1)):
+            t0 = time.perf_counter()
+            data = STDFParser(options).parse(file_path)
+            elapsed = time.perf_counter() - t0
+            if best is None or elapsed < best[0]:
+                best = (elapsed, data)
+        elapsed, data = best
+        elapsed = max(elapsed, 1e-9)
+        records = data.stats.record_count
+        rows.append({
+            "mode": mode,
+            "seconds": elapsed,
+            "mb_per_s": size_mb / elapsed,
+            "records_per_s": records / elapsed,
+            "records": records,
+            "results": len(data.test_results),
+        })
+    return rows
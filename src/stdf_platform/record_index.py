@@ -0,0 +1,141 @@
+"""Record index: per-wafer byte ranges of an STDF file, cached next to it.
+
+Building the index is a headers-only walk (like parser.census; only WIR bodies
+are read, for the wafer id). Once it exists, filtered re-parses — a few
+wafers, a few tests — seek straight to the wafers they need instead of
+decoding the whole multi-GB file again:
+
+    data = parse_indexed(path, wafers={"W03"}, tests={1001, 1002})
+
+The index is JSON at ``_idx/<file name>.json`` beside the file (a separate
+directory so `stdf ingest-all`'s ``*.stdf*`` glob never picks it up) and is
+rebuilt automatically when the STDF file's size or mtime no longer match.
+
+Layout: ``head`` = everything before the first WIR (FAR/MIR/PMR/...), one
+range per wafer (WIR up to the next WIR, the last one up to its WRR), and
+``tail`` = the rest (summary HBR/SBR/TSR, MRR). Files without WIR (FT) are a
+single head range.
+"""
+
+import json
+import os
+import struct
+from dataclasses import replace
+from pathlib import Path
+
+from .parser import (
+    REC_PIR, REC_WIR, REC_WRR, ParseOptions, STDFData, STDFParser, sniff_byte_order,
+)
+
+INDEX_VERSION = 1
+
+
+def index_path_for(file_path: Path) -> Path:
+    return file_path.parent / "_idx" / (file_path.name + ".json")
+
+
+def build_index(file_path: Path) -> dict:
+    """Walk record headers once and return the index dict (see module docstring)."""
+    file_path = Path(file_path)
+    st = file_path.stat()
+    wafers: list[dict] = []
+    parts = 0
+    head_end = None
+    tail_start = None  # end of the last WRR seen (None while a wafer is open)
+    tail_parts = 0  # PIRs before tail_start
+
+    with open(file_path, "rb") as f:
+        u2 = struct.Struct(sniff_byte_order(f)[0] + "H")
+        while True:
+            offset = f.tell()
+            header = f.read(4)
+            if len(header) < 4:
+                break
+            rec_len = u2.unpack_from(header, 0)[0]
+            rec_key = (header[2], header[3])
+            if rec_key == REC_WIR:
+                body = f.read(rec_len)
+                n = body[6] if len(body) > 6 else 0
+                wafer_id = body[7:7 + n].decode("ascii", errors="replace").replace("\x00", "").strip()
+                if head_end is None:
+                    head_end = offset
+                wafers.append({"wafer_id": wafer_id, "start": offset, "end": None, "parts_before": parts})
+                tail_start = None
+                continue
+            if rec_key == REC_PIR:
+                parts += 1
+            elif rec_key == REC_WRR and wafers:
+                tail_start = offset + 4 + rec_len
+                tail_parts = parts
+            f.seek(rec_len, 1)
+
+    size = st.st_size
+    tail = [size, size, parts]
+    if wafers:
+        # A wafer closed by its WRR ends there; the records up to the next WIR
+        # (per-wafer HBR/SBR and the like) stay with it.
+        for w, nxt in zip(wafers, wafers[1:]):
+            w["end"] = nxt["start"]
+        wafers[-1]["end"] = tail_start if tail_start is not None else size
+        tail = [wafers[-1]["end"], size, tail_parts]
+
+    return {
+        "version": INDEX_VERSION,
+        "size": size,
+        "mtime_ns": st.st_mtime_ns,
+        "head": [0, head_end if head_end is not None else size],
+        "wafers": wafers,
+        "tail": tail,
+    }
+
+
+def load_or_build_index(file_path: Path, index_path: Path | None = None) -> dict:
+    """Cached index for file_path, rebuilt (and re-saved) if missing or stale."""
+    file_path = Path(file_path)
+    index_path = index_path or index_path_for(file_path)
+    st = file_path.stat()
+    try:
+        index = json.loads(index_path.read_text(encoding="utf-8"))
+        if (index.get("version"), index.get("size"), index.get("mtime_ns")) == (
+            INDEX_VERSION, st.st_size, st.st_mtime_ns,
+        ):
+            return index
+    except (OSError, ValueError):
+        pass
+
+    index = build_index(file_path)
+    try:
+        index_path.parent.mkdir(parents=True, exist_ok=True)
+        tmp = index_path.with_name(index_path.name + ".tmp")
+        tmp.write_text(json.dumps(index), encoding="utf-8")
+        os.replace(tmp, index_path)
+    except OSError:
+        pass  # read-only location: the index just isn't cached
+    return index
+
+
+def parse_indexed(
+    file_path: Path,
+    wafers: set[str] | None = None,
+    tests: set[int] | None = None,
+    options: ParseOptions | None = None,
+    index_path: Path | None = None,
+) -> STDFData:
+    """Parse only the selected wafers (None = all) and tests (None = all).
+
+    Part ids, test definitions and lot metadata match a full parse; wafers
+    not selected are never read.
+    """
+    index = load_or_build_index(Path(file_path), index_path)
+    options = options or ParseOptions()
+    if tests is not None:
+        options = replace(options, tests=set(tests))
+
+    ranges = [(index["head"][0], index["head"][1], 0)]
+    ranges += [
+        (w["start"], w["end"], w["parts_before"])
+        for w in index["wafers"]
+        if wafers is None or w["wafer_id"] in wafers
+    ]
+    ranges.append(tuple(index["tail"]))
+    return STDFParser(options).parse_ranges(Path(file_path), ranges)
@@ -0,0 +1,1037 @@
+"""Record-level STDF access: every field of every record, decoded and re-encoded.
+
+The parser (parser.py) turns records into test data; this module keeps them
+as records. FIELD_LAYOUTS (defined in parser.py, whose decoder shares it)
+lists the fields of each STDF V4 / V4-2007 record in file order;
+decode_fields() / encode_fields() convert a record body to a {FIELD: value}
+dict and back, byte for byte.
+
+Values in the dicts are JSON-friendly: numbers, C*n / S*n / C*1 as latin-1
+text (every byte maps to one character), B*n as hex, D*n as {"bits", "data"
+(hex)}, arrays as lists, GDR V*n as [[type code, value], ...]. Trailing
+optional fields a writer omitted are simply absent.
+
+For code that prefers objects to dicts there is one dataclass per record
+type — Mir, Ptr, Prr, ... (RECORD_CLASSES), attributes named after the
+fields in lower case, None for omitted ones. Record.parse() turns a record
+into its class (RawRecord when there is none: STR, vendor types) and
+read_records() iterates a file that way.
+
+filter_records() copies a file leaving out whole record types (summary
+archives without the per-test detail); repair_stdf() copies a corrupt one
+minus the bytes between an implausible record header and the next
+plausible one.
+
+dump_json() writes a file as JSON Lines, one record per line, and
+build_stdf() rebuilds the identical file from it. A record whose fields
+don't re-encode to its exact bytes (STR, truncated or vendor records, odd
+padding) is kept as raw hex instead, so the round trip is always lossless.
+"""
+
+import json
+import os
+import shutil
+import struct
+import tempfile
+from dataclasses import dataclass, field
+from pathlib import Path
+from typing import BinaryIO, ClassVar, Iterable, Iterator, TextIO
+
+from .parser import (
+    FIELD_LAYOUTS, REC_NAMES, STDFFormatError, STDFRecordError, find_record_header,
+    plausible_header, sniff_byte_order,
+)
+
+JSON_FORMAT = "stdf-records"
+JSON_VERSION = 1
+
+
+REC_KEYS = {name: key for key, name in REC_NAMES.items()}
+
+# Byte offset of HEAD_NUM in records carrying head / site (SITE_NUM follows)
+HEAD_OFFSET = {
+    "PIR": 0, "PRR": 0, "HBR": 0, "SBR": 0, "PCR": 0, "TSR": 0,
+    "PTR": 4, "MPR": 4, "FTR": 4, "STR": 5,
+}
+
+_STRUCT_CODES = {
+    "U1": "B", "B1": "B", "U2": "H", "U4": "I", "U8": "Q",
+    "I1": "b", "I2": "h", "I4": "i", "R4": "f", "R8": "d",
+}
+# GDR V*n type codes -> field type (0 is the B*0 pad byte, 13 a nibble in a byte)
+_VN_KINDS = {
+    0: None, 1: "U1", 2: "U2", 3: "U4", 4: "I1", 5: "I2", 6: "I4", 7: "R4", 8: "R8",
+    10: "Cn", 11: "Bn", 12: "Dn", 13: "U1",
+}
+
+
+class _Short(Exception):
+    """The record body ends inside a field."""
+
+
+def _need(body: bytes, end: int):
+    if end > len(body):
+        raise _Short
+
+
+def _read(kind: str, body: bytes, offset: int, endian: str) -> tuple[object, int]:
+    code = _STRUCT_CODES.get(kind)
+    if code is not None:
+        end = offset + struct.calcsize(code)
+        _need(body, end)
+        return struct.unpack_from(endian + code, body, offset)[0], end
+    if kind == "C1":
+        _need(body, offset + 1)
+        return body[offset:offset + 1].decode("latin-1"), offset + 1
+    if kind in ("Cn", "Bn", "Sn"):
+        if kind == "Sn":
+            _need(body, offset + 2)
+            n = struct.unpack_from(endian + "H", body, offset)[0]
+            offset += 2
+        else:
+            _need(body, offset + 1)
+            n = body[offset]
+            offset += 1
+        _need(body, offset + n)
+        raw = body[offset:offset + n]
+        return (raw.hex() if kind == "Bn" else raw.decode("latin-1")), offset + n
+    if kind == "Dn":
+        _need(body, offset + 2)
+        bits = struct.unpack_from(endian + "H", body, offset)[0]
+        end = offset + 2 + (bits + 7) // 8
+        _need(body, end)
+        return {"bits": bits, "data": body[offset + 2:end].hex()}, end
+    if kind == "Vn":
+        _need(body, offset + 1)
+        type_code = body[offset]
+        if type_code not in _VN_KINDS:
+            raise _Short  # not a V*n type: keep the record raw
+        value_kind = _VN_KINDS[type_code]
+        if value_kind is None:
+            return [type_code, None], offset + 1
+        value, end = _read(value_kind, body, offset + 1, endian)
+        return [type_code, value], end
+    raise ValueError(f"unknown field type {kind!r}")
+
+
+def _write(kind: str, value, endian: str) -> bytes:
+    code = _STRUCT_CODES.get(kind)
+    if code is not None:
+        return struct.pack(endian + code, value)
+    if kind == "C1":
+        return (value or " ")[:1].encode("latin-1")
+    if kind in ("Cn", "Sn"):
+        raw = value.encode("latin-1")
+        return (struct.pack(endian + "H", len(raw)) if kind == "Sn" else bytes([len(raw)])) + raw
+    if kind == "Bn":
+        raw = bytes.fromhex(value)
+        return bytes([len(raw)]) + raw
+    if kind == "Dn":
+        return struct.pack(endian + "H", value["bits"]) + bytes.fromhex(value["data"])
+    if kind == "Vn":
+        type_code, item = value
+        value_kind = _VN_KINDS[type_code]
+        return bytes([type_code]) + (b"" if value_kind is None else _write(value_kind, item, endian))
+    raise ValueError(f"unknown field type {kind!r}")
+
+
+def decode_fields(rec: str, body: bytes, endian: str = "<") -> tuple[dict, int]:
+    """Fields of record `rec` from its body: ({FIELD: value}, bytes consumed).
+
+    Decoding stops where the body ends (trailing fields omitted); a body that
+    ends inside a field raises STDFRecordError (a ValueError).
+    """
+    fields: dict = {}
+    offset = 0
+    try:
+        for name, kind, *count in FIELD_LAYOUTS[rec]:
+            if offset >= len(body):
+                break
+            if kind == "kN1":  # nibbles, two per byte, low nibble first
+                n = fields[count[0]]
+                end = offset + (n + 1) // 2
+                _need(body, end)
+                packed = body[offset:end]
+                fields[name] = [packed[i >> 1] >> (4 * (i & 1)) & 0x0F for i in range(n)]
+                offset = end
+            elif kind.startswith("k"):
+                values = []
+                for _ in range(fields[count[0]]):
+                    value, offset = _read(kind[1:], body, offset, endian)
+                    values.append(value)
+                fields[name] = values
+            else:
+                fields[name], offset = _read(kind, body, offset, endian)
+    except (_Short, KeyError):
+        raise STDFRecordError(
+            f"{rec} body ends inside a field ({len(body)} bytes)", rec
+        ) from None
+    return fields, offset
+
+
+def encode_fields(rec: str, fields: dict, endian: str = "<") -> bytes:
+    """Record body from {FIELD: value}: fields in layout order up to the first
+    one absent. Array fields are written as given (keep their counts in step)."""
+    out = bytearray()
+    for name, kind, *_count in FIELD_LAYOUTS[rec]:
+        if name not in fields:
+            break
+        value = fields[name]
+        if kind == "kN1":
+            packed = bytearray((len(value) + 1) // 2)
+            for i, nibble in enumerate(value):
+                packed[i >> 1] |= (nibble & 0x0F) << (4 * (i & 1))
+            out += packed
+        elif kind.startswith("k"):
+            for item in value:
+                out += _write(kind[1:], item, endian)
+        else:
+            out += _write(kind, value, endian)
+    return bytes(out)
+
+
+def iter_records(f: BinaryIO, endian: str) -> Iterator[tuple[int, int, int, bytes]]:
+    """(rec_typ, rec_sub, REC_LEN, body) per record from f's position to EOF.
+    The body of a record cut off by the end of the file is shorter than
+    REC_LEN; fewer than 4 bytes left at the end come as (-1, -1, 0, bytes)."""
+    header = struct.Struct(endian + "HBB")
+    while True:
+        head = f.read(4)
+        if len(head) < 4:
+            if head:
+                yield -1, -1, 0, head
+            return
+        rec_len, rec_typ, rec_sub = header.unpack(head)
+        yield rec_typ, rec_sub, rec_len, f.read(rec_len)
+
+
+@dataclass
+class Record:
+    """Base of the typed record classes (RECORD_CLASSES): one attribute per
+    field of FIELD_LAYOUTS[NAME], lower case, None where the record ends
+    before it; `tail` holds bytes past the last field."""
+
+    NAME: ClassVar[str] = ""
+
+    @property
+    def rec_key(self) -> tuple[int, int]:
+        return REC_KEYS[self.NAME]
+
+    @staticmethod
+    def parse(rec_typ: int, rec_sub: int, body: bytes, endian: str = "<") -> "Record":
+        """The record as an instance of its class (RawRecord for types
+        without a layout). STDFRecordError if the body ends inside a field."""
+        cls = RECORD_CLASSES.get(REC_NAMES.get((rec_typ, rec_sub), ""))
+        if cls is None:
+            return RawRecord(rec_typ, rec_sub, bytes(body))
+        values, used = decode_fields(cls.NAME, body, endian)
+        return cls(**{name.lower(): value for name, value in values.items()}, tail=bytes(body[used:]))
+
+    def fields(self) -> dict:
+        """{FIELD: value} up to the first field that is None (encode_fields)."""
+        out = {}
+        for name, *_ in FIELD_LAYOUTS[self.NAME]:
+            value = getattr(self, name.lower())
+            if value is None:
+                break
+            out[name] = value
+        return out
+
+    def encode(self, endian: str = "<") -> bytes:
+        """Header and body, as written to a file."""
+        body = encode_fields(self.NAME, self.fields(), endian) + self.tail
+        return struct.pack(endian + "HBB", len(body), *self.rec_key) + body
+
+
+@dataclass
+class RawRecord(Record):
+    """A record kept as bytes: no layout (STR, vendor types), or cut off."""
+
+    rec_typ: int = 0
+    rec_sub: int = 0
+    body: bytes = b""
+
+    @property
+    def rec_key(self) -> tuple[int, int]:
+        return self.rec_typ, self.rec_sub
+
+    def fields(self) -> dict:
+        return {}
+
+    def encode(self, endian: str = "<") -> bytes:
+        return struct.pack(endian + "HBB", len(self.body), self.rec_typ, self.rec_sub) + self.body
+
+
+# One class per FIELD_LAYOUTS entry, attributes in its field order (lower
+# case); list-typed ones are arrays (k<type> fields), B*n is hex text
+@dataclass
+class Far(Record):
+    """File Attributes Record."""
+
+    NAME: ClassVar[str] = "FAR"
+    cpu_type: int | None = None
+    stdf_ver: int | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Atr(Record):
+    """Audit Trail Record."""
+
+    NAME: ClassVar[str] = "ATR"
+    mod_tim: int | None = None
+    cmd_line: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Vur(Record):
+    """Version Update Record."""
+
+    NAME: ClassVar[str] = "VUR"
+    upd_cnt: int | None = None
+    upd_nam: list[str] | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Mir(Record):
+    """Master Information Record."""
+
+    NAME: ClassVar[str] = "MIR"
+    setup_t: int | None = None
+    start_t: int | None = None
+    stat_num: int | None = None
+    mode_cod: str | None = None
+    rtst_cod: str | None = None
+    prot_cod: str | None = None
+    burn_tim: int | None = None
+    cmod_cod: str | None = None
+    lot_id: str | None = None
+    part_typ: str | None = None
+    node_nam: str | None = None
+    tstr_typ: str | None = None
+    job_nam: str | None = None
+    job_rev: str | None = None
+    sblot_id: str | None = None
+    oper_nam: str | None = None
+    exec_typ: str | None = None
+    exec_ver: str | None = None
+    test_cod: str | None = None
+    tst_temp: str | None = None
+    user_txt: str | None = None
+    aux_file: str | None = None
+    pkg_typ: str | None = None
+    famly_id: str | None = None
+    date_cod: str | None = None
+    facil_id: str | None = None
+    floor_id: str | None = None
+    proc_id: str | None = None
+    oper_frq: str | None = None
+    spec_nam: str | None = None
+    spec_ver: str | None = None
+    flow_id: str | None = None
+    setup_id: str | None = None
+    dsgn_rev: str | None = None
+    eng_id: str | None = None
+    rom_cod: str | None = None
+    serl_num: str | None = None
+    supr_nam: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Mrr(Record):
+    """Master Results Record."""
+
+    NAME: ClassVar[str] = "MRR"
+    finish_t: int | None = None
+    disp_cod: str | None = None
+    usr_desc: str | None = None
+    exc_desc: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Pcr(Record):
+    """Part Count Record."""
+
+    NAME: ClassVar[str] = "PCR"
+    head_num: int | None = None
+    site_num: int | None = None
+    part_cnt: int | None = None
+    rtst_cnt: int | None = None
+    abrt_cnt: int | None = None
+    good_cnt: int | None = None
+    func_cnt: int | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Hbr(Record):
+    """Hardware Bin Record."""
+
+    NAME: ClassVar[str] = "HBR"
+    head_num: int | None = None
+    site_num: int | None = None
+    hbin_num: int | None = None
+    hbin_cnt: int | None = None
+    hbin_pf: str | None = None
+    hbin_nam: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Sbr(Record):
+    """Software Bin Record."""
+
+    NAME: ClassVar[str] = "SBR"
+    head_num: int | None = None
+    site_num: int | None = None
+    sbin_num: int | None = None
+    sbin_cnt: int | None = None
+    sbin_pf: str | None = None
+    sbin_nam: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Pmr(Record):
+    """Pin Map Record."""
+
+    NAME: ClassVar[str] = "PMR"
+    pmr_indx: int | None = None
+    chan_typ: int | None = None
+    chan_nam: str | None = None
+    phy_nam: str | None = None
+    log_nam: str | None = None
+    head_num: int | None = None
+    site_num: int | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Pgr(Record):
+    """Pin Group Record."""
+
+    NAME: ClassVar[str] = "PGR"
+    grp_indx: int | None = None
+    grp_nam: str | None = None
+    indx_cnt: int | None = None
+    pmr_indx: list[int] | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Plr(Record):
+    """Pin List Record."""
+
+    NAME: ClassVar[str] = "PLR"
+    grp_cnt: int | None = None
+    grp_indx: list[int] | None = None
+    grp_mode: list[int] | None = None
+    grp_radx: list[int] | None = None
+    pgm_char: list[str] | None = None
+    rtn_char: list[str] | None = None
+    pgm_chal: list[str] | None = None
+    rtn_chal: list[str] | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Rdr(Record):
+    """Retest Data Record."""
+
+    NAME: ClassVar[str] = "RDR"
+    num_bins: int | None = None
+    rtst_bin: list[int] | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Sdr(Record):
+    """Site Description Record."""
+
+    NAME: ClassVar[str] = "SDR"
+    head_num: int | None = None
+    site_grp: int | None = None
+    site_cnt: int | None = None
+    site_num: list[int] | None = None
+    hand_typ: str | None = None
+    hand_id: str | None = None
+    card_typ: str | None = None
+    card_id: str | None = None
+    load_typ: str | None = None
+    load_id: str | None = None
+    dib_typ: str | None = None
+    dib_id: str | None = None
+    cabl_typ: str | None = None
+    cabl_id: str | None = None
+    cont_typ: str | None = None
+    cont_id: str | None = None
+    lasr_typ: str | None = None
+    lasr_id: str | None = None
+    extr_typ: str | None = None
+    extr_id: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Psr(Record):
+    """Pattern Sequence Record."""
+
+    NAME: ClassVar[str] = "PSR"
+    cont_flg: int | None = None
+    psr_indx: int | None = None
+    psr_nam: str | None = None
+    opt_flg: int | None = None
+    totp_cnt: int | None = None
+    locp_cnt: int | None = None
+    pat_bgn: list[int] | None = None
+    pat_end: list[int] | None = None
+    pat_file: list[str] | None = None
+    pat_lbl: list[str] | None = None
+    file_uid: list[str] | None = None
+    atpg_dsc: list[str] | None = None
+    src_id: list[str] | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Nmr(Record):
+    """Name Map Record."""
+
+    NAME: ClassVar[str] = "NMR"
+    cont_flg: int | None = None
+    totm_cnt: int | None = None
+    locm_cnt: int | None = None
+    pmr_indx: list[int] | None = None
+    atpg_nam: list[str] | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Cnr(Record):
+    """Cell Name Record."""
+
+    NAME: ClassVar[str] = "CNR"
+    chn_num: int | None = None
+    bit_pos: int | None = None
+    cell_nam: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Ssr(Record):
+    """Scan Structure Record."""
+
+    NAME: ClassVar[str] = "SSR"
+    ssr_nam: str | None = None
+    chn_cnt: int | None = None
+    chn_list: list[int] | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Cdr(Record):
+    """Chain Description Record."""
+
+    NAME: ClassVar[str] = "CDR"
+    cont_flg: int | None = None
+    cdr_indx: int | None = None
+    chn_nam: str | None = None
+    chn_len: int | None = None
+    sin_pin: int | None = None
+    sout_pin: int | None = None
+    mstr_cnt: int | None = None
+    m_clks: list[int] | None = None
+    slav_cnt: int | None = None
+    s_clks: list[int] | None = None
+    inv_val: int | None = None
+    lst_cnt: int | None = None
+    cell_lst: list[str] | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Wir(Record):
+    """Wafer Information Record."""
+
+    NAME: ClassVar[str] = "WIR"
+    head_num: int | None = None
+    site_grp: int | None = None
+    start_t: int | None = None
+    wafer_id: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Wrr(Record):
+    """Wafer Results Record."""
+
+    NAME: ClassVar[str] = "WRR"
+    head_num: int | None = None
+    site_grp: int | None = None
+    finish_t: int | None = None
+    part_cnt: int | None = None
+    rtst_cnt: int | None = None
+    abrt_cnt: int | None = None
+    good_cnt: int | None = None
+    func_cnt: int | None = None
+    wafer_id: str | None = None
+    fabwf_id: str | None = None
+    frame_id: str | None = None
+    mask_id: str | None = None
+    usr_desc: str | None = None
+    exc_desc: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Wcr(Record):
+    """Wafer Configuration Record."""
+
+    NAME: ClassVar[str] = "WCR"
+    wafr_siz: float | None = None
+    die_ht: float | None = None
+    die_wid: float | None = None
+    wf_units: int | None = None
+    wf_flat: str | None = None
+    center_x: int | None = None
+    center_y: int | None = None
+    pos_x: str | None = None
+    pos_y: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Pir(Record):
+    """Part Information Record."""
+
+    NAME: ClassVar[str] = "PIR"
+    head_num: int | None = None
+    site_num: int | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Prr(Record):
+    """Part Results Record."""
+
+    NAME: ClassVar[str] = "PRR"
+    head_num: int | None = None
+    site_num: int | None = None
+    part_flg: int | None = None
+    num_test: int | None = None
+    hard_bin: int | None = None
+    soft_bin: int | None = None
+    x_coord: int | None = None
+    y_coord: int | None = None
+    test_t: int | None = None
+    part_id: str | None = None
+    part_txt: str | None = None
+    part_fix: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Tsr(Record):
+    """Test Synopsis Record."""
+
+    NAME: ClassVar[str] = "TSR"
+    head_num: int | None = None
+    site_num: int | None = None
+    test_typ: str | None = None
+    test_num: int | None = None
+    exec_cnt: int | None = None
+    fail_cnt: int | None = None
+    alrm_cnt: int | None = None
+    test_nam: str | None = None
+    seq_name: str | None = None
+    test_lbl: str | None = None
+    opt_flag: int | None = None
+    test_tim: float | None = None
+    test_min: float | None = None
+    test_max: float | None = None
+    tst_sums: float | None = None
+    tst_sqrs: float | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Ptr(Record):
+    """Parametric Test Record."""
+
+    NAME: ClassVar[str] = "PTR"
+    test_num: int | None = None
+    head_num: int | None = None
+    site_num: int | None = None
+    test_flg: int | None = None
+    parm_flg: int | None = None
+    result: float | None = None
+    test_txt: str | None = None
+    alarm_id: str | None = None
+    opt_flag: int | None = None
+    res_scal: int | None = None
+    llm_scal: int | None = None
+    hlm_scal: int | None = None
+    lo_limit: float | None = None
+    hi_limit: float | None = None
+    units: str | None = None
+    c_resfmt: str | None = None
+    c_llmfmt: str | None = None
+    c_hlmfmt: str | None = None
+    lo_spec: float | None = None
+    hi_spec: float | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Mpr(Record):
+    """Multiple-Result Parametric Record."""
+
+    NAME: ClassVar[str] = "MPR"
+    test_num: int | None = None
+    head_num: int | None = None
+    site_num: int | None = None
+    test_flg: int | None = None
+    parm_flg: int | None = None
+    rtn_icnt: int | None = None
+    rslt_cnt: int | None = None
+    rtn_stat: list[int] | None = None
+    rtn_rslt: list[float] | None = None
+    test_txt: str | None = None
+    alarm_id: str | None = None
+    opt_flag: int | None = None
+    res_scal: int | None = None
+    llm_scal: int | None = None
+    hlm_scal: int | None = None
+    lo_limit: float | None = None
+    hi_limit: float | None = None
+    start_in: float | None = None
+    incr_in: float | None = None
+    rtn_indx: list[int] | None = None
+    units: str | None = None
+    units_in: str | None = None
+    c_resfmt: str | None = None
+    c_llmfmt: str | None = None
+    c_hlmfmt: str | None = None
+    lo_spec: float | None = None
+    hi_spec: float | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Ftr(Record):
+    """Functional Test Record."""
+
+    NAME: ClassVar[str] = "FTR"
+    test_num: int | None = None
+    head_num: int | None = None
+    site_num: int | None = None
+    test_flg: int | None = None
+    opt_flag: int | None = None
+    cycl_cnt: int | None = None
+    rel_vadr: int | None = None
+    rept_cnt: int | None = None
+    num_fail: int | None = None
+    xfail_ad: int | None = None
+    yfail_ad: int | None = None
+    vect_off: int | None = None
+    rtn_icnt: int | None = None
+    pgm_icnt: int | None = None
+    rtn_indx: list[int] | None = None
+    rtn_stat: list[int] | None = None
+    pgm_indx: list[int] | None = None
+    pgm_stat: list[int] | None = None
+    fail_pin: dict | None = None
+    vect_nam: str | None = None
+    time_set: str | None = None
+    op_code: str | None = None
+    test_txt: str | None = None
+    alarm_id: str | None = None
+    prog_txt: str | None = None
+    rslt_txt: str | None = None
+    patg_num: int | None = None
+    spin_map: dict | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Bps(Record):
+    """Begin Program Section Record."""
+
+    NAME: ClassVar[str] = "BPS"
+    seq_name: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Eps(Record):
+    """End Program Section Record."""
+
+    NAME: ClassVar[str] = "EPS"
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Gdr(Record):
+    """Generic Data Record."""
+
+    NAME: ClassVar[str] = "GDR"
+    fld_cnt: int | None = None
+    gen_data: list | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+@dataclass
+class Dtr(Record):
+    """Datalog Text Record."""
+
+    NAME: ClassVar[str] = "DTR"
+    text_dat: str | None = None
+    tail: bytes = field(default=b"", repr=False)
+
+
+RECORD_CLASSES: dict[str, type[Record]] = {cls.NAME: cls for cls in (
+    Far, Atr, Vur, Mir, Mrr, Pcr, Hbr, Sbr, Pmr, Pgr, Plr, Rdr, Sdr, Psr, Nmr, Cnr, Ssr, Cdr,
+    Wir, Wrr, Wcr, Pir, Prr, Tsr, Ptr, Mpr, Ftr, Bps, Eps, Gdr, Dtr,
+)}
+
+
+def read_records(path: Path) -> Iterator[Record]:
+    """Every record of the (uncompressed) STDF file at `path` as a Record;
+    one that doesn't decode (cut off, malformed) comes as a RawRecord."""
+    with open(path, "rb") as f:
+        endian = sniff_byte_order(f)[0]
+        for rec_typ, rec_sub, rec_len, body in iter_records(f, endian):
+            if rec_typ < 0:
+                return
+            try:
+                if rec_len != len(body):
+                    raise ValueError("cut off")
+                yield Record.parse(rec_typ, rec_sub, body, endian)
+            except ValueError:
+                yield RawRecord(rec_typ, rec_sub, body)
+
+
+def record_to_json(rec_typ: int, rec_sub: int, rec_len: int, body: bytes, endian: str) -> dict:
+    """JSON object for one record: decoded fields when they re-encode to the
+    exact body (plus a hex "tail" for undecoded trailing bytes), else "raw"."""
+    name = REC_NAMES.get((rec_typ, rec_sub), f"{rec_typ}/{rec_sub}")
+    obj: dict = {"rec": name, "typ": rec_typ, "sub": rec_sub}
+    if rec_len != len(body):
+        obj["rec_len"] = rec_len  # cut off by the end of the file
+    elif name in FIELD_LAYOUTS:
+        try:
+            fields, used = decode_fields(name, body, endian)
+        except ValueError:
+            fields, used = None, 0
+        if fields is not None and encode_fields(name, fields, endian) == body[:used]:
+            obj["fields"] = fields
+            if used < len(body):
+                obj["tail"] = body[used:].hex()
+            return obj
+    obj["raw"] = body.hex()
+    return obj
+
+
+def record_from_json(obj: dict, endian: str) -> bytes:
+    """Header + body bytes for a record_to_json() object."""
+    if "fields" in obj:
+        body = encode_fields(obj["rec"], obj["fields"], endian) + bytes.fromhex(obj.get("tail", ""))
+    else:
+        body = bytes.fromhex(obj["raw"])
+    rec_len = obj.get("rec_len", len(body))
+    return struct.pack(endian + "HBB", rec_len, obj["typ"], obj["sub"]) + body
+
+
+def dump_json(path: Path, out: TextIO) -> int:
+    """Write every record of the (uncompressed) STDF file at `path` as JSON
+    Lines: a header line {"format", "version", "byte_order"}, then one object
+    per record (see record_to_json). Returns the record count."""
+    count = 0
+    with open(path, "rb") as f:
+        endian = sniff_byte_order(f)[0]
+        out.write(json.dumps({
+            "format": JSON_FORMAT, "version": JSON_VERSION,
+            "byte_order": "big" if endian == ">" else "little",
+        }) + "\n")
+        for rec_typ, rec_sub, rec_len, body in iter_records(f, endian):
+            if rec_typ < 0:
+                out.write(json.dumps({"trailing": body.hex()}) + "\n")
+                continue
+            obj = record_to_json(rec_typ, rec_sub, rec_len, body, endian)
+            out.write(json.dumps(obj, separators=(",", ":")) + "\n")
+            count += 1
+    return count
+
+
+def build_stdf(lines: Iterable[str], out: BinaryIO) -> int:
+    """Write the STDF file described by dump_json() output `lines` to `out`.
+    Returns the record count."""
+    lines = iter(lines)
+    header = json.loads(next(lines))
+    if header.get("format") != JSON_FORMAT:
+        raise ValueError("not an STDF record dump (no stdf-records header line)")
+    endian = ">" if header["byte_order"] == "big" else "<"
+    count = 0
+    for n, line in enumerate(lines, 2):
+        if not line.strip():
+            continue
+        obj = json.loads(line)
+        if "trailing" in obj:
+            out.write(bytes.fromhex(obj["trailing"]))
+            continue
+        try:
+            out.write(record_from_json(obj, endian))
+        except (KeyError, ValueError, TypeError, struct.error) as e:
+            raise ValueError(f"line {n} ({obj.get('rec', '?')}): {e}") from e
+        count += 1
+    return count
+
+
+# Per-test detail records: dropping them leaves a "summary STDF" (parts, bins,
+# TSR test synopses, wafers) at a fraction of the size
+DETAIL_RECORDS = frozenset({"PTR", "MPR", "FTR", "STR"})
+
+
+def filter_records(
+    path: Path, out: BinaryIO, drop: Iterable[str] = (), keep: Iterable[str] | None = None,
+) -> tuple[int, dict[str, int]]:
+    """Copy the (uncompressed) STDF file at `path` to `out`, leaving out the
+    record types named in `drop` — or, with `keep`, every type not named
+    there (FAR is always kept). Names as in REC_NAMES; unknown types as
+    "typ/sub". Records are copied byte for byte.
+
+    PRR NUM_TEST still counts the results left out, so the parser reports
+    them as num_test_delta. Returns (records written, {name: records dropped}).
+    """
+    drop, keep = set(drop), None if keep is None else set(keep) | {"FAR"}
+    written = 0
+    dropped: dict[str, int] = {}
+    with open(path, "rb") as f:
+        endian = sniff_byte_order(f)[0]
+        header = struct.Struct(endian + "HBB")
+        for rec_typ, rec_sub, rec_len, body in iter_records(f, endian):
+            if rec_typ < 0:
+                out.write(body)  # trailing bytes, as they were
+                continue
+            name = REC_NAMES.get((rec_typ, rec_sub), f"{rec_typ}/{rec_sub}")
+            if name in drop or keep is not None and name not in keep:
+                dropped[name] = dropped.get(name, 0) + 1
+                continue
+            out.write(header.pack(rec_len, rec_typ, rec_sub) + body)
+            written += 1
+    return written, dropped
+
+
+def repair_stdf(path: Path, out: BinaryIO) -> tuple[int, list[tuple[int, int]]]:
+    """Copy the (uncompressed) STDF file at `path` to `out`, skipping what the
+    parser would resynchronize past: from a record header whose REC_LEN runs
+    past the end of the file, or whose unknown type (below the vendor range)
+    isn't followed by a plausible header, on to the next plausible header
+    (parser.find_record_header). A record cut off by the end of the file and
+    trailing bytes are left out too. Records are copied byte for byte.
+
+    Returns (records written, [(offset, bytes skipped), ...]).
+    """
+    written = 0
+    skipped: list[tuple[int, int]] = []
+    with open(path, "rb") as f:
+        endian = sniff_byte_order(f)[0]
+        header = struct.Struct(endian + "HBB")
+        end = f.seek(0, os.SEEK_END)
+        pos = 0
+        while pos + 4 <= end:
+            f.seek(pos)
+            head = f.read(4)
+            rec_len, rec_typ, rec_sub = header.unpack(head)
+            body_end = pos + 4 + rec_len
+            bad = body_end > end or (
+                (rec_typ, rec_sub) not in REC_NAMES and rec_typ < 180 and body_end < end
+                and not plausible_header(f, body_end, end, endian)
+            )
+            if bad:
+                found = find_record_header(f, pos + 1, end, endian)
+                skipped.append((pos, (end if found is None else found) - pos))
+                if found is None:
+                    return written, skipped
+                pos = found
+                continue
+            out.write(head + f.read(rec_len))
+            written += 1
+            pos = body_end
+    if pos < end:
+        skipped.append((pos, end - pos))
+    return written, skipped
+
+
+def edit_mir(path: Path, changes: dict) -> bool:
+    """Set MIR fields of the (uncompressed) STDF file at `path` in place.
+
+    `changes` maps MIR field names (FIELD_LAYOUTS["MIR"], e.g. LOT_ID,
+    SBLOT_ID, START_T, OPER_NAM) to values. Only the MIR is re-encoded: when
+    its length is unchanged it is overwritten where it is; otherwise the file
+    is rewritten around it (temp file + os.replace), every other record
+    copied as is. Optional fields the file left out are added as needed,
+    empty up to the one set. Returns True when the MIR was patched in place.
+    """
+    path = Path(path)
+    layout = {name: kind for name, kind, *_ in FIELD_LAYOUTS["MIR"]}
+    unknown = sorted(set(changes) - layout.keys())
+    if unknown:
+        raise ValueError(f"not MIR fields: {', '.join(unknown)}")
+    for name, value in changes.items():
+        if layout[name] == "Cn" and len(str(value).encode("latin-1")) > 255:
+            raise ValueError(f"MIR {name} is longer than 255 characters")
+
+    with open(path, "r+b") as f:
+        endian = sniff_byte_order(f)[0]
+        header = struct.Struct(endian + "HBB")
+        while True:
+            pos = f.tell()
+            head = f.read(4)
+            if len(head) < 4:
+                raise STDFFormatError(f"{path}: no MIR")
+            rec_len, rec_typ, rec_sub = header.unpack(head)
+            if (rec_typ, rec_sub) == REC_KEYS["MIR"]:
+                break
+            f.seek(rec_len, os.SEEK_CUR)
+        body = f.read(rec_len)
+        if len(body) < rec_len:
+            raise STDFRecordError(
+                f"{path}: MIR at offset 0x{pos:X} is cut off by the end of the file", "MIR", pos
+            )
+        fields, used = decode_fields("MIR", body, endian)
+        names = list(layout)
+        last = max(names.index(name) for name in changes) if changes else -1
+        if last >= len(fields) and used < len(body):
+            raise STDFRecordError(
+                f"{path}: MIR has {len(body) - used} undecoded trailing bytes", "MIR", pos
+            )
+        for name in names[len(fields):last + 1]:
+            fields[name] = "" if layout[name] == "Cn" else " " if layout[name] == "C1" else 0
+        fields.update(changes)
+        new_body = encode_fields("MIR", fields, endian) + body[used:]
+        if len(new_body) > 0xFFFF:
+            raise ValueError("MIR would exceed the 65535-byte record limit")
+
+        if len(new_body) == rec_len:
+            f.seek(pos + 4)
+            f.write(new_body)
+            return True
+        fd, tmp = tempfile.mkstemp(dir=path.parent, prefix=f".{path.name}.", suffix=".tmp")
+        try:
+            with os.fdopen(fd, "wb") as out:
+                f.seek(0)
+                out.write(f.read(pos))
+                out.write(header.pack(len(new_body), *REC_KEYS["MIR"]) + new_body)
+                f.seek(pos + 4 + rec_len)
+                shutil.copyfileobj(f, out, 1024 * 1024)
+            shutil.copymode(path, tmp)
+        except BaseException:
+            os.unlink(tmp)
+            raise
+    os.replace(tmp, path)
+    return False
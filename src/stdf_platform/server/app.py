@@ -1,4 +1,4 @@
-"""Read-only HTTP query API over the Parquet store.
+"""Read-only HTTP query API over the Parquet store (+ opt-in parse-by-upload, see parse.py).
 
 A thin FastAPI layer over AnalysisSession: one request = one :memory: DuckDB
 session with the canonical views (setup_views), so every consumer computes
@@ -27,6 +27,7 @@ from pydantic import BaseModel
 from .. import __version__
 from ..analysis import AnalysisSession
 from ..config import Config
+from .parse import router as parse_router
 
 router = APIRouter()
 
@@ -150,6 +151,7 @@ def index():
         "\n"
         "POST /api/query   {\"sql\": \"SELECT ...\", \"limit\": 100, \"format\": \"json|csv\"}\n"
         "GET  /api/views   available views\n"
+        "POST /api/parse   STDF file as body -> ?format=summary|jsonl|parquet (if enabled)\n"
         "GET  /health      liveness\n"
         "\n"
         "VSCode: use client/stdf_client.py  (see docs/multi-user-server.md)\n"
@@ -162,4 +164,5 @@ def create_app(config: Config | None = None) -> FastAPI:
     if config is not None:
         app.state.stdf_config = config
     app.include_router(router)
+    app.include_router(parse_router)
     return app
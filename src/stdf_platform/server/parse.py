@@ -0,0 +1,96 @@
+"""Parse-by-upload endpoint: POST an STDF file, get a summary, JSON Lines or Parquet.
+
+For teams without a Python environment: the raw file is the request body
+(no multipart), parsed in memory and discarded — nothing is written to
+the Parquet store. Off unless server.enable_parse is set, since the rest of
+the server is read-only; uploads are capped at server.max_upload_mb (checked
+while the body is read, not after).
+"""
+
+import io
+import tempfile
+from dataclasses import asdict
+from pathlib import Path
+
+from fastapi import APIRouter, HTTPException, Request
+from fastapi.responses import Response
+from starlette.concurrency import run_in_threadpool
+
+from ..export import ParquetSink, export_to_sink, lot_metadata, write_part_payloads
+from ..parser import STDFData, STDFError, parse_bytes
+
+router = APIRouter()
+
+_MAX_WARNINGS_RETURNED = 100
+
+
+def _summary(data: STDFData) -> dict:
+    return {
+        **lot_metadata(data),
+        "wafer_count": len(data.wafers),
+        "part_count": len(data.parts),
+        "good_count": sum(1 for p in data.parts if p["passed"]),
+        "test_count": len(data.tests),
+        "result_count": len(data.test_results),
+        "is_complete": data.is_complete,
+        "stats": asdict(data.stats),
+        "warnings": data.warnings[:_MAX_WARNINGS_RETURNED],
+        "diagnostics": [asdict(d) for d in data.diagnostics[:_MAX_WARNINGS_RETURNED]],
+    }
+
+
+def _run(body: bytes | bytearray, fmt: str, table: str) -> Response | dict:
+    data = parse_bytes(body, name="upload.stdf")
+
+    if fmt == "summary":
+        return _summary(data)
+    if fmt == "jsonl":
+        buf = io.StringIO()
+        write_part_payloads(data, buf)
+        return Response(content=buf.getvalue(), media_type="application/x-ndjson")
+    with tempfile.TemporaryDirectory(prefix="stdf-parse-") as tmp:
+        out_dir = Path(tmp) / "out"
+        export_to_sink(data, ParquetSink(out_dir))
+        return Response(
+            content=(out_dir / f"{table}.parquet").read_bytes(),
+            media_type="application/vnd.apache.parquet",
+            headers={"Content-Disposition": f"attachment; filename={table}.parquet"},
+        )
+
+
+@router.post("/api/parse")
+async def parse(request: Request, format: str = "summary", table: str = "parts"):
+    from .app import _resolve_config  # app includes this router: avoid an import cycle
+
+    config = _resolve_config(request)
+    if not config.server.enable_parse:
+        raise HTTPException(status_code=403, detail="parse endpoint disabled (server.enable_parse)")
+    if format not in ("summary", "jsonl", "parquet"):
+        raise HTTPException(status_code=400, detail="format must be summary, jsonl or parquet")
+    if table not in ("parts", "results"):
+        raise HTTPException(status_code=400, detail="table must be parts or results")
+
+    # Refuse oversize uploads before buffering them: by Content-Length when
+    # sent, else (chunked) as soon as the bytes read pass the cap
+    limit = config.server.max_upload_mb * 1024 * 1024
+    too_big = HTTPException(
+        status_code=413, detail=f"upload exceeds {config.server.max_upload_mb} MB",
+    )
+    declared = request.headers.get("content-length", "")
+    if declared.isdigit() and int(declared) > limit:
+        raise too_big
+    body = bytearray()
+    async for chunk in request.stream():
+        body += chunk
+        if len(body) > limit:
+            raise too_big
+    if not body:
+        raise HTTPException(
+            status_code=400, detail="empty body (send the STDF file as the request body)",
+        )
+
+    # Parsing is CPU-bound; keep the event loop free for other requests
+    try:
+        return await run_in_threadpool(_run, body, format, table)
+    except STDFError as exc:  # the upload is bad, not the server
+        raise HTTPException(status_code=422, detail=str(exc))
@@ -0,0 +1,189 @@
+"""Split an STDF file into one file per wafer or per test site.
+
+split_stdf() copies records into the outputs without re-encoding them; each
+output starts with the lot header (FAR, MIR, SDR, PMR, WCR, ...) and ends
+with the MRR, so every piece is a valid STDF file on its own.
+
+- by wafer: a WIR and everything up to the next WIR go to that wafer's file
+  (a wafer tested twice gets both blocks). Lot-level summaries after the
+  last WRR are dropped.
+- by site: part and test records go to the file of their head / site,
+  other records (WIR, DTR outside a part, ...) to every file; each WRR's
+  counts are redone for the site's own parts.
+
+Lot-level HBR / SBR summaries (head 255) are rebuilt in each output from
+its own PRRs; head-255 TSR and PCR records, which can't be split, are left
+out.
+"""
+
+import re
+import struct
+from pathlib import Path
+from typing import BinaryIO
+
+from .parser import REC_NAMES, U4_MISSING, sniff_byte_order
+from .records import HEAD_OFFSET, REC_KEYS, decode_fields, encode_fields, iter_records
+
+_SUMMARY = {"HBR", "SBR", "PCR", "TSR"}
+
+
+def _file_part(text: str) -> str:
+    """`text` made safe as part of a file name."""
+    return re.sub(r"[^A-Za-z0-9._-]+", "_", text) or "_"
+
+
+class _Output:
+    """One split file being written, with the bin counts of its PRRs."""
+
+    def __init__(self, path: Path, header: list[bytes]):
+        self.path = path
+        self.f = open(path, "wb")
+        self.f.writelines(header)
+        self.bins: dict[tuple[str, int], int] = {}  # (H|S, bin) -> parts
+        self.wafer = [0, 0, 0, 0]  # part, retest, abort, good counts since the WIR
+
+    def count_part(self, prr: dict) -> None:
+        for bin_type, key in (("H", "HARD_BIN"), ("S", "SOFT_BIN")):
+            self.bins[bin_type, prr[key]] = self.bins.get((bin_type, prr[key]), 0) + 1
+        flags = prr["PART_FLG"]
+        self.wafer[0] += 1
+        self.wafer[1] += bool(flags & 0x03)
+        self.wafer[2] += bool(flags & 0x04)
+        self.wafer[3] += not flags & 0x18  # passed, with a valid pass/fail
+
+
+class _Outputs(dict):
+    """Split files by wafer id or (head, site), plus the MRR they all end with."""
+
+    mrr: bytes | None = None
+
+
+def split_stdf(path: Path, out_dir: Path, by: str = "wafer") -> list[Path]:
+    """Split the (uncompressed) STDF file at `path` into `out_dir`, one file
+    per wafer (``<stem>_<wafer id>.stdf``) or per site (``<stem>_site<n>``,
+    ``<stem>_h<head>_site<n>`` on heads other than 1). Returns the files
+    written, in order of first appearance."""
+    if by not in ("wafer", "site"):
+        raise ValueError(f"split by 'wafer' or 'site', not {by!r}")
+    path = Path(path)
+    out_dir.mkdir(parents=True, exist_ok=True)
+
+    with open(path, "rb") as f:
+        endian = sniff_byte_order(f)[0]
+        header = struct.Struct(endian + "HBB")
+        bin_info: dict[tuple[str, int], tuple[str, str]] = {}  # -> (pf, name)
+        has_wafers = False
+        sites: list[tuple[int, int]] = []  # PIR head / site, first-seen order
+        for typ, sub, rec_len, body in iter_records(f, endian):
+            name = REC_NAMES.get((typ, sub))
+            has_wafers |= name == "WIR"
+            if name == "PIR" and len(body) >= 2 and tuple(body[:2]) not in sites:
+                sites.append(tuple(body[:2]))
+            if name in ("HBR", "SBR") and rec_len == len(body):
+                fields = decode_fields(name, body, endian)[0]
+                p = name[0]
+                info = bin_info.get((p, fields[f"{p}BIN_NUM"]), (" ", ""))
+                pf = fields.get(f"{p}BIN_PF", " ")
+                bin_info[p, fields[f"{p}BIN_NUM"]] = (
+                    pf if pf.strip() else info[0], fields.get(f"{p}BIN_NAM") or info[1],
+                )
+        if by == "wafer" and not has_wafers:
+            raise ValueError(f"{path}: no WIR records — nothing to split by wafer")
+        f.seek(0)
+        outputs = _split(f, endian, path, out_dir, by, sites)
+
+    for out in outputs.values():
+        for (bin_type, bin_num), count in sorted(out.bins.items()):
+            pf, bin_name = bin_info.get((bin_type, bin_num), (" ", ""))
+            rec = "HBR" if bin_type == "H" else "SBR"
+            body = encode_fields(rec, {
+                "HEAD_NUM": 255, "SITE_NUM": 255, f"{bin_type}BIN_NUM": bin_num,
+                f"{bin_type}BIN_CNT": count, f"{bin_type}BIN_PF": pf, f"{bin_type}BIN_NAM": bin_name,
+            }, endian)
+            out.f.write(header.pack(len(body), *REC_KEYS[rec]) + body)
+        if outputs.mrr is not None:
+            out.f.write(outputs.mrr)
+        out.f.close()
+    return [out.path for out in outputs.values()]
+
+
+def _split(
+    f: BinaryIO, endian: str, path: Path, out_dir: Path, by: str, sites: list[tuple[int, int]],
+) -> _Outputs:
+    header = struct.Struct(endian + "HBB")
+    outputs = _Outputs()
+    lot_header: list[bytes] = []  # records before the first wafer / part
+    current: _Output | None = None  # wafer mode: the open wafer's file
+    after_wrr = False  # wafer mode: past the open wafer's WRR
+    open_parts: dict[tuple[int, int], _Output] = {}  # site mode
+
+    for typ, sub, rec_len, body in iter_records(f, endian):
+        if typ < 0 or rec_len != len(body):
+            break  # cut off by the end of the file
+        raw = header.pack(rec_len, typ, sub) + body
+        name = REC_NAMES.get((typ, sub))
+        offset = HEAD_OFFSET.get(name)
+        head_site = tuple(body[offset:offset + 2]) if offset is not None and len(body) > offset + 1 else None
+        if name == "MRR":
+            outputs.mrr = raw
+            continue
+        if name in _SUMMARY and head_site and head_site[0] == 255:
+            continue  # lot-level summary: rebuilt (HBR/SBR) or dropped
+        try:
+            prr = decode_fields("PRR", body, endian)[0] if name == "PRR" else None
+        except ValueError:
+            prr = None  # truncated PRR: copied, not counted
+
+        if by == "wafer":
+            if name == "WIR":
+                wafer_id = decode_fields("WIR", body, endian)[0].get("WAFER_ID", "")
+                if wafer_id not in outputs:
+                    outputs[wafer_id] = _Output(
+                        out_dir / f"{path.stem}_{_file_part(wafer_id)}.stdf", lot_header,
+                    )
+                current, after_wrr = outputs[wafer_id], False
+            if current is None:
+                lot_header.append(raw)
+                continue
+            if after_wrr and name in _SUMMARY:
+                continue  # per-site lot summaries after the last WRR
+            after_wrr |= name == "WRR"
+            if prr is not None:
+                current.count_part(prr)
+            current.f.write(raw)
+            continue
+
+        if not outputs and (name == "WIR" or head_site is not None):
+            for head, site in sites:  # the lot header ends here
+                label = f"site{site}" if head == 1 else f"h{head}_site{site}"
+                outputs[head, site] = _Output(out_dir / f"{path.stem}_{label}.stdf", lot_header)
+        if not outputs:
+            lot_header.append(raw)
+        elif head_site is not None:
+            out = outputs.get(head_site)
+            if out is None:
+                continue  # summary for a site without parts
+            if name == "PIR":
+                open_parts[head_site] = out
+            elif name == "PRR":
+                open_parts.pop(head_site, None)
+                if prr is not None:
+                    out.count_part(prr)
+            out.f.write(raw)
+        elif len(open_parts) == 1 and name in ("DTR", "GDR"):
+            next(iter(open_parts.values())).f.write(raw)  # inside that site's part
+        elif name == "WRR":
+            fields, used = decode_fields("WRR", body, endian)
+            for out in outputs.values():
+                parts, retests, aborts, good = out.wafer
+                site_body = encode_fields("WRR", {
+                    **fields, "PART_CNT": parts, "RTST_CNT": retests, "ABRT_CNT": aborts,
+                    "GOOD_CNT": good, "FUNC_CNT": U4_MISSING,
+                }, endian) + body[used:]
+                out.f.write(header.pack(len(site_body), typ, sub) + site_body)
+        else:
+            for out in outputs.values():
+                if name == "WIR":
+                    out.wafer = [0, 0, 0, 0]
+                out.f.write(raw)
+    return outputs
@@ -110,7 +110,7 @@ TEST_DATA_SCHEMA = pa.schema([
 # Decoded EN-SO-CHIPID_R ChipID schema (one row per die occurrence)
 CHIPID_SCHEMA = pa.schema([
     ("lot_id", pa.string()),
-    ("part_id", pa.string()),          # synthesized FT part id (stream order)
+    ("part_id", pa.string()),          # FT part id (PRR PART_ID, else stream order)
     ("part_txt", pa.string()),         # 2D barcode — unique package key
     ("chip_occurrence_index", pa.int64()),  # 0,1,... per DUT (2-die chiplet -> 0/1)
     ("efuse_raw", pa.string()),        # normalized 64-bit binary string (or original if invalid)
@@ -0,0 +1,234 @@
+"""Synthetic STDF files: deterministic fixtures for benchmarks and tests.
+
+generate_stdf() writes a complete, valid STDF V4 lot — FAR, MIR, SDR, per
+wafer WIR / parts / WRR (or no wafers: final test), TSR / HBR / SBR / PCR
+summaries and the MRR — from a GenOptions description: wafer count, parts
+per wafer, test sites, the test list with limits and failure rates, byte
+order and a random seed. The same options always give the same bytes.
+
+Results are drawn from a normal distribution centred between a test's
+limits, with the spread that puts `fail_rate` of them outside. A part fails
+at its first failing test's soft bin (2 + that test's index) and hard bin 2.
+"""
+
+import math
+import random
+import struct
+from dataclasses import dataclass, field
+from pathlib import Path
+from statistics import NormalDist
+from typing import BinaryIO
+
+from .parser import U4_MISSING
+from .records import REC_KEYS, encode_fields
+
+
+@dataclass
+class GenTestSpec:
+    """One parametric test (a PTR per part)."""
+
+    test_num: int
+    name: str
+    lo_limit: float
+    hi_limit: float
+    units: str = ""
+    # Fraction of results outside the limits; None = GenOptions.fail_rate
+    fail_rate: float | None = None
+
+
+def default_tests(count: int) -> list[GenTestSpec]:
+    """`count` tests numbered from 1000, with limits 0..1, 0..2, ... (V)."""
+    return [
+        GenTestSpec(1000 + i, f"TEST_{1000 + i}", 0.0, float(i % 10 + 1), "V")
+        for i in range(count)
+    ]
+
+
+@dataclass
+class GenOptions:
+    """What generate_stdf() writes."""
+
+    lot_id: str = "SYNTH01"
+    part_type: str = "SYNTHDEV"
+    job_name: str = "SYNTH_JOB"
+    test_code: str = "CP1"
+    # 0 = final test: no WIR / WRR, parts have no coordinates
+    wafers: int = 1
+    parts_per_wafer: int = 100  # parts in total when wafers == 0
+    # Parts are tested `sites` at a time (PIRs, then each site's results
+    # interleaved test by test, then the PRRs)
+    sites: int = 1
+    tests: list[GenTestSpec] = field(default_factory=lambda: default_tests(10))
+    fail_rate: float = 0.01  # per test, unless the GenTestSpec sets its own
+    endian: str = "<"  # "<" little (CPU_TYPE 2) or ">" big (CPU_TYPE 1)
+    seed: int = 0
+    start_time: int = 1700000000
+    test_time_ms: int = 100  # PRR TEST_T
+
+
+@dataclass
+class GenReport:
+    """Counts of what generate_stdf() wrote."""
+
+    records: int = 0
+    parts: int = 0
+    good: int = 0
+    results: int = 0
+    failed_results: int = 0
+
+
+class _Writer:
+    def __init__(self, out: BinaryIO, endian: str):
+        self.out = out
+        self.endian = endian
+        self.header = struct.Struct(endian + "HBB")
+        self.records = 0
+
+    def write(self, rec: str, fields: dict):
+        self.raw(rec, encode_fields(rec, fields, self.endian))
+
+    def raw(self, rec: str, body: bytes):
+        self.out.write(self.header.pack(len(body), *REC_KEYS[rec]) + body)
+        self.records += 1
+
+
+def _sigma(spec: GenTestSpec, fail_rate: float) -> float:
+    """Spread putting fail_rate of normal results outside the limits."""
+    half = (spec.hi_limit - spec.lo_limit) / 2
+    if fail_rate <= 0:
+        return half / 6  # one in ~500 million
+    if fail_rate >= 1:
+        return half * 1e6
+    return half / NormalDist().inv_cdf(1 - fail_rate / 2)
+
+
+def generate_stdf(out: Path | BinaryIO, options: GenOptions | None = None) -> GenReport:
+    """Write a synthetic STDF lot to `out` (a path or a binary file)."""
+    if isinstance(out, (str, Path)):
+        with open(out, "wb") as f:
+            return generate_stdf(f, options)
+    opts = options or GenOptions()
+    if opts.endian not in ("<", ">"):
+        raise ValueError(f"endian must be '<' or '>', not {opts.endian!r}")
+    if not 1 <= opts.sites <= 255:
+        raise ValueError(f"sites must be 1..255, not {opts.sites}")
+    rng = random.Random(opts.seed)
+    w = _Writer(out, opts.endian)
+    report = GenReport()
+    t0 = opts.start_time
+    sites = list(range(1, opts.sites + 1))
+
+    w.write("FAR", {"CPU_TYPE": 1 if opts.endian == ">" else 2, "STDF_VER": 4})
+    w.write("MIR", {
+        "SETUP_T": t0, "START_T": t0, "STAT_NUM": 1, "MODE_COD": "P", "RTST_COD": " ",
+        "PROT_COD": " ", "BURN_TIM": 65535, "CMOD_COD": " ", "LOT_ID": opts.lot_id,
+        "PART_TYP": opts.part_type, "NODE_NAM": "SYNTH", "TSTR_TYP": "SYNTH",
+        "JOB_NAM": opts.job_name, "JOB_REV": "1", "SBLOT_ID": "", "OPER_NAM": "testgen",
+        "EXEC_TYP": "", "EXEC_VER": "", "TEST_COD": opts.test_code,
+    })
+    w.write("SDR", {"HEAD_NUM": 1, "SITE_GRP": 1, "SITE_CNT": len(sites), "SITE_NUM": sites})
+
+    # Per test: PTR bytes after RESULT (constant), the result distribution
+    # and TSR tallies [exec, fail, min, max, sum, sum of squares]
+    ptr_head = struct.Struct(opts.endian + "IBBBBf")
+    tails, dists, tallies = [], [], []
+    for spec in opts.tests:
+        full = encode_fields("PTR", {
+            "TEST_NUM": spec.test_num, "HEAD_NUM": 1, "SITE_NUM": 1, "TEST_FLG": 0,
+            "PARM_FLG": 0, "RESULT": 0.0, "TEST_TXT": spec.name, "ALARM_ID": "",
+            "OPT_FLAG": 0, "RES_SCAL": 0, "LLM_SCAL": 0, "HLM_SCAL": 0,
+            "LO_LIMIT": spec.lo_limit, "HI_LIMIT": spec.hi_limit, "UNITS": spec.units,
+        }, opts.endian)
+        tails.append(full[ptr_head.size:])
+        rate = opts.fail_rate if spec.fail_rate is None else spec.fail_rate
+        dists.append(((spec.lo_limit + spec.hi_limit) / 2, _sigma(spec, rate)))
+        tallies.append([0, 0, math.inf, -math.inf, 0.0, 0.0])
+
+    bins: dict[tuple[str, int], int] = {}
+
+    def test_parts(count: int, wafer: bool) -> tuple[int, int]:
+        """Test `count` parts, sites at a time; returns (parts, good)."""
+        cols = max(1, math.ceil(math.sqrt(count)))
+        good = 0
+        for first in range(0, count, len(sites)):
+            touchdown = list(zip(sites, range(first, min(first + len(sites), count))))
+            for site, _ in touchdown:
+                w.write("PIR", {"HEAD_NUM": 1, "SITE_NUM": site})
+            failing: dict[int, int | None] = {site: None for site, _ in touchdown}
+            for t, (mean, sigma) in enumerate(dists):
+                spec = opts.tests[t]
+                tally = tallies[t]
+                for site, _ in touchdown:
+                    value = struct.unpack("f", struct.pack("f", rng.gauss(mean, sigma)))[0]
+                    passed = spec.lo_limit <= value <= spec.hi_limit
+                    if not passed and failing[site] is None:
+                        failing[site] = t
+                    w.raw("PTR", ptr_head.pack(spec.test_num, 1, site, 0 if passed else 0x80,
+                                               0, value) + tails[t])
+                    tally[0] += 1
+                    tally[1] += not passed
+                    tally[2], tally[3] = min(tally[2], value), max(tally[3], value)
+                    tally[4] += value
+                    tally[5] += value * value
+                    report.results += 1
+                    report.failed_results += not passed
+            for site, index in touchdown:
+                t = failing[site]
+                hard, soft = (1, 1) if t is None else (2, 2 + t)
+                good += t is None
+                bins["H", hard] = bins.get(("H", hard), 0) + 1
+                bins["S", soft] = bins.get(("S", soft), 0) + 1
+                report.parts += 1
+                w.write("PRR", {
+                    "HEAD_NUM": 1, "SITE_NUM": site, "PART_FLG": 0 if t is None else 0x08,
+                    "NUM_TEST": len(opts.tests), "HARD_BIN": hard, "SOFT_BIN": soft,
+                    "X_COORD": index % cols if wafer else -32768,
+                    "Y_COORD": index // cols if wafer else -32768,
+                    "TEST_T": opts.test_time_ms, "PART_ID": str(report.parts), "PART_TXT": "",
+                    "PART_FIX": "",
+                })
+        return count, good
+
+    for wafer_index in range(opts.wafers):
+        wafer_id = f"W{wafer_index + 1:02d}"
+        w.write("WIR", {"HEAD_NUM": 1, "SITE_GRP": 255, "START_T": t0 + wafer_index * 3600,
+                        "WAFER_ID": wafer_id})
+        parts, good = test_parts(opts.parts_per_wafer, wafer=True)
+        w.write("WRR", {
+            "HEAD_NUM": 1, "SITE_GRP": 255, "FINISH_T": t0 + wafer_index * 3600 + 1800,
+            "PART_CNT": parts, "RTST_CNT": 0, "ABRT_CNT": 0, "GOOD_CNT": good,
+            "FUNC_CNT": U4_MISSING, "WAFER_ID": wafer_id,
+        })
+    if opts.wafers == 0:
+        test_parts(opts.parts_per_wafer, wafer=False)
+    report.good = bins.get(("H", 1), 0)
+
+    for spec, (execs, fails, lo, hi, total, squares) in zip(opts.tests, tallies):
+        opt_flag = 0xCC  # bits 3, 6, 7 reserved (1); bit 2: no TEST_TIM
+        if not execs:
+            opt_flag |= 0x03  # no TEST_MIN / TEST_MAX
+        w.write("TSR", {
+            "HEAD_NUM": 255, "SITE_NUM": 255, "TEST_TYP": "P", "TEST_NUM": spec.test_num,
+            "EXEC_CNT": execs, "FAIL_CNT": fails, "ALRM_CNT": 0, "TEST_NAM": spec.name,
+            "SEQ_NAME": "", "TEST_LBL": "", "OPT_FLAG": opt_flag,
+            "TEST_TIM": 0.0, "TEST_MIN": lo if execs else 0.0, "TEST_MAX": hi if execs else 0.0,
+            "TST_SUMS": total, "TST_SQRS": squares,
+        })
+    for (bin_type, num), count in sorted(bins.items()):
+        rec = "HBR" if bin_type == "H" else "SBR"
+        name = "PASS" if num == 1 else (
+            "FAIL" if bin_type == "H" else f"FAIL_{opts.tests[num - 2].name}"
+        )
+        w.write(rec, {
+            "HEAD_NUM": 255, "SITE_NUM": 255, f"{bin_type}BIN_NUM": num,
+            f"{bin_type}BIN_CNT": count, f"{bin_type}BIN_PF": "P" if num == 1 else "F",
+            f"{bin_type}BIN_NAM": name,
+        })
+    w.write("PCR", {
+        "HEAD_NUM": 255, "SITE_NUM": 255, "PART_CNT": report.parts, "RTST_CNT": 0,
+        "ABRT_CNT": 0, "GOOD_CNT": report.good, "FUNC_CNT": U4_MISSING,
+    })
+    w.write("MRR", {"FINISH_T": t0 + max(opts.wafers, 1) * 3600, "DISP_COD": " ",
+                    "USR_DESC": "", "EXC_DESC": ""})
+    report.records = w.records
+    return report
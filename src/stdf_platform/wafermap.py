@@ -0,0 +1,321 @@
+"""Wafer map export (per wafer) from parsed part bins/coordinates — SEMI E142 XML and SINF.
+
+Assembly houses do inkless pick from these maps. The die grid is built from PRR
+x/y + bin (last PRR per coordinate wins, so in-file retests resolve to the final
+bin); FT parts (x = y = -32768) have no coordinate and are ignored.
+Retests run as separate files are merged with final_dies() (latest file wins).
+
+Grid orientation follows the WCR: X grows to the right unless POS_X is "L",
+Y grows downward unless POS_Y is "U" (no WCR: right/down, the usual prober
+convention). Row 0 is the top row and column 0 the leftmost column as seen.
+
+Bin codes are 2 hex digits, 4 when a bin exceeds 254; the all-F code marks
+untested positions. A bin of 65535 would read as that null code, so a wafer
+carrying one is written with 5-digit codes — outside what E142 / SINF
+readers expect, but not silently wrong.
+"""
+
+import re
+import xml.etree.ElementTree as ET
+from dataclasses import dataclass, field
+from pathlib import Path
+
+from .parser import STDFData, WaferConfig
+
+_NO_COORD = -32768
+# WCR.WF_FLAT -> SINF FNLOC (flat/notch angle, degrees)
+_FLAT_ANGLE = {"U": 0, "R": 90, "D": 180, "L": 270}
+
+E142_NS = "urn:semi-org:xsd.E142-1.V1005.SubstrateMap"
+
+
+@dataclass
+class WaferGrid:
+    """Final bin per die coordinate for one wafer."""
+
+    lot_id: str
+    wafer_id: str
+    bins: dict[tuple[int, int], int] = field(default_factory=dict)  # (x, y) -> bin
+    x_right: bool = True  # X increases to the right (WCR POS_X != "L")
+    y_down: bool = True  # Y increases downward (WCR POS_Y != "U")
+
+    @property
+    def x_min(self) -> int:
+        return min(x for x, _ in self.bins)
+
+    @property
+    def y_min(self) -> int:
+        return min(y for _, y in self.bins)
+
+    @property
+    def x_max(self) -> int:
+        return max(x for x, _ in self.bins)
+
+    @property
+    def y_max(self) -> int:
+        return max(y for _, y in self.bins)
+
+    @property
+    def cols(self) -> int:
+        return self.x_max - self.x_min + 1
+
+    @property
+    def rows(self) -> int:
+        return self.y_max - self.y_min + 1
+
+    @property
+    def ref_x(self) -> int:
+        """X of the leftmost column."""
+        return self.x_min if self.x_right else self.x_max
+
+    @property
+    def ref_y(self) -> int:
+        """Y of the top row."""
+        return self.y_min if self.y_down else self.y_max
+
+    def row_bins(self) -> list[list[int | None]]:
+        """Dense row-major grid (top row first); None where no die was tested."""
+        x0, y0 = self.ref_x, self.ref_y
+        dx = 1 if self.x_right else -1
+        dy = 1 if self.y_down else -1
+        grid: list[list[int | None]] = [[None] * self.cols for _ in range(self.rows)]
+        for (x, y), b in self.bins.items():
+            grid[(y - y0) * dy][(x - x0) * dx] = b
+        return grid
+
+    def bin_counts(self) -> dict[int, int]:
+        counts: dict[int, int] = {}
+        for b in self.bins.values():
+            counts[b] = counts.get(b, 0) + 1
+        return dict(sorted(counts.items()))
+
+
+def set_orientation(
+    data: STDFData, pos_x: str | None = None, pos_y: str | None = None, flat: str | None = None,
+) -> WaferConfig:
+    """Override the WCR POS_X (L/R), POS_Y (U/D) and WF_FLAT (U/D/L/R) the maps
+    are oriented by, for files whose WCR is missing or wrong. A WaferConfig
+    is created when the file has none; None leaves a value as the file has it."""
+    wcr = data.wafer_config
+    if wcr is None:
+        wcr = data.wafer_config = WaferConfig()
+    for name, value in (("pos_x", pos_x), ("pos_y", pos_y), ("flat", flat)):
+        if value is not None:
+            setattr(wcr, name, value.upper())
+    return wcr
+
+
+def final_dies(original: STDFData, retests: list[STDFData]) -> list[dict]:
+    """Merge an original test pass with its retest files into final per-die results.
+
+    Dies are keyed by (wafer_id, x, y); the latest PRR wins — later retest
+    files over earlier ones, later PRRs over earlier ones within a file. Each
+    returned row is that part dict plus provenance:
+
+        source_pass   0 = original file, n = retests[n - 1]
+        from_retest   True if the final bin came from a retest file
+        test_passes   how many PRRs (all files) the die had
+
+    Rows come out in original die order, dies first seen in a retest appended.
+    FT parts (no coordinates) are left out.
+    """
+    dies: dict[tuple[str, int, int], dict] = {}
+    for n, data in enumerate([original, *retests]):
+        for part in data.parts:
+            x, y = part["x_coord"], part["y_coord"]
+            if x == _NO_COORD and y == _NO_COORD:
+                continue
+            key = (part["wafer_id"], x, y)
+            prev = dies.get(key)
+            dies[key] = {
+                **part,
+                "source_pass": n,
+                "from_retest": n > 0,
+                "test_passes": prev["test_passes"] + 1 if prev else 1,
+            }
+    return list(dies.values())
+
+
+def wafer_grids(
+    data: STDFData, bin_field: str = "soft_bin", parts: list[dict] | None = None,
+) -> list[WaferGrid]:
+    """One WaferGrid per wafer (file order), from parts with real coordinates.
+
+    `parts` overrides data.parts, e.g. with final_dies() for retest-merged maps.
+    """
+    wcr = data.wafer_config
+    x_right = wcr is None or wcr.pos_x != "L"
+    y_down = wcr is None or wcr.pos_y != "U"
+    grids: dict[str, WaferGrid] = {}
+    for part in data.parts if parts is None else parts:
+        x, y = part["x_coord"], part["y_coord"]
+        if x == _NO_COORD and y == _NO_COORD:
+            continue
+        wafer_id = part["wafer_id"]
+        grid = grids.get(wafer_id)
+        if grid is None:
+            grid = grids[wafer_id] = WaferGrid(
+                lot_id=part["lot_id"], wafer_id=wafer_id, x_right=x_right, y_down=y_down,
+            )
+        grid.bins[(x, y)] = part[bin_field]
+    return list(grids.values())
+
+
+def _bin_defs(data: STDFData, bin_field: str) -> dict[int, dict]:
+    return data.bins_hard if bin_field == "hard_bin" else data.bins_soft
+
+
+def _code_width(grid: WaferGrid) -> int:
+    """Hex digits per die: 2, or 4 if a bin exceeds 254 (5 for bin 65535, see above)."""
+    top = max(grid.bins.values())
+    if top == 0xFFFF:
+        return 5  # non-standard: FFFF is the 4-digit null code
+    return 2 if top < 0xFF else 4
+
+
+def _e142_tag(tag: str) -> str:
+    return f"{{{E142_NS}}}{tag}"
+
+
+def e142_xml(data: STDFData, grid: WaferGrid, bin_field: str = "soft_bin") -> str:
+    """Render one wafer as a SEMI E142 substrate map (BinCodeMap overlay).
+
+    Bin codes are hexadecimal, 2 digits per die (4 if any bin exceeds 254;
+    see the module docstring for bin 65535); the all-F code is the NullBin
+    for untested grid positions.
+    """
+    width = _code_width(grid)
+    null_bin = "F" * width
+
+    ET.register_namespace("", E142_NS)
+    q = _e142_tag
+
+    root = ET.Element(q("MapData"))
+    layouts = ET.SubElement(root, q("Layouts"))
+    wafer_layout = ET.SubElement(layouts, q("Layout"), LayoutId="WaferLayout",
+                                 DefaultUnits="mm", TopLevel="true")
+    ET.SubElement(wafer_layout, q("Dimension"), X="1", Y="1")
+    children = ET.SubElement(wafer_layout, q("ChildLayouts"))
+    ET.SubElement(children, q("ChildLayout"), LayoutId="Devices")
+    devices = ET.SubElement(layouts, q("Layout"), LayoutId="Devices", DefaultUnits="mm")
+    ET.SubElement(devices, q("Dimension"), X=str(grid.cols), Y=str(grid.rows))
+
+    substrates = ET.SubElement(root, q("Substrates"))
+    substrate = ET.SubElement(substrates, q("Substrate"),
+                              SubstrateType="Wafer", SubstrateId=grid.wafer_id)
+    ET.SubElement(substrate, q("LotId")).text = grid.lot_id
+
+    maps = ET.SubElement(root, q("SubstrateMaps"))
+    smap = ET.SubElement(maps, q("SubstrateMap"), SubstrateType="Wafer",
+                         SubstrateId=grid.wafer_id, LayoutSpecifier="WaferLayout/Devices",
+                         SubstrateSide="TopSide", OriginLocation="UpperLeft",
+                         AxisDirection="DownRight")
+    overlay = ET.SubElement(smap, q("Overlay"), MapName="BinMap", MapVersion="1")
+    code_map = ET.SubElement(overlay, q("BinCodeMap"), BinType="HexaDecimal", NullBin=null_bin)
+    definitions = ET.SubElement(code_map, q("BinDefinitions"))
+    defs = _bin_defs(data, bin_field)
+    for b, count in grid.bin_counts().items():
+        d = defs.get(b, {})
+        quality = {"P": "Pass", "F": "Fail"}.get(d.get("bin_pf", ""), "")
+        attrs = {"BinCode": f"{b:0{width}X}", "BinCount": str(count)}
+        if quality:
+            attrs["BinQuality"] = quality
+        if d.get("bin_name"):
+            attrs["BinDescription"] = d["bin_name"]
+        ET.SubElement(definitions, q("BinDefinition"), **attrs)
+    for row in grid.row_bins():
+        ET.SubElement(code_map, q("BinCode")).text = "".join(
+            null_bin if b is None else f"{b:0{width}X}" for b in row
+        )
+
+    ET.indent(root)
+    return '<?xml version="1.0" encoding="UTF-8"?>\n' + ET.tostring(root, encoding="unicode") + "\n"
+
+
+def sinf_text(data: STDFData, grid: WaferGrid, bin_field: str = "soft_bin") -> str:
+    """Render one wafer as a SINF map.
+
+    RowData codes are hexadecimal (2 digits, 4 if any bin exceeds 254; see the
+    module docstring for bin 65535) and a run of ``_`` as wide as a code marks
+    untested positions.
+    Die size, flat and units come from the WCR; those header lines are left
+    out when the file has no WCR or the values are unknown. REFPX/REFPY are
+    the X/Y of the first column/row (see the module docstring for orientation).
+    """
+    width = _code_width(grid)
+    null_code = "_" * width
+    defs = _bin_defs(data, bin_field)
+    pass_bins = sorted(b for b in grid.bin_counts() if defs.get(b, {}).get("bin_pf") == "P")
+
+    wcr = data.wafer_config
+    lines = [
+        f"DEVICE:{data.part_type}",
+        f"LOT:{grid.lot_id}",
+        f"WAFER:{grid.wafer_id}",
+    ]
+    if wcr is not None and wcr.flat in _FLAT_ANGLE:
+        lines.append(f"FNLOC:{_FLAT_ANGLE[wcr.flat]}")
+    lines += [
+        f"ROWCT:{grid.rows}",
+        f"COLCT:{grid.cols}",
+        "BCEQU:" + " ".join(f"{b:0{width}X}" for b in pass_bins),
+        f"REFPX:{grid.ref_x}",
+        f"REFPY:{grid.ref_y}",
+    ]
+    mm = wcr.mm_per_unit if wcr is not None else None
+    if mm and wcr.die_width > 0 and wcr.die_height > 0:
+        lines += [
+            "DUTMS:mm",
+            f"XDIES:{wcr.die_width * mm:.6g}",
+            f"YDIES:{wcr.die_height * mm:.6g}",
+        ]
+    for row in grid.row_bins():
+        lines.append("RowData:" + " ".join(
+            null_code if b is None else f"{b:0{width}X}" for b in row
+        ))
+    return "\n".join(lines) + "\n"
+
+
+def _map_path(out_dir: Path, grid: WaferGrid, suffix: str, taken: set[str]) -> Path:
+    """{lot}_{wafer}{suffix}, with Windows-invalid characters replaced (as in storage).
+
+    Wafer ids that come out the same ("W/1", "W:1") get _2, _3, ... rather
+    than overwrite each other; `taken` holds the names used so far (lower
+    case: Windows file names ignore case).
+    """
+    name = re.sub(r'[\\/:*?"<>|]', "_", re.sub(r"[\x00-\x1f]", "", f"{grid.lot_id}_{grid.wafer_id}"))
+    name = base = name.strip() or "wafer"
+    n = 1
+    while name.lower() in taken:
+        n += 1
+        name = f"{base}_{n}"
+    taken.add(name.lower())
+    return out_dir / f"{name}{suffix}"
+
+
+def _write_maps(
+    data: STDFData, out_dir: Path, bin_field: str, render, suffix: str,
+    parts: list[dict] | None,
+) -> list[Path]:
+    out_dir.mkdir(parents=True, exist_ok=True)
+    paths: list[Path] = []
+    taken: set[str] = set()
+    for grid in wafer_grids(data, bin_field, parts):
+        path = _map_path(out_dir, grid, suffix, taken)
+        path.write_text(render(data, grid, bin_field), encoding="utf-8")
+        paths.append(path)
+    return paths
+
+
+def write_e142(
+    data: STDFData, out_dir: Path, bin_field: str = "soft_bin", parts: list[dict] | None = None,
+) -> list[Path]:
+    """Write one E142 XML file per wafer into out_dir. Returns the written paths."""
+    return _write_maps(data, out_dir, bin_field, e142_xml, ".xml", parts)
+
+
+def write_sinf(
+    data: STDFData, out_dir: Path, bin_field: str = "soft_bin", parts: list[dict] | None = None,
+) -> list[Path]:
+    """Write one SINF file per wafer into out_dir. Returns the written paths."""
+    return _write_maps(data, out_dir, bin_field, sinf_text, ".sinf", parts)
@@ -199,13 +199,13 @@ def make_stdf(path: Path, lot_id: str, num_wafers: int = 3, parts_per_wafer: int
 
         # HBR
         for bin_num, name, pf, cnt in [(1, "PASS", "P", good), (2, "FAIL", "F", parts_per_wafer - good)]:
-            hbr = struct.pack("<BBHIBx", 1, 0, bin_num, cnt, ord(pf)) + cn(name)
+            hbr = struct.pack("<BBHIB", 1, 0, bin_num, cnt, ord(pf)) + cn(name)
             buf += record(1, 40, hbr)
 
         # SBR
         for bin_num, name, pf in [(1, "BIN1_PASS", "P"), (2, "BIN2_FAIL_A", "F"), (3, "BIN3_FAIL_B", "F")]:
             cnt = good if bin_num == 1 else (parts_per_wafer - good) // 2
-            sbr = struct.pack("<BBHIBx", 1, 0, bin_num, cnt, ord(pf)) + cn(name)
+            sbr = struct.pack("<BBHIB", 1, 0, bin_num, cnt, ord(pf)) + cn(name)
             buf += record(1, 50, sbr)
 
         # WRR
@@ -0,0 +1,135 @@
+"""Tests for anonymize.py (scrubbed STDF copies)."""
+
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import cn, make_ft_stdf, make_stdf, record  # noqa: E402
+
+from stdf_platform.anonymize import AnonymizeOptions, anonymize  # noqa: E402
+from stdf_platform.parser import parse_stdf  # noqa: E402
+from stdf_platform.records import encode_fields  # noqa: E402
+
+
+def _anonymize(src: Path, options: AnonymizeOptions | None = None):
+    dst = src.with_name("anon_" + src.name)
+    with open(dst, "wb") as out:
+        report = anonymize(src, out, options)
+    return parse_stdf(dst), report
+
+
+def test_anonymize_wafer_lot(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "CUSTLOT7", num_wafers=2, parts_per_wafer=3)
+    data = parse_stdf(path)
+
+    anon, report = _anonymize(path, AnonymizeOptions(salt="s3cret", time_offset=-86400))
+
+    assert anon.lot_id not in ("", "CUSTLOT7") and len(anon.lot_id) == 12
+    assert report.key[anon.lot_id] == "CUSTLOT7"
+    assert anon.part_type != data.part_type and anon.operator != data.operator
+    assert anon.tester_type == data.tester_type and anon.test_code == data.test_code
+    assert anon.start_time == data.start_time - 86400
+    assert anon.finish_time == data.finish_time - 86400
+    assert [report.key[w["wafer_id"]] for w in anon.wafers] == ["W01", "W02"]
+    # WRR wafer ids hash like the WIR ones: wafers still close cleanly
+    assert anon.is_complete and anon.warnings == []
+    assert [r["result"] for r in anon.test_results] == [r["result"] for r in data.test_results]
+    assert [(p["x_coord"], p["hard_bin"]) for p in anon.parts] == [
+        (p["x_coord"], p["hard_bin"]) for p in data.parts
+    ]
+    assert anon.tests == data.tests and anon.bins_soft == data.bins_soft
+
+    # deterministic per salt, different across salts
+    again, _ = _anonymize(path, AnonymizeOptions(salt="s3cret"))
+    other, _ = _anonymize(path, AnonymizeOptions(salt="other"))
+    assert again.lot_id == anon.lot_id and other.lot_id != anon.lot_id
+
+
+def test_anonymize_drops_text_and_vendor_records(tmp_path):
+    path = tmp_path / "ft.stdf"
+    make_ft_stdf(path, "FTLOT", parts=2)
+    with open(path, "ab") as f:
+        f.write(record(50, 30, b"\x0aoperator note: customer X"))  # DTR
+        f.write(record(180, 7, b"vendor"))
+
+    anon, report = _anonymize(path)
+
+    assert anon.chip_ids == [] and anon.generic_data == [] and anon.datalog_text == []
+    assert report.dropped == {"GDR": 4, "DTR": 1, "180/7": 1}
+    assert {report.key[p["part_txt"]] for p in anon.parts} == {"2D-FTLOT-0000", "2D-FTLOT-0001"}
+    assert [p["part_id"] for p in anon.parts] == [f"{anon.lot_id}__UNIT0000", f"{anon.lot_id}__UNIT0001"]
+
+    kept, report = _anonymize(path, AnonymizeOptions(drop_records=set(), keep_unknown_records=True))
+    assert len(kept.chip_ids) == 4 and report.dropped == {}
+
+
+def _scan_str(user_txt: bytes) -> bytes:
+    """STR with free text in TEST_TXT..RSLT_TXT, one fail and a 4-wide USER_TXT."""
+    def arr(code, values):
+        return struct.pack(f"<H{len(values)}{code}", len(values), *values)
+
+    return record(15, 30, (
+        struct.pack("<BIBBHB", 0, 500, 1, 1, 0, 0x80)
+        + cn("") + cn("CUST_SCAN") + cn("ALARM_CUST") + cn("PROG_CUST") + cn("RSLT_CUST")
+        + struct.pack("<BB", 0, 0) + struct.pack("<QIIQIHH", 1000, 1, 1, 0, 0, 1, 0)
+        + struct.pack("<9BH", 4, 2, 1, 2, 2, 1, 1, 1, 4, 0) + cn("VDD=0.9")
+        + arr("I", [7]) + arr("H", [3]) + arr("B", []) + arr("B", [1]) + arr("B", [0])
+        + arr("B", []) + arr("H", []) + arr("H", []) + arr("B", []) * 3
+        + struct.pack("<H", 1) + user_txt
+    ))
+
+
+def test_anonymize_scrubs_equipment_ids_and_scan_text(tmp_path):
+    mir = {name: "" for name in (
+        "LOT_ID", "PART_TYP", "NODE_NAM", "TSTR_TYP", "JOB_NAM", "JOB_REV", "SBLOT_ID",
+        "OPER_NAM", "EXEC_TYP", "EXEC_VER", "TEST_COD", "TST_TEMP", "USER_TXT", "AUX_FILE",
+    )}
+    mir.update(
+        SETUP_T=1, START_T=1, STAT_NUM=1, MODE_COD="P", RTST_COD=" ", PROT_COD=" ",
+        BURN_TIM=0, CMOD_COD=" ", LOT_ID="LOTX", PKG_TYP="CUSTPKG", FAMLY_ID="", DATE_COD="",
+        FACIL_ID="", FLOOR_ID="", PROC_ID="CUSTPROC", OPER_FRQ="", SPEC_NAM="", SPEC_VER="",
+        FLOW_ID="", SETUP_ID="CUSTSETUP", DSGN_REV="CUSTREV",
+    )
+    sdr = {
+        "HEAD_NUM": 1, "SITE_GRP": 1, "SITE_CNT": 1, "SITE_NUM": [1], "HAND_TYP": "",
+        "HAND_ID": "CUSTHAND", "CARD_TYP": "", "CARD_ID": "CUSTCARD", "LOAD_TYP": "",
+        "LOAD_ID": "CUSTLOAD", "DIB_TYP": "", "DIB_ID": "CUSTDIB",
+    }
+    path = tmp_path / "scan.stdf"
+    path.write_bytes(
+        record(0, 10, struct.pack("BB", 2, 4)) + record(1, 10, encode_fields("MIR", mir))
+        + record(1, 80, encode_fields("SDR", sdr)) + record(5, 10, struct.pack("BB", 1, 1))
+        + _scan_str(b"CUST") + record(5, 20, struct.pack("<BBBHHHhh", 1, 1, 8, 1, 2, 2, 0, 0))
+        + record(1, 20, struct.pack("<I", 2))
+    )
+
+    anon, report = _anonymize(path)
+
+    raw = (tmp_path / "anon_scan.stdf").read_bytes()
+    assert b"CUST" not in raw
+    assert {report.key[v] for v in (
+        anon.site_descriptions[0]["hand_id"], anon.site_descriptions[0]["card_id"],
+        anon.site_descriptions[0]["load_id"], anon.site_descriptions[0]["dib_id"],
+    )} == {"CUSTHAND", "CUSTCARD", "CUSTLOAD", "CUSTDIB"}
+    assert {"CUSTPKG", "CUSTPROC", "CUSTSETUP", "CUSTREV"} <= set(report.key.values())
+    [scan] = anon.scan_tests
+    assert (scan["alarm_id"], scan["prog_txt"], scan["rslt_txt"]) == ("", "", "")
+    assert scan["user_text"] == [""] and anon.tests[500]["test_name"] == ""  # C*f: blanks
+    # the scan fail itself is kept
+    assert [(r["cycle"], r["pin_num"]) for r in anon.scan_failures] == [(7, 3)]
+    assert anon.warnings == [] and not anon.stats.leftover_bytes
+
+
+def test_anonymize_refuses_undecodable_record(tmp_path):
+    path = tmp_path / "bad.stdf"
+    path.write_bytes(
+        record(0, 10, struct.pack("BB", 2, 4))
+        + record(1, 10, struct.pack("<IIB", 1, 1, 1) + b"  \x00\x00 \x09LOT")  # LOT_ID cut short
+    )
+    with pytest.raises(ValueError, match="cannot anonymize record 2"):
+        with open(tmp_path / "out.stdf", "wb") as out:
+            anonymize(path, out)
@@ -0,0 +1,161 @@
+"""Tests for atdf.py (ATDF text output)."""
+
+import io
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import cn, make_stdf, record  # noqa: E402
+
+from stdf_platform.atdf import (  # noqa: E402
+    format_r4, format_record, format_time, parse_time, write_atdf,
+)
+from stdf_platform.parser import parse_reader, parse_stdf  # noqa: E402
+
+
+def _records(text: str) -> list[str]:
+    """Records with continuation lines joined back."""
+    out: list[str] = []
+    for line in text.splitlines():
+        if line.startswith(" "):
+            out[-1] += line[1:]
+        else:
+            out.append(line)
+    return out
+
+
+def test_write_atdf_record_sequence(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=3)
+    data = parse_stdf(path)
+    out = io.StringIO()
+
+    n = write_atdf(data, out)
+
+    records = _records(out.getvalue())
+    assert len(records) == n
+    assert records[0] == "FAR:A|4|2|U"
+    assert records[1].startswith("MIR:LOT1|SCT101A|CP_TEST||J750||22:13:20 14-NOV-2023|OPE01")
+    assert [r[:3] for r in records if r[:3] in ("WIR", "WRR")] == ["WIR", "WRR"] * 2
+    assert sum(r.startswith("PIR:") for r in records) == 6
+    assert sum(r.startswith("PTR:") for r in records) == 30
+    assert records[-1] == "MRR:00:13:20 15-NOV-2023"
+
+    # names, limits and units on the first result of each test only
+    ptrs = [r for r in records if r.startswith("PTR:1001|")]
+    assert ptrs[0].endswith("|Vth_N||V|0.3|0.8")
+    first = data.test_results[0]
+    assert ptrs[0].split("|")[3] == format_r4(first["result"])
+    assert all(p.count("|") <= 4 for p in ptrs[1:])
+    prr = next(r for r in records if r.startswith("PRR:"))
+    part = data.parts[0]
+    assert prr.split("|")[4:9] == [
+        "P" if part["passed"] else "F", str(part["hard_bin"]), str(part["soft_bin"]), "-5", "-2",
+    ]
+
+
+def test_atdf_final_test_ftr_and_continuation(tmp_path):
+    long_text = "functional " * 10
+    ftr = (
+        struct.pack("<IBBB", 2001, 1, 1, 0x80) + b"\x3f" + b"\x00" * 26
+        + struct.pack("<HH", 0, 0) + b"\x00\x00"
+        + cn("pat_main") + cn("") + cn("") + cn(long_text.strip())
+    )
+    buf = (
+        record(0, 10, struct.pack("BB", 2, 4))
+        + record(1, 10, struct.pack("<IIBBBBHB", 0, 0, 1, 32, 32, 32, 0, 32)
+                 + cn("FT1") + b"".join(cn("") for _ in range(10)))
+        + record(5, 10, struct.pack("BB", 1, 1))
+        + record(15, 20, ftr)
+        + record(5, 20, struct.pack("<BBBHHHhh", 1, 1, 0x08, 1, 5, 5, -32768, -32768))
+        + record(1, 20, struct.pack("<I", 0))
+    )
+    path = tmp_path / "ft.stdf"
+    path.write_bytes(buf)
+    out = io.StringIO()
+
+    write_atdf(parse_stdf(path), out)
+
+    lines = out.getvalue().splitlines()
+    assert all(len(line) <= 80 for line in lines)
+    assert any(line.startswith(" ") for line in lines)
+    records = _records(out.getvalue())
+    assert not any(r.startswith("WIR:") for r in records)
+    ftr_rec = next(r for r in records if r.startswith("FTR:"))
+    assert ftr_rec.startswith("FTR:2001|1|1|F||pat_main|")
+    assert ftr_rec.endswith("|" + long_text.strip())
+    assert records[-1] == "MRR:"
+
+
+def test_atdf_input_round_trips(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=4)
+    data = parse_stdf(path)
+    atd = tmp_path / "lot.atd"
+    with open(atd, "w", encoding="utf-8") as out:
+        write_atdf(data, out)
+
+    again = parse_stdf(atd)
+
+    for name in ("lot_id", "part_type", "start_time", "finish_time", "wafers", "parts",
+                 "test_results", "tests", "site_limits", "bins_hard", "site_bins_soft"):
+        assert getattr(again, name) == getattr(data, name), name
+    assert again.is_complete and again.warnings == []
+    with open(atd, "rb") as stream:
+        assert parse_reader(stream).parts == data.parts
+
+
+def test_atdf_input_subcontractor_file(tmp_path):
+    atd = tmp_path / "sub.atd"
+    atd.write_text(
+        "FAR:A|4|2|U\n"
+        "MIR:SUB7|DEV9|FT_PROG||93K||08:00:00 02-JAN-2024|op||1||FT1\n"
+        "SDR:1|1|1,2\n"
+        "PIR:1|2\n"
+        "PTR:100|1|2|1.25|||Vdd leak\n"
+        " age||uA|0|2\n"
+        "PTR:101|1|2|9.5|F||Vout||V|1|5\n"
+        "FTR:200|1|2|F||main_pat\n"
+        "PRR:1|2|17|3|F|4|40|||||1200|SN0017\n"
+        "TSR:1|2|100|Vdd leakage|P|1|0|0||||1.25|1.25\n"
+        "HBR:255|255|4|1|F|LEAK\n"
+        "MRR:08:05:00 02-JAN-2024\n",
+        encoding="utf-8",
+    )
+
+    data = parse_stdf(atd)
+
+    assert data.lot_id == "SUB7" and data.test_code == "FT1"
+    assert data.start_time == parse_time("08:00:00 02-JAN-2024")
+    assert data.finish_time - data.start_time == 300
+    [part] = data.parts
+    assert part["part_id"] == "SUB7__17" and part["part_txt"] == "SN0017"
+    assert (part["site_num"], part["hard_bin"], part["soft_bin"]) == (2, 4, 40)
+    assert part["passed"] is False and part["test_time"] == 1200
+    assert part["x_coord"] == -32768
+    assert [(r["test_num"], r["passed"]) for r in data.test_results] == [
+        (100, True), (101, False), (200, False),
+    ]
+    assert data.tests[100]["test_name"] == "Vdd leakage"
+    assert (data.tests[100]["lo_limit"], data.tests[100]["units"]) == (0.0, "uA")
+    assert data.test_synopses[0]["test_min"] == 1.25
+    assert data.test_synopses[0]["test_time"] is None
+    assert data.bins_hard[4]["bin_name"] == "LEAK"
+    assert data.stats.skipped_records == {"SDR": 1}
+
+    atd.write_text("FAR:A|4|2|U\nPIR:1|x\n", encoding="utf-8")
+    with pytest.raises(ValueError, match="ATDF PIR record 2"):
+        parse_stdf(atd)
+
+
+def test_format_helpers():
+    assert format_time(1700000000) == "22:13:20 14-NOV-2023"
+    assert format_time(0) == ""
+    assert parse_time("22:13:20 14-nov-2023") == 1700000000
+    value = struct.unpack("<f", struct.pack("<f", 0.3))[0]
+    assert format_r4(value) == "0.3"
+    assert format_r4(None) == ""
+    assert format_record("HBR", {"HEAD_NUM": 255, "SITE_NUM": 255, "HBIN_NUM": 1}) == "HBR:255|255|1"
@@ -0,0 +1,34 @@
+"""Tests for the minimal Avro container writer/reader (stdf_platform.avro)."""
+
+import pytest
+
+from stdf_platform.avro import infer_schema, read_avro, write_avro
+
+
+@pytest.mark.parametrize("codec", ["null", "deflate"])
+def test_round_trip_nullable_fields(tmp_path, codec):
+    rows = [
+        {"n": 1, "x": None, "s": "a", "ok": True},
+        {"n": -(2 ** 40), "x": 2, "s": "µ", "ok": False, "raw": b"\x00\xff"},
+        {"n": None, "x": 0.5, "s": None, "ok": None},
+    ] * 3000  # spans several container blocks
+    path = tmp_path / "t.avro"
+
+    assert write_avro(path, rows, "T", codec=codec) == len(rows)
+
+    schema, decoded = read_avro(path)
+    assert {f["name"]: f["type"][1] for f in schema["fields"]} == {
+        "n": "long", "x": "double", "s": "string", "ok": "boolean", "raw": "bytes",
+    }
+    assert list(decoded) == [{"raw": None, **row} for row in rows]
+
+
+def test_infer_schema_all_null_column_is_string():
+    assert infer_schema([{"a": None}], "T")["fields"] == [
+        {"name": "a", "type": ["null", "string"], "default": None},
+    ]
+
+
+def test_write_avro_rejects_unknown_codec(tmp_path):
+    with pytest.raises(ValueError, match="codec"):
+        write_avro(tmp_path / "t.avro", [], "T", codec="snappy")
@@ -0,0 +1,67 @@
+"""Tests for parser.census (header-only record counts), bench_parse and size estimates."""
+
+import struct
+import sys
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import make_stdf  # noqa: E402
+
+from stdf_platform.parser import bench_parse, census, parse_stdf  # noqa: E402
+
+
+def test_census_counts_and_sizes(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=5)
+
+    stats = census(path)
+
+    assert stats["FAR"]["count"] == 1
+    assert stats["WIR"]["count"] == 2
+    assert stats["PIR"]["count"] == stats["PRR"]["count"] == 10
+    assert stats["PTR"]["count"] == 50
+    assert stats["PTR"]["min_len"] <= stats["PTR"]["avg_len"] <= stats["PTR"]["max_len"]
+    # total_bytes includes the 4-byte headers, so the census covers the whole file
+    assert sum(e["total_bytes"] for e in stats.values()) == path.stat().st_size
+
+
+def test_census_big_endian_and_unknown_records(tmp_path):
+    path = tmp_path / "be.stdf"
+    buf = struct.pack(">HBB", 2, 0, 10) + bytes([1, 4])          # FAR, cpu_type=1 (BE)
+    buf += struct.pack(">HBB", 3, 180, 7) + b"abc"               # vendor record
+    buf += struct.pack(">HBB", 5, 180, 7) + b"abcde"
+    path.write_bytes(buf)
+
+    stats = census(path)
+
+    assert stats["FAR"]["count"] == 1
+    assert stats["180/7"] == {
+        "rec_typ": 180, "rec_sub": 7, "count": 2, "total_bytes": 16,
+        "min_len": 3, "avg_len": 4.0, "max_len": 5,
+    }
+
+
+def test_bench_parse_reports_both_modes(tmp_path):
+    path = tmp_path / "bench.stdf"
+    make_stdf(path, "BENCH", num_wafers=1, parts_per_wafer=5)
+
+    rows = bench_parse(path, repeat=1)
+
+    assert [r["mode"] for r in rows] == ["full", "no results"]
+    assert rows[0]["records"] == rows[1]["records"] > 0
+    assert rows[0]["results"] == 25 and rows[1]["results"] == 0
+    assert all(r["mb_per_s"] > 0 and r["records_per_s"] > 0 for r in rows)
+
+
+def test_estimated_bytes_breakdown(tmp_path):
+    small, large = tmp_path / "small.stdf", tmp_path / "large.stdf"
+    make_stdf(small, "LOT1", num_wafers=1, parts_per_wafer=5)
+    make_stdf(large, "LOT1", num_wafers=1, parts_per_wafer=50)
+
+    s, big = parse_stdf(small), parse_stdf(large)
+
+    sizes = big.table_sizes()
+    assert big.stats.table_bytes == sizes
+    assert big.estimated_bytes() == sum(sizes.values())
+    assert sizes["test_results"] > sizes["parts"] > 0
+    assert big.estimated_bytes() > s.estimated_bytes()
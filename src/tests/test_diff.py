@@ -0,0 +1,80 @@
+"""Tests for diff.py (compare two STDF files)."""
+
+import copy
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import make_ft_stdf, make_stdf  # noqa: E402
+
+from stdf_platform.diff import compare  # noqa: E402
+from stdf_platform.parser import parse_stdf  # noqa: E402
+
+
+def test_compare_same_file_is_identical(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=4)
+
+    diff = compare(path, path)
+
+    assert diff.identical
+    assert diff.part_count == (8, 8) and diff.tests == []
+
+
+def test_compare_reports_header_bin_and_result_changes(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=4)
+    a = parse_stdf(path)
+    b = copy.deepcopy(a)
+    b.job_rev = "Rev02"
+    first = b.parts[0]
+    new_bin = 7 if first["hard_bin"] != 7 else 8
+    first.update(hard_bin=new_bin, passed=False)
+    b.bins_hard[new_bin] = {"bin_num": new_bin, "bin_count": 1}
+    [r for r in b.test_results if r["part_id"] == first["part_id"] and r["test_num"] == 1002][0]["result"] += 0.5
+    dropped = b.parts.pop()
+    b.test_results = [r for r in b.test_results if r["part_id"] != dropped["part_id"]]
+
+    diff = compare(a, b)
+
+    assert not diff.identical
+    assert diff.header == {"job_rev": ("Rev01", "Rev02")}
+    assert diff.part_count == (4, 3)
+    assert diff.bins_hard[new_bin] == (0, 1)
+    assert diff.bin_changes == [{
+        "die": ("W01", a.parts[0]["x_coord"], a.parts[0]["y_coord"]),
+        "hard_bin": (a.parts[0]["hard_bin"], new_bin),
+        "soft_bin": (a.parts[0]["soft_bin"],) * 2,
+        "passed": (a.parts[0]["passed"], False),
+    }]
+    assert diff.dies_only_a == [("W01", dropped["x_coord"], dropped["y_coord"])]
+    assert diff.dies_only_b == []
+    # every test lost the dropped die's result; only 1002 changed a value
+    assert [t.test_num for t in diff.tests] == [1001, 1002, 1003, 1004, 1005]
+    t1002 = diff.tests[1]
+    assert t1002.test_name == "Idsat_N" and (t1002.count_a, t1002.count_b) == (4, 3)
+    assert t1002.paired == 3 and t1002.changed == 1
+    assert t1002.max_abs_delta == pytest.approx(0.5)
+    assert diff.tests[0].changed == 0
+
+    assert compare(a, b, tolerance=1.0).tests[1].changed == 0
+
+
+def test_compare_pairs_unlabeled_ft_parts_by_file_order(tmp_path):
+    path = tmp_path / "ft.stdf"
+    make_ft_stdf(path, "FT1", parts=4)
+    a = parse_stdf(path)
+    for part in a.parts:
+        part.update(tester_part_id="", part_txt="")  # blank PART_ID and PART_TXT
+    b = copy.deepcopy(a)
+    b.parts[2].update(hard_bin=2, soft_bin=3, passed=False)
+    [r for r in b.test_results if r["part_id"] == b.parts[2]["part_id"]][0]["result"] += 1.0
+
+    diff = compare(a, b)
+
+    assert diff.dies_only_a == diff.dies_only_b == []
+    assert [c["die"] for c in diff.bin_changes] == [("#", 2)]
+    [t5001] = diff.tests
+    assert (t5001.count_a, t5001.count_b, t5001.paired, t5001.changed) == (4, 4, 4, 1)
@@ -0,0 +1,574 @@
+"""Tests for export.py (exporters working directly on parsed STDFData)."""
+
+import csv
+import json
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import cn, make_ft_stdf, make_stdf, record  # noqa: E402
+
+from stdf_platform.avro import read_avro  # noqa: E402
+from stdf_platform.export import (  # noqa: E402
+    AvroSink,
+    CsvSink,
+    DeltaSink,
+    DuckDBSink,
+    ExportSink,
+    KafkaSink,
+    MemorySink,
+    NdjsonSink,
+    ParquetSink,
+    PostgresSink,
+    bin_rows,
+    export_part_payloads,
+    export_pin_configuration,
+    export_limit_table,
+    export_to_sink,
+    iter_part_payloads,
+    lot_metadata,
+    parquet_metadata,
+    parse_to_parquet,
+    stream_to_sink,
+)
+from stdf_platform.parser import parse_stdf  # noqa: E402
+
+
+def test_part_payloads_nest_results(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+    data = parse_stdf(path)
+
+    payloads = list(iter_part_payloads(data))
+
+    assert [p["part_id"] for p in payloads] == [p["part_id"] for p in data.parts]
+    first = payloads[0]
+    assert first["lot_id"] == "LOT1"
+    assert [r["test_num"] for r in first["results"]] == [1001, 1002, 1003, 1004, 1005]
+    assert first["results"][0]["test_name"] == "Vth_N"
+    assert first["results"][0]["units"] == "V"
+
+
+def test_part_payloads_jsonl(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=2)
+    out = tmp_path / "parts.jsonl"
+
+    n = export_part_payloads(parse_stdf(path), out)
+
+    lines = out.read_text(encoding="utf-8").splitlines()
+    assert n == len(lines) == 4
+    assert all(len(json.loads(line)["results"]) == 5 for line in lines)
+
+
+class _RecordingSink(ExportSink):
+    def __init__(self):
+        self.calls = []
+
+    def begin_lot(self, lot):
+        self.calls.append(("begin_lot", lot["lot_id"]))
+
+    def write_part(self, part):
+        self.calls.append(("part", part["part_id"]))
+
+    def write_results_batch(self, results):
+        self.calls.append(("results", len(results)))
+
+    def write_wafer(self, wafer):
+        self.calls.append(("wafer", wafer["wafer_id"]))
+
+    def finish(self):
+        self.calls.append(("finish",))
+
+
+def test_export_to_sink_call_sequence(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=2)
+    data = parse_stdf(path)
+    sink = _RecordingSink()
+
+    assert export_to_sink(data, sink) == 2
+
+    ids = [p["part_id"] for p in data.parts]
+    assert sink.calls == [
+        ("begin_lot", "LOT1"),
+        ("part", ids[0]), ("results", 5),
+        ("part", ids[1]), ("results", 5),
+        ("wafer", "W01"),
+        ("finish",),
+    ]
+
+
+def test_stream_to_sink_matches_export_to_sink(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=2)
+    streamed, parsed = _RecordingSink(), _RecordingSink()
+
+    assert stream_to_sink(path, streamed) == export_to_sink(parse_stdf(path), parsed) == 4
+    assert streamed.calls == parsed.calls
+    assert [c for c in streamed.calls if c[0] == "wafer"] == [("wafer", "W01"), ("wafer", "W02")]
+    assert streamed.calls.index(("wafer", "W01")) == 5  # after wafer 1's two parts
+
+    with open(path, "rb") as stream:
+        assert stream_to_sink(stream, _RecordingSink()) == 4
+
+
+def test_ndjson_sink_streams_flat_lines(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=2)
+    out = tmp_path / "lot.ndjson"
+
+    with open(out, "w", encoding="utf-8") as f:
+        assert stream_to_sink(path, NdjsonSink(f)) == 2
+
+    rows = [json.loads(line) for line in out.read_text(encoding="utf-8").splitlines()]
+    assert [r["type"] for r in rows] == (["part"] + ["result"] * 5) * 2
+    assert rows[1]["part_id"] == rows[0]["part_id"]
+    assert rows[1]["test_name"] == "Vth_N"
+
+
+def test_csv_sink(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+
+    export_to_sink(parse_stdf(path), CsvSink(tmp_path / "out"))
+
+    with open(tmp_path / "out" / "parts.csv", newline="") as f:
+        parts = list(csv.DictReader(f))
+    with open(tmp_path / "out" / "results.csv", newline="") as f:
+        results = list(csv.DictReader(f))
+    assert len(parts) == 3
+    assert len(results) == 15
+    assert results[0]["part_id"] == parts[0]["part_id"]
+    assert results[0]["test_name"] == "Vth_N"
+    with open(tmp_path / "out" / "wafers.csv", newline="") as f:
+        wafers = list(csv.DictReader(f))
+    assert [w["wafer_id"] for w in wafers] == ["W01"]
+    assert wafers[0]["part_count"] == "3"
+
+
+def test_csv_sink_mixed_results_and_empty_lot(tmp_path):
+    sink = CsvSink(tmp_path / "out")
+    sink.write_part({"part_id": "P1", "lot_id": "LOT1", "source_file": "a.stdf"})
+    sink.write_results_batch([
+        {"part_id": "P1", "test_num": 1, "rec_type": "PTR", "result": 1.5},
+        {"part_id": "P1", "test_num": 2, "rec_type": "FTR", "vect_nam": "v1", "rtn_indx": [3]},
+    ])
+    sink.finish()
+
+    with open(tmp_path / "out" / "parts.csv", newline="") as f:
+        parts = list(csv.DictReader(f))
+    with open(tmp_path / "out" / "results.csv", newline="") as f:
+        results = list(csv.DictReader(f))
+    assert parts[0]["source_file"] == "a.stdf" and parts[0]["hard_bin"] == ""
+    assert [(r["test_num"], r["rec_type"]) for r in results] == [("1", "PTR"), ("2", "FTR")]
+    assert "vect_nam" not in results[1]
+
+    CsvSink(tmp_path / "empty").finish()
+    for name in ("parts.csv", "results.csv"):
+        header = (tmp_path / "empty" / name).read_text(encoding="utf-8").splitlines()
+        assert header[0].startswith("part_id,") and len(header) == 1
+
+
+def test_memory_sink_collects_stream(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=2)
+    sink = MemorySink()
+
+    assert stream_to_sink(path, sink) == 4
+
+    assert sink.lot["lot_id"] == "LOT1"
+    assert len(sink.parts) == 4 and len(sink.results) == 20
+    assert [w["wafer_id"] for w in sink.wafers] == ["W01", "W02"]
+    assert sink.wafers[1]["part_count"] == 2
+
+
+def test_parse_to_parquet_tables(tmp_path):
+    pq = pytest.importorskip("pyarrow.parquet")
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=3)
+
+    counts = parse_to_parquet(path, tmp_path / "out")
+
+    assert counts == {
+        "parts": 6, "test_results": 30, "wafers": 2, "bins": len(bin_rows(parse_stdf(path))),
+    }
+    for name, n in counts.items():
+        assert pq.read_table(tmp_path / "out" / f"{name}.parquet").num_rows == n
+    wafers = pq.read_table(tmp_path / "out" / "wafers.parquet").to_pylist()
+    assert [w["wafer_id"] for w in wafers] == ["W01", "W02"]
+
+
+def test_parquet_metadata_describes_lot(tmp_path):
+    import hashlib
+
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=2)
+    lot = lot_metadata(parse_stdf(path))
+
+    meta = parquet_metadata(lot, path)
+
+    assert meta["stdf.lot_id"] == "LOT1"
+    assert meta["stdf.start_time"] == str(lot["start_time"])
+    assert meta["stdf.source_file"] == "lot.stdf"
+    assert meta["stdf.source_sha256"] == hashlib.sha256(path.read_bytes()).hexdigest()
+    assert "stdf.source_sha256" not in parquet_metadata(lot)
+
+    pq = pytest.importorskip("pyarrow.parquet")
+    parse_to_parquet(path, tmp_path / "out")
+    kv = pq.read_schema(tmp_path / "out" / "parts.parquet").metadata
+    assert kv[b"stdf.lot_id"] == b"LOT1"
+    assert kv[b"stdf.source_sha256"] == meta["stdf.source_sha256"].encode()
+
+
+def test_parse_to_parquet_hive_partitions(tmp_path):
+    pq = pytest.importorskip("pyarrow.parquet")
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=3)
+    out = tmp_path / "out"
+
+    parse_to_parquet(path, out, partition_by=["lot_id", "wafer_id"])
+
+    part_file = out / "parts" / "lot_id=LOT1" / "wafer_id=W02" / "lot-0.parquet"
+    parts = pq.read_table(part_file)
+    assert parts.num_rows == 3 and "wafer_id" not in parts.column_names
+    assert (out / "test_results" / "lot_id=LOT1" / "wafer_id=W01" / "lot-0.parquet").exists()
+    assert (out / "bins" / "lot_id=LOT1" / "lot-0.parquet").exists()  # no wafer_id column
+
+    retest = tmp_path / "lot_rt1.stdf"  # same lot and wafers: lands beside, not over
+    make_stdf(retest, "LOT1", num_wafers=1, parts_per_wafer=2)
+    parse_to_parquet(retest, out, partition_by=["lot_id", "wafer_id"])
+
+    wafer_dir = out / "parts" / "lot_id=LOT1" / "wafer_id=W01"
+    assert sorted(p.name for p in wafer_dir.iterdir()) == ["lot-0.parquet", "lot_rt1-0.parquet"]
+    assert pq.read_table(wafer_dir / "lot-0.parquet").num_rows == 3
+
+
+def test_parquet_sink_streams_row_groups(tmp_path):
+    pq = pytest.importorskip("pyarrow.parquet")
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=3)
+    out = tmp_path / "out"
+
+    assert stream_to_sink(path, ParquetSink(out, source_file=path, row_group_rows=10)) == 6
+
+    results = pq.ParquetFile(out / "results.parquet")
+    assert results.num_row_groups == 3  # 30 results, flushed every 10
+    assert results.metadata.num_rows == 30
+    assert results.schema_arrow.field("result").type == "double"
+    assert results.schema_arrow.metadata[b"stdf.lot_id"] == b"LOT1"
+    assert pq.read_table(out / "parts.parquet").num_rows == 6
+    assert pq.read_table(out / "wafers.parquet").column("wafer_id").to_pylist() == ["W01", "W02"]
+
+
+def test_bin_rows_per_site_then_totals(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+
+    rows = bin_rows(parse_stdf(path))
+
+    # make_stdf bins at random: check the shape, not the counts
+    for bin_type in ("H", "S"):
+        typed = [r for r in rows if r["bin_type"] == bin_type]
+        sites = [r for r in typed if r["head_num"] != 255]
+        totals = [r for r in typed if r["head_num"] == 255]
+        assert typed == sites + totals and totals
+        assert all(r["site_num"] == 255 for r in totals)
+        assert sum(r["bin_count"] for r in totals) == sum(r["bin_count"] for r in sites)
+
+
+def test_avro_sink_embeds_schema(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+
+    assert export_to_sink(parse_stdf(path), AvroSink(tmp_path / "out")) == 3
+
+    schema, parts = read_avro(tmp_path / "out" / "parts.avro")
+    parts = list(parts)
+    assert schema["name"] == "Part"
+    types = {f["name"]: f["type"] for f in schema["fields"]}
+    assert types["part_id"] == ["null", "string"] and types["passed"] == ["null", "boolean"]
+    assert types["x_coord"] == ["null", "long"]
+    assert len(parts) == 3 and parts[0]["lot_id"] == "LOT1"
+
+    schema, results = read_avro(tmp_path / "out" / "results.avro")
+    results = list(results)
+    assert {f["name"]: f["type"] for f in schema["fields"]}["result"] == ["null", "double"]
+    assert len(results) == 15
+    assert results[0]["part_id"] == parts[0]["part_id"]
+    assert results[0]["test_name"] == "Vth_N"
+
+
+def test_duckdb_sink_appends_across_files(tmp_path):
+    pytest.importorskip("duckdb")
+    pytest.importorskip("pyarrow")
+    db = tmp_path / "yield.duckdb"
+    for lot in ("LOT1", "LOT2"):
+        path = tmp_path / f"{lot}.stdf"
+        make_stdf(path, lot, num_wafers=1, parts_per_wafer=3)
+        assert stream_to_sink(path, DuckDBSink(db, batch_rows=4)) == 3
+
+    import duckdb
+
+    with duckdb.connect(str(db)) as conn:
+        lots = conn.execute("SELECT lot_id, count(*) FROM parts GROUP BY 1 ORDER BY 1").fetchall()
+        joined = conn.execute(
+            "SELECT count(*) FROM results JOIN parts USING (part_id)"
+        ).fetchone()[0]
+    assert lots == [("LOT1", 3), ("LOT2", 3)]
+    assert joined == 30
+
+    ft = tmp_path / "FT1.stdf"
+    make_ft_stdf(ft, "FT1", parts=2)
+    data = parse_stdf(ft)
+    for part in data.parts:
+        part["wafer_id"] = None  # final test: no wafer
+        part["source_file"] = "FT1.stdf"  # undeclared column, new to the table
+    assert export_to_sink(data, DuckDBSink(db)) == 2
+    with duckdb.connect(str(db)) as conn:
+        assert conn.execute(
+            "SELECT count(*), count(wafer_id), count(source_file) FROM parts WHERE lot_id = 'FT1'"
+        ).fetchone() == (2, 0, 2)
+
+
+def test_duckdb_sink_types_columns_a_first_lot_leaves_empty(tmp_path):
+    pytest.importorskip("duckdb")
+    pytest.importorskip("pyarrow")
+    db = tmp_path / "yield.duckdb"
+    ft = tmp_path / "FT1.stdf"
+    make_ft_stdf(ft, "FT1", parts=2)
+    data = parse_stdf(ft)
+    for part in data.parts:
+        part["wafer_id"] = None
+    assert export_to_sink(data, DuckDBSink(db)) == 2  # PTR only: pin columns all None
+
+    mir = (struct.pack("<IIBBBBHB", 0, 0, 1, 32, 32, 32, 0, 32)
+           + cn("PINS1") + b"".join(cn("") for _ in range(10)))
+    pmr = record(1, 60, struct.pack("<HH", 4, 0) + cn("") + cn("") + cn("VDD1"))
+    mpr = record(15, 15, (
+        struct.pack("<IBBBBHH", 40, 1, 1, 0, 0, 1, 1) + bytes([0x00])
+        + struct.pack("<f", 1.1) + cn("VDD_PINS") + cn("")
+        + struct.pack("<BbbbffffH", 0, 0, 0, 0, 0, 2, 0, 0, 4) + cn("V")
+    ))
+    ftr = record(15, 20, struct.pack("<IBBB", 50, 1, 1, 0))
+    path = tmp_path / "pins.stdf"
+    path.write_bytes(
+        record(0, 10, struct.pack("BB", 2, 4)) + record(1, 10, mir) + pmr
+        + record(5, 10, struct.pack("BB", 1, 1)) + mpr + ftr
+        + record(5, 20, struct.pack("<BBBHHHhh", 1, 1, 0, 2, 1, 1, 0, 0))
+    )
+    assert stream_to_sink(path, DuckDBSink(db)) == 1
+
+    import duckdb
+
+    with duckdb.connect(str(db)) as conn:
+        types = dict(conn.execute(
+            "SELECT column_name, data_type FROM information_schema.columns "
+            "WHERE table_name = 'results'"
+        ).fetchall())
+        pins = conn.execute(
+            "SELECT test_num, pin_num, pin_name FROM results WHERE pin_num IS NOT NULL"
+        ).fetchall()
+    assert (types["pin_num"], types["pin_name"]) == ("BIGINT", "VARCHAR")
+    assert pins == [(40, 4, "VDD1")]
+
+
+def test_delta_sink_appends_partitioned_by_lot(tmp_path):
+    pytest.importorskip("deltalake")
+    pytest.importorskip("pyarrow")
+    lake = tmp_path / "lake"
+    for lot in ("LOT1", "LOT2"):
+        path = tmp_path / f"{lot}.stdf"
+        make_stdf(path, lot, num_wafers=1, parts_per_wafer=3)
+        assert stream_to_sink(path, DeltaSink(lake, batch_rows=4)) == 3
+
+    from deltalake import DeltaTable
+
+    parts = DeltaTable(str(lake / "parts"))
+    results = DeltaTable(str(lake / "results")).to_pyarrow_table()
+    assert parts.metadata().partition_columns == ["lot_id"]
+    assert sorted(parts.to_pyarrow_table().column("lot_id").to_pylist()) == ["LOT1"] * 3 + ["LOT2"] * 3
+    assert results.num_rows == 30
+    assert set(results.column("lot_id").to_pylist()) == {"LOT1", "LOT2"}
+    assert (lake / "parts" / "lot_id=LOT2").is_dir()
+
+
+def test_postgres_sink_copy_files(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+    data = parse_stdf(path)
+    data.test_results[0]["result"] = float("nan")
+    data.tests[1002]["lo_limit"] = None
+    data.parts[1]["part_txt"] = "a\tb\\c"
+    for part in data.parts:
+        part["test_time"] = None  # NULL throughout: still its declared type
+        part["source_file"] = "lot.stdf"  # undeclared: text
+    out = tmp_path / "pg"
+
+    assert export_to_sink(data, PostgresSink(out)) == 3
+
+    parts = (out / "parts.copy").read_text(encoding="utf-8").splitlines()
+    results = [line.split("\t") for line in (out / "results.copy").read_text(encoding="utf-8").splitlines()]
+    assert len(parts) == 3 and len(results) == 15
+    columns = list(data.parts[0])
+    assert parts[1].split("\t")[columns.index("part_txt")] == "a\\tb\\\\c"
+    assert parts[0].split("\t")[columns.index("passed")] in ("t", "f")
+    # part_id, test_num, test_name, rec_type, section, result, passed, lo_limit, ...
+    assert results[0][5] == "\\N"
+    assert {r[7] for r in results if r[1] == "1002"} == {"\\N"}
+    assert all(len(r) == 12 for r in results)
+    load = (out / "load.sql").read_text(encoding="utf-8")
+    assert "CREATE TABLE IF NOT EXISTS parts (" in load
+    assert "    passed boolean" in load and "    x_coord bigint" in load
+    assert "    test_time bigint" in load and "    source_file text" in load
+    assert "    result double precision" in load
+    assert "\\copy results (part_id, test_num," in load
+    assert str((out / "results.copy").resolve()) in load
+
+
+class _FakeCopy:
+    def __init__(self, sink: list):
+        self.sink = sink
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *exc):
+        return False
+
+    def write(self, text: str):
+        self.sink.append(text)
+
+
+class _FakeCursor:
+    def __init__(self, log: list):
+        self.log = log
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *exc):
+        return False
+
+    def execute(self, sql: str):
+        self.log.append(("execute", sql))
+
+    def copy(self, sql: str):
+        lines: list[str] = []
+        self.log.append(("copy", sql, lines))
+        return _FakeCopy(lines)
+
+
+class _FakeConnection:
+    def __init__(self):
+        self.log: list = []
+
+    def cursor(self):
+        return _FakeCursor(self.log)
+
+
+def test_postgres_sink_copies_over_connection(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+    conn = _FakeConnection()
+
+    assert stream_to_sink(path, PostgresSink(connection=conn, batch_rows=10)) == 3
+
+    creates = [entry[1] for entry in conn.log if entry[0] == "execute"]
+    assert [sql.split()[5] for sql in creates] == ["parts", "results"]
+    copies = [entry for entry in conn.log if entry[0] == "copy"]
+    assert sum(len(c[2]) for c in copies if c[1].startswith("COPY results")) == 15
+    assert copies[0][1].startswith("COPY parts (part_id, ")
+    with pytest.raises(ValueError):
+        PostgresSink()
+
+
+class _FakeProducer:
+    def __init__(self):
+        self.messages = []
+        self.flushed = False
+
+    def produce(self, topic, key, value):
+        self.messages.append((topic, key, value))
+
+    def poll(self, timeout):
+        return 0
+
+    def flush(self):
+        self.flushed = True
+
+
+def test_kafka_sink_publishes_part_payloads(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+    producer = _FakeProducer()
+
+    n = export_to_sink(parse_stdf(path), KafkaSink("stdf.parts", producer=producer))
+
+    assert n == 3 and producer.flushed
+    topic, key, value = producer.messages[0]
+    payload = json.loads(value)
+    assert topic == "stdf.parts"
+    assert key == payload["part_id"].encode()
+    assert len(payload["results"]) == 5
+
+
+def test_pin_configuration_json(tmp_path):
+    pmr = [
+        record(1, 60, struct.pack("<HH", i, 0) + cn(f"CH{i}") + cn(f"PAD{i}") + cn(log)
+               + struct.pack("BB", 1, site))
+        for i, log, site in [(1, "DQ0", 1), (2, "DQ1", 1), (3, "", 2)]
+    ]
+    pgr = record(1, 62, struct.pack("<H", 32768) + cn("DQ") + struct.pack("<HHH", 2, 1, 2))
+    path = tmp_path / "pins.stdf"
+    path.write_bytes(record(0, 10, struct.pack("BB", 2, 4)) + b"".join(pmr) + pgr)
+
+    out = tmp_path / "pins.json"
+    export_pin_configuration(parse_stdf(path), out)
+
+    config = json.loads(out.read_text())
+    assert config["pins"]["1"] == {
+        "chan_typ": 0, "chan_nam": "CH1", "phy_nam": "PAD1", "log_nam": "DQ0",
+        "head_num": 1, "site_num": 1, "name": "DQ0",
+    }
+    assert (config["pins"]["3"]["name"], config["pins"]["3"]["site_num"]) == ("PAD3", 2)
+    assert config["groups"]["32768"] == {"name": "DQ", "pins": [1, 2], "pin_names": ["DQ0", "DQ1"]}
+
+
+def _ptr(test_num, site, lo, hi, name="", units=""):
+    return record(15, 10, (
+        struct.pack("<IBBBBf", test_num, 1, site, 0, 0, 0.5) + cn(name) + cn("")
+        + struct.pack("<Bbbbff", 0, 0, 0, 0, lo, hi) + cn(units)
+    ))
+
+
+def test_limit_table_with_site_variants(tmp_path):
+    path = tmp_path / "limits.stdf"
+    path.write_bytes(
+        record(0, 10, struct.pack("BB", 2, 4))
+        + _ptr(100, 1, 0.0, 1.0, "VDD_LEAK", "A") + _ptr(100, 2, 0.0, 1.0)
+        + _ptr(100, 3, 0.0, 1.5)                      # site 3 has a wider limit
+        + _ptr(200, 1, -1.0, 1.0, "IDD", "mA")
+    )
+    data = parse_stdf(path)
+
+    out = tmp_path / "limits.json"
+    assert export_limit_table(data, out) == 2
+    table = json.loads(out.read_text())
+    assert [(t["test_num"], t["test_name"], t["lo_limit"], t["hi_limit"], t["units"])
+            for t in table] == [(100, "VDD_LEAK", 0.0, 1.0, "A"), (200, "IDD", -1.0, 1.0, "mA")]
+    assert table[0]["site_limits"] == [
+        {"head_num": 1, "site_num": 3, "lo_limit": 0.0, "hi_limit": 1.5},
+    ]
+    assert table[1]["site_limits"] == []
+    assert (table[0]["lo_spec"], table[0]["hi_spec"]) == (None, None)  # no spec limits in file
+
+    out = tmp_path / "limits.csv"
+    export_limit_table(data, out)
+    rows = list(csv.DictReader(out.open()))
+    assert [(r["test_num"], r["site_num"], r["hi_limit"]) for r in rows] == [
+        ("100", "", "1.0"), ("100", "3", "1.5"), ("200", "", "1.0"),
+    ]
@@ -0,0 +1,136 @@
+"""Tests for merge.py (one logical lot from per-site / retest files)."""
+
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import cn, make_stdf, record  # noqa: E402
+
+from stdf_platform.merge import merge, merge_stdf  # noqa: E402
+from stdf_platform.parser import parse_stdf  # noqa: E402
+
+
+def _site_file(path: Path, site: int, fails: set[int], start_t: int, parts: int = 4,
+               id_prefix: str = "", pmr: bool = False):
+    """FT file of lot LOTM from one site controller: PART_IDs <id_prefix>1..parts,
+    summary HBR/SBR/PCR/TSR for its site and the head-255 totals; with `pmr`, a
+    PMR index 1 on its site."""
+    mir = (
+        struct.pack("<IIB", start_t, start_t, 1) + b"    \x00 "
+        + cn("LOTM") + cn("DEV") + cn("N1") + cn("93K") + cn("FTJOB") + cn("R1")
+        + cn("") + cn("op") + cn("") + cn("") + cn("FT1")
+    )
+    buf = record(0, 10, struct.pack("BB", 2, 4)) + record(1, 10, mir)
+    if pmr:
+        buf += record(1, 60, struct.pack("<HH", 1, 0) + cn("CH1") + cn("P1") + cn("VDD")
+                      + struct.pack("BB", 1, site))
+    good = 0
+    for i in range(parts):
+        passed = i not in fails
+        good += passed
+        buf += record(5, 10, struct.pack("BB", 1, site))
+        buf += record(15, 10, struct.pack("<IBBBBf", 100, 1, site, 0 if passed else 0x80, 0,
+                                          1.0 + site + i) + cn("Idd") + cn("") + b"\x00\x00\x00\x00"
+                      + struct.pack("<ff", 0.0, 10.0) + cn("mA"))
+        buf += record(5, 20, struct.pack("<BBBHHHhhI", 1, site, 0 if passed else 0x08, 1,
+                                         1 if passed else 2, 1 if passed else 20,
+                                         -32768, -32768, 0) + cn(f"{id_prefix}{i + 1}"))
+    for head, s in ((1, site), (255, 255)):
+        for rec_sub, bins in ((40, ((1, good, "P", "PASS"), (2, parts - good, "F", "FAIL"))),
+                              (50, ((1, good, "P", "GOOD"), (20, parts - good, "F", "IDD")))):
+            for num, cnt, pf, name in bins:
+                buf += record(1, rec_sub, struct.pack("<BBHI", head, s, num, cnt) + pf.encode() + cn(name))
+        buf += record(1, 30, struct.pack("<BBIIIII", head, s, parts, 0, 0, good, 0))
+        buf += record(10, 30, struct.pack("<BBBIIII", head, s, ord("P"), 100, parts, parts - good, 0)
+                      + cn("Idd") + cn("") + cn("") + b"\xc8"
+                      + struct.pack("<fffff", 0.01, 1.0 + site, site + parts, 10.0, 30.0))
+    buf += record(1, 20, struct.pack("<I", start_t + 600))
+    path.write_bytes(buf)
+
+
+def _site_files(tmp_path) -> list[Path]:
+    a, b = tmp_path / "LOTM_s2.stdf", tmp_path / "LOTM_s1.stdf"
+    _site_file(a, site=2, fails={0, 3}, start_t=1700000100)
+    _site_file(b, site=1, fails={1}, start_t=1700000000)
+    return [a, b]
+
+
+def test_merge_per_site_files(tmp_path):
+    paths = _site_files(tmp_path)
+
+    data = merge(paths)
+
+    assert data.lot_id == "LOTM" and data.start_time == 1700000000
+    assert data.finish_time == 1700000700
+    # test order: site 1's file (earlier start) first
+    assert [p["site_num"] for p in data.parts] == [1] * 4 + [2] * 4
+    ids = [p["part_id"] for p in data.parts]
+    assert len(set(ids)) == 8
+    assert ids[:4] == [f"LOTM__{n}" for n in range(1, 5)]
+    assert ids[4:] == [f"LOTM__{n}" for n in range(5, 9)]
+    results = {r["part_id"]: r["result"] for r in data.test_results}
+    assert results[ids[4]] == pytest.approx(3.0) and len(results) == 8
+    assert data.bins_hard[1]["bin_count"] == 5 and data.bins_hard[2]["bin_count"] == 3
+    assert data.bins_soft[20]["bin_name"] == "IDD" and data.bins_soft[20]["bin_count"] == 3
+    assert data.site_bins_hard[(1, 2, 2)]["bin_count"] == 2
+    assert data.part_counts[(255, 255)]["part_count"] == 8
+    assert data.part_counts[(255, 255)]["good_count"] == 5
+    [summary] = [s for s in data.test_synopses if s["head_num"] == 255]
+    assert (summary["exec_count"], summary["fail_count"]) == (8, 3)
+    assert (summary["test_min"], summary["test_max"]) == (pytest.approx(2.0), pytest.approx(6.0))
+    assert summary["test_sum"] == pytest.approx(20.0)
+    assert data.is_complete
+
+
+def test_merge_stdf_writes_one_file(tmp_path):
+    paths = _site_files(tmp_path)
+    out = tmp_path / "LOTM.stdf"
+    with open(out, "wb") as f:
+        merge_stdf(paths, f)
+
+    data = parse_stdf(out)
+    expected = merge(paths)
+
+    assert data.is_complete and data.warnings == []
+    assert len(data.parts) == 8
+    assert sorted(p["tester_part_id"] for p in data.parts) == [str(n) for n in range(1, 9)]
+    assert data.bins_hard == expected.bins_hard and data.bins_soft == expected.bins_soft
+    assert data.site_bins_soft == expected.site_bins_soft
+    assert data.part_counts == expected.part_counts
+    assert data.test_synopses == expected.test_synopses
+    assert data.finish_time == expected.finish_time
+    assert [r["result"] for r in data.test_results] == [r["result"] for r in expected.test_results]
+
+
+def test_merge_stdf_part_ids_and_pins_match_merge(tmp_path):
+    a, b = tmp_path / "LOTM_s2.stdf", tmp_path / "LOTM_s1.stdf"
+    _site_file(a, site=2, fails=set(), start_t=1700000100, id_prefix="D", pmr=True)
+    _site_file(b, site=1, fails=set(), start_t=1700000000, id_prefix="D", pmr=True)
+    out = tmp_path / "LOTM.stdf"
+    with open(out, "wb") as f:
+        merge_stdf([a, b], f)
+
+    data = parse_stdf(out)
+    expected = merge([a, b])
+
+    # site 2's D1..D4 are taken by site 1's file: both fall back to the sequential ids
+    assert [p["part_id"] for p in data.parts] == [p["part_id"] for p in expected.parts]
+    assert [p["part_id"] for p in data.parts][3:5] == ["LOTM__D4", "LOTM__5"]
+    raw, kinds = out.read_bytes(), []
+    while raw:
+        rec_len, typ, sub = struct.unpack("<HBB", raw[:4])
+        kinds.append((typ, sub))
+        raw = raw[4 + rec_len:]
+    assert kinds.count((1, 60)) == 1
+    assert data.pins[1]["site_num"] == 1
+
+
+def test_merge_rejects_other_lots(tmp_path):
+    a, b = tmp_path / "a.stdf", tmp_path / "b.stdf"
+    make_stdf(a, "LOT1", num_wafers=1, parts_per_wafer=2)
+    make_stdf(b, "LOT2", num_wafers=1, parts_per_wafer=2)
+    with pytest.raises(ValueError, match="different lots: LOT1, LOT2"):
+        merge([a, b])
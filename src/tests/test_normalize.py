@@ -0,0 +1,95 @@
+"""Tests for normalize.py (canonical record order)."""
+
+import io
+import struct
+import sys
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import cn, make_stdf, record  # noqa: E402
+
+from stdf_platform.normalize import normalize_stdf  # noqa: E402
+from stdf_platform.parser import REC_NAMES, parse_stdf  # noqa: E402
+from stdf_platform.records import iter_records  # noqa: E402
+
+
+def _names(data: bytes) -> list[str]:
+    return [REC_NAMES[typ, sub] for typ, sub, _, _ in iter_records(io.BytesIO(data), "<")]
+
+
+def _normalize(path: Path):
+    out = path.with_name("norm_" + path.name)
+    with open(out, "wb") as f:
+        report = normalize_stdf(path, f)
+    return out, report
+
+
+def test_normalize_scrambled_multisite_file(tmp_path):
+    mir = (
+        struct.pack("<IIB", 1700000000, 1700000000, 1) + b"    \x00 "
+        + cn("LOTN") + cn("DEV") + cn("N1") + cn("93K") + cn("JOB") + cn("R1")
+    )
+
+    def ptr(site, value):
+        return record(15, 10, struct.pack("<IBBBBf", 7, 1, site, 0, 0, value))
+
+    def prr(site):
+        return record(5, 20, struct.pack("<BBBHHHhh", 1, site, 0, 1, 1, 1, site, 0))
+
+    path = tmp_path / "scrambled.stdf"
+    path.write_bytes(
+        record(0, 10, struct.pack("BB", 2, 4))
+        + record(1, 80, struct.pack("<BBBBB", 1, 1, 2, 1, 2))  # SDR ahead of the MIR
+        + record(1, 10, mir)
+        + record(2, 10, struct.pack("<BBI", 1, 0, 1700000000) + cn("W01"))
+        + record(5, 10, struct.pack("BB", 1, 1)) + record(5, 10, struct.pack("BB", 1, 2))
+        + ptr(1, 1.5) + ptr(2, 2.5)
+        + record(50, 30, cn("site 2 note"))
+        + prr(2) + prr(1)
+        + record(10, 30, struct.pack("<BBBIIII", 1, 1, ord("P"), 7, 1, 0, 0) + cn("T7"))
+        + record(2, 20, struct.pack("<BBIIIII", 1, 0, 1700000500, 2, 0, 0, 2))
+        + record(1, 40, struct.pack("<BBHI", 255, 255, 1, 2) + b"P" + cn("GOOD"))
+        + record(1, 20, struct.pack("<I", 1700000600))
+        + record(1, 30, struct.pack("<BBIIIII", 255, 255, 2, 0, 0, 2, 0))  # PCR after the MRR
+    )
+    before = parse_stdf(path)
+
+    out, report = _normalize(path)
+
+    assert _names(out.read_bytes()) == [
+        "FAR", "MIR", "SDR", "WIR",
+        "PIR", "PTR", "PRR",
+        "PIR", "PTR", "DTR", "PRR",
+        "WRR", "TSR", "HBR", "PCR", "MRR",
+    ]
+    assert report.records == 16 and report.moved == 5 and report.dropped == {}
+    assert before.stats.order_violations
+    data = parse_stdf(out)
+    assert data.warnings == [] and data.is_complete
+    assert [(p["site_num"], p["x_coord"]) for p in data.parts] == [(1, 1), (2, 2)]
+    assert [(r["site_num"], r["result"]) for r in data.test_results] == [(1, 1.5), (2, 2.5)]
+    assert data.datalog_text == before.datalog_text
+    assert data.part_counts == before.part_counts
+
+
+def test_normalize_moves_wafer_summaries_and_is_idempotent(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=3)
+    with open(path, "ab") as f:
+        f.write(record(1, 20, struct.pack("<I", 1700000999)))  # a second MRR
+        f.write(b"\x00\x01")  # trailing bytes
+
+    out, report = _normalize(path)
+
+    names = _names(out.read_bytes())
+    # per-wafer HBR/SBRs (written before each WRR) move behind the last wafer
+    last_wrr = len(names) - 1 - names[::-1].index("WRR")
+    assert names[last_wrr + 1:] == ["HBR"] * 4 + ["SBR"] * 6 + ["MRR"]
+    assert report.moved == 6  # wafer 1's summaries and the last WRR
+    assert report.dropped == {"MRR": 1, "cut off": 1}
+    full, data = parse_stdf(path), parse_stdf(out)
+    assert data.parts == full.parts and data.bins_hard == full.bins_hard
+    assert data.finish_time == 1700000999
+
+    again, report = _normalize(out)
+    assert report.moved == 0 and again.read_bytes() == out.read_bytes()
@@ -0,0 +1,79 @@
+"""Tests for the STDFData cache (save_cache / load_cache / parse_cached)."""
+
+import sys
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import make_stdf  # noqa: E402
+
+from stdf_platform import parser  # noqa: E402
+from stdf_platform.parser import (  # noqa: E402
+    ParseOptions, STDFData, cache_path_for, parse_cached, parse_stdf,
+)
+
+
+def test_save_and_load_cache(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=4)
+    data = parse_stdf(path)
+    cache = tmp_path / "lot.json"
+
+    data.save_cache(cache, path)
+    loaded = STDFData.load_cache(cache, path)
+
+    assert loaded == data
+    assert loaded.site_limits == data.site_limits and loaded.stats == data.stats
+    assert STDFData.load_cache(cache) == data  # no source: no staleness check
+    assert STDFData.load_cache(cache, path, ParseOptions(collect_results=False)) is None
+    assert STDFData.load_cache(tmp_path / "missing.json") is None
+    junk = tmp_path / "junk.json"
+    for garbage in (b"not a cache", parser._CACHE_MAGIC + b"I1a\n.", parser._CACHE_MAGIC + b"[]\n"):
+        junk.write_bytes(garbage)
+        assert STDFData.load_cache(junk) is None
+
+
+def test_cache_is_plain_data(tmp_path):
+    # tuples, bytes, tuple keys and nested dataclasses round-trip without pickle
+    data = STDFData(lot_id="LOT1")
+    data.unknown_records.append((200, 1, 64, b"\x00\xff"))
+    data.site_limits[(1000, 1, 2)] = {"lo_limit": 0.0, "hi_limit": float("inf")}
+    data.generic_data.append(
+        {"wafer_id": "", "part_id": "", "fields": [("U1", 7), ("Bn", b"\x01")]},
+    )
+    data.diagnostics.append(parser.Diagnostic("truncated", "cut off", "PTR", 128))
+    cache = tmp_path / "data.json"
+
+    data.save_cache(cache)
+
+    assert STDFData.load_cache(cache) == data
+    text = cache.read_bytes()[len(parser._CACHE_MAGIC):]
+    assert b"!tuple" in text and b"!class" in text  # JSON all the way
+    cache.write_bytes(parser._CACHE_MAGIC + text.replace(b'"Diagnostic"', b'"os.system"'))
+    assert STDFData.load_cache(cache) is None
+
+
+def test_parse_cached_reuses_until_file_changes(tmp_path, monkeypatch):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+    calls = []
+    real_parse = parser.parse_stdf
+    monkeypatch.setattr(parser, "parse_stdf", lambda p, o=None: calls.append(p) or real_parse(p, o))
+
+    cache_dir = tmp_path / "cache"
+
+    first = parse_cached(path, cache_dir)
+    assert cache_path_for(path, cache_dir).exists() and calls == [path]
+    assert cache_path_for(path, cache_dir).parent == cache_dir  # nothing beside the data
+    assert parse_cached(path, cache_dir) == first
+    assert calls == [path]
+
+    options = ParseOptions(tests={1002, 1001})
+    assert {r["test_num"] for r in parse_cached(path, cache_dir, options).test_results} == {
+        1001, 1002,
+    }
+    assert parse_cached(path, cache_dir, ParseOptions(tests={1001, 1002})).test_results
+    assert len(calls) == 2
+
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=3)
+    assert len(parse_cached(path, cache_dir).parts) == 6
+    assert len(calls) == 3
@@ -0,0 +1,146 @@
+"""Tests for parsing from streams (stdin, asyncio) and compressed input."""
+
+import asyncio
+import bz2
+import gzip
+import io
+import lzma
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import make_stdf, record  # noqa: E402
+
+from stdf_platform import parser  # noqa: E402
+from stdf_platform.parser import (  # noqa: E402
+    ParseOptions,
+    detect_compression,
+    parse_async,
+    parse_bytes,
+    parse_reader,
+    parse_stdf,
+)
+
+
+class _Pipe(io.RawIOBase):
+    """Non-seekable reader handing out a few bytes per read, like a pipe."""
+
+    def __init__(self, payload: bytes):
+        self._buf = io.BytesIO(payload)
+
+    def readable(self):
+        return True
+
+    def readinto(self, b):
+        chunk = self._buf.read(min(len(b), 4093))
+        b[:len(chunk)] = chunk
+        return len(chunk)
+
+
+@pytest.mark.parametrize("compress", [None, gzip.compress, bz2.compress, lzma.compress])
+def test_parse_reader_matches_file_parse(tmp_path, compress):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=5)
+    expected = parse_stdf(path)
+
+    raw = path.read_bytes()
+    payload = compress(raw) if compress else raw
+    data = parse_reader(_Pipe(payload))
+
+    assert data.lot_id == "LOT1"
+    assert data.parts == expected.parts
+    assert data.test_results == expected.test_results
+
+
+@pytest.mark.parametrize("compress", [None, gzip.compress])
+def test_parse_bytes_matches_file_parse(tmp_path, compress):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=4)
+    expected = parse_stdf(path)
+
+    raw = path.read_bytes()
+    data = parse_bytes(compress(raw) if compress else raw)
+
+    assert data.parts == expected.parts
+    assert data.test_results == expected.test_results
+    assert parse_bytes(memoryview(bytearray(raw))).parts == expected.parts
+
+
+@pytest.mark.parametrize("compress", [None, gzip.compress])
+def test_parse_async_matches_file_parse(tmp_path, compress):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=4)
+    expected = parse_stdf(path)
+    payload = compress(path.read_bytes()) if compress else path.read_bytes()
+
+    async def from_stream_reader():
+        reader = asyncio.StreamReader()
+        reader.feed_data(payload)
+        reader.feed_eof()
+        return await parse_async(reader, name="LOT1.stdf")
+
+    async def chunks():
+        for i in range(0, len(payload), 4093):
+            await asyncio.sleep(0)
+            yield payload[i:i + 4093]
+
+    for data in (asyncio.run(from_stream_reader()), asyncio.run(parse_async(chunks()))):
+        assert data.parts == expected.parts
+        assert data.test_results == expected.test_results
+
+
+@pytest.mark.parametrize("compress", [None, gzip.compress])
+def test_parse_async_yields_to_the_loop(tmp_path, monkeypatch, compress):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=4)
+    payload = compress(path.read_bytes()) if compress else path.read_bytes()
+    monkeypatch.setattr(parser, "_ASYNC_SLICE", 128)
+
+    async def chunks():
+        yield payload
+
+    async def main():
+        ticks = 0
+
+        async def ticker():
+            nonlocal ticks
+            while True:
+                ticks += 1
+                await asyncio.sleep(0)
+
+        task = asyncio.ensure_future(ticker())
+        data = await parse_async(chunks())
+        task.cancel()
+        return data, ticks
+
+    data, ticks = asyncio.run(main())
+
+    assert data.parts == parse_stdf(path).parts
+    assert ticks > 5  # other coroutines ran while the file was decoded
+
+
+def test_compressed_file_detected_by_content(tmp_path):
+    path = tmp_path / "LOTX.stdf"
+    make_stdf(path, "LOTX", num_wafers=1, parts_per_wafer=3)
+    gz = tmp_path / "LOTX.stdf.gz"
+    gz.write_bytes(gzip.compress(path.read_bytes()))
+
+    assert detect_compression(gz.read_bytes()[:6]) == "gzip"
+    assert detect_compression(path.read_bytes()[:6]) is None
+    assert parse_stdf(gz).parts == parse_stdf(path).parts
+
+
+def test_lot_id_from_compressed_file_name(tmp_path):
+    no_mir = (
+        record(0, 10, struct.pack("BB", 2, 4)) + record(5, 10, struct.pack("BB", 1, 1))
+        + record(5, 20, struct.pack("<BBBHHHhh", 1, 1, 0, 0, 1, 1, 0, 0))
+    )
+    gz = tmp_path / "LOTZ.stdf.gz"
+    gz.write_bytes(gzip.compress(no_mir))
+    options = ParseOptions(lot_id_from_filename=True)
+
+    assert parse_stdf(gz, options).lot_id == "LOTZ"
+    assert parse_reader(io.BytesIO(no_mir), options).lot_id == ""  # stdin: no name
@@ -0,0 +1,107 @@
+"""Tests for the STDFError hierarchy raised by the parser and record tools."""
+
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import make_stdf, record  # noqa: E402
+
+from stdf_platform.parser import (  # noqa: E402
+    ParseOptions,
+    STDFDecodeError,
+    STDFError,
+    STDFFormatError,
+    STDFRecordError,
+    STDFVersionError,
+    parse_bytes,
+)
+from stdf_platform.records import decode_fields, edit_mir  # noqa: E402
+
+
+FAR = record(0, 10, struct.pack("BB", 2, 4))
+PIR = record(5, 10, struct.pack("BB", 1, 1))
+
+
+def test_unsupported_version_rejected_in_strict_mode():
+    v3 = record(0, 10, struct.pack("BB", 2, 3)) + PIR
+
+    with pytest.raises(STDFVersionError, match="STDF_VER 3") as info:
+        parse_bytes(v3, ParseOptions(strict=True))
+    assert isinstance(info.value, STDFFormatError) and isinstance(info.value, ValueError)
+    assert (info.value.version, info.value.offset) == (3, 0)
+
+
+def test_unsupported_version_warned_in_lenient_mode(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=2)
+    raw = path.read_bytes()
+    v3 = raw[:5] + b"\x03" + raw[6:]  # FAR STDF_VER
+
+    data = parse_bytes(v3)
+
+    assert len(data.parts) == 2
+    assert [(d.kind, d.offset) for d in data.diagnostics if d.kind == "version"] == [("version", 0)]
+
+
+def test_decode_error_carries_offset():
+    mir = record(1, 10, struct.pack("<IIBcccHc", 0, 0, 1, b"P", b" ", b" ", 0, b" ")
+                 + b"\x03L\xb5T")
+
+    with pytest.raises(STDFError) as info:
+        parse_bytes(FAR + mir, ParseOptions(string_errors="strict"))
+    assert isinstance(info.value, STDFDecodeError)
+    assert info.value.offset == len(FAR)
+
+
+def test_record_tools_raise_stdf_errors(tmp_path):
+    with pytest.raises(STDFRecordError, match="WIR body ends inside a field") as info:
+        decode_fields("WIR", b"\x01\xff\x00")  # START_T cut off
+    assert info.value.rec == "WIR"
+
+    path = tmp_path / "no_mir.stdf"
+    path.write_bytes(FAR + PIR)
+    with pytest.raises(STDFFormatError, match="no MIR"):
+        edit_mir(path, {"LOT_ID": "X"})
+
+
+def test_strict_mode_rejects_what_lenient_mode_reports(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=2)
+    raw = path.read_bytes()
+    strict = ParseOptions(strict=True)
+    assert len(parse_bytes(raw, strict).parts) == 2
+
+    short_hbr = struct.pack("<HBB", 3, 1, 40) + b"\x01\x01\x05"  # needs 8 bytes
+    bad = raw[:6] + short_hbr + raw[6:]
+    assert parse_bytes(bad).stats.overrun_records == {"HBR": 1}
+    with pytest.raises(STDFRecordError, match="HBR at offset 0x6") as info:
+        parse_bytes(bad, strict)
+    assert (info.value.rec, info.value.offset) == ("HBR", 6)
+
+    hbr = struct.pack("<BBHIcB", 255, 255, 1, 2, b"P", 0) + b"xx"  # 2 bytes after HBIN_NAM
+    padded = raw[:6] + record(1, 40, hbr) + raw[6:]
+    assert parse_bytes(padded).stats.leftover_bytes == {"HBR": 2}
+    with pytest.raises(STDFRecordError, match="2 byte"):
+        parse_bytes(padded, strict)
+
+    cut = raw[:-3]  # MRR cut off
+    assert not parse_bytes(cut).is_complete
+    with pytest.raises(STDFRecordError, match="MRR"):
+        parse_bytes(cut, strict)
+
+
+def test_strict_mode_requires_mandatory_records(tmp_path):
+    pir = record(5, 10, struct.pack("BB", 1, 1))
+    prr = record(5, 20, struct.pack("<BBBHHHhh", 1, 1, 0, 0, 1, 1, 0, 0))
+    mrr = record(1, 20, struct.pack("<I", 0))
+
+    assert parse_bytes(FAR + pir + prr + mrr).parts  # lenient: warned
+    with pytest.raises(STDFFormatError, match="no MIR before first PIR"):
+        parse_bytes(FAR + pir + prr + mrr, ParseOptions(strict=True))
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=2)
+    with pytest.raises(STDFFormatError, match="no FAR"):
+        parse_bytes(path.read_bytes()[len(FAR):], ParseOptions(strict=True))
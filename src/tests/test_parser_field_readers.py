@@ -0,0 +1,129 @@
+"""Tests for the STDFParser field readers (_read_*) in both byte orders."""
+
+import io
+import struct
+
+import pytest
+
+from stdf_platform.parser import REC_HBR, REC_WCR, ParseOptions, STDFParser
+
+
+def reader(endian: str, data: bytes) -> tuple[STDFParser, io.BytesIO]:
+    parser = STDFParser()
+    parser._set_endian(endian)
+    return parser, io.BytesIO(data)
+
+
+@pytest.mark.parametrize("endian", ["<", ">"])
+def test_read_r8(endian):
+    parser, f = reader(endian, struct.pack(endian + "dd", 1.0e-12, -2.5))
+    assert parser._read_r8(f) == 1.0e-12
+    assert parser._read_r8(f) == -2.5
+    with pytest.raises(EOFError):
+        parser._read_r8(io.BytesIO(b"\x00" * 7))
+
+
+@pytest.mark.parametrize("endian", ["<", ">"])
+def test_read_i4_and_u8(endian):
+    parser, f = reader(endian, struct.pack(endian + "iiQ", -2, 2**31 - 1, 2**64 - 1))
+    assert parser._read_i4(f) == -2
+    assert parser._read_i4(f) == 2**31 - 1
+    assert parser._read_u8(f) == 2**64 - 1
+    with pytest.raises(EOFError):
+        parser._read_i4(io.BytesIO(b"\x00" * 3))
+    with pytest.raises(EOFError):
+        parser._read_u8(io.BytesIO(b"\x00" * 7))
+
+
+def test_byte_order_matters():
+    data = struct.pack(">i", -2)
+    assert reader(">", data)[0]._read_i4(io.BytesIO(data)) == -2
+    assert reader("<", data)[0]._read_i4(io.BytesIO(data)) == -16777217
+
+
+def test_read_bn():
+    parser, f = reader("<", b"\x03\x81\x00\xff" + b"\x00" + b"\x02\x01")
+    assert parser._read_bn(f) == b"\x81\x00\xff"
+    assert parser._read_bn(f) == b""  # zero length
+    with pytest.raises(EOFError):
+        parser._read_bn(f)  # declares 2 bytes, only 1 left
+
+
+@pytest.mark.parametrize("endian", ["<", ">"])
+def test_read_vn_every_type(endian):
+    def pack(code, fmt, value):
+        return bytes([code]) + struct.pack(endian + fmt, value)
+
+    data = (
+        pack(1, "B", 200) + pack(2, "H", 60000) + pack(3, "I", 4000000000)
+        + b"\x00"  # B*0 pad byte
+        + pack(4, "b", -1) + pack(5, "h", -300) + pack(6, "i", -70000)
+        + pack(7, "f", 0.5) + pack(8, "d", 1.0e-9)
+        + b"\x0a\x03abc" + b"\x0b\x02\x01\x02"
+        + b"\x0c" + struct.pack(endian + "H", 9) + b"\xff\x01"
+        + b"\x0d\xf7"
+    )
+    parser, f = reader(endian, data)
+    assert parser._read_vn(f, len(data)) == [
+        ("U1", 200), ("U2", 60000), ("U4", 4000000000),
+        ("I1", -1), ("I2", -300), ("I4", -70000),
+        ("R4", 0.5), ("R8", 1.0e-9),
+        ("Cn", "abc"), ("Bn", b"\x01\x02"), ("Dn", b"\xff\x01"), ("N1", 7),
+    ]
+    assert f.tell() == len(data)
+
+
+def test_read_vn_stops_at_unknown_type():
+    parser, f = reader("<", b"\x01\x05\x0e\x01\x02")
+    assert parser._read_vn(f, 5) == [("U1", 5)]
+    assert f.tell() == 3
+    assert "unknown V*n type code 14" in parser.data.warnings[0]
+
+
+def test_read_nibbles():
+    parser, f = reader("<", b"\x21\x43\x05")
+    assert parser._read_nibbles(f, 5) == [1, 2, 3, 4, 5]  # low nibble first, odd count
+    assert parser._read_nibbles(f, 0) == []
+    with pytest.raises(EOFError):
+        parser._read_nibbles(io.BytesIO(b"\x21"), 3)
+
+
+def test_read_cf():
+    parser, f = reader("<", b"AB  \x00CD \x00")
+    assert parser._read_cf(f, 4) == "AB"
+    assert parser._read_cf(f, 4) == "CD"  # NUL padding dropped too
+    assert parser._read_cf(f, 0) == ""
+    with pytest.raises(EOFError):
+        parser._read_cf(f, 2)
+
+    untrimmed = STDFParser(ParseOptions(trim_cf=False))
+    assert untrimmed._read_cf(io.BytesIO(b" X  "), 4) == " X  "
+
+
+@pytest.mark.parametrize("endian", ["<", ">"])
+def test_decode_fields_defaults_truncated_optionals(endian):
+    # WCR cut after WF_UNITS: the remaining optional fields take their defaults
+    data = struct.pack(endian + "fffB", 8.0, 0.5, 0.25, 3)
+    parser, f = reader(endian, data)
+    assert parser._decode_fields(f, len(data), REC_WCR) == {
+        "wafr_siz": 8.0, "die_ht": 0.5, "die_wid": 0.25, "wf_units": 3,
+        "wf_flat": " ", "center_x": -32768, "center_y": -32768, "pos_x": " ", "pos_y": " ",
+    }
+
+    # required fields are read even past the declared length
+    parser, f = reader(endian, struct.pack(endian + "BBH", 1, 0, 7))
+    with pytest.raises(EOFError):
+        parser._decode_fields(f, 4, REC_HBR)
+
+
+def test_read_cn_shares_decoded_strings():
+    parser, f = reader("<", b"\x03Vdd\x03Vdd\x02\xb5A\x02\xb5A")
+    first = parser._read_cn(f)
+    assert first == "Vdd" and parser._read_cn(f) is first
+    # the view of a record body looks up the same entry without a copy
+    assert parser._decode_cn(memoryview(b"\x03Vdd")[1:], 0) is first
+    # non-ASCII strings are not cached: each one still counts as lossy
+    assert parser._read_cn(f) == parser._read_cn(f) == "�A"
+    assert parser.data.stats.lossy_strings == {"?": 2}
+    with pytest.raises(EOFError):
+        parser._read_cn(io.BytesIO(b"\x05ab"))
@@ -0,0 +1,534 @@
+"""Tests for part-level (PIR/PRR) handling in the parser."""
+
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import cn, make_stdf, record  # noqa: E402
+
+from stdf_platform.parser import (  # noqa: E402
+    ParseOptions, STDFParser, consistency_report, parse_stdf,
+)
+from stdf_platform.testgen import GenOptions, default_tests, generate_stdf  # noqa: E402
+
+
+FAR = record(0, 10, struct.pack("BB", 2, 4))
+
+
+def pir(site=1):
+    return record(5, 10, struct.pack("BB", 1, site))
+
+
+def prr(part_flg, x, site=1, num_test=1, hard_bin=1):
+    return record(5, 20, struct.pack("<BBBHHHhh", 1, site, part_flg, num_test, hard_bin, hard_bin, x, 0))
+
+
+def ptr(test_num, passed, site=1):
+    return record(15, 10, (
+        struct.pack("<IBBBB", test_num, 1, site, 0x00 if passed else 0x80, 0)
+        + struct.pack("<f", 1.0) + cn("T")
+    ))
+
+
+def test_pass_fail_cross_validation(tmp_path):
+    path = tmp_path / "xval.stdf"
+    path.write_bytes(
+        FAR
+        + pir() + ptr(1, True) + prr(0x00, 0)                     # consistent pass
+        + pir() + ptr(1, False) + prr(0x08, 1, hard_bin=2)        # consistent fail
+        + pir() + ptr(1, True) + prr(0x08, 2, hard_bin=7)         # bin override
+        + pir() + ptr(1, False) + ptr(2, False) + prr(0x00, 3, num_test=2)  # fail logged, PRR pass
+    )
+
+    data = parse_stdf(path)
+
+    assert [p["fail_count"] for p in data.parts] == [0, 1, 0, 2]
+    report = consistency_report(data)
+    assert [(r["x_coord"], r["issue"]) for r in report] == [
+        (2, "pass_fail_mismatch"), (3, "pass_fail_mismatch"),
+    ]
+    assert report[0]["detail"] == "PRR fail but no failing test records"
+    assert report[1]["detail"] == "PRR pass but 2 failing test record(s)"
+
+
+def test_fail_counts_are_tracked_per_site(tmp_path):
+    # Two sites interleaved: site 2's failure must not leak into site 1's part
+    path = tmp_path / "multisite.stdf"
+    path.write_bytes(
+        FAR + pir(1) + pir(2)
+        + ptr(1, True, site=1) + ptr(1, False, site=2)
+        + prr(0x00, 0, site=1) + prr(0x08, 1, site=2)
+    )
+
+    data = parse_stdf(path)
+
+    assert [p["fail_count"] for p in data.parts] == [0, 1]
+    assert consistency_report(data) == []
+
+
+def test_num_test_versus_collected_records(tmp_path):
+    path = tmp_path / "numtest.stdf"
+    path.write_bytes(
+        FAR
+        + pir() + ptr(1, True) + ptr(2, True) + prr(0x00, 0, num_test=2)
+        + pir() + ptr(1, True) + prr(0x00, 1, num_test=5)     # 4 results never arrived
+    )
+
+    data = parse_stdf(path)
+
+    assert [p["result_count"] for p in data.parts] == [2, 1]
+    assert [p["num_test_delta"] for p in data.parts] == [0, 4]
+    report = consistency_report(data)
+    assert [(r["x_coord"], r["issue"]) for r in report] == [(1, "num_test_mismatch")]
+    assert report[0]["detail"] == "NUM_TEST 5 but 1 test record(s) seen"
+
+
+def test_keep_first_n_failures_per_part(tmp_path):
+    path = tmp_path / "reduce.stdf"
+    path.write_bytes(
+        FAR
+        + pir() + ptr(1, True) + ptr(2, False) + ptr(3, False) + ptr(4, False) + prr(0x08, 0)
+        + pir() + ptr(1, True) + prr(0x00, 1)
+    )
+
+    data = parse_stdf(path, ParseOptions(max_fails_per_part=2))
+
+    # passing results dropped; only the first two failures of part 0 kept
+    assert [(r["part_id"][-1], r["test_num"]) for r in data.test_results] == [("1", 2), ("1", 3)]
+    # summary survives the reduction
+    assert [p["fail_count"] for p in data.parts] == [3, 0]
+    assert set(data.tests) == {1, 2, 3, 4}
+
+
+def test_part_flg_decoded_flags(tmp_path):
+    path = tmp_path / "flags.stdf"
+    path.write_bytes(
+        FAR
+        + pir() + prr(0x00, 0)
+        + pir() + prr(0x01, 1)          # retest (same PART_ID)
+        + pir() + prr(0x02, 2)          # retest (same XY)
+        + pir() + prr(0x0C, 3)          # aborted + failed
+        + pir() + ptr(1, False) + prr(0x10, 4)   # no pass/fail indication
+    )
+
+    parts = parse_stdf(path).parts
+
+    assert [p["retested"] for p in parts] == [False, True, True, False, False]
+    assert [p["aborted"] for p in parts] == [False, False, False, True, False]
+    assert [p["pass_fail_valid"] for p in parts] == [True, True, True, True, False]
+    assert [p["passed"] for p in parts] == [True, True, True, False, True]
+
+
+def test_no_pass_fail_indication_not_reported(tmp_path):
+    path = tmp_path / "noind.stdf"
+    path.write_bytes(FAR + pir() + ptr(1, False) + prr(0x10, 0))
+    assert consistency_report(parse_stdf(path)) == []
+
+
+def test_collect_results_false_keeps_counts(tmp_path):
+    path = tmp_path / "nores.stdf"
+    path.write_bytes(FAR + pir() + ptr(1, True) + ptr(2, False) + prr(0x08, 0, num_test=2))
+
+    data = parse_stdf(path, ParseOptions(collect_results=False))
+
+    assert data.test_results == []
+    assert sorted(data.tests) == [1, 2]
+    assert (data.parts[0]["result_count"], data.parts[0]["fail_count"]) == (2, 1)
+
+
+def test_collection_options(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=3)
+    full = parse_stdf(path)
+
+    data = parse_stdf(path, ParseOptions(collect_parts=False, collect_results=False))
+    assert data.parts == [] and data.test_results == []
+    assert data.lot_id == "LOT1" and [w["part_count"] for w in data.wafers] == [3, 3]
+    assert data.bins_hard == full.bins_hard
+
+    data = parse_stdf(path, ParseOptions(max_results=7))
+    assert len(data.test_results) == 7 and len(data.parts) == 6
+    assert data.parts[-1]["result_count"] == 5
+    assert any("max_results (7) reached" in w for w in data.warnings)
+
+    data = parse_stdf(path, ParseOptions(records={"MIR", "WRR", "MRR"}))
+    assert data.lot_id == "LOT1" and data.parts == [] and data.tests == {}
+    assert data.stats.record_count == full.stats.record_count
+
+
+def test_records_option_rejects_unknown_names():
+    with pytest.raises(ValueError, match="XYZ"):
+        STDFParser(ParseOptions(records={"MIR", "XYZ"}))
+
+
+def test_missing_mir_lot_id_fallbacks(tmp_path):
+    path = tmp_path / "LOTX42.stdf"
+    path.write_bytes(FAR + pir() + ptr(1, True) + prr(0x00, 0))
+
+    data = parse_stdf(path)
+    assert data.lot_id == "" and data.parts[0]["part_id"] == "__1"
+    assert data.warnings[0] == "no MIR before first PIR: lot metadata left empty"
+
+    data = parse_stdf(path, ParseOptions(lot_id_from_filename=True))
+    assert data.parts[0]["part_id"] == "LOTX42__1"
+    assert data.test_results[0]["lot_id"] == "LOTX42"
+
+    data = parse_stdf(path, ParseOptions(fallback_lot_id="L1", lot_id_from_filename=True))
+    assert data.lot_id == "L1"
+    assert data.warnings[0] == "no MIR before first PIR: lot_id set to 'L1'"
+
+
+MRR = record(1, 20, struct.pack("<I", 0))
+WIR = record(2, 10, struct.pack("<BBI", 1, 0, 0) + cn("W01"))
+WRR = record(2, 20, struct.pack("<BBIIIIII", 1, 0, 0, 1, 0, 0, 1, 0))
+
+
+def test_is_complete(tmp_path):
+    path = tmp_path / "done.stdf"
+    path.write_bytes(FAR + WIR + pir() + ptr(1, True) + prr(0x00, 0) + WRR + MRR)
+    data = parse_stdf(path)
+    assert data.is_complete
+    assert not [w for w in data.warnings if w.startswith("incomplete")]
+
+    path.write_bytes(FAR + WIR + pir() + ptr(1, True) + prr(0x00, 0) + pir(2) + ptr(1, True))
+    data = parse_stdf(path)
+    assert not data.is_complete
+    assert [w for w in data.warnings if w.startswith("incomplete")] == [
+        "incomplete file: no MRR",
+        "incomplete file: 1 part(s) with PIR but no PRR",
+        "incomplete file: wafer 'W01' with WIR but no WRR",
+    ]
+
+
+def test_record_order_violations(tmp_path):
+    path = tmp_path / "order.stdf"
+    path.write_bytes(
+        FAR
+        + ptr(1, True)             # before any PIR
+        + pir() + prr(0x00, 0)
+        + prr(0x00, 1)             # PRR without PIR
+        + WRR                      # WRR without WIR
+        + MRR + pir()              # after MRR
+    )
+
+    data = parse_stdf(path)
+
+    assert data.stats.order_violations == {"PTR": 1, "PRR": 1, "WRR": 1, "PIR": 1}
+    assert [w for w in data.warnings if " at offset " in w] == [
+        "PTR at offset 0x6: outside PIR/PRR (head 1, site 1)",
+        "PRR at offset 0x2F: PRR without PIR (head 1, site 1)",
+        "WRR at offset 0x40: WRR without WIR",
+        "PIR at offset 0x66: record after MRR",
+    ]
+
+
+def test_unterminated_wafers_and_parts_are_closed(tmp_path):
+    wir2 = record(2, 10, struct.pack("<BBI", 1, 0, 0) + cn("W02"))
+    path = tmp_path / "open.stdf"
+    path.write_bytes(
+        FAR
+        + WIR + pir() + ptr(1, True) + prr(0x00, 0)
+        + pir() + ptr(1, False)                          # no PRR, then no WRR
+        + wir2 + pir() + ptr(1, True) + prr(0x00, 0)
+        + pir() + ptr(1, True) + ptr(2, True)            # no PRR at EOF
+    )
+
+    data = parse_stdf(path)
+
+    assert not data.is_complete
+    assert [(p["wafer_id"], p["passed"], p["aborted"], p["pass_fail_valid"]) for p in data.parts] == [
+        ("W01", True, False, True),
+        ("W01", False, True, False),
+        ("W02", True, False, True),
+        ("W02", False, True, False),
+    ]
+    assert data.parts[3]["result_count"] == data.parts[3]["test_count"] == 2
+    assert [(w["part_count"], w["good_count"], w["abrt_count"]) for w in data.wafers] == [
+        (2, 1, 1), (2, 1, 1),
+    ]
+    closing = [w for w in data.warnings if "closed" in w]
+    assert closing[0].startswith("WIR at offset 0x")
+    assert closing[-1] == "end of file: wafer 'W02' has no WRR — closed with counts from its parts"
+
+
+def test_wafer_bins_scoped_per_wafer(tmp_path):
+    wir2 = record(2, 10, struct.pack("<BBI", 1, 0, 0) + cn("W02"))
+    hbr = record(1, 40, struct.pack("<BBHIB", 255, 255, 2, 9, ord("F")) + cn("FAIL"))
+    path = tmp_path / "wbins.stdf"
+    path.write_bytes(
+        FAR
+        + WIR + pir() + prr(0x00, 0) + pir() + prr(0x08, 1, hard_bin=2) + WRR
+        + wir2 + pir() + prr(0x08, 0, hard_bin=2) + pir() + prr(0x08, 1, hard_bin=2) + WRR
+        + hbr + MRR
+    )
+
+    data = parse_stdf(path)
+
+    hard = [(b["wafer_id"], b["bin_num"], b["bin_name"], b["part_count"])
+            for b in data.wafer_bins if b["bin_type"] == "H"]
+    assert hard == [("W01", 1, "", 1), ("W01", 2, "FAIL", 1), ("W02", 2, "FAIL", 2)]
+    assert sum(b["part_count"] for b in data.wafer_bins if b["bin_type"] == "S") == 4
+
+
+def _mir(test_cod="CP1", tst_temp="", flow_id=""):
+    fields = ["LOT1"] + [""] * 9 + [test_cod, tst_temp] + [""] * 11 + [flow_id]
+    return record(1, 10, struct.pack("<IIBBBBHB", 0, 0, 1, 32, 32, 32, 0, 32)
+                  + b"".join(cn(v) for v in fields))
+
+
+def test_test_conditions_from_mir_dtr_gdr(tmp_path):
+    dtr = record(50, 30, cn("COND: TEMP=125C, CORNER=HV"))
+    gdr = record(50, 10, struct.pack("<H", 2) + b"\x0a" + cn("FLOW") + b"\x0a" + cn("RT"))
+    path = tmp_path / "cond.stdf"
+
+    path.write_bytes(FAR + _mir(tst_temp="-40 C", flow_id="P") + dtr + gdr)
+    c = parse_stdf(path).conditions
+    assert (c.temperature_raw, c.temperature_c, c.flow_id, c.test_code, c.voltage_corner) == (
+        "-40 C", -40.0, "P", "CP1", "HV",
+    )
+
+    path.write_bytes(FAR + _mir() + dtr + gdr)
+    c = parse_stdf(path).conditions
+    assert (c.temperature_raw, c.temperature_c, c.flow_id) == ("125C", 125.0, "RT")
+
+    path.write_bytes(FAR + _mir(tst_temp="HOT"))
+    c = parse_stdf(path).conditions
+    assert (c.temperature_raw, c.temperature_c) == ("HOT", None)
+
+
+def test_pcr_part_counts(tmp_path):
+    pcr1 = record(1, 30, struct.pack("<BBIIIII", 1, 1, 12, 2, 0, 9, 4294967295))
+    pcr_all = record(1, 30, struct.pack("<BBI", 255, 0, 20))  # trailing counts omitted
+    path = tmp_path / "pcr.stdf"
+    path.write_bytes(FAR + pir() + prr(0x00, 0) + pcr1 + pcr_all + MRR)
+
+    counts = parse_stdf(path).part_counts
+
+    assert counts[(1, 1)] == {
+        "head_num": 1, "site_num": 1, "part_count": 12, "rtst_count": 2,
+        "abrt_count": 0, "good_count": 9, "func_count": None,
+    }
+    assert counts[(255, 0)]["part_count"] == 20
+    assert counts[(255, 0)]["good_count"] is None
+
+
+def test_sdr_site_descriptions(tmp_path):
+    sdr = record(1, 80, (
+        struct.pack("<BBB4B", 1, 3, 4, 1, 2, 3, 4)
+        + cn("HT-9000") + cn("H42") + cn("PC-A") + cn("CARD-0017") + cn("") + cn("LB-5")
+    ))  # DIB..EXTR omitted
+    path = tmp_path / "sdr.stdf"
+    path.write_bytes(FAR + sdr + MRR)
+
+    (site,) = parse_stdf(path).site_descriptions
+
+    assert (site["head_num"], site["site_grp"], site["site_nums"]) == (1, 3, [1, 2, 3, 4])
+    assert (site["hand_typ"], site["hand_id"], site["card_typ"], site["card_id"]) == (
+        "HT-9000", "H42", "PC-A", "CARD-0017",
+    )
+    assert (site["load_id"], site["dib_id"], site["extr_id"]) == ("LB-5", "", "")
+
+
+def test_dtr_lines_keep_wafer_and_part_context(tmp_path):
+    path = tmp_path / "dtr.stdf"
+    path.write_bytes(
+        FAR + record(50, 30, cn("program start"))
+        + WIR + pir() + record(50, 30, cn("VDD trim=3")) + prr(0x00, 0) + WRR
+    )
+
+    data = parse_stdf(path)
+
+    assert data.datalog_text == [
+        {"wafer_id": "", "part_id": "", "text": "program start"},
+        {"wafer_id": "W01", "part_id": data.parts[0]["part_id"], "text": "VDD trim=3"},
+    ]
+
+
+def test_atr_audit_trail(tmp_path):
+    path = tmp_path / "atr.stdf"
+    path.write_bytes(
+        FAR
+        + record(0, 20, struct.pack("<I", 1700000000) + cn("stdfmerge -o out.stdf a.stdf b.stdf"))
+        + record(0, 20, struct.pack("<I", 1700000100) + cn("stdfscrub out.stdf"))
+        + _mir()
+    )
+
+    assert parse_stdf(path).audit_trail == [
+        {"mod_time": 1700000000, "cmd_line": "stdfmerge -o out.stdf a.stdf b.stdf"},
+        {"mod_time": 1700000100, "cmd_line": "stdfscrub out.stdf"},
+    ]
+
+
+def test_rdr_retest_bins_flag_parts(tmp_path):
+    path = tmp_path / "rdr.stdf"
+    body = pir() + prr(0x08, 0, hard_bin=3) + pir() + prr(0x08, 1, hard_bin=5)
+
+    path.write_bytes(FAR + body)
+    data = parse_stdf(path)
+    assert data.retest_bins is None
+    assert [p["in_retest_bin"] for p in data.parts] == [False, False]
+
+    path.write_bytes(FAR + record(1, 70, struct.pack("<HHH", 2, 3, 4)) + body)
+    data = parse_stdf(path)
+    assert data.retest_bins == [3, 4]
+    assert [p["in_retest_bin"] for p in data.parts] == [True, False]
+
+    path.write_bytes(FAR + record(1, 70, struct.pack("<H", 0)) + body)  # all bins
+    data = parse_stdf(path)
+    assert data.retest_bins == []
+    assert [p["in_retest_bin"] for p in data.parts] == [True, True]
+
+
+def test_mrr_disposition(tmp_path):
+    path = tmp_path / "mrr.stdf"
+    path.write_bytes(FAR + MRR)
+    data = parse_stdf(path)
+    assert (data.disp_code, data.user_desc, data.exc_desc) == ("", "", "")
+
+    mrr = record(1, 20, struct.pack("<IB", 1700000000, ord("H")) + cn("yield low") + cn("probe card"))
+    path.write_bytes(FAR + mrr)
+    data = parse_stdf(path)
+    assert (data.finish_time, data.disp_code) == (1700000000, "H")
+    assert (data.user_desc, data.exc_desc) == ("yield low", "probe card")
+
+
+def test_prr_tester_part_id_preferred(tmp_path):
+    def prr_ids(part_id, part_txt="", part_fix=b""):
+        return record(5, 20, (
+            struct.pack("<BBBHHHhhI", 1, 1, 0x00, 1, 1, 1, 0, 0, 0)
+            + cn(part_id) + cn(part_txt) + bytes([len(part_fix)]) + part_fix
+        ))
+
+    path = tmp_path / "partid.stdf"
+    path.write_bytes(
+        FAR + _mir()
+        + pir() + ptr(1, True) + prr_ids("U17", "2D-0001", b"\x81")
+        + pir() + ptr(1, True) + prr_ids("")              # no PART_ID
+        + pir() + ptr(1, True) + prr_ids("U17")           # retest of U17
+    )
+
+    data = parse_stdf(path)
+
+    assert [p["part_id"] for p in data.parts] == ["LOT1__U17", "LOT1__2", "LOT1__3"]
+    assert [p["seq_part_id"] for p in data.parts] == ["LOT1__1", "LOT1__2", "LOT1__3"]
+    assert [p["tester_part_id"] for p in data.parts] == ["U17", "", "U17"]
+    assert (data.parts[0]["part_txt"], data.parts[0]["part_fix"]) == ("2D-0001", "81")
+    # results read before the PRR follow the part to its tester id
+    assert [r["part_id"] for r in data.test_results] == [p["part_id"] for p in data.parts]
+
+
+def test_multi_site_results_stay_with_their_part(tmp_path):
+    path = tmp_path / "quad.stdf"
+    generate_stdf(path, GenOptions(parts_per_wafer=8, sites=4, tests=default_tests(3)))
+
+    data = parse_stdf(path)
+
+    sites = {p["part_id"]: p["site_num"] for p in data.parts}
+    assert len(sites) == 8
+    for part_id, site in sites.items():
+        rows = [r for r in data.test_results if r["part_id"] == part_id]
+        assert [r["site_num"] for r in rows] == [site] * 3
+
+    # PIRs of a touchdown before its PRRs: a PART_ID taking the number the
+    # other site's part was given keeps neither part's rows from the other
+    def prr_id(site, part_id):
+        return record(5, 20, struct.pack("<BBBHHHhhI", 1, site, 0, 1, 1, 1, 0, 0, 0) + cn(part_id))
+
+    path.write_bytes(
+        FAR + _mir() + pir(1) + pir(2) + ptr(1, True, site=1) + ptr(2, True, site=2)
+        + prr_id(1, "2") + prr_id(2, "") + pir(1) + ptr(3, True, site=1) + prr_id(1, "")
+    )
+    data = parse_stdf(path)
+
+    assert [p["part_id"] for p in data.parts] == ["LOT1__1", "LOT1__2", "LOT1__3"]
+    assert [(r["test_num"], r["part_id"]) for r in data.test_results] == [
+        (1, "LOT1__1"), (2, "LOT1__2"), (3, "LOT1__3"),
+    ]
+
+    # a sequential number a tester PART_ID already took gets a suffix
+    path.write_bytes(
+        FAR + _mir() + pir() + ptr(1, True) + prr_id(1, "2")
+        + pir() + ptr(2, True) + prr_id(1, "")
+    )
+    data = parse_stdf(path)
+
+    assert [p["part_id"] for p in data.parts] == ["LOT1__2", "LOT1__2-2"]
+    assert [r["part_id"] for r in data.test_results] == ["LOT1__2", "LOT1__2-2"]
+
+
+def test_wrr_fields_and_wafer_id_backfill(tmp_path):
+    wir = record(2, 10, struct.pack("<BBI", 1, 0, 0) + cn(""))
+    wrr = record(2, 20, (
+        struct.pack("<BBIIIIII", 1, 0, 0, 1, 0, 0, 1, 0)
+        + cn("W07") + cn("FAB-07") + cn("FR9") + cn("MASK-A") + cn("") + cn("edge chip")
+    ))
+    path = tmp_path / "wrr.stdf"
+    path.write_bytes(FAR + _mir() + wir + pir() + ptr(1, True) + prr(0x00, 0) + wrr + WIR + WRR)
+
+    data = parse_stdf(path)
+
+    first = data.wafers[0]
+    assert first["wafer_id"] == "W07"
+    assert (first["fabwf_id"], first["frame_id"], first["mask_id"]) == ("FAB-07", "FR9", "MASK-A")
+    assert (first["user_desc"], first["exc_desc"]) == ("", "edge chip")
+    assert data.wafers[1]["wafer_id"] == "W01"  # WIR id kept; short WRR leaves fields empty
+    assert data.wafers[1]["mask_id"] == ""
+    part = data.parts[0]
+    assert (part["wafer_id"], part["part_id"], part["seq_part_id"]) == (
+        "W07", "LOT1_W07_1", "LOT1_W07_1",
+    )
+    assert [(r["wafer_id"], r["part_id"]) for r in data.test_results] == [("W07", "LOT1_W07_1")]
+
+
+def test_per_site_bin_counts(tmp_path):
+    def hbr(head, site, bin_num, count, name=""):
+        return record(1, 40, struct.pack("<BBHIB", head, site, bin_num, count, ord("P")) + cn(name))
+
+    def sbr(head, site, bin_num, count):
+        return record(1, 50, struct.pack("<BBHIB", head, site, bin_num, count, ord("P")) + cn(""))
+
+    path = tmp_path / "sitebins.stdf"
+    path.write_bytes(
+        FAR
+        + hbr(1, 1, 1, 40, "PASS") + hbr(1, 2, 1, 38) + hbr(255, 255, 1, 78)
+        + sbr(1, 1, 7, 5) + sbr(1, 2, 7, 6)  # no SBR summary
+    )
+
+    data = parse_stdf(path)
+
+    assert {k: v["bin_count"] for k, v in data.site_bins_hard.items()} == {
+        (1, 1, 1): 40, (1, 2, 1): 38,
+    }
+    assert data.bins_hard[1] == {"bin_num": 1, "bin_name": "PASS", "bin_pf": "P", "bin_count": 78}
+    assert data.bins_soft[7]["bin_count"] == 11  # per-site sum
+    assert data.site_bins_soft[(1, 2, 7)]["site_num"] == 2
+
+
+def test_stream_parts_per_touchdown(tmp_path):
+    # two-site touchdown, then a single-site one: parts go out once none is open
+    path = tmp_path / "stream.stdf"
+    path.write_bytes(
+        FAR + pir(1) + pir(2)
+        + ptr(1, True, site=1) + ptr(1, False, site=2) + ptr(2, True, site=1)
+        + prr(0x00, 0, site=1) + prr(0x08, 1, site=2)
+        + pir(1) + ptr(1, True, site=1) + prr(0x00, 2, site=1)
+    )
+    streamed = []
+
+    def handler(part, results):
+        streamed.append((part["x_coord"], len(parser.data.parts), len(parser.data.test_results)))
+        received.append([(r["test_num"], r["site_num"], r["passed"]) for r in results])
+
+    received = []
+    parser = STDFParser().stream_parts(handler)
+    data = parser.parse(path)
+
+    # both parts of the first touchdown were held (with its 3 results) until
+    # its last PRR; the second touchdown went out on its own
+    assert streamed == [(0, 2, 3), (1, 2, 3), (2, 1, 1)]
+    # each part got its own site's results
+    assert received == [[(1, 1, True), (2, 1, True)], [(1, 2, False)], [(1, 1, True)]]
+    assert data.parts == [] and data.test_results == []
@@ -0,0 +1,254 @@
+"""Tests for REC_LEN consumption checks and record accounting (ParseStats counters, warnings)."""
+
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import cn, record, make_stdf  # noqa: E402
+
+from stdf_platform.parser import ParseOptions, STDFParser, parse_stdf  # noqa: E402
+
+
+FAR = record(0, 10, struct.pack("BB", 2, 4))
+
+
+def test_clean_file_has_no_overruns(tmp_path):
+    path = tmp_path / "ok.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+    data = parse_stdf(path)
+    assert data.stats.overrun_records == {}
+    assert data.stats.leftover_bytes == {}
+    hbr, sbr = data.bins_hard[1], data.bins_soft[3]
+    assert (hbr["bin_name"], sbr["bin_name"]) == ("PASS", "BIN3_FAIL_B")
+    assert data.warnings == []
+    assert data.stats.record_count > 0
+
+
+def test_short_required_fields_flagged_and_resynced(tmp_path):
+    # HBR's HEAD/SITE/HBIN_NUM/HBIN_CNT are required (8 bytes); declare only 3.
+    short_hbr = struct.pack("<HBB", 3, 1, 40) + b"\x01\x01\x05"
+    wir = record(2, 10, struct.pack("<BBI", 1, 0, 0) + cn("W01"))
+    path = tmp_path / "short.stdf"
+    path.write_bytes(FAR + short_hbr + wir)
+
+    data = parse_stdf(path)
+
+    assert data.stats.overrun_records == {"HBR": 1}
+    assert "HBR at offset 0x6" in data.warnings[0]
+    # the parser re-synced on the declared boundary: the WIR still decodes
+    assert [w["wafer_id"] for w in data.wafers] == ["W01"]
+
+
+def test_short_ptr_flagged(tmp_path):
+    path = tmp_path / "ptr.stdf"
+    path.write_bytes(FAR + record(15, 10, struct.pack("<IB", 1001, 1)))
+    data = parse_stdf(path)
+    assert data.stats.overrun_records == {"PTR": 1}
+    assert data.test_results == []
+
+
+def test_truncated_final_record_flagged(tmp_path):
+    path = tmp_path / "trunc.stdf"
+    mrr = record(1, 20, struct.pack("<I", 1700000000))
+    path.write_bytes(FAR + mrr[:6])  # header + 2 of 4 FINISH_T bytes
+    data = parse_stdf(path)
+    assert data.stats.overrun_records == {"MRR": 1}
+    assert "truncated by end of file" in data.warnings[0]
+
+
+def test_truncated_field_named_in_warning(tmp_path):
+    # PTR with 2 of RESULT's 4 bytes; HBR whose REC_LEN ends inside HBIN_CNT;
+    # a final MRR cut off by the end of the file
+    ptr = record(15, 10, struct.pack("<IBBBB", 1001, 1, 1, 0, 0) + b"\x00\x3f")
+    hbr = record(1, 40, struct.pack("<BBH", 1, 0, 1) + b"\x0a\x00")
+    wir = record(2, 10, struct.pack("<BBI", 1, 0, 0) + cn("W01"))
+    path = tmp_path / "fields.stdf"
+    path.write_bytes(FAR + ptr + hbr + wir + record(1, 20, b"\x00\x00"))
+
+    data = parse_stdf(path)
+
+    assert [w for w in data.warnings if "field truncated" in w] == [
+        "PTR at offset 0x6: RESULT field truncated (2 of 4 bytes)",
+        f"HBR at offset 0x{len(FAR) + len(ptr):X}: HBIN_CNT field truncated (2 of 4 bytes)",
+        f"MRR at offset 0x{len(FAR) + len(ptr) + len(hbr) + len(wir):X}: "
+        "FINISH_T field truncated by end of file (2 of 4 bytes)",
+    ]
+    assert data.stats.overrun_records == {"PTR": 1, "HBR": 1, "MRR": 1}
+    assert data.test_results == [] and data.bins_hard == {}
+    assert [w["wafer_id"] for w in data.wafers] == ["W01"]
+
+
+def test_leftover_bytes_counted_per_record_type(tmp_path):
+    # HBR with a 3-byte vendor tail after HBIN_NAM
+    hbr = struct.pack("<BBHIB", 1, 0, 1, 10, ord("P")) + cn("PASS") + b"\xAA\xBB\xCC"
+    ptr = (
+        struct.pack("<IBBBB", 1001, 1, 1, 0, 0) + struct.pack("<f", 0.5)
+        + cn("Vth") + cn("") + struct.pack("<Bbbb", 0, 0, 0, 0)
+        + struct.pack("<ff", 0.3, 0.8) + cn("V")
+        + cn("%7.3f") + cn("") + cn("") + struct.pack("<ff", 0.2, 0.9) + b"\x00\x00"
+    )
+    path = tmp_path / "tail.stdf"
+    path.write_bytes(FAR + record(1, 40, hbr) + record(1, 40, hbr) + record(15, 10, ptr))
+
+    data = parse_stdf(path)
+
+    assert data.bins_hard[1]["bin_name"] == "PASS"
+    assert data.stats.leftover_bytes == {"HBR": 6, "PTR": 2}
+    assert data.stats.leftover_records == {"HBR": 2, "PTR": 1}
+    assert data.stats.overrun_records == {}
+
+
+def test_diagnostics_carry_kind_record_and_offset(tmp_path):
+    hbr = struct.pack("<BBHIB", 1, 0, 1, 10, ord("P")) + cn("PASS") + b"\xAA"
+    short_hbr = struct.pack("<HBB", 3, 1, 40) + b"\x01\x01\x05"
+    path = tmp_path / "diag.stdf"
+    path.write_bytes(FAR + record(1, 40, hbr) + short_hbr + record(200, 1, b""))
+
+    data = parse_stdf(path)
+
+    kinds = [(d.kind, d.rec, d.offset) for d in data.diagnostics]
+    assert kinds[:2] == [("leftover", "HBR", 6), ("truncated", "HBR", 6 + 4 + len(hbr))]
+    assert ("missing", "MIR", None) in kinds and ("incomplete", "", None) in kinds
+    # every warning is a diagnostic; the leftover byte is only a diagnostic
+    assert data.warnings == [d.message for d in data.diagnostics if d.kind != "leftover"]
+
+
+def test_skipped_and_failed_records_counted(tmp_path):
+    other = record(200, 1, struct.pack("<I", 0) + cn("merge"))
+    vendor = record(180, 7, b"\x01\x02\x03")
+    hbr = record(1, 40, struct.pack("<BBHIB", 1, 0, 1, 10, ord("P")) + cn("PASS"))
+    path = tmp_path / "skip.stdf"
+    path.write_bytes(FAR + other + vendor + other + hbr + record(1, 50, b"\x01") + hbr)
+
+    parser = STDFParser()
+
+    def boom(f, rec_len):
+        f.read(1)
+        raise ValueError("bad SBR")
+
+    parser._parse_sbr = boom
+    data = parser.parse(path)
+
+    assert data.stats.skipped_records == {"200/1": 2, "180/7": 1}
+    assert data.stats.error_records == {"SBR": 1}
+    # re-synced past the failing record: the trailing HBR still decodes
+    assert data.stats.record_count == 7
+    assert "skipped 3 record(s) with no decoder: 200/1 x2, 180/7 x1" in data.warnings
+    assert any(w.startswith("SBR at offset 0x") and "bad SBR" in w for w in data.warnings)
+
+
+def test_registered_vendor_record_handlers(tmp_path):
+    vendor = record(180, 7, struct.pack("<HI", 3, 1234) + cn("probe"))
+    path = tmp_path / "vendor.stdf"
+    path.write_bytes(FAR + vendor + record(181, 1, b"\x00") + vendor + record(200, 1, b"\x01"))
+
+    def decode(body, endian):
+        code, value = struct.unpack_from(endian + "HI", body)
+        return {"code": code, "value": value, "text": body[7:].decode()}
+
+    def boom(body, endian):
+        raise ValueError("bad vendor record")
+
+    parser = STDFParser().register_record(180, 7, decode).register_record(181, 1, boom)
+    data = parser.parse(path)
+
+    assert data.user_records == {(180, 7): [{"code": 3, "value": 1234, "text": "probe"}] * 2}
+    assert data.stats.error_records == {"181/1": 1}
+    assert data.stats.skipped_records == {"200/1": 1}
+    assert not data.stats.leftover_bytes
+
+    with pytest.raises(ValueError, match="180-255"):
+        STDFParser().register_record(15, 10, decode)  # built-in types can't be overridden
+
+
+def test_unknown_records_kept_as_raw_bytes(tmp_path):
+    vendor = record(180, 7, b"\x01\x02\x03")
+    hbr = record(1, 40, struct.pack("<BBHIB", 1, 0, 1, 10, ord("P")) + cn("PASS"))
+    path = tmp_path / "unknown.stdf"
+    path.write_bytes(FAR + vendor + hbr + record(200, 1, b"\xff"))
+
+    assert parse_stdf(path).unknown_records == []
+
+    data = parse_stdf(path, ParseOptions(keep_unknown_records=True))
+    assert data.unknown_records == [
+        (180, 7, len(FAR), b"\x01\x02\x03"),
+        (200, 1, len(FAR) + len(vendor) + len(hbr), b"\xff"),
+    ]
+    assert data.stats.skipped_records == {"180/7": 1, "200/1": 1}
+
+
+def test_records_before_far_and_missing_far(tmp_path):
+    def be_record(rec_typ, rec_sub, body):
+        return struct.pack(">HBB", len(body), rec_typ, rec_sub) + body
+
+    atr = struct.pack(">I", 1700000000) + cn("stdfmerge a.stdf b.stdf")
+    hbr = struct.pack(">BBHIB", 1, 0, 1, 300, ord("P")) + cn("PASS")
+    path = tmp_path / "atr_first.stdf"
+    path.write_bytes(be_record(0, 20, atr) + be_record(0, 10, b"\x01\x04") + be_record(1, 40, hbr))
+
+    data = parse_stdf(path)
+
+    assert data.audit_trail[0]["mod_time"] == 1700000000
+    assert data.bins_hard[1]["bin_count"] == 300
+    assert data.stats.order_violations == {"FAR": 1}
+    assert "FAR is not the first record" in data.warnings[0]
+
+    path.write_bytes(be_record(0, 20, atr) + be_record(1, 40, hbr))  # no FAR at all
+    data = parse_stdf(path)
+    assert data.bins_hard[1]["bin_count"] == 300
+    assert "no FAR in file — byte order inferred as big endian" in data.warnings
+
+
+def _make(path: Path) -> Path:
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=4)
+    return path
+
+
+def _corrupt(path: Path) -> tuple[int, int]:
+    """make_stdf lot with a PTR's REC_LEN overwritten by 0xFFFF and 3 stray
+    bytes inserted before a later PIR. Returns the bytes each one spans."""
+    raw = bytearray(_make(path).read_bytes())
+    offsets, pos = [], 0
+    while pos < len(raw):
+        rec_len = struct.unpack_from("<H", raw, pos)[0]
+        offsets.append((pos, raw[pos + 2], raw[pos + 3], rec_len))
+        pos += 4 + rec_len
+    ptr = [o for o in offsets if o[1:3] == (15, 10)][7]  # part 2, test 1003
+    pir = [o for o in offsets if o[1:3] == (5, 10)][3]
+    raw[ptr[0]:ptr[0] + 2] = b"\xff\xff"
+    raw[pir[0]:pir[0]] = b"\xab\xcd\xef"
+    path.write_bytes(raw)
+    return 4 + ptr[3], 3
+
+
+def test_corrupt_headers_resynchronized(tmp_path):
+    clean = parse_stdf(_make(tmp_path / "clean.stdf"))
+    path = tmp_path / "bad.stdf"
+    ptr_bytes, stray = _corrupt(path)
+
+    data = parse_stdf(path)
+
+    # everything after the damage still decodes: only the one PTR is lost
+    assert len(data.parts) == 4 and data.is_complete
+    assert [p["x_coord"] for p in data.parts] == [p["x_coord"] for p in clean.parts]
+    assert len(data.test_results) == len(clean.test_results) - 1
+    assert data.stats.resync_count == 2
+    assert data.stats.resync_bytes == ptr_bytes + stray
+    assert any(
+        "implausible record header (PTR, REC_LEN 65535)" in w
+        and f"skipped {ptr_bytes} bytes to the PTR" in w for w in data.warnings
+    )
+    assert any("skipped 3 bytes to the PIR" in w for w in data.warnings)
+
+
+def test_truncated_final_record_not_resynchronized(tmp_path):
+    path = _make(tmp_path / "cut.stdf")
+    path.write_bytes(path.read_bytes()[:-2])  # MRR cut short
+
+    data = parse_stdf(path)
+
+    assert data.stats.resync_count == 0 and not data.is_complete
+    assert data.stats.overrun_records == {"MRR": 1}
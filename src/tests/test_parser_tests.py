@@ -0,0 +1,504 @@
+"""Tests for test definition handling in the parser (data.tests)."""
+
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import cn, record  # noqa: E402
+
+from stdf_platform.parser import ParseOptions, STDFDecodeError, parse_stdf  # noqa: E402
+
+
+FAR = record(0, 10, struct.pack("BB", 2, 4))
+PIR = record(5, 10, struct.pack("BB", 1, 1))
+PRR = record(5, 20, struct.pack("<BBBHHHhh", 1, 1, 0, 1, 1, 1, 0, 0))
+
+
+def ptr(test_num, test_txt="", lo=0.0, hi=1.0, units="V", result=0.5):
+    return record(15, 10, (
+        struct.pack("<IBBBB", test_num, 1, 1, 0, 0) + struct.pack("<f", result)
+        + cn(test_txt) + cn("") + struct.pack("<Bbbb", 0, 0, 0, 0)
+        + struct.pack("<ff", lo, hi) + cn(units)
+    ))
+
+
+def ftr(test_num):
+    return record(15, 20, struct.pack("<IBBB", test_num, 1, 1, 0))
+
+
+def tsr(test_num, name, test_typ="P", head=255, site=255):
+    return record(10, 30, (
+        struct.pack("<BBBI", head, site, ord(test_typ), test_num)
+        + struct.pack("<III", 10, 1, 0) + cn(name) + cn("") + cn("")
+    ))
+
+
+def test_empty_test_txt_backfilled_from_tsr(tmp_path):
+    path = tmp_path / "tsr.stdf"
+    path.write_bytes(
+        FAR + PIR + ptr(1001, "") + ptr(1002, "Idsat") + ftr(2001) + PRR
+        + tsr(1001, "Vth_N") + tsr(1002, "TSR_NAME") + tsr(2001, "FUNC_MAIN", "F")
+    )
+
+    data = parse_stdf(path)
+
+    assert data.tests[1001]["test_name"] == "Vth_N"
+    assert data.tests[1002]["test_name"] == "Idsat"   # record name wins over TSR
+    assert data.tests[2001]["test_name"] == "FUNC_MAIN"
+
+
+def test_later_records_fill_empty_test_def_fields(tmp_path):
+    # First PTR: no name, no limits/units (truncated after ALARM_ID)
+    bare = record(15, 10, (
+        struct.pack("<IBBBB", 1001, 1, 1, 0, 0) + struct.pack("<f", 0.4) + cn("") + cn("")
+    ))
+    path = tmp_path / "merge.stdf"
+    path.write_bytes(
+        FAR + PIR + bare + PRR
+        + PIR + ptr(1001, "Vth_N", lo=0.3, hi=0.8, units="V") + PRR
+        + PIR + ptr(1001, "OTHER", lo=9.0, hi=9.0, units="A") + PRR
+    )
+
+    test = parse_stdf(path).tests[1001]
+
+    assert test["test_name"] == "Vth_N"
+    assert test["lo_limit"] == pytest.approx(0.3)
+    assert test["hi_limit"] == pytest.approx(0.8)
+    assert test["units"] == "V"     # filled once, never overwritten by later values
+
+
+def test_ptr_spec_limits_and_formats(tmp_path):
+    def full_ptr(test_num, opt_flag):
+        return record(15, 10, (
+            struct.pack("<IBBBBf", test_num, 1, 1, 0, 0, 0.5) + cn("VDD") + cn("")
+            + struct.pack("<Bbbbff", opt_flag, 0, 0, 0, 0.3, 0.8) + cn("V")
+            + cn("%7.3f") + cn("%6.2f") + cn("") + struct.pack("<ff", 0.1, 1.0)
+        ))
+
+    path = tmp_path / "spec.stdf"
+    path.write_bytes(FAR + PIR + full_ptr(1, 0x00) + full_ptr(2, 0x08) + ptr(3) + PRR)
+
+    data = parse_stdf(path)
+
+    t1 = data.tests[1]
+    assert (t1["lo_spec"], t1["hi_spec"]) == (pytest.approx(0.1), 1.0)
+    assert (t1["res_fmt"], t1["llm_fmt"], t1["hlm_fmt"]) == ("%7.3f", "%6.2f", "")
+    assert (data.tests[2]["lo_spec"], data.tests[2]["hi_spec"]) == (pytest.approx(0.1), None)
+    assert (data.tests[3]["lo_spec"], data.tests[3]["res_fmt"]) == (None, "")  # ends at UNITS
+    assert not data.stats.leftover_bytes and not data.stats.overrun_records
+
+
+def test_non_ascii_strings_counted_reported_or_rejected(tmp_path):
+    body = struct.pack("<IBBBB", 1, 1, 1, 0, 0) + struct.pack("<f", 1.0) + b"\x03\xb5" + b"Ax"
+    path = tmp_path / "lossy.stdf"
+    path.write_bytes(FAR + record(15, 10, body))
+
+    data = parse_stdf(path)
+    assert data.tests[1]["test_name"] == "�Ax"
+    assert data.stats.lossy_strings == {"PTR": 1}
+    assert not [w for w in data.warnings if "ASCII" in w]
+
+    data = parse_stdf(path, ParseOptions(string_errors="report"))
+    assert [w for w in data.warnings if "ASCII" in w] == [
+        "PTR at offset 0x6: C*n field at +12 is not ASCII (b'\\xb5Ax'), "
+        "decoded with replacement characters"
+    ]
+
+    with pytest.raises(STDFDecodeError, match="PTR at offset 0x6"):
+        parse_stdf(path, ParseOptions(string_errors="strict"))
+
+
+def test_nibble_arrays_decoded(tmp_path):
+    mpr = (
+        struct.pack("<IBBBBHH", 7, 1, 1, 0, 0, 3, 3)
+        + bytes([0x21, 0x03])                      # RTN_STAT 1, 2, 3
+        + struct.pack("<3f", 0.1, 0.2, 0.3)
+        + cn("M") + cn("") + struct.pack("<BbbbffffHHH", 0, 0, 0, 0, 0, 1, 0, 0, 10, 11, 12)
+    )
+    ftr = (
+        struct.pack("<IBBB", 8, 1, 1, 0x80)
+        + bytes(27)                                # OPT_FLAG .. VECT_OFF
+        + struct.pack("<HH", 3, 0)                 # RTN_ICNT, PGM_ICNT
+        + struct.pack("<3H", 10, 11, 12) + bytes([0xF5, 0x07])
+    )
+    path = tmp_path / "nib.stdf"
+    path.write_bytes(FAR + record(15, 15, mpr) + record(15, 20, ftr))
+
+    data = parse_stdf(path)
+
+    assert [(r["pin_num"], r["rtn_stat"]) for r in data.test_results[:3]] == [(10, 1), (11, 2), (12, 3)]
+    ftr_row = data.test_results[3]
+    assert (ftr_row["rtn_indx"], ftr_row["rtn_stat"]) == ([10, 11, 12], [5, 15, 7])
+    assert data.stats.leftover_bytes == {}
+
+
+def str_record(cont_flg, test_flg, cyc_base, cycles, pins, patterns, bits):
+    """STR (15,30) with 4-byte cycle offsets, 2-byte PMR/pattern/bit arrays, no chains."""
+    def arr(code, values):
+        return struct.pack(f"<H{len(values)}{code}", len(values), *values)
+
+    return record(15, 30, (
+        struct.pack("<BIBBHB", cont_flg, 500, 1, 1, 0, test_flg)
+        + cn("") + cn("SCAN_CHAIN_A") + cn("") + cn("") + cn("")
+        + struct.pack("<BB", 0, 0x04) + struct.pack("<H", 8) + b"\xff"  # FMU_FLG: FAL_MAP only
+        + struct.pack("<QIIQIHH", 1000, 3, 3, cyc_base, 10, 0, 0)
+        + struct.pack("<9BH", 4, 2, 1, 2, 2, 1, 1, 1, 0, 0)
+        + arr("I", cycles) + arr("H", pins) + arr("B", [])
+        + arr("B", [1] * len(cycles)) + arr("B", [0] * len(cycles)) + arr("B", [])
+        + arr("H", patterns) + arr("H", bits)
+        + arr("B", []) + arr("B", []) + arr("B", []) + struct.pack("<H", 0)
+    ))
+
+
+def test_str_scan_failures(tmp_path):
+    from stdf_platform.export import export_scan_failures
+
+    pmr = record(1, 60, struct.pack("<HH", 3, 0) + cn("") + cn("") + cn("SO0"))
+    path = tmp_path / "scan.stdf"
+    path.write_bytes(
+        FAR + pmr + PIR
+        + str_record(1, 0x80, 100, [5, 7], [3, 4], [2, 2], [0, 1])   # continued...
+        + str_record(0, 0x80, 200, [1], [3], [9], [5])              # ...last record
+        + PRR
+    )
+
+    data = parse_stdf(path)
+
+    assert [(r["test_num"], r["pattern"], r["cycle"], r["pin_num"], r["pin_name"], r["bit_pos"])
+            for r in data.scan_failures] == [
+        (500, 2, 105, 3, "SO0", 10),
+        (500, 2, 107, 4, None, 11),
+        (500, 9, 201, 3, "SO0", 15),
+    ]
+    first = data.scan_failures[0]
+    assert (first["chain"], first["expected"], first["captured"], first["new_data"]) == (
+        None, 1, 0, None,
+    )
+    # the continued test is one scan_tests row, headed by its first STR
+    [scan] = data.scan_tests
+    assert (scan["test_num"], scan["passed"], scan["cycle_count"], scan["total_fails"]) == (
+        500, False, 1000, 3,
+    )
+    assert (scan["records"], scan["fail_rows"]) == (2, 3)
+    assert scan["part_id"] == data.parts[0]["part_id"]
+    assert (data.tests[500]["test_name"], data.tests[500]["rec_type"]) == ("SCAN_CHAIN_A", "STR")
+    assert data.parts[0]["result_count"] == data.parts[0]["fail_count"] == 1
+    assert not data.stats.leftover_bytes and not data.stats.skipped_records
+
+    out = tmp_path / "scan.csv"
+    assert export_scan_failures(data, out) == 3
+    lines = out.read_text().splitlines()
+    assert lines[0].startswith("lot_id,wafer_id,part_id,head_num,site_num,test_num,pattern,cycle")
+    assert lines[3].endswith(",500,9,201,3,SO0,,15,1,0,")
+
+
+def psr_record(cont_flg, opt_flg, total, patterns):
+    """PSR (1,90) index 7 with (begin, end, file, label) per pattern."""
+    body = (
+        struct.pack("<BH", cont_flg, 7) + cn("SEQ_A")
+        + struct.pack("<BHH", opt_flg, total, len(patterns))
+    )
+    body += b"".join(struct.pack("<Q", p[0]) for p in patterns)
+    body += b"".join(struct.pack("<Q", p[1]) for p in patterns)
+    body += b"".join(cn(p[2]) for p in patterns)
+    if not opt_flg & 0x01:
+        body += b"".join(cn(p[3]) for p in patterns)
+    return record(1, 90, body)
+
+
+def test_psr_pattern_sequences(tmp_path):
+    path = tmp_path / "psr.stdf"
+    path.write_bytes(
+        FAR
+        + psr_record(1, 0x0E, 3, [(0, 99, "a.pat", "A"), (100, 199, "b.pat", "B")])
+        + psr_record(0, 0x0F, 3, [(200, 299, "c.pat", None)])
+    )
+
+    data = parse_stdf(path)
+
+    seq = data.pattern_sequences[7]
+    assert (seq["name"], seq["total_patterns"], len(seq["patterns"])) == ("SEQ_A", 3, 3)
+    assert seq["patterns"][1] == {
+        "begin": 100, "end": 199, "file": "b.pat", "label": "B",
+        "file_uid": None, "atpg_desc": None, "src_id": None,
+    }
+    assert data.pattern(7, 2)["file"] == "c.pat"
+    assert data.pattern(7, 2)["label"] is None
+    assert data.pattern(7, 3) is None and data.pattern(8, 0) is None
+    assert not data.stats.leftover_bytes and not data.stats.skipped_records
+
+
+def test_pin_group_indexes_resolved(tmp_path):
+    pmr = b"".join(record(1, 60, struct.pack("<HH", i, 0) + cn("") + cn("") + cn(f"DQ{i}"))
+                   for i in (1, 2))
+    pgr = record(1, 62, struct.pack("<H", 32768) + cn("DQ") + struct.pack("<HHH", 2, 1, 2))
+    mpr = record(15, 15, (
+        struct.pack("<IBBBBHH", 7, 1, 1, 0, 0, 1, 1) + b"\x01" + struct.pack("<f", 0.5)
+        + cn("M") + cn("") + struct.pack("<BbbbffffH", 0, 0, 0, 0, 0, 1, 0, 0, 32768)
+    ))
+    ftr = record(15, 20, (
+        struct.pack("<IBBB", 8, 1, 1, 0x80) + bytes(27) + struct.pack("<HH", 2, 0)
+        + struct.pack("<2H", 32768, 2) + b"\x11"
+    ))
+    path = tmp_path / "pgr.stdf"
+    path.write_bytes(FAR + pmr + pgr + mpr + ftr)
+
+    data = parse_stdf(path)
+
+    assert (data.test_results[0]["pin_num"], data.test_results[0]["pin_name"]) == (32768, "DQ")
+    assert data.resolve_pins(data.test_results[1]["rtn_indx"]) == [1, 2]
+    assert [data.pin_name(i) for i in (1, 32768, 99)] == ["DQ1", "DQ", None]
+
+
+def test_plr_pin_list_formats(tmp_path):
+    plr = record(1, 63, (
+        struct.pack("<H2H2H2B", 2, 1, 32768, 0x10, 0x20, 16, 20)
+        + cn("01") + cn("LH") + cn("") + cn("") + cn("") + cn("") + cn("") + cn("XZ")
+    ))
+    path = tmp_path / "plr.stdf"
+    path.write_bytes(FAR + plr + record(1, 63, struct.pack("<HH", 1, 5)))  # 2nd: index only
+
+    data = parse_stdf(path)
+
+    assert data.pin_lists[1] == {
+        "mode": 0x10, "radix": 16, "pgm_char": "01", "rtn_char": "",
+        "pgm_chal": "", "rtn_chal": "",
+    }
+    assert (data.pin_lists[32768]["radix"], data.pin_lists[32768]["pgm_char"],
+            data.pin_lists[32768]["rtn_chal"]) == (20, "LH", "XZ")
+    assert data.pin_lists[5]["mode"] == 0
+    assert data.stats.leftover_bytes == {} and data.stats.overrun_records == {}
+
+
+def test_tsr_test_synopses(tmp_path):
+    full = record(10, 30, (
+        struct.pack("<BBBI", 1, 2, ord("P"), 100) + struct.pack("<III", 50, 3, 4294967295)
+        + cn("VDD_LEAK") + cn("SEQ1") + cn("L1")
+        + struct.pack("<B5f", 0x02, 0.25, -1.0, 9.0, 10.0, 30.0)   # TEST_MAX invalid
+    ))
+    path = tmp_path / "tsr.stdf"
+    path.write_bytes(FAR + full + tsr(200, "IDD"))
+
+    rows = parse_stdf(path).test_synopses
+
+    assert rows[0] == {
+        "head_num": 1, "site_num": 2, "test_num": 100, "test_type": "P",
+        "test_name": "VDD_LEAK", "seq_name": "SEQ1", "test_label": "L1",
+        "exec_count": 50, "fail_count": 3, "alarm_count": None,
+        "test_time": 0.25, "test_min": -1.0, "test_max": None,
+        "test_sum": 10.0, "test_sum_sq": 30.0,
+    }
+    # no OPT_FLAG: statistics absent
+    assert (rows[1]["head_num"], rows[1]["exec_count"], rows[1]["test_min"]) == (255, 10, None)
+
+
+def test_gdr_typed_fields(tmp_path):
+    gdr = record(50, 10, (
+        struct.pack("<H", 12)
+        + b"\x00"                                   # pad byte (not a field)
+        + b"\x01\x07" + b"\x02" + struct.pack("<H", 513) + b"\x03" + struct.pack("<I", 70000)
+        + b"\x04\xff" + b"\x05" + struct.pack("<h", -2) + b"\x06" + struct.pack("<i", -70000)
+        + b"\x07" + struct.pack("<f", 1.5) + b"\x08" + struct.pack("<d", 2.25)
+        + b"\x0a" + cn("OPT") + b"\x0b\x02\xab\xcd" + b"\x0c" + struct.pack("<H", 9) + b"\x01\x01"
+        + b"\x0d\x3f"
+    ))
+    path = tmp_path / "gdr.stdf"
+    path.write_bytes(FAR + gdr + PIR + record(50, 10, struct.pack("<H", 1) + b"\x01\x05") + PRR)
+
+    data = parse_stdf(path)
+
+    assert data.generic_data[0]["fields"] == [
+        ("U1", 7), ("U2", 513), ("U4", 70000), ("I1", -1), ("I2", -2), ("I4", -70000),
+        ("R4", 1.5), ("R8", 2.25), ("Cn", "OPT"), ("Bn", b"\xab\xcd"), ("Dn", b"\x01\x01"),
+        ("N1", 15),
+    ]
+    assert data.generic_data[0]["part_id"] == ""
+    assert data.generic_data[1]["part_id"] == data.parts[0]["part_id"]
+    assert data.stats.leftover_bytes == {}
+
+
+def test_program_sections_stamped_on_results(tmp_path):
+    def bps(name):
+        return record(20, 10, cn(name))
+
+    eps = record(20, 20, b"")
+    path = tmp_path / "sections.stdf"
+    path.write_bytes(
+        FAR + PIR
+        + ptr(1) + bps("DC_TESTS") + bps("CONTINUITY") + ptr(10) + eps + ptr(11) + eps
+        + bps("FUNC") + ftr(10) + eps + eps
+        + PRR
+    )
+
+    data = parse_stdf(path)
+
+    assert [(r["test_num"], r["section"]) for r in data.test_results] == [
+        (1, ""), (10, "DC_TESTS/CONTINUITY"), (11, "DC_TESTS"), (10, "FUNC"),
+    ]
+    assert data.stats.order_violations == {"EPS": 1}
+
+
+def test_ftr_full_fields(tmp_path):
+    body = (
+        struct.pack("<IBBB", 30, 1, 1, 0x80)
+        + struct.pack("<BIIIIiih", 0x20, 1200, 44, 1, 3, 7, -2, 0)   # VECT_OFF invalid
+        + struct.pack("<HH", 1, 2)                                  # RTN_ICNT, PGM_ICNT
+        + struct.pack("<H", 5) + b"\x06"                            # RTN_INDX, RTN_STAT
+        + struct.pack("<2H", 1, 2) + b"\x31"                        # PGM_INDX, PGM_STAT
+        + struct.pack("<H", 12) + b"\x22\x08"                       # FAIL_PIN: 1, 5, 11
+        + cn("march_c") + cn("TS1") + cn("") + cn("MBIST_RAM0") + cn("ALM")
+        + cn("") + cn("fail @ row 3") + b"\x04"
+        + struct.pack("<H", 0)                                      # SPIN_MAP
+    )
+    path = tmp_path / "ftr.stdf"
+    path.write_bytes(FAR + PIR + record(15, 20, body) + PRR)
+
+    data = parse_stdf(path)
+    row = data.test_results[0]
+
+    assert data.tests[30]["test_name"] == "MBIST_RAM0"
+    assert (row["test_txt"], row["vect_nam"], row["time_set"], row["alarm_id"], row["rslt_txt"]) == (
+        "MBIST_RAM0", "march_c", "TS1", "ALM", "fail @ row 3",
+    )
+    assert (row["cycl_cnt"], row["rel_vadr"], row["num_fail"], row["xfail_ad"], row["yfail_ad"]) == (
+        1200, 44, 3, 7, -2,
+    )
+    assert row["vect_off"] is None and row["patg_num"] == 4
+    assert (row["rtn_indx"], row["rtn_stat"]) == ([5], [6])
+    assert (row["pgm_indx"], row["pgm_stat"]) == ([1, 2], [1, 3])
+    assert row["fail_pins"] == [1, 5, 11]
+    assert data.stats.leftover_bytes == {} and data.stats.overrun_records == {}
+
+
+def test_ftr_string_past_rec_len_is_overrun(tmp_path):
+    body = (
+        struct.pack("<IBBB", 30, 1, 1, 0) + bytes(27) + struct.pack("<HH", 0, 0)
+        + struct.pack("<H", 0) + b"\x09abc"                         # VECT_NAM claims 9 bytes
+    )
+    path = tmp_path / "ftr.stdf"
+    path.write_bytes(FAR + record(15, 20, body))
+
+    data = parse_stdf(path)
+
+    assert data.stats.overrun_records == {"FTR": 1}
+    assert data.test_results[0]["vect_nam"] == "abc"
+
+
+def test_mpr_emits_one_row_per_pin(tmp_path):
+    pmr = b"".join(record(1, 60, struct.pack("<HH", i, 0) + cn("") + cn("") + cn(name))
+                   for i, name in [(4, "VDD1"), (5, "VDD2"), (6, "VDD3")])
+    mpr = record(15, 15, (
+        struct.pack("<IBBBBHH", 40, 1, 1, 0, 0, 3, 3) + bytes([0x00, 0x00])
+        + struct.pack("<3f", 1.1, 1.2, 1.3)
+        + cn("VDD_PINS") + cn("") + struct.pack("<BbbbffffHHH", 0, 0, 0, 0, 0, 2, 0, 0, 4, 5, 6)
+        + cn("V")
+    ))
+    path = tmp_path / "mpr.stdf"
+    path.write_bytes(FAR + pmr + PIR + mpr + PRR)
+
+    data = parse_stdf(path)
+
+    assert [(r["test_num"], r["pin_num"], r["pin_name"], round(r["result"], 3))
+            for r in data.test_results] == [
+        (40, 4, "VDD1", 1.1), (40, 5, "VDD2", 1.2), (40, 6, "VDD3", 1.3),
+    ]
+    # one MPR is one test record, however many pins it carries
+    assert data.parts[0]["result_count"] == 1
+
+
+def test_nmr_and_cnr_names(tmp_path):
+    pmr = record(1, 60, struct.pack("<HH", 5, 0) + cn("") + cn("") + cn("SCAN_OU"))
+    nmr = record(1, 91, struct.pack("<BHH2H", 0, 2, 2, 5, 9) + cn("scan_out_long_name") + cn(""))
+    cell = "core/ü_reg[3]".encode("utf-8")
+    cnr = record(1, 92, struct.pack("<HIH", 2, 17, len(cell)) + cell)
+    path = tmp_path / "nmr.stdf"
+    path.write_bytes(FAR + pmr + nmr + cnr)
+
+    data = parse_stdf(path)
+
+    assert data.pin_map[5] == "SCAN_OU"
+    assert data.pin_name(5) == "scan_out_long_name"
+    assert 9 not in data.atpg_names  # empty ATPG names don't override
+    assert data.cell_names == {(2, 17): "core/ü_reg[3]"}
+    assert not data.stats.leftover_bytes and not data.stats.lossy_strings
+
+
+def test_ssr_and_cdr_scan_structure(tmp_path):
+    def sn(text):
+        raw = text.encode("utf-8")
+        return struct.pack("<H", len(raw)) + raw
+
+    def cdr(cont_flg, cells):
+        return record(1, 94, (
+            struct.pack("<BH", cont_flg, 3) + cn("CHAIN_3")
+            + struct.pack("<IHHB2HBHB", 3, 10, 11, 2, 20, 21, 1, 22, 0)
+            + struct.pack("<H", len(cells)) + b"".join(sn(c) for c in cells)
+        ))
+
+    ssr = record(1, 93, cn("CORE") + struct.pack("<H2H", 2, 3, 4))
+    path = tmp_path / "ssr.stdf"
+    path.write_bytes(FAR + ssr + cdr(1, ["u0/q", "u1/q"]) + cdr(0, ["u2/q"]))
+
+    data = parse_stdf(path)
+
+    assert data.scan_structures == [{"name": "CORE", "chains": [3, 4]}]
+    chain = data.scan_chains[3]
+    assert (chain["name"], chain["length"], chain["sin_pin"], chain["sout_pin"]) == (
+        "CHAIN_3", 3, 10, 11,
+    )
+    assert (chain["master_clocks"], chain["slave_clocks"]) == ([20, 21], [22])
+    assert chain["cells"] == ["u0/q", "u1/q", "u2/q"]
+    assert not data.stats.leftover_bytes and not data.stats.skipped_records
+
+
+def test_vur_spec_version(tmp_path):
+    cases = {
+        "v4.stdf": (FAR, "V4", []),
+        "list.stdf": (FAR + record(0, 30, b"\x02" + cn("V4-2007") + cn("Scan:2007.1")),
+                      "V4-2007", ["V4-2007", "Scan:2007.1"]),
+        "single.stdf": (FAR + record(0, 30, cn("V4-2007")), "V4-2007", ["V4-2007"]),
+    }
+    for name, (content, version, updates) in cases.items():
+        path = tmp_path / name
+        path.write_bytes(content)
+        data = parse_stdf(path)
+        assert (data.spec_version, data.version_updates) == (version, updates), name
+        assert not data.stats.skipped_records and not data.stats.leftover_bytes
+
+
+def test_ptr_mpr_default_values_per_test_and_site(tmp_path):
+    def ptr_flagged(opt_flag, lo, hi):
+        return record(15, 10, (
+            struct.pack("<IBBBBf", 7, 1, 1, 0, 0, 0.5) + cn("") + cn("")
+            + struct.pack("<Bbbbff", opt_flag, 0, 0, 0, lo, hi) + cn("")
+        ))
+
+    def mpr(pins, results):
+        return record(15, 15, (
+            struct.pack("<IBBBBHH", 40, 1, 1, 0, 0, len(pins), len(results))
+            + bytes((len(pins) + 1) // 2) + struct.pack(f"<{len(results)}f", *results)
+            + cn("") + cn("") + struct.pack("<Bbbbffff", 0, 0, 0, 0, 0, 1, 0, 0)
+            + struct.pack(f"<{len(pins)}H", *pins) + cn("V")
+        ))
+
+    path = tmp_path / "defaults.stdf"
+    path.write_bytes(
+        FAR
+        + PIR + ptr_flagged(0x30, 9.0, 9.0) + mpr([4, 5], [1.0, 2.0]) + PRR  # limits invalid
+        + PIR + ptr(7, "", lo=0.3, hi=0.8) + mpr([], [1.1, 2.1]) + PRR      # pins defaulted
+    )
+
+    data = parse_stdf(path)
+
+    # the invalid 9.0 placeholders never become the test's limits
+    assert (data.tests[7]["lo_limit"], data.tests[7]["hi_limit"]) == (
+        pytest.approx(0.3), pytest.approx(0.8),
+    )
+    assert data.site_limits[(7, 1, 1)]["lo_limit"] == pytest.approx(0.3)
+    mpr_rows = [r for r in data.test_results if r["test_num"] == 40]
+    assert [(r["pin_num"], round(r["result"], 1)) for r in mpr_rows] == [
+        (4, 1.0), (5, 2.0), (4, 1.1), (5, 2.1),
+    ]
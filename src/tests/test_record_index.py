@@ -0,0 +1,50 @@
+"""Tests for record_index (cached per-wafer byte ranges, filtered re-parse)."""
+
+import sys
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import make_stdf  # noqa: E402
+
+from stdf_platform import record_index  # noqa: E402
+from stdf_platform.parser import parse_stdf  # noqa: E402
+from stdf_platform.record_index import index_path_for, parse_indexed  # noqa: E402
+
+
+def test_parse_indexed_matches_full_parse(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=3, parts_per_wafer=5)
+    full = parse_stdf(path)
+
+    data = parse_indexed(path, wafers={"W02"}, tests={1002})
+
+    assert index_path_for(path).exists()
+    assert data.lot_id == "LOT1" and data.is_complete
+    assert [w["wafer_id"] for w in data.wafers] == ["W02"]
+    assert data.parts == [p for p in full.parts if p["wafer_id"] == "W02"]
+    assert {r["test_num"] for r in data.test_results} == {1002}
+    assert len(data.test_results) == 5
+    assert sorted(data.tests) == sorted(full.tests)
+    # make_stdf writes HBR/SBR per wafer: names match, counts are W02's own
+    assert {b: d["bin_name"] for b, d in data.bins_soft.items()} == {
+        b: d["bin_name"] for b, d in full.bins_soft.items()
+    }
+
+
+def test_index_cached_until_file_changes(tmp_path, monkeypatch):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=3)
+    first = record_index.load_or_build_index(path)
+    assert [w["wafer_id"] for w in first["wafers"]] == ["W01", "W02"]
+    assert first["wafers"][1]["parts_before"] == 3
+
+    calls = []
+    real_build = record_index.build_index
+    monkeypatch.setattr(record_index, "build_index", lambda p: calls.append(p) or real_build(p))
+
+    assert record_index.load_or_build_index(path) == first
+    assert calls == []
+
+    make_stdf(path, "LOT1", num_wafers=3, parts_per_wafer=3)
+    assert len(record_index.load_or_build_index(path)["wafers"]) == 3
+    assert calls == [path]
@@ -0,0 +1,239 @@
+"""Tests for records.py (lossless JSON record dump and rebuild)."""
+
+import dataclasses
+import io
+import json
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import cn, make_ft_stdf, make_stdf, record  # noqa: E402
+
+from stdf_platform.parser import FIELD_LAYOUTS, parse_stdf  # noqa: E402
+from stdf_platform.records import (  # noqa: E402
+    DETAIL_RECORDS, RECORD_CLASSES, Mir, Prr, Ptr, RawRecord, Record, build_stdf, decode_fields,
+    dump_json, edit_mir, encode_fields, filter_records, read_records, repair_stdf,
+)
+
+
+def _round_trip(path: Path) -> tuple[list[dict], bytes]:
+    out = io.StringIO()
+    n = dump_json(path, out)
+    lines = out.getvalue().splitlines()
+    objs = [json.loads(line) for line in lines]
+    assert sum("rec" in o for o in objs) == n
+    rebuilt = io.BytesIO()
+    assert build_stdf(lines, rebuilt) == n
+    return objs, rebuilt.getvalue()
+
+
+def test_dump_and_build_are_byte_identical(tmp_path):
+    wafer = tmp_path / "cp.stdf"
+    make_stdf(wafer, "LOT1", num_wafers=2, parts_per_wafer=4)
+    ft = tmp_path / "ft.stdf"
+    make_ft_stdf(ft, "FT01", parts=3)
+
+    for path in (wafer, ft):
+        objs, rebuilt = _round_trip(path)
+        assert rebuilt == path.read_bytes()
+        assert objs[0]["format"] == "stdf-records" and objs[0]["byte_order"] == "little"
+        assert not any("raw" in o for o in objs[1:]), path.name
+    objs, _ = _round_trip(ft)
+    gdr = next(o for o in objs if o.get("rec") == "GDR")
+    assert gdr["fields"]["GEN_DATA"][0] == [10, "EN-S0-CHIPID_R"]
+    mir = next(o for o in objs if o.get("rec") == "MIR")
+    assert mir["fields"]["LOT_ID"] == "FT01"
+
+
+def test_odd_records_kept_raw(tmp_path):
+    buf = (
+        record(0, 10, struct.pack("BB", 2, 4))
+        + record(180, 7, b"\x01\x02vendor")                  # unknown record
+        + record(5, 10, struct.pack("BB", 1, 1) + b"\xee")   # PIR + a stray byte
+        + record(15, 10, struct.pack("<IBBBBf", 7, 1, 1, 0, 0, 1.5) + cn("t")[:1])  # ends inside TEST_TXT
+        + record(1, 20, struct.pack("<I", 0))[:6]            # MRR cut off by EOF
+    )
+    path = tmp_path / "odd.stdf"
+    path.write_bytes(buf)
+
+    objs, rebuilt = _round_trip(path)
+
+    assert rebuilt == buf
+    assert objs[2] == {"rec": "180/7", "typ": 180, "sub": 7, "raw": "0102" + b"vendor".hex()}
+    assert objs[3]["fields"] == {"HEAD_NUM": 1, "SITE_NUM": 1} and objs[3]["tail"] == "ee"
+    assert "raw" in objs[4]
+    assert objs[5]["rec_len"] == 4 and objs[5]["raw"] == "0000"
+
+
+def test_big_endian_and_trailing_bytes(tmp_path):
+    buf = (
+        struct.pack(">HBB", 2, 0, 10) + struct.pack("BB", 1, 4)
+        + struct.pack(">HBB", 14, 1, 50) + struct.pack(">BBHI", 1, 1, 300, 12345) + b"P" + cn("GOOD")
+        + b"\x00\x01"
+    )
+    path = tmp_path / "be.stdf"
+    path.write_bytes(buf)
+
+    objs, rebuilt = _round_trip(path)
+
+    assert rebuilt == buf
+    assert objs[0]["byte_order"] == "big"
+    assert objs[2]["fields"] == {
+        "HEAD_NUM": 1, "SITE_NUM": 1, "SBIN_NUM": 300, "SBIN_CNT": 12345,
+        "SBIN_PF": "P", "SBIN_NAM": "GOOD",
+    }
+    assert objs[-1] == {"trailing": "0001"}
+
+
+def test_edited_dump_rebuilds_valid_stdf(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+    out = io.StringIO()
+    dump_json(path, out)
+    lines = out.getvalue().splitlines()
+    for i, line in enumerate(lines):
+        obj = json.loads(line)
+        if obj.get("rec") == "MIR":
+            obj["fields"]["LOT_ID"] = "LOT1-RENAMED"
+            lines[i] = json.dumps(obj)
+    edited = tmp_path / "edited.stdf"
+    with open(edited, "wb") as f:
+        build_stdf(lines, f)
+
+    data = parse_stdf(edited)
+
+    assert data.lot_id == "LOT1-RENAMED"
+    assert data.parts and len(data.parts) == len(parse_stdf(path).parts)
+
+    lines[3] = '{"rec":"PIR","typ":5,"sub":10,"fields":{"HEAD_NUM":"x"}}'
+    with pytest.raises(ValueError, match="line 4"):
+        build_stdf(lines, io.BytesIO())
+
+
+def test_field_codec_types():
+    mpr = {
+        "TEST_NUM": 9, "HEAD_NUM": 1, "SITE_NUM": 2, "TEST_FLG": 0, "PARM_FLG": 0,
+        "RTN_ICNT": 3, "RSLT_CNT": 2, "RTN_STAT": [1, 2, 15], "RTN_RSLT": [0.5, -1.25],
+    }
+    body = encode_fields("MPR", mpr)
+    assert decode_fields("MPR", body) == (mpr, len(body))
+    ftr = {"TEST_NUM": 1, "HEAD_NUM": 1, "SITE_NUM": 1, "TEST_FLG": 0, "OPT_FLAG": 0xFF,
+           "CYCL_CNT": 0, "REL_VADR": 0, "REPT_CNT": 0, "NUM_FAIL": 0, "XFAIL_AD": 0,
+           "YFAIL_AD": 0, "VECT_OFF": 0, "RTN_ICNT": 0, "PGM_ICNT": 0, "RTN_INDX": [],
+           "RTN_STAT": [], "PGM_INDX": [], "PGM_STAT": [], "FAIL_PIN": {"bits": 10, "data": "0502"}}
+    body = encode_fields("FTR", ftr)
+    assert decode_fields("FTR", body) == (ftr, len(body))
+    with pytest.raises(ValueError):
+        decode_fields("PTR", b"\x01\x00")
+
+
+def test_filter_records_keeps_summary(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=2, parts_per_wafer=4)
+    full = parse_stdf(path)
+    out = tmp_path / "summary.stdf"
+
+    with open(out, "wb") as f:
+        written, dropped = filter_records(path, f, DETAIL_RECORDS)
+
+    data = parse_stdf(out)
+    assert dropped == {"PTR": 40}
+    assert out.stat().st_size < path.stat().st_size / 2
+    assert data.test_results == [] and data.is_complete
+    assert [(p["part_id"], p["hard_bin"]) for p in data.parts] == [
+        (p["part_id"], p["hard_bin"]) for p in full.parts
+    ]
+    assert data.bins_soft == full.bins_soft and data.wafers == full.wafers
+    assert {p["num_test_delta"] for p in data.parts} == {5}
+
+    with open(out, "wb") as f:
+        written, dropped = filter_records(path, f, keep={"MIR", "PIR", "PRR", "MRR"})
+    assert written == 1 + 1 + 16 + 1
+    assert set(dropped) == {"WIR", "WRR", "PTR", "HBR", "SBR"}
+
+
+def test_repair_skips_garbage_and_cut_off_record(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+    full = parse_stdf(path)
+    raw = path.read_bytes()
+    mir_end = 6 + 4 + struct.unpack_from("<H", raw, 6)[0]  # after FAR, MIR
+    bad = tmp_path / "bad.stdf"
+    bad.write_bytes(raw[:mir_end] + b"\xde\xad\xbe\xef\x00" + raw[mir_end:] + record(15, 10, b"\x01\x02")[:-1])
+    out = tmp_path / "fixed.stdf"
+
+    with open(out, "wb") as f:
+        written, skipped = repair_stdf(bad, f)
+
+    assert skipped == [(mir_end, 5), (len(raw) + 5, 5)]
+    assert out.read_bytes() == raw
+    data = parse_stdf(out)
+    assert data.warnings == [] and data.parts == full.parts
+    assert written == full.stats.record_count
+
+
+def test_edit_mir_in_place_and_resized(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=3)
+    before = parse_stdf(path)
+    raw = path.read_bytes()
+
+    assert edit_mir(path, {"LOT_ID": "LOT9", "START_T": 1800000000}) is True
+    data = parse_stdf(path)
+    assert len(path.read_bytes()) == len(raw)
+    assert data.lot_id == "LOT9" and data.start_time == 1800000000
+    assert data.operator == before.operator
+    assert [p["part_id"] for p in data.parts] == [p["part_id"].replace("LOT1", "LOT9") for p in before.parts]
+
+    # longer LOT_ID, an operator, and a field the file never had (FLOW_ID)
+    assert edit_mir(path, {"LOT_ID": "LOT9-RERUN", "OPER_NAM": "night shift", "FLOW_ID": "R1"}) is False
+    data = parse_stdf(path)
+    assert data.lot_id == "LOT9-RERUN" and data.operator == "night shift"
+    assert data.conditions.flow_id == "R1"
+    assert data.test_code == before.test_code and data.warnings == []
+    assert [r["result"] for r in data.test_results] == [r["result"] for r in before.test_results]
+    new = path.read_bytes()
+    mir_end = [6 + 4 + struct.unpack_from("<H", b, 6)[0] for b in (raw, new)]
+    assert new[mir_end[1]:] == raw[mir_end[0]:]  # records after the MIR untouched
+
+    with pytest.raises(ValueError, match="not MIR fields: LOT"):
+        edit_mir(path, {"LOT": "x"})
+
+
+def test_typed_records(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=1, parts_per_wafer=2)
+    with open(path, "ab") as f:
+        f.write(record(180, 7, b"\x01\x02") + record(15, 10, b"\x01\x00"))  # vendor, cut-short PTR
+
+    recs = list(read_records(path))
+
+    assert b"".join(r.encode() for r in recs) == path.read_bytes()
+    mir = recs[1]
+    assert isinstance(mir, Mir) and mir.lot_id == "LOT1" and mir.test_cod == "CP11"
+    assert mir.tst_temp is None  # omitted by the writer
+    ptrs = [r for r in recs if isinstance(r, Ptr)]
+    assert len(ptrs) == 10 and ptrs[0].test_num == 1001 and ptrs[0].test_txt == "Vth_N"
+    assert isinstance(ptrs[0].result, float) and ptrs[0].units == "V"
+    prrs = [r for r in recs if isinstance(r, Prr)]
+    assert [(p.x_coord, p.y_coord) for p in prrs] == [(-5, -2), (-4, -2)]
+    assert recs[-2] == RawRecord(180, 7, b"\x01\x02") and recs[-1].rec_key == (15, 10)
+    assert isinstance(recs[-1], RawRecord)
+    assert "PTR" in RECORD_CLASSES and "STR" not in RECORD_CLASSES
+
+    body = mir.encode(">")[4:]
+    assert Record.parse(1, 10, body, ">") == mir
+    edited = Ptr(test_num=5, head_num=1, site_num=2, test_flg=0, parm_flg=0, result=1.5)
+    assert Record.parse(15, 10, edited.encode()[4:]) == edited
+
+
+def test_record_classes_follow_field_layouts():
+    assert list(RECORD_CLASSES) == list(FIELD_LAYOUTS)
+    for name, cls in RECORD_CLASSES.items():
+        assert cls.__name__ == name.capitalize()
+        assert [f.name for f in dataclasses.fields(cls)] == [
+            field_name.lower() for field_name, *_ in FIELD_LAYOUTS[name]
+        ] + ["tail"]
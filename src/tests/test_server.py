@@ -1,5 +1,6 @@
 """Query-server endpoint + connection-lockdown tests over synthetic Parquet."""
 
+import json
 import sys
 from pathlib import Path
 
@@ -10,6 +11,7 @@ from fastapi.testclient import TestClient
 from stdf_platform.config import Config, ServerConfig, StorageConfig
 from stdf_platform.server import create_app
 from synth_data import _write_cp
+from make_test_stdf import make_stdf
 
 
 def _client(tmp_path, **server_kw) -> TestClient:
@@ -105,3 +107,44 @@ def test_invalid_sql_returns_duckdb_message(tmp_path):
     )
     assert resp.status_code == 400
     assert "no_such_view" in resp.json()["detail"]
+
+
+def test_parse_disabled_by_default(tmp_path):
+    resp = _client(tmp_path).post("/api/parse", content=b"\x02\x00\x00\x0a\x02\x04")
+    assert resp.status_code == 403
+
+
+def test_parse_upload_summary_and_jsonl(tmp_path):
+    stdf = tmp_path / "up.stdf"
+    make_stdf(stdf, "UPLOT", num_wafers=1, parts_per_wafer=4)
+    client = _client(tmp_path, enable_parse=True)
+
+    resp = client.post("/api/parse", content=stdf.read_bytes())
+    assert resp.status_code == 200
+    summary = resp.json()
+    assert summary["lot_id"] == "UPLOT"
+    assert summary["part_count"] == 4
+    assert summary["is_complete"] is True
+
+    resp = client.post("/api/parse?format=jsonl", content=stdf.read_bytes())
+    lines = resp.text.splitlines()
+    assert len(lines) == 4
+    assert json.loads(lines[0])["lot_id"] == "UPLOT"
+
+
+def test_parse_upload_rejects_bad_stdf(tmp_path):
+    client = _client(tmp_path, enable_parse=True)
+    resp = client.post("/api/parse", content=b"\x02\x00\x00\x0a\x02\x03")  # STDF_VER 3
+    assert resp.status_code == 422
+    assert "STDF_VER 3" in resp.json()["detail"]
+
+
+def test_parse_upload_size_cap(tmp_path):
+    client = _client(tmp_path, enable_parse=True, max_upload_mb=0)
+    resp = client.post("/api/parse", content=b"\x02\x00\x00\x0a\x02\x04")
+    assert resp.status_code == 413
+
+    client = _client(tmp_path, enable_parse=True, max_upload_mb=1)
+    chunks = (b"\x00" * 65536 for _ in range(32))  # 2 MB, chunked: no Content-Length
+    resp = client.post("/api/parse", content=chunks)
+    assert resp.status_code == 413
@@ -0,0 +1,94 @@
+"""Tests for split.py (one STDF file per wafer / per site)."""
+
+import struct
+import sys
+from pathlib import Path
+
+import pytest
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import cn, make_ft_stdf, make_stdf, record  # noqa: E402
+
+from stdf_platform.parser import parse_stdf  # noqa: E402
+from stdf_platform.split import split_stdf  # noqa: E402
+
+
+def _die(part: dict) -> tuple:
+    return part["x_coord"], part["y_coord"], part["hard_bin"], part["soft_bin"], part["passed"]
+
+
+def test_split_by_wafer(tmp_path):
+    path = tmp_path / "lot.stdf"
+    make_stdf(path, "LOT1", num_wafers=3, parts_per_wafer=4)
+    full = parse_stdf(path)
+
+    paths = split_stdf(path, tmp_path / "out")
+
+    assert [p.name for p in paths] == ["lot_W01.stdf", "lot_W02.stdf", "lot_W03.stdf"]
+    for out, wafer_id in zip(paths, ("W01", "W02", "W03")):
+        data = parse_stdf(out)
+        assert data.is_complete and data.warnings == []
+        assert data.lot_id == "LOT1" and data.finish_time == full.finish_time
+        assert [w["wafer_id"] for w in data.wafers] == [wafer_id]
+        mine = [p for p in full.parts if p["wafer_id"] == wafer_id]
+        assert [_die(p) for p in data.parts] == [_die(p) for p in mine]
+        assert sum(b["bin_count"] for b in data.bins_hard.values()) == 4
+        good = sum(p["passed"] for p in mine)
+        assert data.bins_hard.get(1, {"bin_count": 0})["bin_count"] == good
+        assert data.tests == full.tests
+
+    with pytest.raises(ValueError, match="no WIR"):
+        make_ft_stdf(tmp_path / "ft.stdf", "FT1", parts=2)
+        split_stdf(tmp_path / "ft.stdf", tmp_path / "ft")
+
+
+def _multisite(path: Path):
+    """One wafer, sites 1 and 2 tested in parallel (interleaved records)."""
+    mir = (
+        struct.pack("<IIB", 1700000000, 1700000000, 1) + b"    \x00 "
+        + cn("LOTS") + cn("DEV") + cn("N1") + cn("93K") + cn("JOB") + cn("R1")
+    )
+    buf = record(0, 10, struct.pack("BB", 2, 4)) + record(1, 10, mir)
+    buf += record(1, 80, struct.pack("<BBBBB", 1, 1, 2, 1, 2))  # SDR sites 1, 2
+    buf += record(2, 10, struct.pack("<BBI", 1, 0, 1700000000) + cn("W07"))
+    for touchdown in range(3):
+        for site in (1, 2):
+            buf += record(5, 10, struct.pack("BB", 1, site))
+        for site in (1, 2):
+            value = 10.0 * site + touchdown
+            buf += record(15, 10, struct.pack("<IBBBBf", 7, 1, site, 0, 0, value))
+        for site in (1, 2):
+            failed = site == 2 and touchdown == 1
+            buf += record(5, 20, struct.pack("<BBBHHHhh", 1, site, 0x08 if failed else 0, 1,
+                                             2 if failed else 1, 9 if failed else 1,
+                                             touchdown, site))
+        buf += record(50, 30, cn(f"touchdown {touchdown}"))  # DTR between touchdowns
+    buf += record(2, 20, struct.pack("<BBIIIII", 1, 0, 1700000500, 6, 0, 0, 5))
+    for site in (1, 2):
+        buf += record(10, 30, struct.pack("<BBBIIII", 1, site, ord("P"), 7, 3, 0, 0) + cn("T7"))
+    buf += record(1, 40, struct.pack("<BBHI", 255, 255, 1, 5) + b"P" + cn("GOOD"))
+    buf += record(1, 40, struct.pack("<BBHI", 255, 255, 2, 1) + b"F" + cn("BAD"))
+    buf += record(1, 20, struct.pack("<I", 1700000600))
+    path.write_bytes(buf)
+
+
+def test_split_by_site(tmp_path):
+    path = tmp_path / "ms.stdf"
+    _multisite(path)
+
+    paths = split_stdf(path, tmp_path / "out", by="site")
+
+    assert [p.name for p in paths] == ["ms_site1.stdf", "ms_site2.stdf"]
+    site1, site2 = (parse_stdf(p) for p in paths)
+    for data, site in ((site1, 1), (site2, 2)):
+        assert data.is_complete and data.warnings == []
+        assert {p["site_num"] for p in data.parts} == {site}
+        assert [r["result"] for r in data.test_results] == [10.0 * site + t for t in range(3)]
+        assert len(data.datalog_text) == 3
+        assert [s["site_num"] for s in data.test_synopses] == [site]
+        assert data.site_descriptions == parse_stdf(path).site_descriptions
+    assert site1.wafers[0]["part_count"] == 3 and site1.wafers[0]["good_count"] == 3
+    assert site2.wafers[0]["good_count"] == 2
+    assert {b: r["bin_count"] for b, r in site1.bins_hard.items()} == {1: 3}
+    assert {b: r["bin_count"] for b, r in site2.bins_hard.items()} == {1: 2, 2: 1}
+    assert site2.bins_hard[2]["bin_name"] == "BAD" and site2.bins_soft[9]["bin_count"] == 1
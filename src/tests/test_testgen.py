@@ -0,0 +1,75 @@
+"""Tests for testgen.py (synthetic STDF files)."""
+
+import io
+from dataclasses import replace
+
+import pytest
+
+from stdf_platform.parser import parse_reader
+from stdf_platform.records import decode_fields, iter_records
+from stdf_platform.testgen import GenOptions, GenTestSpec, default_tests, generate_stdf
+
+
+def _generate(options: GenOptions):
+    out = io.BytesIO()
+    report = generate_stdf(out, options)
+    return out.getvalue(), report
+
+
+def test_generated_wafer_lot_parses_cleanly():
+    options = GenOptions(lot_id="GEN1", wafers=2, parts_per_wafer=30, sites=4,
+                         tests=default_tests(6), fail_rate=0.05, seed=7)
+    raw, report = _generate(options)
+
+    data = parse_reader(io.BytesIO(raw))
+
+    assert data.is_complete and data.warnings == []
+    assert not data.stats.leftover_bytes and not data.stats.order_violations
+    assert data.lot_id == "GEN1" and [w["wafer_id"] for w in data.wafers] == ["W01", "W02"]
+    assert len(data.parts) == report.parts == 60
+    assert len({(p["wafer_id"], p["x_coord"], p["y_coord"]) for p in data.parts}) == 60
+    assert {p["site_num"] for p in data.parts} == {1, 2, 3, 4}
+    assert len(data.test_results) == report.results == 360
+    assert sum(not r["passed"] for r in data.test_results) == report.failed_results > 0
+    assert sum(p["passed"] for p in data.parts) == report.good
+    assert data.bins_hard[1]["bin_count"] == report.good
+    assert sum(b["bin_count"] for b in data.bins_soft.values()) == 60
+    assert [w["good_count"] for w in data.wafers] == [
+        sum(p["passed"] for p in data.parts if p["wafer_id"] == w["wafer_id"]) for w in data.wafers
+    ]
+    assert data.part_counts[(255, 255)]["part_count"] == 60
+    tsr = {s["test_num"]: s for s in data.test_synopses}
+    assert tsr[1000]["exec_count"] == 60
+    assert tsr[1000]["fail_count"] == sum(
+        not r["passed"] for r in data.test_results if r["test_num"] == 1000
+    )
+    # reserved OPT_FLAG bits 3, 6, 7 set, as the spec asks
+    assert {
+        decode_fields("TSR", body, "<")[0]["OPT_FLAG"]
+        for typ, sub, _, body in iter_records(io.BytesIO(raw), "<") if (typ, sub) == (10, 30)
+    } == {0xCC}
+
+    # deterministic per seed
+    assert _generate(options)[0] == raw
+    assert _generate(replace(options, seed=8))[0] != raw
+
+
+def test_big_endian_final_test_and_fail_rates():
+    tests = [GenTestSpec(1, "always_ok", 0.0, 1.0, "V", fail_rate=0.0),
+             GenTestSpec(2, "always_bad", 0.0, 1.0, "V", fail_rate=1.0)]
+    little, _ = _generate(GenOptions(wafers=0, parts_per_wafer=20, tests=tests))
+    big, report = _generate(GenOptions(wafers=0, parts_per_wafer=20, tests=tests, endian=">"))
+
+    data = parse_reader(io.BytesIO(big))
+
+    assert big != little and data.is_complete and data.wafers == []
+    assert [r["result"] for r in data.test_results] == [
+        r["result"] for r in parse_reader(io.BytesIO(little)).test_results
+    ]
+    assert {p["x_coord"] for p in data.parts} == {-32768}
+    assert all(r["passed"] == (r["test_num"] == 1) for r in data.test_results)
+    assert report.good == 0 and {p["soft_bin"] for p in data.parts} == {3}
+    assert data.bins_soft[3]["bin_name"] == "FAIL_always_bad"
+
+    with pytest.raises(ValueError, match="endian"):
+        generate_stdf(io.BytesIO(), GenOptions(endian="="))
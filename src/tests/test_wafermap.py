@@ -0,0 +1,184 @@
+"""Tests for wafermap.py (per-wafer map export)."""
+
+import struct
+import sys
+import xml.etree.ElementTree as ET
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent))
+from make_test_stdf import cn, record  # noqa: E402
+
+from stdf_platform.parser import parse_stdf  # noqa: E402
+from stdf_platform.wafermap import (  # noqa: E402
+    E142_NS, final_dies, set_orientation, wafer_grids, write_e142, write_sinf,
+)
+
+
+def _die(x, y, soft_bin):
+    flg = 0x00 if soft_bin == 1 else 0x08
+    return (
+        record(5, 10, struct.pack("BB", 1, 1))
+        + record(5, 20, struct.pack("<BBBHHHhh", 1, 1, flg, 0, soft_bin, soft_bin, x, y))
+    )
+
+
+def _wcr(pos_x: bytes = b"R", pos_y: bytes = b"D") -> bytes:
+    """200 mm wafer, 0.5 x 0.4 mm dies (in cm), flat down, X right / Y down by default."""
+    return record(2, 30, struct.pack("<fffBchhcc", 20.0, 0.04, 0.05, 2, b"D", 0, 0, pos_x, pos_y))
+
+
+def _stdf(tmp_path, wcr: bytes = b"") -> Path:
+    """2x2 wafer with one untested corner; die (0,0) fails then passes on retest."""
+    buf = record(0, 10, struct.pack("BB", 2, 4))
+    buf += record(1, 10, struct.pack("<IIBBBBHB", 0, 0, 1, 32, 32, 32, 0, 32)
+                  + cn("LOT1") + b"".join(cn("") for _ in range(10)))
+    buf += wcr
+    buf += record(2, 10, struct.pack("<BBI", 1, 0, 0) + cn("W01"))
+    buf += _die(0, 0, 3) + _die(1, 0, 1) + _die(0, 1, 1) + _die(0, 0, 1)
+    buf += record(2, 20, struct.pack("<BBIIIIII", 1, 0, 0, 4, 1, 0, 3, 0))
+    for num, pf, name in [(1, "P", "GOOD"), (3, "F", "OPEN")]:
+        buf += record(1, 50, struct.pack("<BBHIB", 255, 255, num, 0, ord(pf)) + cn(name))
+    path = tmp_path / "map.stdf"
+    path.write_bytes(buf)
+    return path
+
+
+def test_wafer_grid_last_prr_wins(tmp_path):
+    grids = wafer_grids(parse_stdf(_stdf(tmp_path)))
+    assert len(grids) == 1
+    grid = grids[0]
+    assert (grid.lot_id, grid.wafer_id) == ("LOT1", "W01")
+    assert grid.row_bins() == [[1, 1], [1, None]]
+    assert grid.bin_counts() == {1: 3}
+
+
+def test_e142_xml(tmp_path):
+    data = parse_stdf(_stdf(tmp_path))
+    data.parts[0]["soft_bin"] = 3  # keep one failing die on the map
+    data.parts.pop()
+
+    paths = write_e142(data, tmp_path / "maps")
+
+    assert [p.name for p in paths] == ["LOT1_W01.xml"]
+    ns = {"e": E142_NS}
+    root = ET.parse(paths[0]).getroot()
+    assert root.find("e:Substrates/e:Substrate", ns).get("SubstrateId") == "W01"
+    code_map = root.find(".//e:BinCodeMap", ns)
+    assert code_map.get("NullBin") == "FF"
+    assert [r.text for r in code_map.findall("e:BinCode", ns)] == ["0301", "01FF"]
+    defs = {d.get("BinCode"): d.attrib for d in code_map.findall("e:BinDefinitions/e:BinDefinition", ns)}
+    assert defs["01"]["BinCount"] == "2"
+    assert defs["01"]["BinQuality"] == "Pass"
+    assert defs["03"]["BinDescription"] == "OPEN"
+
+
+def test_code_width_keeps_bins_off_the_null_code(tmp_path):
+    data = parse_stdf(_stdf(tmp_path))
+    data.parts.pop()
+
+    def codes(soft_bin):
+        data.parts[0]["soft_bin"] = soft_bin
+        root = ET.parse(write_e142(data, tmp_path / "maps")[0]).getroot()
+        code_map = root.find(f".//{{{E142_NS}}}BinCodeMap")
+        return code_map.get("NullBin"), [
+            r.text for r in code_map.findall(f"{{{E142_NS}}}BinCode")
+        ]
+
+    assert codes(0xFFFE) == ("FFFF", ["FFFE0001", "0001FFFF"])
+    # only bin 65535, which 4 digits can't tell from the null code, widens to 5
+    assert codes(0xFFFF) == ("FFFFF", ["0FFFF00001", "00001FFFFF"])
+
+
+def test_map_names_that_collide_get_a_suffix(tmp_path):
+    data = parse_stdf(_stdf(tmp_path))
+    data.parts = [{**p, "wafer_id": w} for w in ("W/1", "W:1", "w|1") for p in data.parts]
+
+    paths = write_sinf(data, tmp_path / "maps")
+
+    assert [p.name for p in paths] == ["LOT1_W_1.sinf", "LOT1_W_1_2.sinf", "LOT1_w_1_3.sinf"]
+    assert [p.read_text().splitlines()[2] for p in paths] == [
+        "WAFER:W/1", "WAFER:W:1", "WAFER:w|1",
+    ]
+
+
+def test_sinf(tmp_path):
+    data = parse_stdf(_stdf(tmp_path, wcr=_wcr()))
+    assert data.wafer_config.flat == "D"
+    data.parts[0]["soft_bin"] = 3
+    data.parts.pop()
+
+    paths = write_sinf(data, tmp_path / "maps")
+
+    assert [p.name for p in paths] == ["LOT1_W01.sinf"]
+    header = dict(
+        line.split(":", 1) for line in paths[0].read_text().splitlines() if not line.startswith("RowData")
+    )
+    assert header["WAFER"] == "W01"
+    assert header["FNLOC"] == "180"
+    assert (header["ROWCT"], header["COLCT"]) == ("2", "2")
+    assert header["BCEQU"] == "01"
+    assert (header["DUTMS"], header["XDIES"], header["YDIES"]) == ("mm", "0.5", "0.4")
+    rows = [line for line in paths[0].read_text().splitlines() if line.startswith("RowData")]
+    assert rows == ["RowData:03 01", "RowData:01 __"]
+
+
+def test_sinf_without_wcr_omits_geometry(tmp_path):
+    data = parse_stdf(_stdf(tmp_path))
+    assert data.wafer_config is None
+    text = write_sinf(data, tmp_path / "maps")[0].read_text()
+    assert "FNLOC" not in text and "XDIES" not in text
+
+
+def test_grid_oriented_by_wcr_axis_directions(tmp_path):
+    data = parse_stdf(_stdf(tmp_path, wcr=_wcr(pos_x=b"L", pos_y=b"U")))
+    assert (data.wafer_config.pos_x, data.wafer_config.pos_y) == ("L", "U")
+    assert data.wafer_config.mm_per_unit == 10.0
+    data.parts[0]["soft_bin"] = 3
+    data.parts.pop()  # (0,0)=3, (1,0)=1, (0,1)=1, (1,1) untested
+
+    grid = wafer_grids(data)[0]
+
+    # X grows leftward, Y upward: top-left is (1, 1)
+    assert grid.row_bins() == [[None, 1], [1, 3]]
+    text = write_sinf(data, tmp_path / "maps")[0].read_text()
+    assert "REFPX:1\nREFPY:1\n" in text
+    assert text.endswith("RowData:__ 01\nRowData:01 03\n")
+
+
+def test_orientation_override_without_wcr(tmp_path):
+    data = parse_stdf(_stdf(tmp_path))
+    data.parts.pop()  # (0,0)=3, (1,0)=1, (0,1)=1, (1,1) untested
+
+    set_orientation(data, pos_x="l", flat="d")
+
+    assert (data.wafer_config.pos_x, data.wafer_config.pos_y) == ("L", "")
+    assert wafer_grids(data)[0].row_bins() == [[1, 3], [None, 1]]
+    text = write_sinf(data, tmp_path / "maps")[0].read_text()
+    assert "FNLOC:180\n" in text and "XDIES" not in text  # die size still unknown
+
+
+def test_final_dies_merges_retest_files(tmp_path):
+    original = parse_stdf(_stdf(tmp_path))  # (0,0) 3 -> 1, (1,0) 1, (0,1) 1
+
+    rt_path = tmp_path / "rt.stdf"
+    rt_path.write_bytes(
+        record(0, 10, struct.pack("BB", 2, 4))
+        + record(2, 10, struct.pack("<BBI", 1, 0, 0) + cn("W01"))
+        + _die(1, 0, 3) + _die(1, 1, 1)
+    )
+    retest = parse_stdf(rt_path)
+
+    dies = final_dies(original, [retest])
+
+    assert [(d["x_coord"], d["y_coord"], d["soft_bin"], d["source_pass"], d["from_retest"],
+             d["test_passes"]) for d in dies] == [
+        (0, 0, 1, 0, False, 2),
+        (1, 0, 3, 1, True, 2),
+        (0, 1, 1, 0, False, 1),
+        (1, 1, 1, 1, True, 1),
+    ]
+    grid = wafer_grids(original, parts=dies)[0]
+    assert grid.row_bins() == [[1, 3], [1, 1]]
+
+    paths = write_sinf(original, tmp_path / "maps", parts=dies)
+    assert "RowData:01 03" in paths[0].read_text()